@@ -0,0 +1,11 @@
+#version 330 core
+#include "../include/lighting.glsl"
+
+in vec3 vFragPos;
+in vec3 vNormal;
+in vec2 vTexCoord;
+out vec4 FragColor;
+
+void main() {
+    FragColor = vec4(calcLighting(normalize(vNormal), vFragPos, vTexCoord), 1.0);
+}
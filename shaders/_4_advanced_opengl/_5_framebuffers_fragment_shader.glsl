@@ -0,0 +1,9 @@
+#version 330 core
+in vec2 vTexCoord;
+out vec4 FragColor;
+
+uniform sampler2D wall;
+
+void main() {
+    FragColor = texture(wall, vTexCoord);
+}
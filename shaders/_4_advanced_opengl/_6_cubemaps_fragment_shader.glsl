@@ -0,0 +1,12 @@
+#version 330 core
+#include "../include/environment.glsl"
+
+in vec3 vFragPos;
+in vec3 vNormal;
+in vec3 vViewPos;
+
+out vec4 FragColor;
+
+void main() {
+    FragColor = vec4(environmentColor(vNormal, vFragPos, vViewPos), 1.0);
+}
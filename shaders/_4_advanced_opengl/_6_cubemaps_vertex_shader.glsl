@@ -0,0 +1,20 @@
+#version 330 core
+#include "../include/matrices.glsl"
+
+in vec3 aPos;
+in vec3 aNormal;
+
+out vec3 vFragPos;
+out vec3 vNormal;
+out vec3 vViewPos;
+
+uniform mat4 model;
+uniform mat3 normalMatrix;
+
+void main() {
+    vec4 worldPos = model * vec4(aPos, 1.0);
+    gl_Position = projection * view * worldPos;
+    vFragPos = worldPos.xyz;
+    vNormal = normalMatrix * aNormal;
+    vViewPos = cameraPosition();
+}
@@ -0,0 +1,12 @@
+#version 330 core
+#define SHADOWS
+#include "../include/lighting.glsl"
+
+in vec3 vFragPos;
+in vec3 vNormal;
+in vec2 vTexCoord;
+out vec4 FragColor;
+
+void main() {
+    FragColor = vec4(calcLighting(normalize(vNormal), vFragPos, vTexCoord), 1.0);
+}
@@ -0,0 +1,19 @@
+#version 330 core
+#include "../include/lighting.glsl"
+
+in vec3 vFragPos;
+in vec3 vNormal;
+in vec2 vTexCoord;
+#ifdef TANGENT_SPACE
+in vec4 vTangent;
+#endif
+out vec4 FragColor;
+
+void main() {
+    vec3 normal = normalize(vNormal);
+    vec2 texCoord = vTexCoord;
+#ifdef TANGENT_SPACE
+    applyMaterialMaps(texCoord, normal, vTangent, vFragPos);
+#endif
+    FragColor = vec4(calcLighting(normal, vFragPos, texCoord), 1.0);
+}
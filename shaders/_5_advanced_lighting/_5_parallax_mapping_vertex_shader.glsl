@@ -0,0 +1,29 @@
+#version 330 core
+#include "../include/matrices.glsl"
+#include "../include/material_features.glsl"
+
+in vec3 aPos;
+in vec3 aNormal;
+in vec2 aTexCoord;
+#ifdef TANGENT_SPACE
+in vec4 aTangent;
+out vec4 vTangent;
+#endif
+
+out vec3 vFragPos;
+out vec3 vNormal;
+out vec2 vTexCoord;
+
+uniform mat4 model;
+uniform mat3 normalMatrix;
+
+void main() {
+    vec4 worldPos = model * vec4(aPos, 1.0);
+    gl_Position = projection * view * worldPos;
+    vFragPos = worldPos.xyz;
+    vNormal = normalMatrix * aNormal;
+    vTexCoord = aTexCoord;
+#ifdef TANGENT_SPACE
+    vTangent = vec4(mat3(model) * aTangent.xyz, aTangent.w);
+#endif
+}
@@ -0,0 +1,13 @@
+#version 330 core
+#include "../include/pbr.glsl"
+
+in vec3 vFragPos;
+in vec3 vNormal;
+in vec2 vTexCoord;
+in vec4 vTangent;
+out vec4 FragColor;
+
+void main() {
+    vec3 normal = materialNormal(vNormal, vTangent, vTexCoord);
+    FragColor = vec4(shadePbrSurface(materialSurface(vTexCoord), normal, vFragPos), 1.0);
+}
@@ -0,0 +1,24 @@
+#version 330 core
+#include "../include/matrices.glsl"
+
+in vec3 aPos;
+in vec3 aNormal;
+in vec2 aTexCoord;
+in vec4 aTangent;
+
+out vec3 vFragPos;
+out vec3 vNormal;
+out vec2 vTexCoord;
+out vec4 vTangent;
+
+uniform mat4 model;
+uniform mat3 normalMatrix;
+
+void main() {
+    vec4 worldPos = model * vec4(aPos, 1.0);
+    gl_Position = projection * view * worldPos;
+    vFragPos = worldPos.xyz;
+    vNormal = normalMatrix * aNormal;
+    vTexCoord = aTexCoord;
+    vTangent = vec4(mat3(model) * aTangent.xyz, aTangent.w);
+}
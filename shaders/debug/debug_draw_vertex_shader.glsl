@@ -0,0 +1,11 @@
+#version 330 core
+#include "../include/matrices.glsl"
+
+in vec3 aPos;
+in vec4 aCol;
+out vec4 vColor;
+
+void main() {
+    gl_Position = projection * view * vec4(aPos, 1.0);
+    vColor = aCol;
+}
@@ -0,0 +1,25 @@
+// Reads the surface at a pixel back from the G-buffer written by `geometry_fragment_shader.glsl`.
+// Needs `lighting.glsl` to be included first.
+
+uniform sampler2D gPosition;
+uniform sampler2D gNormal;
+uniform sampler2D gAlbedoSpec;
+uniform sampler2D gAmbient;
+// Ambient occlusion from `ssao_fragment_shader.glsl`, or white without it.
+uniform sampler2D ssao;
+
+// False where no opaque geometry was drawn.
+bool readGBuffer(vec2 texCoord, out Surface surface, out vec3 normal, out vec3 fragPos) {
+    vec4 position = texture(gPosition, texCoord);
+    vec4 normalShininess = texture(gNormal, texCoord);
+    vec4 albedoSpec = texture(gAlbedoSpec, texCoord);
+    surface = Surface(
+        texture(gAmbient, texCoord).rgb * texture(ssao, texCoord).r,
+        albedoSpec.rgb,
+        vec3(albedoSpec.a),
+        normalShininess.a
+    );
+    normal = normalShininess.xyz;
+    fragPos = position.xyz;
+    return position.a > 0.0;
+}
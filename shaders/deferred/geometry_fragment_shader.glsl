@@ -0,0 +1,31 @@
+#version 330 core
+in vec3 vFragPos;
+in vec3 vNormal;
+in vec2 vTexCoord;
+
+// Read back by `lighting_fragment_shader.glsl` and `volume_fragment_shader.glsl`. Alpha of the
+// position marks pixels that were drawn to.
+layout(location = 0) out vec4 gPosition;
+layout(location = 1) out vec4 gNormal;
+layout(location = 2) out vec4 gAlbedoSpec;
+layout(location = 3) out vec4 gAmbient;
+
+// Same as in `lighting.glsl`, so that a `PhongMaterial` can be drawn into the G-buffer.
+struct PhongMaterial {
+    sampler2D ambient;
+    sampler2D diffuse;
+    sampler2D specular;
+    float shininess;
+};
+
+uniform PhongMaterial material;
+
+void main() {
+    gPosition = vec4(vFragPos, 1.0);
+    gNormal = vec4(normalize(vNormal), material.shininess);
+    gAlbedoSpec = vec4(
+        texture(material.diffuse, vTexCoord).rgb,
+        texture(material.specular, vTexCoord).r
+    );
+    gAmbient = vec4(texture(material.ambient, vTexCoord).rgb, 1.0);
+}
@@ -0,0 +1,16 @@
+#version 330 core
+#include "../include/lighting.glsl"
+#include "gbuffer.glsl"
+
+in vec2 vTexCoord;
+out vec4 FragColor;
+
+void main() {
+    Surface surface;
+    vec3 normal;
+    vec3 fragPos;
+    if (!readGBuffer(vTexCoord, surface, normal, fragPos)) {
+        discard;
+    }
+    FragColor = vec4(shadeSurface(surface, normal, fragPos), 1.0);
+}
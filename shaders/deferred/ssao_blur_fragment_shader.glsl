@@ -0,0 +1,17 @@
+#version 330 core
+in vec2 vTexCoord;
+out float FragColor;
+
+uniform sampler2D ssaoInput;
+
+void main() {
+    // Averages a block the size of the noise texture, which cancels out its pattern.
+    vec2 texelSize = 1.0 / vec2(textureSize(ssaoInput, 0));
+    float result = 0.0;
+    for (int x = -2; x < 2; x++) {
+        for (int y = -2; y < 2; y++) {
+            result += texture(ssaoInput, vTexCoord + vec2(x, y) * texelSize).r;
+        }
+    }
+    FragColor = result / 16.0;
+}
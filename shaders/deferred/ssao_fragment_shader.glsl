@@ -0,0 +1,57 @@
+#version 330 core
+#include "../include/matrices.glsl"
+
+in vec2 vTexCoord;
+out float FragColor;
+
+const int MAX_KERNEL_SIZE = 64;
+
+// The first two attachments of the G-buffer, in world space.
+uniform sampler2D gPosition;
+uniform sampler2D gNormal;
+// Tangent space rotations of the kernel about the normal, tiled across the screen.
+uniform sampler2D noise;
+// Points in the hemisphere around +Z, scaled by `radius` and oriented along the normal.
+uniform vec3 samples[MAX_KERNEL_SIZE];
+uniform int kernelSize;
+uniform float radius;
+uniform float bias;
+uniform float power;
+// How many times the noise texture repeats across the screen.
+uniform vec2 noiseScale;
+
+void main() {
+    vec4 position = texture(gPosition, vTexCoord);
+    if (position.a == 0.0) {
+        FragColor = 1.0;
+        return;
+    }
+    // Depths are compared along the view direction, so everything moves to view space.
+    vec3 fragPos = (view * vec4(position.xyz, 1.0)).xyz;
+    vec3 normal = normalize(mat3(view) * texture(gNormal, vTexCoord).xyz);
+    vec3 randomVec = vec3(texture(noise, vTexCoord * noiseScale).xy * 2.0 - 1.0, 0.0);
+
+    // A basis around the normal, rotated by the noise so that neighbouring pixels sample
+    // different points and the banding of a small kernel turns into noise the blur removes.
+    vec3 tangent = normalize(randomVec - normal * dot(randomVec, normal));
+    vec3 bitangent = cross(normal, tangent);
+    mat3 tbn = mat3(tangent, bitangent, normal);
+
+    float occlusion = 0.0;
+    for (int i = 0; i < kernelSize; i++) {
+        vec3 samplePos = fragPos + tbn * samples[i] * radius;
+        vec4 offset = projection * vec4(samplePos, 1.0);
+        vec2 sampleCoord = offset.xy / offset.w * 0.5 + 0.5;
+
+        vec4 surface = texture(gPosition, sampleCoord);
+        if (surface.a == 0.0) {
+            continue;
+        }
+        float surfaceDepth = (view * vec4(surface.xyz, 1.0)).z;
+        // Surfaces much further in front than the radius are separate objects, which shouldn't
+        // darken this one's edges.
+        float rangeCheck = smoothstep(0.0, 1.0, radius / abs(fragPos.z - surfaceDepth));
+        occlusion += (surfaceDepth >= samplePos.z + bias ? 1.0 : 0.0) * rangeCheck;
+    }
+    FragColor = pow(1.0 - occlusion / float(kernelSize), power);
+}
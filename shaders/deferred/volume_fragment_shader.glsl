@@ -0,0 +1,27 @@
+#version 330 core
+#include "../include/lighting.glsl"
+#include "gbuffer.glsl"
+
+out vec4 FragColor;
+
+uniform PointLight pointLight;
+uniform SpotLight spotLight;
+// Whether the volume belongs to `spotLight` rather than `pointLight`.
+uniform bool spot;
+// The G-buffer covers this area of the framebuffer, as x, y, width and height.
+uniform vec4 viewport;
+
+void main() {
+    Surface surface;
+    vec3 normal;
+    vec3 fragPos;
+    vec2 texCoord = (gl_FragCoord.xy - viewport.xy) / viewport.zw;
+    if (!readGBuffer(texCoord, surface, normal, fragPos)) {
+        discard;
+    }
+    vec3 viewDir = normalize(view_position - fragPos);
+    vec3 light = spot
+        ? calcSpotLight(spotLight, surface, normal, fragPos, viewDir, 1.0)
+        : calcPointLight(pointLight, surface, normal, fragPos, viewDir, 1.0);
+    FragColor = vec4(light, 1.0);
+}
@@ -0,0 +1,11 @@
+#version 330 core
+#include "../include/matrices.glsl"
+
+in vec3 aPos;
+
+// Places and scales the sphere around the light.
+uniform mat4 model;
+
+void main() {
+    gl_Position = projection * view * model * vec4(aPos, 1.0);
+}
@@ -0,0 +1,11 @@
+#version 330 core
+in vec2 vTexCoord;
+in vec4 vColor;
+out vec4 FragColor;
+
+// Colors and textures are both premultiplied and gamma encoded, and blended as they are.
+uniform sampler2D image;
+
+void main() {
+    FragColor = vColor * texture(image, vTexCoord);
+}
@@ -0,0 +1,16 @@
+#version 330 core
+in vec2 aPos;
+in vec2 aTexCoord;
+in vec4 aCol;
+out vec2 vTexCoord;
+out vec4 vColor;
+
+// Screen size in egui's points. Positions are in points from the top left corner.
+uniform vec2 screenSize;
+
+void main() {
+    vec2 ndc = aPos / screenSize * 2.0 - 1.0;
+    gl_Position = vec4(ndc.x, -ndc.y, 0.0, 1.0);
+    vTexCoord = aTexCoord;
+    vColor = aCol;
+}
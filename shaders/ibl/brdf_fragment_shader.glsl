@@ -0,0 +1,43 @@
+#version 330 core
+#include "sampling.glsl"
+
+in vec2 vTexCoord;
+out vec4 FragColor;
+
+const uint SAMPLE_COUNT = 1024u;
+
+float geometrySchlickGGX(float nDotV, float roughness) {
+    // Remapped for image-based lighting rather than direct light.
+    float k = roughness * roughness / 2.0;
+    return nDotV / (nDotV * (1.0 - k) + k);
+}
+
+// Scale and bias to the reflectance head-on of the specular light reflected towards a viewer
+// at the angle whose cosine is `nDotV`, from a surface of the given roughness.
+vec2 integrateBRDF(float nDotV, float roughness) {
+    vec3 viewDir = vec3(sqrt(1.0 - nDotV * nDotV), 0.0, nDotV);
+    vec3 normal = vec3(0.0, 0.0, 1.0);
+
+    float scale = 0.0;
+    float bias = 0.0;
+    for (uint i = 0u; i < SAMPLE_COUNT; i++) {
+        vec3 halfway = importanceSampleGGX(hammersley(i, SAMPLE_COUNT), normal, roughness);
+        vec3 lightDir = normalize(2.0 * dot(viewDir, halfway) * halfway - viewDir);
+        float nDotL = max(lightDir.z, 0.0);
+        if (nDotL <= 0.0) {
+            continue;
+        }
+        float nDotH = max(halfway.z, 0.0);
+        float vDotH = max(dot(viewDir, halfway), 0.0);
+        float g = geometrySchlickGGX(nDotV, roughness) * geometrySchlickGGX(nDotL, roughness);
+        float gVis = g * vDotH / (nDotH * nDotV);
+        float fc = pow(1.0 - vDotH, 5.0);
+        scale += (1.0 - fc) * gVis;
+        bias += fc * gVis;
+    }
+    return vec2(scale, bias) / float(SAMPLE_COUNT);
+}
+
+void main() {
+    FragColor = vec4(integrateBRDF(vTexCoord.x, vTexCoord.y), 0.0, 1.0);
+}
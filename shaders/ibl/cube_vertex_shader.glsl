@@ -0,0 +1,12 @@
+#version 330 core
+in vec3 aPos;
+out vec3 vDirection;
+
+// One of the faces of a cube map being rendered from its center, see `CubeFace::view`.
+uniform mat4 projection;
+uniform mat4 view;
+
+void main() {
+    vDirection = aPos;
+    gl_Position = projection * view * vec4(aPos, 1.0);
+}
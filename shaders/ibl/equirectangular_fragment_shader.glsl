@@ -0,0 +1,14 @@
+#version 330 core
+in vec3 vDirection;
+out vec4 FragColor;
+
+// Longitude across and latitude down, with the top row of the image first.
+uniform sampler2D equirectangularMap;
+
+const float PI = 3.14159265359;
+
+void main() {
+    vec3 direction = normalize(vDirection);
+    vec2 uv = vec2(atan(direction.z, direction.x) / (2.0 * PI) + 0.5, acos(direction.y) / PI);
+    FragColor = vec4(texture(equirectangularMap, uv).rgb, 1.0);
+}
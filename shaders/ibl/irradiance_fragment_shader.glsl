@@ -0,0 +1,31 @@
+#version 330 core
+in vec3 vDirection;
+out vec4 FragColor;
+
+uniform samplerCube environmentMap;
+
+const float PI = 3.14159265359;
+const float SAMPLE_DELTA = 0.025;
+
+// Light arriving at a surface facing the sampled direction from the whole hemisphere around
+// it, weighted by the cosine of its angle of incidence.
+void main() {
+    vec3 normal = normalize(vDirection);
+    vec3 up = abs(normal.y) < 0.999 ? vec3(0.0, 1.0, 0.0) : vec3(0.0, 0.0, 1.0);
+    vec3 right = normalize(cross(up, normal));
+    up = cross(normal, right);
+
+    vec3 irradiance = vec3(0.0);
+    float samples = 0.0;
+    for (float phi = 0.0; phi < 2.0 * PI; phi += SAMPLE_DELTA) {
+        for (float theta = 0.0; theta < 0.5 * PI; theta += SAMPLE_DELTA) {
+            vec3 tangentSample = vec3(sin(theta) * cos(phi), sin(theta) * sin(phi), cos(theta));
+            vec3 direction = tangentSample.x * right + tangentSample.y * up
+                + tangentSample.z * normal;
+            // The sine makes up for samples bunching up towards the top of the hemisphere.
+            irradiance += texture(environmentMap, direction).rgb * cos(theta) * sin(theta);
+            samples++;
+        }
+    }
+    FragColor = vec4(PI * irradiance / samples, 1.0);
+}
@@ -0,0 +1,50 @@
+#version 330 core
+#include "sampling.glsl"
+
+in vec3 vDirection;
+out vec4 FragColor;
+
+uniform samplerCube environmentMap;
+uniform float roughness;
+// Size of a face of `environmentMap`'s base level, in texels.
+uniform float environmentSize;
+
+const uint SAMPLE_COUNT = 1024u;
+
+float distributionGGX(float nDotH, float roughness) {
+    float a = roughness * roughness;
+    float a2 = a * a;
+    float denom = nDotH * nDotH * (a2 - 1.0) + 1.0;
+    return a2 / (PI * denom * denom);
+}
+
+// The environment as reflected by a surface of the given roughness, assuming that it's seen
+// head-on so that the view and reflection directions are the normal.
+void main() {
+    vec3 normal = normalize(vDirection);
+    vec3 viewDir = normal;
+
+    vec3 color = vec3(0.0);
+    float totalWeight = 0.0;
+    for (uint i = 0u; i < SAMPLE_COUNT; i++) {
+        vec3 halfway = importanceSampleGGX(hammersley(i, SAMPLE_COUNT), normal, roughness);
+        vec3 lightDir = normalize(2.0 * dot(viewDir, halfway) * halfway - viewDir);
+        float nDotL = dot(normal, lightDir);
+        if (nDotL <= 0.0) {
+            continue;
+        }
+
+        // Samples that are unlikely to be picked stand in for a larger solid angle, so they
+        // read from a blurrier mip level to avoid bright speckles.
+        float nDotH = max(dot(normal, halfway), 0.0);
+        float hDotV = max(dot(halfway, viewDir), 0.0);
+        float pdf = distributionGGX(nDotH, roughness) * nDotH / (4.0 * hDotV) + 0.0001;
+        float texelSolidAngle = 4.0 * PI / (6.0 * environmentSize * environmentSize);
+        float sampleSolidAngle = 1.0 / (float(SAMPLE_COUNT) * pdf + 0.0001);
+        float lod = roughness == 0.0 ? 0.0 : 0.5 * log2(sampleSolidAngle / texelSolidAngle);
+
+        color += textureLod(environmentMap, lightDir, lod).rgb * nDotL;
+        totalWeight += nDotL;
+    }
+    FragColor = vec4(color / totalWeight, 1.0);
+}
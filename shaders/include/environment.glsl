@@ -0,0 +1,26 @@
+// Environment mapping against a cube map, see `environment::EnvironmentMaterial`.
+
+// Ratio of the refractive indices of the medium light comes from and the surface's, or 0 to
+// reflect the environment instead.
+uniform float refractionRatio;
+uniform samplerCube environment;
+
+// The environment as mirrored by a surface with normal `normal`, seen in direction `incident`.
+vec3 reflectEnvironment(samplerCube map, vec3 incident, vec3 normal) {
+    return texture(map, reflect(incident, normalize(normal))).rgb;
+}
+
+// The environment as seen through a surface with normal `normal`, bent by `ratio`.
+vec3 refractEnvironment(samplerCube map, vec3 incident, vec3 normal, float ratio) {
+    return texture(map, refract(incident, normalize(normal), ratio)).rgb;
+}
+
+// Reflects or refracts `environment` depending on `refractionRatio`, for a fragment at
+// `fragPos` seen from `viewPos`.
+vec3 environmentColor(vec3 normal, vec3 fragPos, vec3 viewPos) {
+    vec3 incident = normalize(fragPos - viewPos);
+    if (refractionRatio > 0.0) {
+        return refractEnvironment(environment, incident, normal, refractionRatio);
+    }
+    return reflectEnvironment(environment, incident, normal);
+}
@@ -0,0 +1,216 @@
+// Phong and Blinn-Phong lighting shared by every lit program, see `glutils::lighting`.
+// Defining `SHADOWS` before including this file shadows the lights, see `lights.glsl`. The
+// material's optional maps are turned on by the defines in `material_features.glsl`.
+#include "lights.glsl"
+#include "material_features.glsl"
+
+struct PhongMaterial {
+    sampler2D ambient;
+    sampler2D diffuse;
+    sampler2D specular;
+    float shininess;
+#ifdef NORMAL_MAPPING
+    sampler2D normal;
+#endif
+#ifdef PARALLAX
+    sampler2D height;
+    float heightScale;
+#endif
+};
+
+uniform PhongMaterial material;
+
+// Material properties at the fragment being shaded, whether read from the material's maps or
+// from a G-buffer.
+struct Surface {
+    vec3 ambient;
+    vec3 diffuse;
+    vec3 specular;
+    float shininess;
+};
+
+float specularFactor(vec3 normal, vec3 lightDir, vec3 viewDir, float shininess) {
+    if (blinn_phong) {
+        vec3 halfway = normalize(lightDir + viewDir);
+        return pow(max(dot(normal, halfway), 0.0), shininess);
+    }
+    vec3 reflectDir = reflect(-lightDir, normal);
+    return pow(max(dot(viewDir, reflectDir), 0.0), shininess);
+}
+
+// `visibility` is the fraction of the light that isn't blocked by shadow casters, which only
+// dims diffuse and specular light.
+vec3 calcDirectionalLight(
+    DirectionalLight light,
+    Surface surface,
+    vec3 normal,
+    vec3 viewDir,
+    float visibility
+) {
+    vec3 lightDir = normalize(-light.direction);
+    float diff = max(dot(normal, lightDir), 0.0);
+    float spec = specularFactor(normal, lightDir, viewDir, surface.shininess);
+    return light.ambient * surface.ambient
+        + visibility * (light.diffuse * diff * surface.diffuse
+        + light.specular * spec * surface.specular);
+}
+
+vec3 calcPointLight(
+    PointLight light,
+    Surface surface,
+    vec3 normal,
+    vec3 fragPos,
+    vec3 viewDir,
+    float visibility
+) {
+    vec3 lightDir = normalize(light.position - fragPos);
+    float diff = max(dot(normal, lightDir), 0.0);
+    float spec = specularFactor(normal, lightDir, viewDir, surface.shininess);
+    float att = attenuation(
+        light.constant,
+        light.linear,
+        light.quadratic,
+        length(light.position - fragPos)
+    );
+    return att * (light.ambient * surface.ambient
+        + visibility * (light.diffuse * diff * surface.diffuse
+        + light.specular * spec * surface.specular));
+}
+
+vec3 calcSpotLight(
+    SpotLight light,
+    Surface surface,
+    vec3 normal,
+    vec3 fragPos,
+    vec3 viewDir,
+    float visibility
+) {
+    vec3 lightDir = normalize(light.position - fragPos);
+    float diff = max(dot(normal, lightDir), 0.0);
+    float spec = specularFactor(normal, lightDir, viewDir, surface.shininess);
+    float att = attenuation(
+        light.constant,
+        light.linear,
+        light.quadratic,
+        length(light.position - fragPos)
+    );
+    float intensity = spotIntensity(light, lightDir);
+    return att * (light.ambient * surface.ambient
+        + intensity * visibility * (light.diffuse * diff * surface.diffuse
+        + light.specular * spec * surface.specular));
+}
+
+#ifdef TANGENT_SPACE
+// Turns tangent space into world space at a fragment with the given interpolated normal and
+// mesh tangent, whose `w` is the handedness of the bitangent.
+mat3 tangentBasis(vec3 normal, vec4 tangent) {
+    vec3 n = normalize(normal);
+    vec3 t = normalize(tangent.xyz - dot(tangent.xyz, n) * n);
+    vec3 b = cross(n, t) * tangent.w;
+    return mat3(t, b, n);
+}
+#endif
+
+#ifdef PARALLAX
+// How far below the top of the surface the height map is at `texCoord`, in `[0, 1]`. Gradients
+// are passed in since the ray marching loops sample in non-uniform control flow.
+float surfaceDepth(vec2 texCoord, vec2 dx, vec2 dy) {
+    return 1.0 - textureGrad(material.height, texCoord, dx, dy).r;
+}
+
+// Where a ray along the tangent space `viewDir` hits the height map, starting from the
+// fragment's own texture coordinates.
+vec2 parallaxTexCoord(vec2 texCoord, vec3 viewDir) {
+    vec2 dx = dFdx(texCoord);
+    vec2 dy = dFdy(texCoord);
+#ifdef PARALLAX_MAPPING
+    float depth = surfaceDepth(texCoord, dx, dy);
+    return texCoord - viewDir.xy / viewDir.z * depth * material.heightScale;
+#else
+    // Grazing rays cross more of the surface, so they take more and thinner steps.
+    float numLayers = mix(32.0, 8.0, abs(viewDir.z));
+    float layerDepth = 1.0 / numLayers;
+    vec2 deltaTexCoord = viewDir.xy / viewDir.z * material.heightScale / numLayers;
+
+    vec2 current = texCoord;
+    float rayDepth = 0.0;
+    float depth = surfaceDepth(current, dx, dy);
+    for (int i = 0; i < 32 && rayDepth < depth; i++) {
+        current -= deltaTexCoord;
+        rayDepth += layerDepth;
+        depth = surfaceDepth(current, dx, dy);
+    }
+#ifdef PARALLAX_OCCLUSION_MAPPING
+    // Interpolates between the layers just above and below the surface, by how far each is
+    // from it.
+    vec2 previous = current + deltaTexCoord;
+    float after = depth - rayDepth;
+    float before = surfaceDepth(previous, dx, dy) - (rayDepth - layerDepth);
+    current = mix(current, previous, after / (after - before));
+#endif
+    return current;
+#endif
+}
+#endif
+
+#ifdef TANGENT_SPACE
+// Applies the material's height and normal maps at a fragment: displaces `texCoord` by the
+// height map and replaces `normal` with the normal map's. `normal` and `tangent` are the
+// interpolated ones of the mesh.
+void applyMaterialMaps(inout vec2 texCoord, inout vec3 normal, vec4 tangent, vec3 fragPos) {
+    mat3 tbn = tangentBasis(normal, tangent);
+#ifdef PARALLAX
+    vec3 viewDir = normalize(transpose(tbn) * (view_position - fragPos));
+    texCoord = parallaxTexCoord(texCoord, viewDir);
+#endif
+#ifdef NORMAL_MAPPING
+    vec3 mapped = texture(material.normal, texCoord).xyz * 2.0 - 1.0;
+    normal = normalize(tbn * mapped);
+#else
+    normal = tbn[2];
+#endif
+}
+#endif
+
+// The material's maps at `texCoord`.
+Surface materialSurface(vec2 texCoord) {
+    return Surface(
+        texture(material.ambient, texCoord).rgb,
+        texture(material.diffuse, texCoord).rgb,
+        texture(material.specular, texCoord).rgb,
+        material.shininess
+    );
+}
+
+// Sum of every light's contribution at a fragment with the given world-space normal and
+// position.
+vec3 shadeSurface(Surface surface, vec3 normal, vec3 fragPos) {
+    vec3 viewDir = normalize(view_position - fragPos);
+
+    vec3 result = vec3(0.0);
+    for (int i = 0; i < num_directional_lights; i++) {
+        float visibility = directionalVisibility(i, normal, fragPos);
+        result += cascadeTint(i, fragPos) * calcDirectionalLight(
+            directional_lights[i],
+            surface,
+            normal,
+            viewDir,
+            visibility
+        );
+    }
+    for (int i = 0; i < num_point_lights; i++) {
+        float visibility = pointVisibility(i, normal, fragPos);
+        result += calcPointLight(point_lights[i], surface, normal, fragPos, viewDir, visibility);
+    }
+    for (int i = 0; i < num_spot_lights; i++) {
+        float visibility = spotVisibility(i, normal, fragPos);
+        result += calcSpotLight(spot_lights[i], surface, normal, fragPos, viewDir, visibility);
+    }
+    return result;
+}
+
+// Sum of every light's contribution at a fragment with the given world-space normal and
+// position, using the material's maps at `texCoord`.
+vec3 calcLighting(vec3 normal, vec3 fragPos, vec2 texCoord) {
+    return shadeSurface(materialSurface(texCoord), normal, fragPos);
+}
@@ -0,0 +1,246 @@
+// Lights shared by every lighting model. The light structs and the `Lights` block mirror
+// `glutils::lighting`, so members need to stay in the same order.
+//
+// Defining `SHADOWS` before including this file looks up the shadow maps of
+// `glutils::shadow::ShadowMaps`, whose `Shadows` block mirrors `glutils::shadow::Shadows`.
+
+#define MAX_DIRECTIONAL_LIGHTS 4
+#define MAX_POINT_LIGHTS 8
+#define MAX_SPOT_LIGHTS 4
+
+struct DirectionalLight {
+    vec3 direction;
+    vec3 ambient;
+    vec3 diffuse;
+    vec3 specular;
+};
+
+struct PointLight {
+    vec3 position;
+    vec3 ambient;
+    vec3 diffuse;
+    vec3 specular;
+    float constant;
+    float linear;
+    float quadratic;
+};
+
+struct SpotLight {
+    vec3 position;
+    vec3 direction;
+    vec3 ambient;
+    vec3 diffuse;
+    vec3 specular;
+    // Cosines of the angles at which the light starts fading out and is fully off.
+    float inner_cutoff;
+    float outer_cutoff;
+    float constant;
+    float linear;
+    float quadratic;
+};
+
+layout(std140) uniform Lights {
+    DirectionalLight directional_lights[MAX_DIRECTIONAL_LIGHTS];
+    PointLight point_lights[MAX_POINT_LIGHTS];
+    SpotLight spot_lights[MAX_SPOT_LIGHTS];
+    int num_directional_lights;
+    int num_point_lights;
+    int num_spot_lights;
+    // Index of the light of each kind that has a shadow map, or -1.
+    int directional_shadow;
+    int spot_shadow;
+    int point_shadow;
+    bool blinn_phong;
+    vec3 view_position;
+};
+
+#ifdef SHADOWS
+#define MAX_CASCADES 4
+
+layout(std140) uniform Shadows {
+    mat4 cascade_matrices[MAX_CASCADES];
+    // Distance along `camera_forward` at which each cascade ends.
+    vec4 cascade_splits;
+    // World space size of a texel of each cascade.
+    vec4 cascade_texel_sizes;
+    mat4 spot_matrix;
+    vec3 camera_forward;
+    int num_cascades;
+    // Spot and point maps store the distance to the light divided by these.
+    float spot_far;
+    float point_far;
+    // Size of a spot or point map texel per unit of distance from the light.
+    float spot_texel_scale;
+    float point_texel_scale;
+    float bias;
+    float normal_bias;
+    int pcf_radius;
+    bool show_cascades;
+};
+
+uniform sampler2DArrayShadow directionalShadowMap;
+uniform sampler2DShadow spotShadowMap;
+uniform samplerCubeShadow pointShadowMap;
+
+// Moves a surface point towards the light and along its normal by `texelSize` sized steps, so
+// that it doesn't shadow itself.
+vec3 shadowLookupPosition(vec3 fragPos, vec3 normal, vec3 lightDir, float texelSize) {
+    return fragPos + lightDir * bias + normal * normal_bias * texelSize;
+}
+
+// Cascade covering a fragment, or -1 past the last one.
+int cascadeIndex(vec3 fragPos) {
+    float depth = dot(fragPos - view_position, camera_forward);
+    for (int i = 0; i < num_cascades; i++) {
+        if (depth < cascade_splits[i]) {
+            return i;
+        }
+    }
+    return -1;
+}
+
+// Fraction of the directional light's shadow map taps around a fragment that are lit.
+float directionalShadow(vec3 normal, vec3 fragPos, vec3 lightDir) {
+    int cascade = cascadeIndex(fragPos);
+    if (cascade < 0) {
+        return 1.0;
+    }
+    vec3 position =
+        shadowLookupPosition(fragPos, normal, lightDir, cascade_texel_sizes[cascade]);
+    vec4 lightSpace = cascade_matrices[cascade] * vec4(position, 1.0);
+    vec3 coords = lightSpace.xyz / lightSpace.w * 0.5 + 0.5;
+    if (coords.z > 1.0) {
+        return 1.0;
+    }
+
+    vec2 texel = 1.0 / vec2(textureSize(directionalShadowMap, 0).xy);
+    float lit = 0.0;
+    for (int x = -pcf_radius; x <= pcf_radius; x++) {
+        for (int y = -pcf_radius; y <= pcf_radius; y++) {
+            vec2 uv = coords.xy + vec2(x, y) * texel;
+            lit += texture(directionalShadowMap, vec4(uv, float(cascade), coords.z));
+        }
+    }
+    float taps = float(2 * pcf_radius + 1);
+    return lit / (taps * taps);
+}
+
+float spotShadow(SpotLight light, vec3 normal, vec3 fragPos, vec3 lightDir) {
+    float dist = length(light.position - fragPos);
+    vec3 position = shadowLookupPosition(fragPos, normal, lightDir, dist * spot_texel_scale);
+    vec4 lightSpace = spot_matrix * vec4(position, 1.0);
+    vec2 uv = lightSpace.xy / lightSpace.w * 0.5 + 0.5;
+    float ref = length(position - light.position) / spot_far;
+    if (lightSpace.w <= 0.0 || ref > 1.0) {
+        return 1.0;
+    }
+
+    vec2 texel = 1.0 / vec2(textureSize(spotShadowMap, 0));
+    float lit = 0.0;
+    for (int x = -pcf_radius; x <= pcf_radius; x++) {
+        for (int y = -pcf_radius; y <= pcf_radius; y++) {
+            lit += texture(spotShadowMap, vec3(uv + vec2(x, y) * texel, ref));
+        }
+    }
+    float taps = float(2 * pcf_radius + 1);
+    return lit / (taps * taps);
+}
+
+// Offsets towards the corners and edge midpoints of a cube, spread around the looked up
+// direction for soft point light shadows.
+const vec3 POINT_PCF_OFFSETS[20] = vec3[](
+    vec3(1, 1, 1), vec3(1, -1, 1), vec3(-1, -1, 1), vec3(-1, 1, 1),
+    vec3(1, 1, -1), vec3(1, -1, -1), vec3(-1, -1, -1), vec3(-1, 1, -1),
+    vec3(1, 1, 0), vec3(1, -1, 0), vec3(-1, -1, 0), vec3(-1, 1, 0),
+    vec3(1, 0, 1), vec3(-1, 0, 1), vec3(1, 0, -1), vec3(-1, 0, -1),
+    vec3(0, 1, 1), vec3(0, -1, 1), vec3(0, -1, -1), vec3(0, 1, -1)
+);
+
+float pointShadow(PointLight light, vec3 normal, vec3 fragPos, vec3 lightDir) {
+    float dist = length(light.position - fragPos);
+    float texelSize = dist * point_texel_scale;
+    vec3 position = shadowLookupPosition(fragPos, normal, lightDir, texelSize);
+    vec3 direction = position - light.position;
+    float ref = length(direction) / point_far;
+    if (ref > 1.0) {
+        return 1.0;
+    }
+    if (pcf_radius == 0) {
+        return texture(pointShadowMap, vec4(direction, ref));
+    }
+
+    float lit = 0.0;
+    for (int i = 0; i < 20; i++) {
+        vec3 offset = POINT_PCF_OFFSETS[i] * texelSize * float(pcf_radius);
+        lit += texture(pointShadowMap, vec4(direction + offset, ref));
+    }
+    return lit / 20.0;
+}
+
+// Colors blended into what the directional light lights to tell the cascades apart.
+const vec3 CASCADE_TINTS[MAX_CASCADES] = vec3[](
+    vec3(1.0, 0.4, 0.4),
+    vec3(0.4, 1.0, 0.4),
+    vec3(0.4, 0.4, 1.0),
+    vec3(1.0, 1.0, 0.4)
+);
+#endif
+
+float attenuation(float constant, float linear, float quadratic, float dist) {
+    return 1.0 / (constant + linear * dist + quadratic * dist * dist);
+}
+
+// How much of a spot light's cone a fragment lit from `lightDir` is in, fading between the
+// cutoffs.
+float spotIntensity(SpotLight light, vec3 lightDir) {
+    float theta = dot(lightDir, normalize(-light.direction));
+    return clamp(
+        (theta - light.outer_cutoff) / (light.inner_cutoff - light.outer_cutoff),
+        0.0,
+        1.0
+    );
+}
+
+// Fraction of each light that isn't blocked by shadow casters at a fragment, always 1 unless
+// `SHADOWS` is defined and the light has a shadow map.
+float directionalVisibility(int i, vec3 normal, vec3 fragPos) {
+#ifdef SHADOWS
+    if (i == directional_shadow) {
+        vec3 lightDir = normalize(-directional_lights[i].direction);
+        return directionalShadow(normal, fragPos, lightDir);
+    }
+#endif
+    return 1.0;
+}
+
+float pointVisibility(int i, vec3 normal, vec3 fragPos) {
+#ifdef SHADOWS
+    if (i == point_shadow) {
+        vec3 lightDir = normalize(point_lights[i].position - fragPos);
+        return pointShadow(point_lights[i], normal, fragPos, lightDir);
+    }
+#endif
+    return 1.0;
+}
+
+float spotVisibility(int i, vec3 normal, vec3 fragPos) {
+#ifdef SHADOWS
+    if (i == spot_shadow) {
+        vec3 lightDir = normalize(spot_lights[i].position - fragPos);
+        return spotShadow(spot_lights[i], normal, fragPos, lightDir);
+    }
+#endif
+    return 1.0;
+}
+
+// Color to multiply directional light `i` by at a fragment, which tells the cascades apart
+// when they're shown.
+vec3 cascadeTint(int i, vec3 fragPos) {
+#ifdef SHADOWS
+    int cascade = cascadeIndex(fragPos);
+    if (show_cascades && i == directional_shadow && cascade >= 0) {
+        return CASCADE_TINTS[cascade];
+    }
+#endif
+    return vec3(1.0);
+}
@@ -0,0 +1,12 @@
+// Derives what a program needs from the material feature defines it was compiled with, see
+// `lighting::MaterialFeatures`. Included by `lighting.glsl`, and by vertex shaders that need to
+// know whether to pass tangents on.
+
+#if defined(PARALLAX_MAPPING) || defined(STEEP_PARALLAX_MAPPING) || defined(PARALLAX_OCCLUSION_MAPPING)
+#define PARALLAX
+#endif
+
+// Either map is sampled in tangent space, so the mesh's tangents are needed.
+#if defined(NORMAL_MAPPING) || defined(PARALLAX)
+#define TANGENT_SPACE
+#endif
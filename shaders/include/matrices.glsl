@@ -0,0 +1,11 @@
+// Camera matrices shared by every program, see `uniform_buffer::Matrices`.
+layout(std140) uniform Matrices {
+    mat4 projection;
+    mat4 view;
+};
+
+// World space position of the camera, recovered from the rotation and translation of the view
+// matrix.
+vec3 cameraPosition() {
+    return -transpose(mat3(view)) * view[3].xyz;
+}
@@ -0,0 +1,171 @@
+// Cook-Torrance lighting of metallic-roughness materials, see `glutils::pbr::PbrMaterial`. The
+// lights are those of `lights.glsl`, with their diffuse color as the radiance they emit. Their
+// ambient color is ignored.
+//
+// Defining `IBL` before including this file lights surfaces with the environment maps of
+// `glutils::pbr::ibl::Ibl` too, and with a faint constant ambient otherwise. Defining `SHADOWS`
+// shadows the lights, see `lights.glsl`.
+#include "lights.glsl"
+
+const float PI = 3.14159265359;
+
+// Albedo is stored in sRGB like most color maps, the other maps are linear. The normal map is
+// in tangent space.
+struct PbrMaterial {
+    sampler2D albedo;
+    sampler2D normal;
+    sampler2D metallic;
+    sampler2D roughness;
+    sampler2D ao;
+};
+
+uniform PbrMaterial material;
+
+#ifdef IBL
+uniform samplerCube irradianceMap;
+uniform samplerCube prefilteredMap;
+uniform sampler2D brdfLut;
+// Mip level of `prefilteredMap` that was prefiltered for a roughness of 1.
+uniform float maxReflectionLod;
+#endif
+
+// Material properties at the fragment being shaded, in linear color.
+struct PbrSurface {
+    vec3 albedo;
+    float metallic;
+    float roughness;
+    float ao;
+};
+
+// The material's maps at `texCoord`.
+PbrSurface materialSurface(vec2 texCoord) {
+    return PbrSurface(
+        pow(texture(material.albedo, texCoord).rgb, vec3(2.2)),
+        texture(material.metallic, texCoord).r,
+        texture(material.roughness, texCoord).r,
+        texture(material.ao, texCoord).r
+    );
+}
+
+// World space normal from the material's normal map, given the interpolated vertex normal and
+// tangent. The tangent's w is the handedness of the bitangent.
+vec3 materialNormal(vec3 normal, vec4 tangent, vec2 texCoord) {
+    vec3 n = normalize(normal);
+    vec3 t = normalize(tangent.xyz - dot(tangent.xyz, n) * n);
+    vec3 b = cross(n, t) * tangent.w;
+    vec3 mapped = texture(material.normal, texCoord).xyz * 2.0 - 1.0;
+    return normalize(mat3(t, b, n) * mapped);
+}
+
+// Trowbridge-Reitz GGX: how many microfacets are aligned with `halfway`.
+float distributionGGX(vec3 normal, vec3 halfway, float roughness) {
+    float a = roughness * roughness;
+    float a2 = a * a;
+    float nDotH = max(dot(normal, halfway), 0.0);
+    float denom = nDotH * nDotH * (a2 - 1.0) + 1.0;
+    return a2 / (PI * denom * denom);
+}
+
+// Schlick-GGX: how much of the surface isn't hidden by other microfacets in one direction.
+float geometrySchlickGGX(float nDotV, float k) {
+    return nDotV / (nDotV * (1.0 - k) + k);
+}
+
+// Smith's method, combining shadowing towards the light and masking towards the viewer. `k`
+// is remapped differently for direct lighting than for IBL.
+float geometrySmith(float nDotV, float nDotL, float k) {
+    return geometrySchlickGGX(nDotV, k) * geometrySchlickGGX(nDotL, k);
+}
+
+// Fraction of light reflected rather than refracted, given the reflectance `f0` head-on.
+vec3 fresnelSchlick(float cosTheta, vec3 f0) {
+    return f0 + (1.0 - f0) * pow(clamp(1.0 - cosTheta, 0.0, 1.0), 5.0);
+}
+
+// Like `fresnelSchlick`, but rough surfaces reflect less at grazing angles. Used for ambient
+// light, which has no single halfway vector.
+vec3 fresnelSchlickRoughness(float cosTheta, vec3 f0, float roughness) {
+    return f0 + (max(vec3(1.0 - roughness), f0) - f0)
+        * pow(clamp(1.0 - cosTheta, 0.0, 1.0), 5.0);
+}
+
+// Reflectance of a surface head-on. Dielectrics all reflect roughly 4%, metals tint their
+// reflections with their albedo.
+vec3 baseReflectivity(PbrSurface surface) {
+    return mix(vec3(0.04), surface.albedo, surface.metallic);
+}
+
+// Light leaving the surface towards `viewDir` from `radiance` arriving from `lightDir`.
+vec3 cookTorrance(
+    PbrSurface surface,
+    vec3 normal,
+    vec3 viewDir,
+    vec3 lightDir,
+    vec3 radiance
+) {
+    vec3 halfway = normalize(viewDir + lightDir);
+    float nDotV = max(dot(normal, viewDir), 0.0);
+    float nDotL = max(dot(normal, lightDir), 0.0);
+    float r = surface.roughness + 1.0;
+
+    float ndf = distributionGGX(normal, halfway, surface.roughness);
+    float g = geometrySmith(nDotV, nDotL, r * r / 8.0);
+    vec3 f = fresnelSchlick(max(dot(halfway, viewDir), 0.0), baseReflectivity(surface));
+
+    vec3 specular = ndf * g * f / (4.0 * nDotV * nDotL + 0.0001);
+    // Whatever isn't reflected is refracted and scattered back out diffusely, except by metals
+    // which absorb it.
+    vec3 kD = (vec3(1.0) - f) * (1.0 - surface.metallic);
+    return (kD * surface.albedo / PI + specular) * radiance * nDotL;
+}
+
+// Light reflected from the surroundings rather than from any one light.
+vec3 ambientLight(PbrSurface surface, vec3 normal, vec3 viewDir) {
+#ifdef IBL
+    float nDotV = max(dot(normal, viewDir), 0.0);
+    vec3 f = fresnelSchlickRoughness(nDotV, baseReflectivity(surface), surface.roughness);
+    vec3 kD = (vec3(1.0) - f) * (1.0 - surface.metallic);
+    vec3 diffuse = texture(irradianceMap, normal).rgb * surface.albedo;
+
+    vec3 reflected = reflect(-viewDir, normal);
+    vec3 prefiltered =
+        textureLod(prefilteredMap, reflected, surface.roughness * maxReflectionLod).rgb;
+    vec2 brdf = texture(brdfLut, vec2(nDotV, surface.roughness)).rg;
+    vec3 specular = prefiltered * (f * brdf.x + brdf.y);
+    return (kD * diffuse + specular) * surface.ao;
+#else
+    return vec3(0.03) * surface.albedo * surface.ao;
+#endif
+}
+
+// Sum of every light's contribution at a fragment with the given world-space normal and
+// position, plus ambient light.
+vec3 shadePbrSurface(PbrSurface surface, vec3 normal, vec3 fragPos) {
+    vec3 viewDir = normalize(view_position - fragPos);
+
+    vec3 result = ambientLight(surface, normal, viewDir);
+    for (int i = 0; i < num_directional_lights; i++) {
+        DirectionalLight light = directional_lights[i];
+        vec3 radiance = light.diffuse * directionalVisibility(i, normal, fragPos);
+        result += cascadeTint(i, fragPos)
+            * cookTorrance(surface, normal, viewDir, normalize(-light.direction), radiance);
+    }
+    for (int i = 0; i < num_point_lights; i++) {
+        PointLight light = point_lights[i];
+        float dist = length(light.position - fragPos);
+        float att = attenuation(light.constant, light.linear, light.quadratic, dist);
+        vec3 radiance = light.diffuse * att * pointVisibility(i, normal, fragPos);
+        vec3 lightDir = normalize(light.position - fragPos);
+        result += cookTorrance(surface, normal, viewDir, lightDir, radiance);
+    }
+    for (int i = 0; i < num_spot_lights; i++) {
+        SpotLight light = spot_lights[i];
+        float dist = length(light.position - fragPos);
+        float att = attenuation(light.constant, light.linear, light.quadratic, dist);
+        vec3 lightDir = normalize(light.position - fragPos);
+        vec3 radiance = light.diffuse * att * spotIntensity(light, lightDir)
+            * spotVisibility(i, normal, fragPos);
+        result += cookTorrance(surface, normal, viewDir, lightDir, radiance);
+    }
+    return result;
+}
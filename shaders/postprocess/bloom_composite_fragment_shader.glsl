@@ -0,0 +1,13 @@
+#version 330 core
+in vec2 vTexCoord;
+out vec4 FragColor;
+
+uniform sampler2D screenTexture;
+uniform sampler2D bloomTexture;
+uniform float intensity;
+
+void main() {
+    vec3 scene = texture(screenTexture, vTexCoord).rgb;
+    vec3 bloom = texture(bloomTexture, vTexCoord).rgb;
+    FragColor = vec4(scene + bloom * intensity, 1.0);
+}
@@ -0,0 +1,25 @@
+#version 330 core
+in vec2 vTexCoord;
+out vec4 FragColor;
+
+uniform sampler2D screenTexture;
+uniform vec2 texelSize;
+uniform float threshold;
+// Width of the band below the threshold over which bloom fades in rather than switching on.
+uniform float knee;
+
+void main() {
+    // Averaging a 4x4 block while halving the resolution keeps single bright pixels from
+    // flickering in and out of the bloom as things move.
+    vec3 color = 0.25 * (
+        texture(screenTexture, vTexCoord + texelSize * vec2(-1.0, -1.0)).rgb +
+        texture(screenTexture, vTexCoord + texelSize * vec2(1.0, -1.0)).rgb +
+        texture(screenTexture, vTexCoord + texelSize * vec2(-1.0, 1.0)).rgb +
+        texture(screenTexture, vTexCoord + texelSize * vec2(1.0, 1.0)).rgb);
+
+    float brightness = max(color.r, max(color.g, color.b));
+    float soft = clamp(brightness - threshold + knee, 0.0, 2.0 * knee);
+    soft = soft * soft / (4.0 * knee + 1e-5);
+    float contribution = max(soft, brightness - threshold) / max(brightness, 1e-5);
+    FragColor = vec4(color * contribution, 1.0);
+}
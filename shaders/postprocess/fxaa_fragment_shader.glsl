@@ -0,0 +1,50 @@
+#version 330 core
+in vec2 vTexCoord;
+out vec4 FragColor;
+
+uniform sampler2D screenTexture;
+uniform vec2 texelSize;
+
+const float SPAN_MAX = 8.0;
+const float REDUCE_MUL = 1.0 / 8.0;
+const float REDUCE_MIN = 1.0 / 128.0;
+const vec3 LUMA = vec3(0.299, 0.587, 0.114);
+
+void main() {
+    vec3 rgbNW = texture(screenTexture, vTexCoord + vec2(-1.0, -1.0) * texelSize).rgb;
+    vec3 rgbNE = texture(screenTexture, vTexCoord + vec2(1.0, -1.0) * texelSize).rgb;
+    vec3 rgbSW = texture(screenTexture, vTexCoord + vec2(-1.0, 1.0) * texelSize).rgb;
+    vec3 rgbSE = texture(screenTexture, vTexCoord + vec2(1.0, 1.0) * texelSize).rgb;
+    vec3 rgbM = texture(screenTexture, vTexCoord).rgb;
+
+    float lumaNW = dot(rgbNW, LUMA);
+    float lumaNE = dot(rgbNE, LUMA);
+    float lumaSW = dot(rgbSW, LUMA);
+    float lumaSE = dot(rgbSE, LUMA);
+    float lumaM = dot(rgbM, LUMA);
+
+    float lumaMin = min(lumaM, min(min(lumaNW, lumaNE), min(lumaSW, lumaSE)));
+    float lumaMax = max(lumaM, max(max(lumaNW, lumaNE), max(lumaSW, lumaSE)));
+
+    // Blur along the edge, i.e. perpendicular to the luminance gradient.
+    vec2 dir = vec2(
+        -((lumaNW + lumaNE) - (lumaSW + lumaSE)),
+        (lumaNW + lumaSW) - (lumaNE + lumaSE)
+    );
+
+    float dirReduce = max((lumaNW + lumaNE + lumaSW + lumaSE) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    float rcpDirMin = 1.0 / (min(abs(dir.x), abs(dir.y)) + dirReduce);
+    dir = clamp(dir * rcpDirMin, vec2(-SPAN_MAX), vec2(SPAN_MAX)) * texelSize;
+
+    vec3 rgbA = 0.5 * (
+        texture(screenTexture, vTexCoord + dir * (1.0 / 3.0 - 0.5)).rgb +
+        texture(screenTexture, vTexCoord + dir * (2.0 / 3.0 - 0.5)).rgb
+    );
+    vec3 rgbB = rgbA * 0.5 + 0.25 * (
+        texture(screenTexture, vTexCoord + dir * -0.5).rgb +
+        texture(screenTexture, vTexCoord + dir * 0.5).rgb
+    );
+
+    float lumaB = dot(rgbB, LUMA);
+    FragColor = vec4((lumaB < lumaMin || lumaB > lumaMax) ? rgbA : rgbB, 1.0);
+}
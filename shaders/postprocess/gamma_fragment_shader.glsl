@@ -0,0 +1,11 @@
+#version 330 core
+in vec2 vTexCoord;
+out vec4 FragColor;
+
+uniform sampler2D screenTexture;
+uniform float gamma;
+
+void main() {
+    vec3 color = texture(screenTexture, vTexCoord).rgb;
+    FragColor = vec4(pow(color, vec3(1.0 / gamma)), 1.0);
+}
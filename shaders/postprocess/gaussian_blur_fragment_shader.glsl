@@ -0,0 +1,21 @@
+#version 330 core
+in vec2 vTexCoord;
+out vec4 FragColor;
+
+uniform sampler2D image;
+// One texel along the axis being blurred.
+uniform vec2 direction;
+
+// A 9 tap Gaussian folded into 5 fetches by sampling between texels and letting bilinear
+// filtering weigh the pairs.
+const float OFFSETS[3] = float[](0.0, 1.3846153846, 3.2307692308);
+const float WEIGHTS[3] = float[](0.2270270270, 0.3162162162, 0.0702702703);
+
+void main() {
+    vec3 result = texture(image, vTexCoord).rgb * WEIGHTS[0];
+    for (int i = 1; i < 3; i++) {
+        result += texture(image, vTexCoord + direction * OFFSETS[i]).rgb * WEIGHTS[i];
+        result += texture(image, vTexCoord - direction * OFFSETS[i]).rgb * WEIGHTS[i];
+    }
+    FragColor = vec4(result, 1.0);
+}
@@ -0,0 +1,12 @@
+#version 330 core
+in vec2 vTexCoord;
+out vec4 FragColor;
+
+uniform sampler2D screenTexture;
+
+void main() {
+    vec3 color = texture(screenTexture, vTexCoord).rgb;
+    // Weighted towards green since that's what the eye is most sensitive to.
+    float luminance = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    FragColor = vec4(vec3(luminance), 1.0);
+}
@@ -0,0 +1,9 @@
+#version 330 core
+in vec2 vTexCoord;
+out vec4 FragColor;
+
+uniform sampler2D screenTexture;
+
+void main() {
+    FragColor = vec4(vec3(1.0 - texture(screenTexture, vTexCoord).rgb), 1.0);
+}
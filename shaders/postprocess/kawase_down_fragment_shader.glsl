@@ -0,0 +1,16 @@
+#version 330 core
+in vec2 vTexCoord;
+out vec4 FragColor;
+
+uniform sampler2D image;
+// Half a texel of the image being downsampled.
+uniform vec2 halfTexel;
+
+void main() {
+    vec3 sum = texture(image, vTexCoord).rgb * 4.0;
+    sum += texture(image, vTexCoord - halfTexel).rgb;
+    sum += texture(image, vTexCoord + halfTexel).rgb;
+    sum += texture(image, vTexCoord + vec2(halfTexel.x, -halfTexel.y)).rgb;
+    sum += texture(image, vTexCoord - vec2(halfTexel.x, -halfTexel.y)).rgb;
+    FragColor = vec4(sum / 8.0, 1.0);
+}
@@ -0,0 +1,20 @@
+#version 330 core
+in vec2 vTexCoord;
+out vec4 FragColor;
+
+uniform sampler2D image;
+// Half a texel of the image being upsampled.
+uniform vec2 halfTexel;
+
+void main() {
+    vec2 h = halfTexel;
+    vec3 sum = texture(image, vTexCoord + vec2(-2.0 * h.x, 0.0)).rgb;
+    sum += texture(image, vTexCoord + vec2(2.0 * h.x, 0.0)).rgb;
+    sum += texture(image, vTexCoord + vec2(0.0, -2.0 * h.y)).rgb;
+    sum += texture(image, vTexCoord + vec2(0.0, 2.0 * h.y)).rgb;
+    sum += texture(image, vTexCoord + vec2(-h.x, h.y)).rgb * 2.0;
+    sum += texture(image, vTexCoord + vec2(h.x, h.y)).rgb * 2.0;
+    sum += texture(image, vTexCoord + vec2(h.x, -h.y)).rgb * 2.0;
+    sum += texture(image, vTexCoord + vec2(-h.x, -h.y)).rgb * 2.0;
+    FragColor = vec4(sum / 12.0, 1.0);
+}
@@ -0,0 +1,18 @@
+#version 330 core
+in vec2 vTexCoord;
+out vec4 FragColor;
+
+uniform sampler2D screenTexture;
+uniform vec2 texelSize;
+uniform float kernel[9];
+
+void main() {
+    vec3 color = vec3(0.0);
+    for (int y = 0; y < 3; y++) {
+        for (int x = 0; x < 3; x++) {
+            vec2 offset = vec2(x - 1, 1 - y) * texelSize;
+            color += texture(screenTexture, vTexCoord + offset).rgb * kernel[y * 3 + x];
+        }
+    }
+    FragColor = vec4(color, 1.0);
+}
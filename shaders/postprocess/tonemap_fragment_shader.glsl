@@ -0,0 +1,44 @@
+#version 330 core
+in vec2 vTexCoord;
+out vec4 FragColor;
+
+uniform sampler2D screenTexture;
+uniform float exposure;
+// Index of `postprocess::effect::Tonemapper`.
+uniform int tonemapper;
+
+vec3 aces(vec3 x) {
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), 0.0, 1.0);
+}
+
+vec3 uncharted2Curve(vec3 x) {
+    const float A = 0.15; // Shoulder strength.
+    const float B = 0.50; // Linear strength.
+    const float C = 0.10; // Linear angle.
+    const float D = 0.20; // Toe strength.
+    const float E = 0.02; // Toe numerator.
+    const float F = 0.30; // Toe denominator.
+    return ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F;
+}
+
+vec3 uncharted2(vec3 x) {
+    // Scene value that maps to white.
+    const float WHITE = 11.2;
+    // The curve is fitted to exposures about twice as bright as the others.
+    return uncharted2Curve(2.0 * x) / uncharted2Curve(vec3(WHITE));
+}
+
+void main() {
+    vec3 hdr = texture(screenTexture, vTexCoord).rgb * exposure;
+    vec3 mapped;
+    if (tonemapper == 1) {
+        mapped = hdr / (hdr + vec3(1.0));
+    } else if (tonemapper == 2) {
+        mapped = aces(hdr);
+    } else if (tonemapper == 3) {
+        mapped = uncharted2(hdr);
+    } else {
+        mapped = vec3(1.0) - exp(-hdr);
+    }
+    FragColor = vec4(mapped, 1.0);
+}
@@ -0,0 +1,5 @@
+#version 330 core
+
+// Only the depth buffer is written, which happens without the shader's help.
+void main() {
+}
@@ -0,0 +1,14 @@
+#version 330 core
+in vec3 aPos;
+
+out vec3 vWorldPos;
+
+uniform mat4 model;
+// Projection and view of the light the shadow map is rendered from.
+uniform mat4 lightSpace;
+
+void main() {
+    vec4 worldPos = model * vec4(aPos, 1.0);
+    vWorldPos = worldPos.xyz;
+    gl_Position = lightSpace * worldPos;
+}
@@ -0,0 +1,11 @@
+#version 330 core
+in vec3 vWorldPos;
+
+uniform vec3 lightPosition;
+uniform float farPlane;
+
+// Stores the distance to the light rather than the projected depth, so that cube map faces
+// agree with each other and lookups only need the direction and distance to the light.
+void main() {
+    gl_FragDepth = length(vWorldPos - lightPosition) / farPlane;
+}
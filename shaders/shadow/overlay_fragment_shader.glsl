@@ -0,0 +1,35 @@
+#version 330 core
+in vec2 vTexCoord;
+
+out vec4 FragColor;
+
+uniform sampler2DArray cascades;
+uniform sampler2D spotMap;
+uniform samplerCube pointMap;
+// 0 shows a cascade, 1 the spot light's map and 2 a face of the point light's cube map.
+uniform int mode;
+// Cascade or cube map face, in `CubeFace` order.
+uniform int layer;
+
+// Same as `CubeFace::direction`, with `t` growing downwards through the face.
+vec3 faceDirection(int face, float s, float t) {
+    if (face == 0) return vec3(1.0, -t, -s);
+    if (face == 1) return vec3(-1.0, -t, s);
+    if (face == 2) return vec3(s, 1.0, t);
+    if (face == 3) return vec3(s, -1.0, -t);
+    if (face == 4) return vec3(s, -t, 1.0);
+    return vec3(-s, -t, -1.0);
+}
+
+void main() {
+    float depth;
+    if (mode == 0) {
+        depth = texture(cascades, vec3(vTexCoord, float(layer))).r;
+    } else if (mode == 1) {
+        depth = texture(spotMap, vTexCoord).r;
+    } else {
+        vec2 st = vTexCoord * 2.0 - 1.0;
+        depth = texture(pointMap, faceDirection(layer, st.x, -st.y)).r;
+    }
+    FragColor = vec4(vec3(depth), 1.0);
+}
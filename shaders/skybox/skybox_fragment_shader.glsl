@@ -0,0 +1,10 @@
+#version 330 core
+in vec3 vDirection;
+
+out vec4 FragColor;
+
+uniform samplerCube skybox;
+
+void main() {
+    FragColor = texture(skybox, vDirection);
+}
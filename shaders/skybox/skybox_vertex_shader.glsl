@@ -0,0 +1,14 @@
+#version 330 core
+#include "../include/matrices.glsl"
+
+in vec3 aPos;
+
+out vec3 vDirection;
+
+void main() {
+    vDirection = aPos;
+    // Dropping the translation keeps the sky centered on the camera.
+    vec4 position = projection * mat4(mat3(view)) * vec4(aPos, 1.0);
+    // A depth of w ends up as 1.0 after the perspective divide, the far plane.
+    gl_Position = position.xyww;
+}
@@ -0,0 +1,11 @@
+#version 330 core
+in vec2 vTexCoord;
+in vec4 vColor;
+out vec4 FragColor;
+
+// White glyphs with their coverage in alpha.
+uniform sampler2D atlas;
+
+void main() {
+    FragColor = vec4(vColor.rgb, vColor.a * texture(atlas, vTexCoord).a);
+}
@@ -1,13 +1,18 @@
+use crate::{
+    cli::Args,
+    harness::{self, Harness},
+};
 use anyhow::{format_err, Result};
 use glfw::{
     self, Context, GlfwReceiver, Key, OpenGlProfileHint, PWindow, WindowEvent, WindowHint,
     WindowMode,
 };
 
-pub fn run() -> Result<()> {
+pub fn run(args: &Args) -> Result<()> {
     let mut glfw_obj = glfw::init_no_callbacks()?;
     glfw_obj.window_hint(WindowHint::OpenGlProfile(OpenGlProfileHint::Core));
     glfw_obj.window_hint(WindowHint::ContextVersion(3, 3));
+    harness::window_hints(&mut glfw_obj, args);
 
     #[cfg(target_os = "macos")]
     glfw_obj.window_hint(WindowHint::OpenGlForwardCompat(true));
@@ -23,22 +28,30 @@ pub fn run() -> Result<()> {
     window.set_key_polling(true);
     window.set_framebuffer_size_polling(true);
 
+    let mut harness = Harness::new(args)?;
+
     while !window.should_close() {
-        handle_event(&mut window, &events_rx);
+        handle_event(&mut window, &events_rx, &mut harness);
         unsafe {
             gl::ClearColor(0.2, 0.2, 0.2, 0.0);
             gl::Clear(gl::COLOR_BUFFER_BIT);
         }
+        harness.end_frame(&mut window)?;
         window.swap_buffers();
         glfw_obj.poll_events();
     }
-    Ok(())
+    harness.finish()
 }
 
-fn handle_event(window: &mut PWindow, events_rx: &GlfwReceiver<(f64, WindowEvent)>) {
+fn handle_event(
+    window: &mut PWindow,
+    events_rx: &GlfwReceiver<(f64, WindowEvent)>,
+    harness: &mut Harness,
+) {
     let Some((_, event)) = events_rx.receive() else {
         return;
     };
+    harness.handle_event(&event);
 
     match event {
         WindowEvent::Key(Key::Escape, _, _, _) => window.set_should_close(true),
@@ -1,8 +1,17 @@
-use crate::glutils::{
-    model::{primitives::Primitive, usage::Usage, ModelBuilder, VertexAttribute},
-    shader::{program::Linker, Shader, ShaderType},
+use crate::{
+    cli::Args,
+    glutils::{
+        model::{primitives::Primitive, usage::Usage, ModelBuilder, VertexAttribute},
+        shader::{program::Linker, Shader, ShaderType},
+    },
+    harness::{
+        self,
+        params::{Handle, ParamsBuilder},
+        Harness,
+    },
 };
 use anyhow::{format_err, Result};
+use glam::{vec3, Vec3};
 use glfw::{
     Context, GlfwReceiver, Key, Modifiers, OpenGlProfileHint, PWindow, WindowEvent, WindowHint,
     WindowMode,
@@ -21,10 +30,16 @@ const INDICES: [u32; 6] = [
     1, 2, 3, // second triangle
 ];
 
-pub fn run() -> Result<()> {
+/// Declares the lesson's only parameter, the clear color.
+pub fn declare_params(params: &mut ParamsBuilder) -> Handle<Vec3> {
+    params.color("clear_color", vec3(0.2, 0.3, 0.3))
+}
+
+pub fn run(args: &Args) -> Result<()> {
     let mut glfw_obj = glfw::init_no_callbacks()?;
     glfw_obj.window_hint(WindowHint::OpenGlProfile(OpenGlProfileHint::Core));
     glfw_obj.window_hint(WindowHint::ContextVersion(3, 3));
+    harness::window_hints(&mut glfw_obj, args);
 
     #[cfg(target_os = "macos")]
     glfw_obj.window_hint(WindowHint::OpenGlForwardCompat(true));
@@ -40,6 +55,8 @@ pub fn run() -> Result<()> {
     window.set_key_polling(true);
     window.set_framebuffer_size_polling(true);
 
+    let mut harness = Harness::new(args)?;
+
     let shaders_dir = PathBuf::new().join("shaders").join("_1_getting_started");
 
     let vs_src = shaders_dir.join("_2_hello_square_vertex_shader.glsl");
@@ -61,26 +78,38 @@ pub fn run() -> Result<()> {
     model.use_program();
     model.bind();
 
+    let mut params = ParamsBuilder::new();
+    let clear_color = declare_params(&mut params);
+    let params = params.build(args)?;
+
     while !window.should_close() {
-        handle_event(&events_rx, &mut window);
+        handle_event(&events_rx, &mut window, &mut harness);
 
         unsafe {
-            gl::ClearColor(0.2, 0.3, 0.3, 0.0);
+            let [r, g, b] = params.get(clear_color).to_array();
+            gl::ClearColor(r, g, b, 0.0);
             gl::Clear(gl::COLOR_BUFFER_BIT);
         }
         model.try_draw_arrays(Primitive::Triangles)?;
 
+        harness.end_frame(&mut window)?;
         window.swap_buffers();
         glfw_obj.poll_events();
     }
 
-    Ok(())
+    params.save()?;
+    harness.finish()
 }
 
-fn handle_event(events_rx: &GlfwReceiver<(f64, WindowEvent)>, window: &mut PWindow) {
+fn handle_event(
+    events_rx: &GlfwReceiver<(f64, WindowEvent)>,
+    window: &mut PWindow,
+    harness: &mut Harness,
+) {
     let Some((_, event)) = events_rx.receive() else {
         return;
     };
+    harness.handle_event(&event);
 
     match event {
         WindowEvent::Key(key, _, _, modifier) if modifier == Modifiers::Super && key == Key::W => {
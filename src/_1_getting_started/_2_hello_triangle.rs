@@ -1,4 +1,13 @@
+use crate::{
+    cli::Args,
+    harness::{
+        self,
+        params::{Handle, ParamsBuilder},
+        Harness,
+    },
+};
 use anyhow::{format_err, Result};
+use glam::{vec3, Vec3};
 use glfw::{
     Action, Context, GlfwReceiver, Key, OpenGlProfileHint, PWindow, WindowEvent, WindowHint,
     WindowMode,
@@ -26,10 +35,16 @@ void main()
 
 const TRIANGLE: [f32; 9] = [-0.5, -0.5, 0.0, 0.5, -0.5, 0.0, 0.0, 0.5, 0.0];
 
-pub fn run() -> Result<()> {
+/// Declares the clear color, the one thing here that isn't part of the raw GL walkthrough.
+pub fn declare_params(params: &mut ParamsBuilder) -> Handle<Vec3> {
+    params.color("clear_color", vec3(0.2, 0.3, 0.3))
+}
+
+pub fn run(args: &Args) -> Result<()> {
     let mut glfw_obj = glfw::init_no_callbacks()?;
     glfw_obj.window_hint(WindowHint::OpenGlProfile(OpenGlProfileHint::Core));
     glfw_obj.window_hint(WindowHint::ContextVersion(3, 3));
+    harness::window_hints(&mut glfw_obj, args);
 
     #[cfg(target_os = "macos")]
     glfw_obj.window_hint(WindowHint::OpenGlForwardCompat(true));
@@ -45,6 +60,8 @@ pub fn run() -> Result<()> {
     window.set_key_polling(true);
     window.set_framebuffer_size_polling(true);
 
+    let mut harness = Harness::new(args)?;
+
     let (vao, vbo) = unsafe {
         let mut vao = 0;
         let mut vbo = 0;
@@ -189,16 +206,22 @@ pub fn run() -> Result<()> {
         gl::BindVertexArray(vao);
     }
 
+    let mut params = ParamsBuilder::new();
+    let clear_color = declare_params(&mut params);
+    let params = params.build(args)?;
+
     while !window.should_close() {
-        handle_event(&events_rx, &mut window);
+        handle_event(&events_rx, &mut window, &mut harness);
 
         unsafe {
-            gl::ClearColor(0.2, 0.3, 0.3, 0.0);
+            let [r, g, b] = params.get(clear_color).to_array();
+            gl::ClearColor(r, g, b, 0.0);
             gl::Clear(gl::COLOR_BUFFER_BIT);
 
             gl::DrawArrays(gl::TRIANGLES, 0, 3)
         }
 
+        harness.end_frame(&mut window)?;
         window.swap_buffers();
         glfw_obj.poll_events();
     }
@@ -209,13 +232,19 @@ pub fn run() -> Result<()> {
         gl::DeleteProgram(shader_program);
     }
 
-    Ok(())
+    params.save()?;
+    harness.finish()
 }
 
-fn handle_event(rx: &GlfwReceiver<(f64, WindowEvent)>, window: &mut PWindow) {
+fn handle_event(
+    rx: &GlfwReceiver<(f64, WindowEvent)>,
+    window: &mut PWindow,
+    harness: &mut Harness,
+) {
     let Some((_, event)) = rx.receive() else {
         return;
     };
+    harness.handle_event(&event);
 
     match event {
         WindowEvent::Key(key, _, action, _) if key == Key::Escape && action == Action::Press => {
@@ -1,21 +1,51 @@
 use anyhow::{format_err, Result};
+use glam::{vec3, Vec3};
 use glfw::{
     self, Context, GlfwReceiver, Key, Modifiers, OpenGlProfileHint, PWindow, WindowEvent,
     WindowHint, WindowMode,
 };
 use std::path::PathBuf;
 
-use crate::glutils::{
-    model::{usage::Usage, ModelBuilder, VertexAttribute},
-    shader::{self, ShaderType},
+use crate::{
+    cli::Args,
+    glutils::{
+        gui::Gui,
+        model::{usage::Usage, ModelBuilder, VertexAttribute},
+        render_state::StateTracker,
+        shader::{self, ShaderType},
+    },
+    harness::{
+        self,
+        params::{Handle, ParamsBuilder},
+        Harness,
+    },
 };
 
 const TRIANGLE: [f32; 9] = [-0.5, -0.5, 0.0, 0.5, -0.5, 0.0, 0.0, 0.5, 0.0];
 
-pub fn run() -> Result<()> {
+/// Handles to the colors the GUI picks and the pulse that animates `our_color`.
+pub struct ShaderParams {
+    clear_color: Handle<Vec3>,
+    color: Handle<Vec3>,
+    pulse: Handle<bool>,
+    pulse_speed: Handle<f32>,
+}
+
+pub fn declare_params(params: &mut ParamsBuilder) -> ShaderParams {
+    ShaderParams {
+        clear_color: params.color("clear_color", vec3(0.2, 0.2, 0.2)),
+        color: params.color("our_color", vec3(0.0, 1.0, 0.0)),
+        // Pulses the green channel of `our_color` over time, as in the original lesson.
+        pulse: params.toggle("pulse", true),
+        pulse_speed: params.float("pulse_speed", 1.0, 0.0..=5.0),
+    }
+}
+
+pub fn run(args: &Args) -> Result<()> {
     let mut glfw_obj = glfw::init_no_callbacks()?;
     glfw_obj.window_hint(WindowHint::OpenGlProfile(OpenGlProfileHint::Core));
     glfw_obj.window_hint(WindowHint::ContextVersion(3, 3));
+    harness::window_hints(&mut glfw_obj, args);
 
     #[cfg(target_os = "macos")]
     glfw_obj.window_hint(WindowHint::OpenGlForwardCompat(true));
@@ -31,6 +61,10 @@ pub fn run() -> Result<()> {
     window.set_key_polling(true);
     window.set_framebuffer_size_polling(true);
 
+    let mut harness = Harness::new(args)?;
+    let mut state = StateTracker::new();
+    let mut gui = Gui::new(&mut window)?;
+
     let shaders_dir = PathBuf::new().join("shaders").join("_1_getting_started");
 
     let vs_src = shaders_dir.join("_3_shaders_vertex_shader.glsl");
@@ -53,41 +87,74 @@ pub fn run() -> Result<()> {
 
     let our_color = program.get_uniform_loc("ourColor")?;
 
-    program.use_program();
-    model.bind();
+    let mut params = ParamsBuilder::new();
+    let ShaderParams {
+        clear_color,
+        color,
+        pulse,
+        pulse_speed,
+    } = declare_params(&mut params);
+    let mut params = params.build(args)?;
 
     while !window.should_close() {
-        process_events(&events_rx, &mut window);
+        process_events(&events_rx, &mut window, &mut harness, &mut gui);
+
+        let time_value = glfw_obj.get_time();
+        gui.run(&window, time_value, |ctx| {
+            params.window(ctx, "Shaders");
+        })?;
+        let mut our_color_value = params.get(color);
+        if params.get(pulse) {
+            our_color_value.y =
+                ((time_value * f64::from(params.get(pulse_speed))).sin() / 2.0 + 0.5) as f32;
+        }
+
+        // The GUI binds its own program and vertex array.
+        program.use_program();
+        model.bind();
         unsafe {
-            gl::ClearColor(0.2, 0.2, 0.2, 0.0);
+            let [r, g, b] = params.get(clear_color).to_array();
+            gl::ClearColor(r, g, b, 0.0);
             gl::Clear(gl::COLOR_BUFFER_BIT);
 
-            let time_value = glfw_obj.get_time();
-            let green_value = time_value.sin() / 2.0 + 0.5;
-            gl::Uniform4f(our_color, 0.0, green_value as f32, 0.0, 1.0);
+            let [r, g, b] = our_color_value.to_array();
+            gl::Uniform4f(our_color, r, g, b, 1.0);
 
             gl::DrawArrays(gl::TRIANGLES, 0, 3);
         }
+        let (width, height) = window.get_framebuffer_size();
+        gui.draw(&mut state, width, height)?;
+
+        harness.end_frame(&mut window)?;
         window.swap_buffers();
         glfw_obj.poll_events();
     }
 
-    Ok(())
+    params.save()?;
+    harness.finish()
 }
 
-fn process_events(events_rx: &GlfwReceiver<(f64, WindowEvent)>, window: &mut PWindow) {
-    let Some((_, event)) = events_rx.receive() else {
-        return;
-    };
-
-    match event {
-        WindowEvent::Key(key, _, _, modifier) => match (modifier, key) {
-            (Modifiers::Super, Key::W) | (_, Key::Escape) => window.set_should_close(true),
+fn process_events(
+    events_rx: &GlfwReceiver<(f64, WindowEvent)>,
+    window: &mut PWindow,
+    harness: &mut Harness,
+    gui: &mut Gui,
+) {
+    for (_, event) in glfw::flush_messages(events_rx) {
+        harness.handle_event(&event);
+        gui.handle_event(&event);
+
+        match event {
+            WindowEvent::Key(key, _, _, modifier) if !gui.wants_keyboard() => {
+                match (modifier, key) {
+                    (Modifiers::Super, Key::W) | (_, Key::Escape) => window.set_should_close(true),
+                    _ => (),
+                }
+            }
+            WindowEvent::FramebufferSize(width, height) => unsafe {
+                gl::Viewport(0, 0, width, height);
+            },
             _ => (),
-        },
-        WindowEvent::FramebufferSize(width, height) => unsafe {
-            gl::Viewport(0, 0, width, height);
-        },
-        _ => (),
+        }
     }
 }
@@ -1,6 +1,10 @@
-use crate::glutils::{
-    model::{primitives::Primitive, usage::Usage, ModelBuilder, VertexAttribute},
-    shader::{program, Shader, ShaderType},
+use crate::{
+    cli::Args,
+    glutils::{
+        model::{primitives::Primitive, usage::Usage, ModelBuilder, VertexAttribute},
+        shader::{program, Shader, ShaderType},
+    },
+    harness::{self, Harness},
 };
 use anyhow::{format_err, Result};
 use glfw::{
@@ -13,10 +17,11 @@ const TRIANGLE_POS: [f32; 9] = [0.5, -0.5, 0.0, -0.5, -0.5, 0.0, 0.0, 0.5, 0.0];
 
 const TRIANGLE_COL: [f32; 9] = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
 
-pub fn run() -> Result<()> {
+pub fn run(args: &Args) -> Result<()> {
     let mut glfw_obj = glfw::init_no_callbacks()?;
     glfw_obj.window_hint(WindowHint::OpenGlProfile(OpenGlProfileHint::Core));
     glfw_obj.window_hint(WindowHint::ContextVersion(3, 3));
+    harness::window_hints(&mut glfw_obj, args);
 
     #[cfg(target_os = "macos")]
     glfw_obj.window_hint(WindowHint::OpenGlForwardCompat(true));
@@ -31,6 +36,8 @@ pub fn run() -> Result<()> {
     window.set_key_polling(true);
     window.set_framebuffer_size_polling(true);
 
+    let mut harness = Harness::new(args)?;
+
     let shader_path = PathBuf::new().join("shaders").join("_1_getting_started");
 
     let vertex_shader_src = shader_path.join("_3_shaders_triangle_vertex_shader.glsl");
@@ -61,14 +68,15 @@ pub fn run() -> Result<()> {
     model.bind();
 
     while !window.should_close() {
-        handle_events(&events_rx, &mut window);
+        handle_events(&events_rx, &mut window, &mut harness);
         clear_color(0.2, 0.2, 0.2, 0.0);
         model.try_draw_arrays(Primitive::Triangles)?;
+        harness.end_frame(&mut window)?;
         window.swap_buffers();
         glfw_obj.poll_events();
     }
 
-    Ok(())
+    harness.finish()
 }
 
 fn clear_color(r: f32, g: f32, b: f32, a: f32) {
@@ -82,16 +90,25 @@ unsafe fn clear_color_impl(r: f32, g: f32, b: f32, a: f32) {
     gl::Clear(gl::COLOR_BUFFER_BIT);
 }
 
-fn handle_events(events_rx: &GlfwReceiver<(f64, WindowEvent)>, window: &mut PWindow) {
+fn handle_events(
+    events_rx: &GlfwReceiver<(f64, WindowEvent)>,
+    window: &mut PWindow,
+    harness: &mut Harness,
+) {
     unsafe {
-        handle_events_impl(events_rx, window);
+        handle_events_impl(events_rx, window, harness);
     }
 }
 
-unsafe fn handle_events_impl(events_rx: &GlfwReceiver<(f64, WindowEvent)>, window: &mut PWindow) {
+unsafe fn handle_events_impl(
+    events_rx: &GlfwReceiver<(f64, WindowEvent)>,
+    window: &mut PWindow,
+    harness: &mut Harness,
+) {
     let Some((_, event)) = events_rx.receive() else {
         return;
     };
+    harness.handle_event(&event);
 
     match event {
         WindowEvent::Key(key, _, _, modifier) if modifier == Modifiers::Super && key == Key::W => {
@@ -0,0 +1,325 @@
+use crate::{
+    cli::Args,
+    glutils::{
+        gui::Gui,
+        lighting::{DirectionalLight, PhongMaterial, PointLight, Shading, SpotLight},
+        material::Material,
+        mesh::{shapes, MeshOptions},
+        model::{usage::Usage, Model},
+        render_state::{Clear, StateTracker},
+        scene::{camera::Camera, renderer::Renderer, transform::Transform, Drawable, Scene},
+        shader::{program::Linker, Shader, ShaderType},
+        texture::TextureBuilder,
+        uniform_buffer::{Matrices, UniformBuffer},
+    },
+    harness::{
+        self,
+        params::{Handle, ParamsBuilder},
+        Harness,
+    },
+};
+use anyhow::{format_err, Result};
+use glam::{vec3, Quat, Vec3};
+use glfw::{
+    Context, GlfwReceiver, Key, Modifiers, OpenGlProfileHint, PWindow, WindowEvent, WindowHint,
+    WindowMode,
+};
+use std::{f32::consts::PI, path::PathBuf};
+
+const CUBE_POSITIONS: [Vec3; 6] = [
+    vec3(0.0, 0.0, 0.0),
+    vec3(2.5, 0.5, -2.0),
+    vec3(-2.0, 0.0, -1.5),
+    vec3(-3.0, 1.0, 2.0),
+    vec3(2.0, 0.0, 2.5),
+    vec3(0.5, 1.5, -4.0),
+];
+
+const POINT_LIGHTS: [(Vec3, Vec3); 4] = [
+    (vec3(0.7, 0.8, 2.0), vec3(1.0, 0.6, 0.2)),
+    (vec3(2.3, 1.5, -4.0), vec3(0.2, 0.5, 1.0)),
+    (vec3(-4.0, 2.0, -1.0), vec3(0.3, 1.0, 0.3)),
+    (vec3(0.0, 0.5, -2.0), vec3(1.0, 1.0, 1.0)),
+];
+
+/// Handles to the light settings the GUI changes.
+pub struct LightParams {
+    sun_color: Handle<Vec3>,
+    point_light_range: Handle<f32>,
+    orbit_speed: Handle<f32>,
+    spot_light: Handle<bool>,
+    spot_color: Handle<Vec3>,
+}
+
+pub fn declare_params(params: &mut ParamsBuilder) -> LightParams {
+    LightParams {
+        sun_color: params.color("sun_color", vec3(0.4, 0.4, 0.5)),
+        point_light_range: params.float("point_light_range", 13.0, 1.0..=50.0),
+        orbit_speed: params.float("orbit_speed", 0.5, 0.0..=3.0),
+        spot_light: params.toggle("spot_light", true),
+        spot_color: params.color("spot_color", Vec3::ONE),
+    }
+}
+
+const TITLE: &str = "Multiple Lights: Phong (left) vs Blinn-Phong (right)";
+
+/// The same scene lit twice, with Phong on the left and Blinn-Phong on the right. The floor's
+/// highlights show the difference best: seen at grazing angles, Phong's highlights are cut off
+/// where the reflected light direction points more than 90° away from the viewer. The lights'
+/// colors, the point lights' range and how fast they orbit can be changed in the GUI.
+pub fn run(args: &Args) -> Result<()> {
+    let mut glfw_obj = glfw::init_no_callbacks()?;
+    glfw_obj.window_hint(WindowHint::OpenGlProfile(OpenGlProfileHint::Core));
+    glfw_obj.window_hint(WindowHint::ContextVersion(3, 3));
+    harness::window_hints(&mut glfw_obj, args);
+
+    #[cfg(target_os = "macos")]
+    glfw_obj.window_hint(WindowHint::OpenGlForwardCompat(true));
+
+    let (mut window, events_rx) = glfw_obj
+        .create_window(1200, 600, TITLE, WindowMode::Windowed)
+        .ok_or_else(|| format_err!("failed to create window"))?;
+
+    glfw_obj.make_context_current(Some(&window));
+    gl::load_with(|sym| window.get_proc_address(sym));
+
+    window.set_key_polling(true);
+    window.set_framebuffer_size_polling(true);
+
+    let mut harness = Harness::new(args)?;
+    let mut state = StateTracker::new();
+    let mut gui = Gui::new(&mut window)?;
+
+    let shaders_dir = PathBuf::new().join("shaders").join("_2_lighting");
+    let link = |vertex: &str, fragment: &str| -> Result<_> {
+        let vs = Shader::new(shaders_dir.join(vertex), ShaderType::Vertex)?;
+        let fs = Shader::new(shaders_dir.join(fragment), ShaderType::Fragment)?;
+        Linker::new().attach_shader(vs).attach_shader(fs).link()
+    };
+    let program = link(
+        "_6_multiple_lights_vertex_shader.glsl",
+        "_6_multiple_lights_fragment_shader.glsl",
+    )?;
+    let light_cube_program = link(
+        "_6_multiple_lights_light_cube_vertex_shader.glsl",
+        "_6_multiple_lights_light_cube_fragment_shader.glsl",
+    )?;
+
+    let mut floor_mesh = shapes::plane(20.0, 20.0, MeshOptions::default());
+    if let Some(uvs) = floor_mesh.uvs.as_mut() {
+        for uv in uvs.iter_mut() {
+            *uv = uv.map(|c| c * 8.0);
+        }
+    }
+
+    let mut scene = Scene::new();
+    let floor = scene.add_model(Model::from_mesh(&floor_mesh, program, Usage::Static)?);
+    let cube = scene.add_model(Model::from_mesh(
+        &shapes::cube(1.0, MeshOptions::default()),
+        program,
+        Usage::Static,
+    )?);
+    let light_cube = scene.add_model(Model::from_mesh(
+        &shapes::cube(
+            0.2,
+            MeshOptions {
+                normals: false,
+                tangents: false,
+                uvs: false,
+            },
+        ),
+        light_cube_program,
+        Usage::Static,
+    )?);
+
+    let textures_dir = PathBuf::new().join("textures");
+    let diffuse = TextureBuilder::new()
+        .flip_vertically(true)
+        .build_from_path(textures_dir.join("wall.jpg"))?;
+    let specular = TextureBuilder::new()
+        .flip_vertically(true)
+        .build_from_path(textures_dir.join("wall_specular.png"))?;
+    let material =
+        scene.add_material(PhongMaterial::new(diffuse, specular, 16.0).into_material(program));
+
+    let floor_node = scene.add_node(
+        "floor",
+        None,
+        Transform::from_translation(vec3(0.0, -0.5, 0.0)),
+    );
+    scene
+        .node_mut(floor_node)
+        .drawables
+        .push(Drawable::new(floor, material));
+
+    for (i, position) in CUBE_POSITIONS.iter().enumerate() {
+        let rotation = Quat::from_axis_angle(vec3(1.0, 0.3, 0.5).normalize(), i as f32);
+        let node = scene.add_node(
+            &format!("cube {i}"),
+            None,
+            Transform::from_translation(*position).with_rotation(rotation),
+        );
+        scene
+            .node_mut(node)
+            .drawables
+            .push(Drawable::new(cube, material));
+    }
+
+    // The point lights orbit the center of the scene by spinning their common parent.
+    let orbit = scene.add_node("point lights", None, Transform::IDENTITY);
+    let mut point_lights = vec![];
+    for (i, (position, color)) in POINT_LIGHTS.into_iter().enumerate() {
+        let mut light_material = Material::new(light_cube_program);
+        light_material.set("lightColor", color);
+        let light_material = scene.add_material(light_material);
+
+        let node = scene.add_node(
+            &format!("point light {i}"),
+            Some(orbit),
+            Transform::from_translation(position),
+        );
+        scene
+            .node_mut(node)
+            .drawables
+            .push(Drawable::new(light_cube, light_material));
+        point_lights.push((node, color));
+    }
+
+    // The lights themselves are set every frame from the parameters below.
+    let sun = scene.add_node("sun", None, Transform::IDENTITY);
+    let spot = scene.add_node(
+        "spot light",
+        None,
+        Transform::from_translation(vec3(0.0, 6.0, 0.0)),
+    );
+
+    let mut params = ParamsBuilder::new();
+    let LightParams {
+        sun_color,
+        point_light_range,
+        orbit_speed,
+        spot_light,
+        spot_color,
+    } = declare_params(&mut params);
+    let mut params = params.build(args)?;
+
+    let camera = scene.add_node("camera", None, Transform::IDENTITY);
+    scene.node_mut(camera).camera = Some(Camera::default());
+
+    let mut renderer = Renderer::new();
+
+    let matrices = UniformBuffer::new(
+        &Matrices::default(),
+        Usage::Dynamic,
+        harness.uniform_bindings(),
+    )?;
+    matrices.attach(&program, "Matrices")?;
+    matrices.attach(&light_cube_program, "Matrices")?;
+    let lights = UniformBuffer::new(
+        &scene.lighting(Shading::Phong).block(Vec3::ZERO)?,
+        Usage::Dynamic,
+        harness.uniform_bindings(),
+    )?;
+    lights.attach(&program, "Lights")?;
+
+    let mut frame = 0u64;
+    let mut last_time = 0.0;
+    // Accumulated rather than derived from the time, so that changing the speed doesn't make
+    // the lights jump.
+    let mut orbit_angle = 0.0;
+    while !window.should_close() {
+        handle_events(&events_rx, &mut window, &mut harness, &mut gui);
+
+        let time = glfw_obj.get_time() as f32;
+        gui.run(&window, f64::from(time), |ctx| {
+            params.window(ctx, "Lights");
+        })?;
+
+        for &(node, color) in &point_lights {
+            scene.node_mut(node).light =
+                Some(PointLight::new(Vec3::ZERO, color, params.get(point_light_range)).into());
+        }
+        scene.node_mut(sun).light =
+            Some(DirectionalLight::new(vec3(-0.2, -1.0, -0.3), params.get(sun_color)).into());
+        scene.node_mut(spot).light = params.get(spot_light).then(|| {
+            SpotLight::new(
+                Vec3::ZERO,
+                Vec3::NEG_Y,
+                params.get(spot_color),
+                PI / 14.0,
+                PI / 10.0,
+                50.0,
+            )
+            .into()
+        });
+
+        orbit_angle += (time - last_time) * params.get(orbit_speed);
+        last_time = time;
+        let eye = vec3((time * 0.2).sin() * 9.0, 2.5, (time * 0.2).cos() * 9.0);
+        scene.set_transform(camera, Transform::looking_at(eye, Vec3::ZERO, Vec3::Y));
+        scene.set_transform(
+            orbit,
+            Transform::from_rotation(Quat::from_rotation_y(orbit_angle)),
+        );
+        scene.update_world_transforms();
+
+        let (width, height) = window.get_framebuffer_size();
+        let half_width = width / 2;
+        let camera_matrices =
+            scene.camera_matrices(camera, half_width as f32 / height.max(1) as f32)?;
+        matrices.update(&camera_matrices);
+
+        state.clear(&Clear::new().color([0.05, 0.05, 0.05, 1.0]).depth(1.0));
+
+        for (i, shading) in [Shading::Phong, Shading::BlinnPhong]
+            .into_iter()
+            .enumerate()
+        {
+            let _scope = harness.profiler().scope(match shading {
+                Shading::Phong => "phong",
+                Shading::BlinnPhong => "blinn-phong",
+            });
+            unsafe { gl::Viewport(half_width * i as i32, 0, half_width, height) };
+
+            lights.update(&scene.lighting(shading).block(eye)?);
+            renderer.render(&mut scene, &mut state, &camera_matrices.view)?;
+        }
+
+        renderer.end_frame();
+        unsafe { gl::Viewport(0, 0, width, height) };
+        gui.draw(&mut state, width, height)?;
+        if frame.is_multiple_of(60) {
+            window.set_title(&format!("{TITLE} - {}", renderer.last_frame_stats()));
+        }
+        frame += 1;
+
+        harness.end_frame(&mut window)?;
+        window.swap_buffers();
+        glfw_obj.poll_events();
+    }
+
+    params.save()?;
+    harness.finish()
+}
+
+fn handle_events(
+    events_rx: &GlfwReceiver<(f64, WindowEvent)>,
+    window: &mut PWindow,
+    harness: &mut Harness,
+    gui: &mut Gui,
+) {
+    for (_, event) in glfw::flush_messages(events_rx) {
+        harness.handle_event(&event);
+        gui.handle_event(&event);
+
+        if let WindowEvent::Key(key, _, _, modifier) = event {
+            if gui.wants_keyboard() {
+                continue;
+            }
+            match (modifier, key) {
+                (Modifiers::Super, Key::W) | (_, Key::Escape) => window.set_should_close(true),
+                _ => (),
+            }
+        }
+    }
+}
@@ -0,0 +1 @@
+pub mod _6_multiple_lights;
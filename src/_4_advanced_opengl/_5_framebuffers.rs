@@ -0,0 +1,122 @@
+use crate::{
+    cli::Args,
+    glutils::{
+        material::Material,
+        mesh::{shapes, MeshOptions},
+        model::{primitives::Primitive, usage::Usage, Model},
+        postprocess::PostProcessor,
+        render_state::{Clear, StateTracker},
+        shader::{program::Linker, Shader, ShaderType},
+        texture::TextureBuilder,
+    },
+    harness::{self, Harness},
+};
+use anyhow::{format_err, Result};
+use glam::{Mat4, Vec3};
+use glfw::{
+    Context, GlfwReceiver, Key, Modifiers, OpenGlProfileHint, PWindow, WindowEvent, WindowHint,
+    WindowMode,
+};
+use std::path::PathBuf;
+
+pub fn run(args: &Args) -> Result<()> {
+    let mut glfw_obj = glfw::init_no_callbacks()?;
+    glfw_obj.window_hint(WindowHint::OpenGlProfile(OpenGlProfileHint::Core));
+    glfw_obj.window_hint(WindowHint::ContextVersion(3, 3));
+    harness::window_hints(&mut glfw_obj, args);
+
+    #[cfg(target_os = "macos")]
+    glfw_obj.window_hint(WindowHint::OpenGlForwardCompat(true));
+
+    let (mut window, events_rx) = glfw_obj
+        .create_window(800, 600, "Framebuffers", WindowMode::Windowed)
+        .ok_or_else(|| format_err!("failed to create window"))?;
+
+    glfw_obj.make_context_current(Some(&window));
+    gl::load_with(|sym| window.get_proc_address(sym));
+
+    window.set_key_polling(true);
+    window.set_framebuffer_size_polling(true);
+
+    let mut harness = Harness::new(args)?;
+
+    let shaders_dir = PathBuf::new().join("shaders").join("_4_advanced_opengl");
+
+    let vs = Shader::new(
+        shaders_dir.join("_5_framebuffers_vertex_shader.glsl"),
+        ShaderType::Vertex,
+    )?;
+    let fs = Shader::new(
+        shaders_dir.join("_5_framebuffers_fragment_shader.glsl"),
+        ShaderType::Fragment,
+    )?;
+    let program = Linker::new().attach_shader(vs).attach_shader(fs).link()?;
+
+    let mut quad = shapes::fullscreen_quad(MeshOptions {
+        normals: false,
+        tangents: false,
+        uvs: true,
+    });
+    quad.transform(Mat4::from_rotation_z(0.3) * Mat4::from_scale(Vec3::splat(0.6)));
+
+    let mut model = Model::from_mesh(&quad, program, Usage::Static)?;
+
+    let wall = TextureBuilder::new()
+        .flip_vertically(true)
+        .build_from_path(PathBuf::new().join("textures").join("wall.jpg"))?;
+
+    let mut material = Material::new(program);
+    material.set_texture("wall", wall);
+
+    let mut state = StateTracker::new();
+    let (width, height) = window.get_framebuffer_size();
+    let mut post_processor =
+        PostProcessor::new(u32::try_from(width)?, u32::try_from(height)?, &args.post)?;
+
+    while !window.should_close() {
+        handle_events(&events_rx, &mut window, &mut harness, &mut post_processor)?;
+
+        {
+            let _scene = harness.profiler().scope("scene");
+            post_processor.begin();
+            state.clear(&Clear::new().color([0.2, 0.3, 0.3, 1.0]).depth(1.0));
+            model.draw(&material, &mut state, Primitive::Triangles)?;
+        }
+
+        {
+            let _post = harness.profiler().scope("post-processing");
+            let (width, height) = window.get_framebuffer_size();
+            post_processor.apply(&mut state, width, height)?;
+        }
+
+        harness.end_frame(&mut window)?;
+        window.swap_buffers();
+        glfw_obj.poll_events();
+    }
+
+    harness.finish()
+}
+
+fn handle_events(
+    events_rx: &GlfwReceiver<(f64, WindowEvent)>,
+    window: &mut PWindow,
+    harness: &mut Harness,
+    post_processor: &mut PostProcessor,
+) -> Result<()> {
+    for (_, event) in glfw::flush_messages(events_rx) {
+        harness.handle_event(&event);
+        post_processor.handle_event(&event)?;
+
+        match event {
+            WindowEvent::Key(key, _, _, modifier) => match (modifier, key) {
+                (Modifiers::Super, Key::W) | (_, Key::Escape) => window.set_should_close(true),
+                _ => (),
+            },
+            WindowEvent::FramebufferSize(width, height) => unsafe {
+                gl::Viewport(0, 0, width, height);
+            },
+            _ => (),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,170 @@
+use crate::{
+    cli::Args,
+    glutils::{
+        environment::{refractive_index, EnvironmentMapping, EnvironmentMaterial},
+        mesh::{shapes, MeshOptions},
+        model::{usage::Usage, Model},
+        render_state::{Clear, StateTracker},
+        scene::{camera::Camera, renderer::Renderer, transform::Transform, Drawable, Scene},
+        shader::{program::Linker, Shader, ShaderType},
+        skybox::Skybox,
+        texture::cubemap::CubemapBuilder,
+        uniform_buffer::{Matrices, UniformBuffer},
+    },
+    harness::{self, Harness},
+};
+use anyhow::{format_err, Result};
+use glam::{vec3, Quat, Vec3};
+use glfw::{
+    Context, GlfwReceiver, Key, Modifiers, OpenGlProfileHint, PWindow, WindowEvent, WindowHint,
+    WindowMode,
+};
+use std::path::PathBuf;
+
+/// A mirrored cube, a glass sphere and a water torus showing the skybox around them.
+pub fn run(args: &Args) -> Result<()> {
+    let mut glfw_obj = glfw::init_no_callbacks()?;
+    glfw_obj.window_hint(WindowHint::OpenGlProfile(OpenGlProfileHint::Core));
+    glfw_obj.window_hint(WindowHint::ContextVersion(3, 3));
+    harness::window_hints(&mut glfw_obj, args);
+
+    #[cfg(target_os = "macos")]
+    glfw_obj.window_hint(WindowHint::OpenGlForwardCompat(true));
+
+    let (mut window, events_rx) = glfw_obj
+        .create_window(800, 600, "Cubemaps", WindowMode::Windowed)
+        .ok_or_else(|| format_err!("failed to create window"))?;
+
+    glfw_obj.make_context_current(Some(&window));
+    gl::load_with(|sym| window.get_proc_address(sym));
+
+    window.set_key_polling(true);
+    window.set_framebuffer_size_polling(true);
+
+    let mut harness = Harness::new(args)?;
+    let mut state = StateTracker::new();
+
+    let shaders_dir = PathBuf::new().join("shaders").join("_4_advanced_opengl");
+    let vs = Shader::new(
+        shaders_dir.join("_6_cubemaps_vertex_shader.glsl"),
+        ShaderType::Vertex,
+    )?;
+    let fs = Shader::new(
+        shaders_dir.join("_6_cubemaps_fragment_shader.glsl"),
+        ShaderType::Fragment,
+    )?;
+    let program = Linker::new().attach_shader(vs).attach_shader(fs).link()?;
+
+    let skybox_dir = PathBuf::new().join("textures").join("skybox");
+    let cubemap = CubemapBuilder::new().build_from_paths(
+        ["right", "left", "top", "bottom", "front", "back"]
+            .map(|face| skybox_dir.join(format!("{face}.jpg"))),
+    )?;
+
+    let matrices = UniformBuffer::new(
+        &Matrices::default(),
+        Usage::Dynamic,
+        harness.uniform_bindings(),
+    )?;
+    matrices.attach(&program, "Matrices")?;
+    let mut skybox = Skybox::new(cubemap, &matrices)?;
+
+    let options = MeshOptions {
+        normals: true,
+        tangents: false,
+        uvs: false,
+    };
+    let mut scene = Scene::new();
+    let objects = [
+        (
+            "mirror cube",
+            shapes::cube(1.5, options),
+            EnvironmentMapping::Reflection,
+            vec3(-2.5, 0.0, 0.0),
+        ),
+        (
+            "glass sphere",
+            shapes::icosphere(1.0, 4, options),
+            EnvironmentMapping::Refraction(refractive_index::GLASS),
+            Vec3::ZERO,
+        ),
+        (
+            "water torus",
+            shapes::torus(0.8, 0.35, 48, 24, options),
+            EnvironmentMapping::Refraction(refractive_index::WATER),
+            vec3(2.5, 0.0, 0.0),
+        ),
+    ];
+    let mut spinning = vec![];
+    for (name, mesh, mapping, position) in objects {
+        let model = scene.add_model(Model::from_mesh(&mesh, program, Usage::Static)?);
+        let material =
+            scene.add_material(EnvironmentMaterial::new(cubemap, mapping)?.into_material(program));
+        let node = scene.add_node(name, None, Transform::from_translation(position));
+        scene
+            .node_mut(node)
+            .drawables
+            .push(Drawable::new(model, material));
+        spinning.push(node);
+    }
+
+    let camera = scene.add_node("camera", None, Transform::IDENTITY);
+    scene.node_mut(camera).camera = Some(Camera::default());
+    let mut renderer = Renderer::new();
+
+    while !window.should_close() {
+        handle_events(&events_rx, &mut window, &mut harness);
+
+        let time = glfw_obj.get_time() as f32;
+        let eye = vec3((time * 0.15).sin() * 7.0, 1.5, (time * 0.15).cos() * 7.0);
+        scene.set_transform(camera, Transform::looking_at(eye, Vec3::ZERO, Vec3::Y));
+        for node in &spinning {
+            let rotation = Quat::from_axis_angle(vec3(0.5, 1.0, 0.2).normalize(), time * 0.4);
+            let transform = scene.node(*node).transform().with_rotation(rotation);
+            scene.set_transform(*node, transform);
+        }
+        scene.update_world_transforms();
+
+        let (width, height) = window.get_framebuffer_size();
+        let camera_matrices = scene.camera_matrices(camera, width as f32 / height.max(1) as f32)?;
+        matrices.update(&camera_matrices);
+
+        state.clear(&Clear::new().depth(1.0));
+        {
+            let _scene = harness.profiler().scope("scene");
+            renderer.render(&mut scene, &mut state, &camera_matrices.view)?;
+        }
+        {
+            let _skybox = harness.profiler().scope("skybox");
+            skybox.draw(&mut state)?;
+        }
+        renderer.end_frame();
+
+        harness.end_frame(&mut window)?;
+        window.swap_buffers();
+        glfw_obj.poll_events();
+    }
+
+    harness.finish()
+}
+
+fn handle_events(
+    events_rx: &GlfwReceiver<(f64, WindowEvent)>,
+    window: &mut PWindow,
+    harness: &mut Harness,
+) {
+    for (_, event) in glfw::flush_messages(events_rx) {
+        harness.handle_event(&event);
+
+        match event {
+            WindowEvent::Key(key, _, _, modifier) => match (modifier, key) {
+                (Modifiers::Super, Key::W) | (_, Key::Escape) => window.set_should_close(true),
+                _ => (),
+            },
+            WindowEvent::FramebufferSize(width, height) => unsafe {
+                gl::Viewport(0, 0, width, height);
+            },
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,2 @@
+pub mod _5_framebuffers;
+pub mod _6_cubemaps;
@@ -0,0 +1,370 @@
+use crate::{
+    cli::Args,
+    glutils::{
+        debug_draw::DebugDraw,
+        lighting::{DirectionalLight, PhongMaterial, PointLight, Shading, SpotLight},
+        material::Material,
+        mesh::{bounds::Aabb, shapes, MeshOptions},
+        model::{usage::Usage, Model},
+        render_state::{Clear, StateTracker},
+        scene::{camera::Camera, renderer::Renderer, transform::Transform, Drawable, Scene},
+        shader::{program::Linker, Shader, ShaderType},
+        shadow::{ShadowMaps, ShadowSettings, ShadowView},
+        texture::{cubemap::CubeFace, TextureBuilder},
+        uniform_buffer::{Matrices, UniformBuffer},
+    },
+    harness::{self, Harness},
+};
+use anyhow::{format_err, Result};
+use glam::{vec3, vec4, Quat, Vec3, Vec4};
+use glfw::{
+    Action, Context, GlfwReceiver, Key, Modifiers, OpenGlProfileHint, PWindow, WindowEvent,
+    WindowHint, WindowMode,
+};
+use std::{f32::consts::PI, path::PathBuf};
+
+const TITLE: &str = "Shadow Mapping";
+
+/// Number of cubes scattered over the floor, far enough apart that the directional light's
+/// cascades all have something to shadow.
+const NUM_CUBES: usize = 60;
+
+/// Side of the shadow map overlay in the bottom left corner, in pixels.
+const OVERLAY_SIZE: i32 = 256;
+
+/// Colors of the directional light's cascades among the debug lines, nearest first.
+const CASCADE_COLORS: [Vec4; 4] = [
+    vec4(1.0, 0.3, 0.3, 1.0),
+    vec4(0.3, 1.0, 0.3, 1.0),
+    vec4(0.3, 0.3, 1.0, 1.0),
+    vec4(1.0, 1.0, 0.3, 1.0),
+];
+
+/// Keyboard controls for inspecting the shadows.
+struct Controls {
+    show_cascades: bool,
+    /// Index into the list of overlays given by [`overlays`], where 0 shows none.
+    overlay: usize,
+    pcf_radius: i32,
+    debug_draw: bool,
+}
+
+/// A large field of cubes lit by the sun, a spot light and an orbiting point light, all casting
+/// shadows. The sun's shadow map is split into cascades that stay sharp near the camera while
+/// covering the whole field. C tints the cascades, O cycles through the shadow maps in the
+/// corner, P through the PCF radius used to soften shadow edges and G toggles debug lines
+/// showing the lights, the volumes their shadow maps cover and the bounds of the nearest cubes.
+pub fn run(args: &Args) -> Result<()> {
+    let mut glfw_obj = glfw::init_no_callbacks()?;
+    glfw_obj.window_hint(WindowHint::OpenGlProfile(OpenGlProfileHint::Core));
+    glfw_obj.window_hint(WindowHint::ContextVersion(3, 3));
+    harness::window_hints(&mut glfw_obj, args);
+
+    #[cfg(target_os = "macos")]
+    glfw_obj.window_hint(WindowHint::OpenGlForwardCompat(true));
+
+    let (mut window, events_rx) = glfw_obj
+        .create_window(1280, 720, TITLE, WindowMode::Windowed)
+        .ok_or_else(|| format_err!("failed to create window"))?;
+
+    glfw_obj.make_context_current(Some(&window));
+    gl::load_with(|sym| window.get_proc_address(sym));
+
+    window.set_key_polling(true);
+    window.set_framebuffer_size_polling(true);
+
+    let mut harness = Harness::new(args)?;
+    let mut state = StateTracker::new();
+
+    let shaders_dir = PathBuf::new().join("shaders").join("_5_advanced_lighting");
+    let link = |vertex: &str, fragment: &str| -> Result<_> {
+        let vs = Shader::new(shaders_dir.join(vertex), ShaderType::Vertex)?;
+        let fs = Shader::new(shaders_dir.join(fragment), ShaderType::Fragment)?;
+        Linker::new().attach_shader(vs).attach_shader(fs).link()
+    };
+    let program = link(
+        "_3_shadow_mapping_vertex_shader.glsl",
+        "_3_shadow_mapping_fragment_shader.glsl",
+    )?;
+    let light_cube_program = link(
+        "_3_shadow_mapping_light_cube_vertex_shader.glsl",
+        "_3_shadow_mapping_light_cube_fragment_shader.glsl",
+    )?;
+
+    let mut floor_mesh = shapes::plane(80.0, 80.0, MeshOptions::default());
+    if let Some(uvs) = floor_mesh.uvs.as_mut() {
+        for uv in uvs.iter_mut() {
+            *uv = uv.map(|c| c * 32.0);
+        }
+    }
+
+    let mut scene = Scene::new();
+    let floor = scene.add_model(Model::from_mesh(&floor_mesh, program, Usage::Static)?);
+    let cube_mesh = shapes::cube(1.0, MeshOptions::default());
+    let cube_bounds = Aabb::from_points(cube_mesh.positions.iter().map(|p| Vec3::from_array(*p)))
+        .ok_or_else(|| format_err!("cube mesh has no vertices"))?;
+    let cube = scene.add_model(Model::from_mesh(&cube_mesh, program, Usage::Static)?);
+    let light_cube = scene.add_model(Model::from_mesh(
+        &shapes::cube(
+            0.2,
+            MeshOptions {
+                normals: false,
+                tangents: false,
+                uvs: false,
+            },
+        ),
+        light_cube_program,
+        Usage::Static,
+    )?);
+
+    let textures_dir = PathBuf::new().join("textures");
+    let diffuse = TextureBuilder::new()
+        .flip_vertically(true)
+        .build_from_path(textures_dir.join("wall.jpg"))?;
+    let specular = TextureBuilder::new()
+        .flip_vertically(true)
+        .build_from_path(textures_dir.join("wall_specular.png"))?;
+    let material =
+        scene.add_material(PhongMaterial::new(diffuse, specular, 16.0).into_material(program));
+
+    let floor_node = scene.add_node("floor", None, Transform::IDENTITY);
+    scene
+        .node_mut(floor_node)
+        .drawables
+        .push(Drawable::new(floor, material));
+
+    // Spread out on a jittered spiral, so the field looks random but is the same every run.
+    let mut cubes = vec![];
+    for i in 0..NUM_CUBES {
+        let angle = i as f32 * 2.4;
+        let radius = 3.0 + (i as f32).sqrt() * 4.2;
+        let scale = 0.8 + (i * 7 % 5) as f32 * 0.4;
+        let position = vec3(angle.cos() * radius, scale * 0.5, angle.sin() * radius);
+        let node = scene.add_node(
+            &format!("cube {i}"),
+            None,
+            Transform::from_translation(position)
+                .with_rotation(Quat::from_rotation_y(angle))
+                .with_scale(Vec3::splat(scale)),
+        );
+        scene
+            .node_mut(node)
+            .drawables
+            .push(Drawable::new(cube, material));
+        cubes.push(node);
+    }
+
+    let sun = scene.add_node("sun", None, Transform::IDENTITY);
+    let sun_node = scene.node_mut(sun);
+    sun_node.light =
+        Some(DirectionalLight::new(vec3(-0.4, -1.0, -0.3), vec3(0.8, 0.75, 0.7)).into());
+    sun_node.shadows = true;
+
+    let spot = scene.add_node(
+        "spot light",
+        None,
+        Transform::looking_at(vec3(-4.0, 7.0, 4.0), vec3(0.0, 0.0, 0.0), Vec3::Y),
+    );
+    let spot_node = scene.node_mut(spot);
+    spot_node.light = Some(
+        SpotLight::new(
+            Vec3::ZERO,
+            Vec3::NEG_Z,
+            vec3(1.0, 0.9, 0.6),
+            PI / 9.0,
+            PI / 7.0,
+            40.0,
+        )
+        .into(),
+    );
+    spot_node.shadows = true;
+
+    // The point light orbits the center of the field by spinning its parent.
+    let orbit = scene.add_node("point light orbit", None, Transform::IDENTITY);
+    let point_color = vec3(0.3, 0.6, 1.0);
+    let point = scene.add_node(
+        "point light",
+        Some(orbit),
+        Transform::from_translation(vec3(6.0, 1.5, 0.0)),
+    );
+    let mut point_material = Material::new(light_cube_program);
+    point_material.set("lightColor", point_color);
+    let point_material = scene.add_material(point_material);
+    let point_node = scene.node_mut(point);
+    // The bulb would otherwise cover the light and shadow everything.
+    point_node.drawables.push(Drawable {
+        casts_shadow: false,
+        ..Drawable::new(light_cube, point_material)
+    });
+    point_node.light = Some(PointLight::new(Vec3::ZERO, point_color, 20.0).into());
+    point_node.shadows = true;
+
+    let camera = scene.add_node("camera", None, Transform::IDENTITY);
+    scene.node_mut(camera).camera = Some(Camera::new(45f32.to_radians(), 0.1, 150.0));
+
+    let mut renderer = Renderer::new();
+    let mut shadows = ShadowMaps::new(ShadowSettings::default(), harness.uniform_bindings())?;
+    shadows.attach(&program)?;
+
+    let matrices = UniformBuffer::new(
+        &Matrices::default(),
+        Usage::Dynamic,
+        harness.uniform_bindings(),
+    )?;
+    matrices.attach(&program, "Matrices")?;
+    matrices.attach(&light_cube_program, "Matrices")?;
+    let lights = UniformBuffer::new(
+        &scene.lighting(Shading::BlinnPhong).block(Vec3::ZERO)?,
+        Usage::Dynamic,
+        harness.uniform_bindings(),
+    )?;
+    lights.attach(&program, "Lights")?;
+    let mut debug_draw = DebugDraw::new(&matrices)?;
+
+    let mut controls = Controls {
+        show_cascades: false,
+        overlay: 0,
+        pcf_radius: shadows.settings().pcf_radius,
+        debug_draw: false,
+    };
+    let overlays = overlays(shadows.settings().cascades);
+
+    let mut frame = 0u64;
+    while !window.should_close() {
+        handle_events(
+            &events_rx,
+            &mut window,
+            &mut harness,
+            &mut controls,
+            overlays.len(),
+        );
+        shadows.set_show_cascades(controls.show_cascades);
+        shadows.set_settings(ShadowSettings {
+            pcf_radius: controls.pcf_radius,
+            ..*shadows.settings()
+        });
+
+        let time = glfw_obj.get_time() as f32;
+        let eye = vec3((time * 0.1).sin() * 18.0, 6.0, (time * 0.1).cos() * 18.0);
+        scene.set_transform(camera, Transform::looking_at(eye, Vec3::ZERO, Vec3::Y));
+        scene.set_transform(
+            orbit,
+            Transform::from_rotation(Quat::from_rotation_y(time * 0.4)),
+        );
+        scene.update_world_transforms();
+
+        let (width, height) = window.get_framebuffer_size();
+        let aspect_ratio = width as f32 / height.max(1) as f32;
+        let lighting = scene.lighting(Shading::BlinnPhong);
+        {
+            let _shadows = harness.profiler().scope("shadows");
+            shadows.render(
+                &mut renderer,
+                &mut scene,
+                &mut state,
+                &lighting,
+                camera,
+                aspect_ratio,
+            )?;
+        }
+
+        let camera_matrices = scene.camera_matrices(camera, aspect_ratio)?;
+        matrices.update(&camera_matrices);
+        lights.update(&lighting.block(eye)?);
+
+        state.clear(&Clear::new().color([0.55, 0.65, 0.8, 1.0]).depth(1.0));
+        {
+            let _scene = harness.profiler().scope("scene");
+            renderer.render(&mut scene, &mut state, &camera_matrices.view)?;
+        }
+        if controls.debug_draw {
+            let _debug = harness.profiler().scope("debug draw");
+            debug_draw.grid(vec3(0.0, 0.01, 0.0), 2.0, 40, vec4(0.2, 0.2, 0.2, 0.5));
+            for node in [spot, point] {
+                debug_draw.axes(scene.node(node).world_transform(), 1.0);
+            }
+            debug_draw.sphere(
+                scene.node(point).world_position(),
+                0.4,
+                point_color.extend(1.0),
+            );
+
+            let block = shadows.block();
+            debug_draw.frustum(block.spot_matrix, vec4(1.0, 0.9, 0.6, 1.0));
+            for (i, matrix) in block.cascade_matrices[..shadows.settings().cascades]
+                .iter()
+                .enumerate()
+            {
+                debug_draw.frustum(*matrix, CASCADE_COLORS[i % CASCADE_COLORS.len()]);
+            }
+
+            // The first cubes of the spiral are the ones nearest the center.
+            for node in &cubes[..8] {
+                let transform = scene.node(*node).world_transform();
+                debug_draw.aabb(&cube_bounds.transformed(transform), Vec4::ONE);
+                debug_draw.normals(&cube_mesh, transform, 0.3, vec4(1.0, 0.0, 1.0, 1.0));
+            }
+            debug_draw.flush(&mut state)?;
+        }
+        if let Some(view) = overlays[controls.overlay] {
+            shadows.draw_overlay(&mut state, view, [0, 0, OVERLAY_SIZE, OVERLAY_SIZE])?;
+        }
+
+        renderer.end_frame();
+        if frame.is_multiple_of(60) {
+            let overlay = match overlays[controls.overlay] {
+                Some(view) => format!("{view:?}"),
+                None => "none".to_string(),
+            };
+            window.set_title(&format!(
+                "{TITLE} - PCF radius {}, overlay {overlay} - {}",
+                controls.pcf_radius,
+                renderer.last_frame_stats()
+            ));
+        }
+        frame += 1;
+
+        harness.end_frame(&mut window)?;
+        window.swap_buffers();
+        glfw_obj.poll_events();
+    }
+
+    debug_draw.delete();
+    harness.finish()
+}
+
+/// Shadow maps the overlay cycles through, starting with none.
+fn overlays(cascades: usize) -> Vec<Option<ShadowView>> {
+    std::iter::once(None)
+        .chain((0..cascades).map(|i| Some(ShadowView::Cascade(i))))
+        .chain(std::iter::once(Some(ShadowView::Spot)))
+        .chain(CubeFace::ALL.map(|face| Some(ShadowView::Point(face))))
+        .collect()
+}
+
+fn handle_events(
+    events_rx: &GlfwReceiver<(f64, WindowEvent)>,
+    window: &mut PWindow,
+    harness: &mut Harness,
+    controls: &mut Controls,
+    num_overlays: usize,
+) {
+    for (_, event) in glfw::flush_messages(events_rx) {
+        harness.handle_event(&event);
+
+        match event {
+            WindowEvent::Key(key, _, Action::Press, modifier) => match (modifier, key) {
+                (Modifiers::Super, Key::W) | (_, Key::Escape) => window.set_should_close(true),
+                (_, Key::C) => controls.show_cascades = !controls.show_cascades,
+                (_, Key::O) => controls.overlay = (controls.overlay + 1) % num_overlays,
+                (_, Key::P) => controls.pcf_radius = (controls.pcf_radius + 1) % 4,
+                (_, Key::G) => controls.debug_draw = !controls.debug_draw,
+                _ => (),
+            },
+            WindowEvent::FramebufferSize(width, height) => unsafe {
+                gl::Viewport(0, 0, width, height);
+            },
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,357 @@
+use crate::{
+    cli::Args,
+    glutils::{
+        lighting::{
+            solid_texture, Lights, MaterialFeatures, ParallaxMode, PhongMaterial, PointLight,
+            Shading,
+        },
+        material::Material,
+        mesh::{shapes, MeshOptions},
+        model::{usage::Usage, Model},
+        render_state::{Clear, StateTracker},
+        scene::{camera::Camera, renderer::Renderer, transform::Transform, Drawable, Scene},
+        shader::{program::Linker, variants::ProgramVariants, Shader, ShaderType},
+        texture::{Texture, TextureBuilder},
+        uniform_buffer::{Matrices, UniformBuffer},
+    },
+    harness::{self, Harness},
+};
+use anyhow::{format_err, Result};
+use glam::{vec3, Quat, Vec3};
+use glfw::{
+    Action, Context, GlfwReceiver, Key, Modifiers, OpenGlProfileHint, PWindow, WindowEvent,
+    WindowHint, WindowMode,
+};
+use std::{f32::consts::FRAC_PI_2, path::PathBuf};
+
+const TITLE: &str = "Normal and Parallax Mapping";
+
+/// Texels per side of the generated brick maps.
+const TEXTURE_SIZE: u32 = 512;
+
+/// Rows of bricks in the generated maps, each with half as many bricks as rows.
+const BRICK_ROWS: u32 = 8;
+
+/// How deep the mortar between the bricks is, in texture units.
+const HEIGHT_SCALE: f32 = 0.05;
+
+/// A brick floor, wall and cube lit by a light orbiting the cube. The bricks are flat geometry: their
+/// relief comes from a normal map and from a height map that displaces texture coordinates. N
+/// toggles the normal map and P cycles through no parallax, parallax, steep parallax and
+/// parallax occlusion mapping.
+pub fn run(args: &Args) -> Result<()> {
+    let mut glfw_obj = glfw::init_no_callbacks()?;
+    glfw_obj.window_hint(WindowHint::OpenGlProfile(OpenGlProfileHint::Core));
+    glfw_obj.window_hint(WindowHint::ContextVersion(3, 3));
+    harness::window_hints(&mut glfw_obj, args);
+
+    #[cfg(target_os = "macos")]
+    glfw_obj.window_hint(WindowHint::OpenGlForwardCompat(true));
+
+    let (mut window, events_rx) = glfw_obj
+        .create_window(1280, 720, TITLE, WindowMode::Windowed)
+        .ok_or_else(|| format_err!("failed to create window"))?;
+
+    glfw_obj.make_context_current(Some(&window));
+    gl::load_with(|sym| window.get_proc_address(sym));
+
+    window.set_key_polling(true);
+    window.set_framebuffer_size_polling(true);
+
+    let mut harness = Harness::new(args)?;
+    let mut state = StateTracker::new();
+
+    let shaders_dir = PathBuf::new().join("shaders").join("_5_advanced_lighting");
+    let mut variants = ProgramVariants::new(
+        shaders_dir.join("_5_parallax_mapping_vertex_shader.glsl"),
+        shaders_dir.join("_5_parallax_mapping_fragment_shader.glsl"),
+    );
+    let light_cube_program = {
+        let vs = Shader::new(
+            shaders_dir.join("_8_deferred_shading_light_cube_vertex_shader.glsl"),
+            ShaderType::Vertex,
+        )?;
+        let fs = Shader::new(
+            shaders_dir.join("_8_deferred_shading_light_cube_fragment_shader.glsl"),
+            ShaderType::Fragment,
+        )?;
+        Linker::new().attach_shader(vs).attach_shader(fs).link()?
+    };
+
+    let (diffuse, height) = brick_textures(TEXTURE_SIZE, BRICK_ROWS)?;
+    let bricks = Bricks {
+        phong: PhongMaterial::new(diffuse, solid_texture(Vec3::splat(0.3))?, 16.0),
+        normal: normal_texture_from_height(TEXTURE_SIZE, BRICK_ROWS)?,
+        height,
+    };
+
+    // Models only upload the vertex streams their program reads, so they're built with a
+    // program that reads tangents even if the first variant drawn doesn't.
+    let all_features = MaterialFeatures {
+        normal_map: true,
+        parallax: Some(ParallaxMode::Occlusion),
+    };
+    let tangent_program = variants.get(&all_features.defines())?;
+    let with_tangents = MeshOptions {
+        tangents: true,
+        ..MeshOptions::default()
+    };
+
+    let mut scene = Scene::new();
+    let plane = scene.add_model(Model::from_mesh(
+        &shapes::plane(4.0, 4.0, with_tangents),
+        tangent_program,
+        Usage::Static,
+    )?);
+    let cube = scene.add_model(Model::from_mesh(
+        &shapes::cube(1.0, with_tangents),
+        tangent_program,
+        Usage::Static,
+    )?);
+    let bulb = scene.add_model(Model::from_mesh(
+        &shapes::cube(
+            0.15,
+            MeshOptions {
+                normals: false,
+                tangents: false,
+                uvs: false,
+            },
+        ),
+        light_cube_program,
+        Usage::Static,
+    )?);
+    let material = scene.add_material(Material::new(tangent_program));
+
+    for (name, transform) in [
+        ("floor", Transform::IDENTITY),
+        (
+            "wall",
+            Transform::from_translation(vec3(0.0, 2.0, -2.0))
+                .with_rotation(Quat::from_rotation_x(FRAC_PI_2)),
+        ),
+    ] {
+        let node = scene.add_node(name, None, transform);
+        scene
+            .node_mut(node)
+            .drawables
+            .push(Drawable::new(plane, material));
+    }
+    let cube_node = scene.add_node(
+        "cube",
+        None,
+        Transform::from_translation(vec3(0.0, 0.5, 0.0)),
+    );
+    scene
+        .node_mut(cube_node)
+        .drawables
+        .push(Drawable::new(cube, material));
+
+    let mut bulb_material = Material::new(light_cube_program);
+    bulb_material.set("lightColor", Vec3::ONE);
+    let bulb_material = scene.add_material(bulb_material);
+    let orbit = scene.add_node("orbit", None, Transform::IDENTITY);
+    let light = scene.add_node(
+        "light",
+        Some(orbit),
+        Transform::from_translation(vec3(1.6, 0.6, 0.0)),
+    );
+    let light = scene.node_mut(light);
+    light.drawables.push(Drawable::new(bulb, bulb_material));
+    light.light = Some(PointLight::new(Vec3::ZERO, Vec3::ONE, 8.0).into());
+
+    let camera = scene.add_node("camera", None, Transform::IDENTITY);
+    scene.node_mut(camera).camera = Some(Camera::new(45f32.to_radians(), 0.1, 100.0));
+
+    let mut renderer = Renderer::new();
+    let matrices = UniformBuffer::new(
+        &Matrices::default(),
+        Usage::Dynamic,
+        harness.uniform_bindings(),
+    )?;
+    let lights = UniformBuffer::new(
+        &Lights::default(),
+        Usage::Dynamic,
+        harness.uniform_bindings(),
+    )?;
+    matrices.attach(&light_cube_program, "Matrices")?;
+
+    let mut features = all_features;
+    let mut previous = None;
+    while !window.should_close() {
+        handle_events(&events_rx, &mut window, &mut harness, &mut features);
+        if previous != Some(features) {
+            let program = variants.get(&features.defines())?;
+            matrices.attach(&program, "Matrices")?;
+            lights.attach(&program, "Lights")?;
+            *scene.material_mut(material) = bricks.with_features(features).into_material(program);
+            window.set_title(&format!("{TITLE} - {}", describe(features)));
+            previous = Some(features);
+        }
+
+        let time = glfw_obj.get_time() as f32;
+        let eye = vec3(
+            (time * 0.2).sin() * 4.0,
+            2.0,
+            2.5 + (time * 0.2).cos() * 1.5,
+        );
+        scene.set_transform(
+            camera,
+            Transform::looking_at(eye, vec3(0.0, 0.6, 0.0), Vec3::Y),
+        );
+        scene.set_transform(
+            orbit,
+            Transform::from_rotation(Quat::from_rotation_y(time * 0.6)),
+        );
+        scene.update_world_transforms();
+
+        let (width, height) = window.get_framebuffer_size();
+        let camera_matrices = scene.camera_matrices(camera, width as f32 / height.max(1) as f32)?;
+        matrices.update(&camera_matrices);
+        lights.update(&scene.lighting(Shading::BlinnPhong).block(eye)?);
+
+        state.clear(&Clear::all([0.05, 0.05, 0.05, 1.0]));
+        {
+            let _scene = harness.profiler().scope("scene");
+            renderer.render(&mut scene, &mut state, &camera_matrices.view)?;
+        }
+        renderer.end_frame();
+
+        harness.end_frame(&mut window)?;
+        window.swap_buffers();
+        glfw_obj.poll_events();
+    }
+
+    harness.finish()
+}
+
+/// The brick maps, of which the material only uses those that the current features ask for.
+#[derive(Copy, Clone, Debug)]
+struct Bricks {
+    phong: PhongMaterial,
+    normal: Texture,
+    height: Texture,
+}
+
+impl Bricks {
+    fn with_features(&self, features: MaterialFeatures) -> PhongMaterial {
+        let mut phong = self.phong;
+        if features.normal_map {
+            phong = phong.with_normal_map(self.normal);
+        }
+        if let Some(mode) = features.parallax {
+            phong = phong.with_height_map(self.height, HEIGHT_SCALE, mode);
+        }
+        phong
+    }
+}
+
+fn describe(features: MaterialFeatures) -> String {
+    format!(
+        "normal map {}, {}",
+        if features.normal_map { "on" } else { "off" },
+        features.parallax.map_or("no parallax", ParallaxMode::name)
+    )
+}
+
+/// The next of no parallax and each mode, wrapping around.
+fn next_parallax(parallax: Option<ParallaxMode>) -> Option<ParallaxMode> {
+    match parallax {
+        None => Some(ParallaxMode::ALL[0]),
+        Some(mode) => ParallaxMode::ALL
+            .into_iter()
+            .skip_while(|&other| other != mode)
+            .nth(1),
+    }
+}
+
+/// Height of the brick pattern at a texel, 1 on top of a brick and 0 deep in the mortar, with
+/// bevelled edges in between.
+fn brick_height(x: u32, y: u32, size: u32, rows: u32) -> f32 {
+    let brick_height = size as f32 / rows as f32;
+    let brick_width = brick_height * 2.0;
+    let row = (y as f32 / brick_height).floor();
+    // Every other row is shifted by half a brick.
+    let offset = if row as u32 % 2 == 1 {
+        brick_width * 0.5
+    } else {
+        0.0
+    };
+    let u = ((x as f32 + offset) % brick_width) / brick_width;
+    let v = (y as f32 % brick_height) / brick_height;
+    // Distance to the nearest edge of the brick, in texels.
+    let edge = (u.min(1.0 - u) * brick_width).min(v.min(1.0 - v) * brick_height);
+    let mortar = 3.0;
+    let bevel = 4.0;
+    ((edge - mortar) / bevel).clamp(0.0, 1.0)
+}
+
+/// The bricks' diffuse color and their height map, both `size` texels square.
+fn brick_textures(size: u32, rows: u32) -> Result<(Texture, Texture)> {
+    let mut diffuse = Vec::with_capacity(usize::try_from(size * size * 4)?);
+    let mut height = Vec::with_capacity(usize::try_from(size * size * 4)?);
+    for y in 0..size {
+        for x in 0..size {
+            let h = brick_height(x, y, size, rows);
+            // Mottles each brick's color a little so that they don't look printed.
+            let noise = ((x * 7 + y * 13) % 17) as f32 / 17.0 * 0.08;
+            let brick = vec3(0.55 + noise, 0.22 + noise * 0.5, 0.15);
+            let mortar = vec3(0.45, 0.43, 0.4);
+            let color = mortar.lerp(brick, h);
+            let [r, g, b] = color.to_array().map(|c| (c * 255.0).round() as u8);
+            diffuse.extend_from_slice(&[r, g, b, 255]);
+            let h = (h * 255.0).round() as u8;
+            height.extend_from_slice(&[h, h, h, 255]);
+        }
+    }
+    Ok((
+        TextureBuilder::new().build_from_rgba(size, size, &diffuse)?,
+        TextureBuilder::new().build_from_rgba(size, size, &height)?,
+    ))
+}
+
+/// Tangent space normals of the brick height map, from its slopes along each axis. Rows go
+/// up the texture, along the bitangent.
+fn normal_texture_from_height(size: u32, rows: u32) -> Result<Texture> {
+    let strength = 4.0;
+    let height = |x: i64, y: i64| {
+        let wrap = |c: i64| u32::try_from(c.rem_euclid(i64::from(size))).unwrap();
+        brick_height(wrap(x), wrap(y), size, rows)
+    };
+    let mut texels = Vec::with_capacity(usize::try_from(size * size * 4)?);
+    for y in 0..i64::from(size) {
+        for x in 0..i64::from(size) {
+            let dx = (height(x + 1, y) - height(x - 1, y)) * 0.5;
+            let dy = (height(x, y + 1) - height(x, y - 1)) * 0.5;
+            let normal = vec3(-dx * strength, -dy * strength, 1.0).normalize();
+            let [r, g, b] = (normal * 0.5 + 0.5)
+                .to_array()
+                .map(|c| (c * 255.0).round() as u8);
+            texels.extend_from_slice(&[r, g, b, 255]);
+        }
+    }
+    TextureBuilder::new().build_from_rgba(size, size, &texels)
+}
+
+fn handle_events(
+    events_rx: &GlfwReceiver<(f64, WindowEvent)>,
+    window: &mut PWindow,
+    harness: &mut Harness,
+    features: &mut MaterialFeatures,
+) {
+    for (_, event) in glfw::flush_messages(events_rx) {
+        harness.handle_event(&event);
+
+        match event {
+            WindowEvent::Key(key, _, Action::Press, modifier) => match (modifier, key) {
+                (Modifiers::Super, Key::W) | (_, Key::Escape) => window.set_should_close(true),
+                (_, Key::N) => features.normal_map = !features.normal_map,
+                (_, Key::P) => features.parallax = next_parallax(features.parallax),
+                _ => (),
+            },
+            WindowEvent::FramebufferSize(width, height) => unsafe {
+                gl::Viewport(0, 0, width, height);
+            },
+            _ => (),
+        }
+    }
+}
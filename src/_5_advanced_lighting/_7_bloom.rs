@@ -0,0 +1,434 @@
+use crate::{
+    cli::Args,
+    glutils::{
+        gui::Gui,
+        lighting::{PhongMaterial, PointLight, Shading},
+        material::Material,
+        mesh::{shapes, MeshOptions},
+        model::{usage::Usage, Model},
+        postprocess::{
+            bloom::{BloomBlur, BloomSettings, MAX_LEVELS},
+            effect::{Effect, Tonemapper},
+            PostProcessor,
+        },
+        render_state::{Clear, StateTracker},
+        scene::{camera::Camera, renderer::Renderer, transform::Transform, Drawable, Scene},
+        shader::{program::Linker, Shader, ShaderType},
+        texture::TextureBuilder,
+        uniform_buffer::{Matrices, UniformBuffer},
+    },
+    harness::{
+        self,
+        params::{Choice, Handle, Params, ParamsBuilder},
+        Harness,
+    },
+};
+use anyhow::{format_err, Result};
+use glam::{vec3, Quat, Vec3};
+use glfw::{
+    Action, Context, GlfwReceiver, Key, Modifiers, OpenGlProfileHint, PWindow, WindowEvent,
+    WindowHint, WindowMode,
+};
+use std::path::PathBuf;
+
+const TITLE: &str = "Bloom";
+
+/// The blurs bloom can use, each with a number of steps set by a parameter of its own.
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Blur {
+    Gaussian,
+    DualKawase,
+}
+
+impl Choice for Blur {
+    const ALL: &'static [Self] = &[Self::Gaussian, Self::DualKawase];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Gaussian => "gaussian",
+            Self::DualKawase => "dual_kawase",
+        }
+    }
+}
+
+/// Handles to the lesson's parameters, which the keys and the GUI tweak.
+pub struct TweakParams {
+    tonemapper: Handle<Tonemapper>,
+    exposure: Handle<f32>,
+    bloom: Handle<bool>,
+    threshold: Handle<f32>,
+    knee: Handle<f32>,
+    intensity: Handle<f32>,
+    blur: Handle<Blur>,
+    /// Passes of the Gaussian blur, or levels of the dual Kawase one.
+    blur_steps: Handle<i32>,
+}
+
+impl TweakParams {
+    fn declare(params: &mut ParamsBuilder) -> Self {
+        let defaults = BloomSettings::default();
+        Self {
+            tonemapper: params.choice("tonemapper", Tonemapper::Aces),
+            exposure: params.float("exposure", 1.0, 0.05..=20.0),
+            bloom: params.toggle("bloom", true),
+            threshold: params.float("threshold", defaults.threshold, 0.0..=5.0),
+            knee: params.float("knee", defaults.knee, 0.0..=1.0),
+            intensity: params.float("intensity", defaults.intensity, 0.0..=3.0),
+            blur: params.choice("blur", Blur::DualKawase),
+            blur_steps: params.int("blur_steps", 5, 1..=MAX_LEVELS as i32),
+        }
+    }
+
+    fn tweaks(&self, params: &Params) -> Tweaks {
+        let steps = params.get(self.blur_steps).unsigned_abs();
+        Tweaks {
+            tonemapper: params.get(self.tonemapper),
+            exposure: params.get(self.exposure),
+            bloom: params.get(self.bloom),
+            settings: BloomSettings {
+                threshold: params.get(self.threshold),
+                knee: params.get(self.knee),
+                intensity: params.get(self.intensity),
+                blur: match params.get(self.blur) {
+                    Blur::Gaussian => BloomBlur::Gaussian { passes: steps },
+                    Blur::DualKawase => BloomBlur::DualKawase { levels: steps },
+                },
+            },
+        }
+    }
+}
+
+/// Declares the lesson's parameters. `main` calls this too, to check `--params` and `--set`
+/// before the window opens.
+pub fn declare_params(params: &mut ParamsBuilder) -> TweakParams {
+    TweakParams::declare(params)
+}
+
+/// Everything the parameters set, applied to the post-processor whenever it changes.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Tweaks {
+    tonemapper: Tonemapper,
+    exposure: f32,
+    bloom: bool,
+    settings: BloomSettings,
+}
+
+impl Tweaks {
+    fn describe(&self) -> String {
+        let bloom = if self.bloom {
+            format!(
+                "bloom on ({}, threshold {:.2})",
+                self.settings.blur.name(),
+                self.settings.threshold
+            )
+        } else {
+            "bloom off".to_owned()
+        };
+        format!(
+            "{}, exposure {:.2}, {bloom}",
+            self.tonemapper.name(),
+            self.exposure
+        )
+    }
+}
+
+/// A few cubes lit by lights far brighter than white, whose bulbs glow into their surroundings.
+/// T cycles the tonemapper, Up and Down change the exposure, B toggles bloom, K switches between
+/// Gaussian and dual Kawase blur, and [ and ] lower and raise the bloom threshold. All of them,
+/// and the rest of the bloom settings, can also be changed in the GUI.
+pub fn run(args: &Args) -> Result<()> {
+    let mut glfw_obj = glfw::init_no_callbacks()?;
+    glfw_obj.window_hint(WindowHint::OpenGlProfile(OpenGlProfileHint::Core));
+    glfw_obj.window_hint(WindowHint::ContextVersion(3, 3));
+    harness::window_hints(&mut glfw_obj, args);
+
+    #[cfg(target_os = "macos")]
+    glfw_obj.window_hint(WindowHint::OpenGlForwardCompat(true));
+
+    let (mut window, events_rx) = glfw_obj
+        .create_window(1280, 720, TITLE, WindowMode::Windowed)
+        .ok_or_else(|| format_err!("failed to create window"))?;
+
+    glfw_obj.make_context_current(Some(&window));
+    gl::load_with(|sym| window.get_proc_address(sym));
+
+    window.set_key_polling(true);
+    window.set_framebuffer_size_polling(true);
+
+    let mut harness = Harness::new(args)?;
+    let mut state = StateTracker::new();
+    let mut gui = Gui::new(&mut window)?;
+
+    let shaders_dir = PathBuf::new().join("shaders").join("_5_advanced_lighting");
+    let link = |vertex: &str, fragment: &str| -> Result<_> {
+        let vs = Shader::new(shaders_dir.join(vertex), ShaderType::Vertex)?;
+        let fs = Shader::new(shaders_dir.join(fragment), ShaderType::Fragment)?;
+        Linker::new().attach_shader(vs).attach_shader(fs).link()
+    };
+    let program = link(
+        "_7_bloom_vertex_shader.glsl",
+        "_7_bloom_fragment_shader.glsl",
+    )?;
+    let light_cube_program = link(
+        "_7_bloom_light_cube_vertex_shader.glsl",
+        "_7_bloom_light_cube_fragment_shader.glsl",
+    )?;
+
+    let textures_dir = PathBuf::new().join("textures");
+    let diffuse = TextureBuilder::new()
+        .flip_vertically(true)
+        .build_from_path(textures_dir.join("wall.jpg"))?;
+    let specular = TextureBuilder::new()
+        .flip_vertically(true)
+        .build_from_path(textures_dir.join("wall_specular.png"))?;
+    let phong = PhongMaterial::new(diffuse, specular, 32.0);
+
+    let mut floor_mesh = shapes::plane(30.0, 30.0, MeshOptions::default());
+    if let Some(uvs) = floor_mesh.uvs.as_mut() {
+        for uv in uvs.iter_mut() {
+            *uv = uv.map(|c| c * 10.0);
+        }
+    }
+
+    let mut scene = Scene::new();
+    let floor = scene.add_model(Model::from_mesh(&floor_mesh, program, Usage::Static)?);
+    let cube = scene.add_model(Model::from_mesh(
+        &shapes::cube(1.0, MeshOptions::default()),
+        program,
+        Usage::Static,
+    )?);
+    let bulb = scene.add_model(Model::from_mesh(
+        &shapes::cube(
+            0.4,
+            MeshOptions {
+                normals: false,
+                tangents: false,
+                uvs: false,
+            },
+        ),
+        light_cube_program,
+        Usage::Static,
+    )?);
+    let material = scene.add_material(phong.into_material(program));
+
+    let floor_node = scene.add_node("floor", None, Transform::IDENTITY);
+    scene
+        .node_mut(floor_node)
+        .drawables
+        .push(Drawable::new(floor, material));
+
+    let mut spinning = vec![];
+    for (i, (position, scale)) in [
+        (vec3(0.0, 1.5, 0.0), 1.0),
+        (vec3(2.0, 0.5, 1.0), 1.0),
+        (vec3(-1.0, 0.5, 2.0), 1.0),
+        (vec3(0.0, 2.7, 4.0), 2.5),
+        (vec3(-2.0, 1.0, -3.0), 2.0),
+        (vec3(-3.0, 0.5, 0.0), 1.0),
+        (vec3(3.5, 1.0, -2.5), 2.0),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let node = scene.add_node(
+            &format!("cube {i}"),
+            None,
+            Transform::from_translation(position).with_scale(Vec3::splat(scale)),
+        );
+        scene
+            .node_mut(node)
+            .drawables
+            .push(Drawable::new(cube, material));
+        spinning.push((node, i as f32 * 0.7));
+    }
+
+    // Colors well past 1, so that the bulbs and the surfaces right next to them are brighter than
+    // white and bloom.
+    for (i, (position, color)) in [
+        (vec3(0.0, 0.5, 1.5), vec3(10.0, 10.0, 10.0)),
+        (vec3(-4.0, 0.5, -3.0), vec3(10.0, 0.0, 0.0)),
+        (vec3(3.0, 0.5, 1.0), vec3(0.0, 0.0, 15.0)),
+        (vec3(-0.8, 2.4, -1.0), vec3(0.0, 5.0, 0.0)),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let mut bulb_material = Material::new(light_cube_program);
+        bulb_material.set("lightColor", color);
+        let bulb_material = scene.add_material(bulb_material);
+
+        let node = scene.add_node(
+            &format!("light {i}"),
+            None,
+            Transform::from_translation(position),
+        );
+        let node = scene.node_mut(node);
+        node.drawables.push(Drawable::new(bulb, bulb_material));
+        node.light = Some(PointLight::new(Vec3::ZERO, color, 12.0).into());
+    }
+
+    let camera = scene.add_node("camera", None, Transform::IDENTITY);
+    scene.node_mut(camera).camera = Some(Camera::new(45f32.to_radians(), 0.1, 100.0));
+
+    let mut renderer = Renderer::new();
+    let matrices = UniformBuffer::new(
+        &Matrices::default(),
+        Usage::Dynamic,
+        harness.uniform_bindings(),
+    )?;
+    let lights = UniformBuffer::new(
+        &scene.lighting(Shading::BlinnPhong).block(Vec3::ZERO)?,
+        Usage::Dynamic,
+        harness.uniform_bindings(),
+    )?;
+    matrices.attach(&program, "Matrices")?;
+    matrices.attach(&light_cube_program, "Matrices")?;
+    lights.attach(&program, "Lights")?;
+
+    let mut params = ParamsBuilder::new();
+    let handles = declare_params(&mut params);
+    let mut params = params.build(args)?;
+    let tweaks = handles.tweaks(&params);
+    // Custom effects only pick up the tweaks if they include a tonemapper.
+    let effects = if args.post.is_empty() {
+        vec![
+            Effect::Tonemap {
+                exposure: tweaks.exposure,
+                operator: tweaks.tonemapper,
+            },
+            Effect::Gamma(2.2),
+        ]
+    } else {
+        args.post.clone()
+    };
+    let (width, height) = window.get_framebuffer_size();
+    let mut post_processor =
+        PostProcessor::new(u32::try_from(width)?, u32::try_from(height)?, &effects)?;
+
+    let mut previous = None;
+    while !window.should_close() {
+        handle_events(
+            &events_rx,
+            &mut window,
+            &mut harness,
+            &mut post_processor,
+            &mut gui,
+            &mut params,
+            &handles,
+        )?;
+        gui.run(&window, glfw_obj.get_time(), |ctx| {
+            params.window(ctx, TITLE);
+        })?;
+        let tweaks = handles.tweaks(&params);
+        if previous != Some(tweaks) {
+            apply_tweaks(&mut post_processor, &tweaks)?;
+            window.set_title(&format!("{TITLE} - {}", tweaks.describe()));
+            previous = Some(tweaks);
+        }
+
+        let time = glfw_obj.get_time() as f32;
+        let eye = vec3((time * 0.15).sin() * 11.0, 4.0, (time * 0.15).cos() * 11.0);
+        scene.set_transform(camera, Transform::looking_at(eye, Vec3::ZERO, Vec3::Y));
+        for (node, phase) in &spinning {
+            scene.set_transform(
+                *node,
+                scene
+                    .node(*node)
+                    .transform()
+                    .with_rotation(Quat::from_rotation_y(time * 0.3 + phase)),
+            );
+        }
+        scene.update_world_transforms();
+
+        let (width, height) = window.get_framebuffer_size();
+        let camera_matrices = scene.camera_matrices(camera, width as f32 / height.max(1) as f32)?;
+        matrices.update(&camera_matrices);
+        lights.update(&scene.lighting(Shading::BlinnPhong).block(eye)?);
+
+        {
+            let _scene = harness.profiler().scope("scene");
+            post_processor.begin();
+            state.clear(&Clear::all([0.0, 0.0, 0.0, 1.0]));
+            renderer.render(&mut scene, &mut state, &camera_matrices.view)?;
+        }
+        renderer.end_frame();
+
+        {
+            let _post = harness.profiler().scope("post-processing");
+            post_processor.apply(&mut state, width, height)?;
+        }
+        gui.draw(&mut state, width, height)?;
+
+        harness.end_frame(&mut window)?;
+        window.swap_buffers();
+        glfw_obj.poll_events();
+    }
+
+    params.save()?;
+    harness.finish()
+}
+
+fn apply_tweaks(post_processor: &mut PostProcessor, tweaks: &Tweaks) -> Result<()> {
+    for effect in post_processor.effects_mut() {
+        if let Effect::Tonemap { exposure, operator } = effect {
+            *exposure = tweaks.exposure;
+            *operator = tweaks.tonemapper;
+        }
+    }
+    post_processor.set_bloom(tweaks.bloom.then_some(tweaks.settings))
+}
+
+fn handle_events(
+    events_rx: &GlfwReceiver<(f64, WindowEvent)>,
+    window: &mut PWindow,
+    harness: &mut Harness,
+    post_processor: &mut PostProcessor,
+    gui: &mut Gui,
+    params: &mut Params,
+    handles: &TweakParams,
+) -> Result<()> {
+    for (_, event) in glfw::flush_messages(events_rx) {
+        harness.handle_event(&event);
+        post_processor.handle_event(&event)?;
+        gui.handle_event(&event);
+
+        match event {
+            WindowEvent::Key(key, _, Action::Press | Action::Repeat, modifier)
+                if !gui.wants_keyboard() =>
+            {
+                match (modifier, key) {
+                    (Modifiers::Super, Key::W) | (_, Key::Escape) => window.set_should_close(true),
+                    (_, Key::T) => {
+                        params.set(handles.tonemapper, params.get(handles.tonemapper).next())
+                    }
+                    (_, Key::Up) => {
+                        params.set(handles.exposure, params.get(handles.exposure) * 1.1)
+                    }
+                    (_, Key::Down) => {
+                        params.set(handles.exposure, params.get(handles.exposure) / 1.1)
+                    }
+                    (_, Key::B) => params.set(handles.bloom, !params.get(handles.bloom)),
+                    (_, Key::K) => {
+                        let blur = match params.get(handles.blur) {
+                            Blur::Gaussian => Blur::DualKawase,
+                            Blur::DualKawase => Blur::Gaussian,
+                        };
+                        params.set(handles.blur, blur);
+                    }
+                    (_, Key::LeftBracket) => {
+                        params.set(handles.threshold, params.get(handles.threshold) - 0.1)
+                    }
+                    (_, Key::RightBracket) => {
+                        params.set(handles.threshold, params.get(handles.threshold) + 0.1)
+                    }
+                    _ => (),
+                }
+            }
+            WindowEvent::FramebufferSize(width, height) => unsafe {
+                gl::Viewport(0, 0, width, height);
+            },
+            _ => (),
+        }
+    }
+    Ok(())
+}
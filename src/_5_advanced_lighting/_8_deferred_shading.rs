@@ -0,0 +1,395 @@
+use crate::{
+    cli::Args,
+    glutils::{
+        deferred::{DeferredRenderer, LightingMode},
+        lighting::{
+            DirectionalLight, Lights, PhongMaterial, PointLight, Shading, MAX_POINT_LIGHTS,
+        },
+        material::Material,
+        mesh::{shapes, MeshOptions},
+        model::{usage::Usage, Model},
+        render_state::{Clear, RenderState, StateTracker},
+        scene::{
+            camera::Camera, renderer::Renderer, transform::Transform, Drawable, MaterialId, Scene,
+        },
+        shader::{
+            program::{Linker, Program},
+            Shader, ShaderType,
+        },
+        texture::TextureBuilder,
+        uniform_buffer::{Matrices, UniformBuffer},
+    },
+    harness::{self, Harness},
+};
+use anyhow::{format_err, Result};
+use glam::{vec3, vec4, Quat, Vec3};
+use glfw::{
+    Action, Context, GlfwReceiver, Key, Modifiers, OpenGlProfileHint, PWindow, WindowEvent,
+    WindowHint, WindowMode,
+};
+use std::path::PathBuf;
+
+const TITLE: &str = "Deferred Shading";
+
+/// Far more than a forward pass can handle, see [`MAX_POINT_LIGHTS`].
+const NUM_LIGHTS: usize = 96;
+
+/// How the lesson shades its opaque geometry, cycled through with R.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Path {
+    Deferred(LightingMode),
+    /// The forward renderer, which only gets to use the first few lights.
+    Forward,
+}
+
+impl Path {
+    fn next(self) -> Self {
+        match self {
+            Self::Deferred(LightingMode::LightVolumes) => Self::Deferred(LightingMode::FullScreen),
+            Self::Deferred(LightingMode::FullScreen) => Self::Forward,
+            Self::Forward => Self::Deferred(LightingMode::LightVolumes),
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Deferred(LightingMode::LightVolumes) => "deferred, light volumes",
+            Self::Deferred(LightingMode::FullScreen) => "deferred, full-screen passes",
+            Self::Forward => "forward, first lights only",
+        }
+    }
+}
+
+/// A grid of cubes lit by a swarm of small colored lights, shaded once per pixel through a
+/// G-buffer rather than once per fragment drawn. The light bulbs and the glass panes in
+/// between are drawn forward on top of the deferred result. R cycles between drawing the
+/// lights as volumes, as full-screen passes and the forward renderer, and O toggles ambient
+/// occlusion, which only the deferred paths have.
+pub fn run(args: &Args) -> Result<()> {
+    let mut glfw_obj = glfw::init_no_callbacks()?;
+    glfw_obj.window_hint(WindowHint::OpenGlProfile(OpenGlProfileHint::Core));
+    glfw_obj.window_hint(WindowHint::ContextVersion(3, 3));
+    harness::window_hints(&mut glfw_obj, args);
+
+    #[cfg(target_os = "macos")]
+    glfw_obj.window_hint(WindowHint::OpenGlForwardCompat(true));
+
+    let (mut window, events_rx) = glfw_obj
+        .create_window(1280, 720, TITLE, WindowMode::Windowed)
+        .ok_or_else(|| format_err!("failed to create window"))?;
+
+    glfw_obj.make_context_current(Some(&window));
+    gl::load_with(|sym| window.get_proc_address(sym));
+
+    window.set_key_polling(true);
+    window.set_framebuffer_size_polling(true);
+
+    let mut harness = Harness::new(args)?;
+    let mut state = StateTracker::new();
+
+    let matrices = UniformBuffer::new(
+        &Matrices::default(),
+        Usage::Dynamic,
+        harness.uniform_bindings(),
+    )?;
+    let (width, height) = window.get_framebuffer_size();
+    let mut deferred = DeferredRenderer::new(
+        try_into(width)?,
+        try_into(height)?,
+        &matrices,
+        harness.uniform_bindings(),
+    )?;
+    deferred.set_ssao_enabled(true);
+
+    let shaders_dir = PathBuf::new().join("shaders").join("_5_advanced_lighting");
+    let link = |vertex: &str, fragment: &str| -> Result<_> {
+        let vs = Shader::new(shaders_dir.join(vertex), ShaderType::Vertex)?;
+        let fs = Shader::new(shaders_dir.join(fragment), ShaderType::Fragment)?;
+        Linker::new().attach_shader(vs).attach_shader(fs).link()
+    };
+    let forward_program = link(
+        "_8_deferred_shading_vertex_shader.glsl",
+        "_8_deferred_shading_fragment_shader.glsl",
+    )?;
+    let light_cube_program = link(
+        "_8_deferred_shading_light_cube_vertex_shader.glsl",
+        "_8_deferred_shading_light_cube_fragment_shader.glsl",
+    )?;
+    let glass_program = link(
+        "_8_deferred_shading_light_cube_vertex_shader.glsl",
+        "_8_deferred_shading_glass_fragment_shader.glsl",
+    )?;
+    for program in [forward_program, light_cube_program, glass_program] {
+        matrices.attach(&program, "Matrices")?;
+    }
+
+    let textures_dir = PathBuf::new().join("textures");
+    let diffuse = TextureBuilder::new()
+        .flip_vertically(true)
+        .build_from_path(textures_dir.join("wall.jpg"))?;
+    let specular = TextureBuilder::new()
+        .flip_vertically(true)
+        .build_from_path(textures_dir.join("wall_specular.png"))?;
+    let phong = PhongMaterial::new(diffuse, specular, 32.0);
+
+    let mut floor_mesh = shapes::plane(40.0, 40.0, MeshOptions::default());
+    if let Some(uvs) = floor_mesh.uvs.as_mut() {
+        for uv in uvs.iter_mut() {
+            *uv = uv.map(|c| c * 16.0);
+        }
+    }
+
+    let mut scene = Scene::new();
+    let geometry_program = deferred.geometry_program();
+    let floor = scene.add_model(Model::from_mesh(
+        &floor_mesh,
+        geometry_program,
+        Usage::Static,
+    )?);
+    let cube = scene.add_model(Model::from_mesh(
+        &shapes::cube(1.0, MeshOptions::default()),
+        geometry_program,
+        Usage::Static,
+    )?);
+    let bulb = scene.add_model(Model::from_mesh(
+        &shapes::icosphere(
+            0.08,
+            1,
+            MeshOptions {
+                normals: false,
+                tangents: false,
+                uvs: false,
+            },
+        ),
+        light_cube_program,
+        Usage::Static,
+    )?);
+    let pane = scene.add_model(Model::from_mesh(
+        &shapes::cube(
+            1.0,
+            MeshOptions {
+                normals: false,
+                tangents: false,
+                uvs: false,
+            },
+        ),
+        glass_program,
+        Usage::Static,
+    )?);
+
+    let material = scene.add_material(phong.into_material(geometry_program));
+    let mut glass = Material::new(glass_program);
+    glass.set("glassColor", vec4(0.6, 0.8, 1.0, 0.25));
+    glass.set_render_state(RenderState::TRANSPARENT);
+    let glass = scene.add_material(glass);
+
+    let floor_node = scene.add_node("floor", None, Transform::IDENTITY);
+    scene
+        .node_mut(floor_node)
+        .drawables
+        .push(Drawable::new(floor, material));
+
+    for x in -4..=4 {
+        for z in -4..=4 {
+            let position = vec3(x as f32 * 3.0, 0.75, z as f32 * 3.0);
+            let node = scene.add_node(
+                &format!("cube {x} {z}"),
+                None,
+                Transform::from_translation(position)
+                    .with_rotation(Quat::from_rotation_y((x * 3 + z) as f32 * 0.4))
+                    .with_scale(Vec3::splat(1.5)),
+            );
+            scene
+                .node_mut(node)
+                .drawables
+                .push(Drawable::new(cube, material));
+        }
+    }
+    for i in 0..4 {
+        let node = scene.add_node(
+            &format!("glass pane {i}"),
+            None,
+            Transform::from_translation(vec3(-4.5 + i as f32 * 3.0, 1.5, 1.5))
+                .with_scale(vec3(2.0, 3.0, 0.05)),
+        );
+        scene
+            .node_mut(node)
+            .drawables
+            .push(Drawable::new(pane, glass));
+    }
+
+    let sun = scene.add_node("sun", None, Transform::IDENTITY);
+    scene.node_mut(sun).light =
+        Some(DirectionalLight::new(vec3(-0.3, -1.0, -0.5), vec3(0.15, 0.15, 0.2)).into());
+
+    // The lights circle the center at different heights and speeds, so each gets a parent to
+    // spin.
+    let mut orbits = vec![];
+    for i in 0..NUM_LIGHTS {
+        let hue = i as f32 / NUM_LIGHTS as f32;
+        let color = hue_to_rgb(hue);
+        let mut bulb_material = Material::new(light_cube_program);
+        bulb_material.set("lightColor", color);
+        let bulb_material = scene.add_material(bulb_material);
+
+        let orbit = scene.add_node(&format!("orbit {i}"), None, Transform::IDENTITY);
+        let radius = 2.0 + (i % 12) as f32 * 1.4;
+        let height = 0.3 + (i * 5 % 7) as f32 * 0.3;
+        let angle = i as f32 * 2.4;
+        let node = scene.add_node(
+            &format!("light {i}"),
+            Some(orbit),
+            Transform::from_translation(vec3(angle.cos() * radius, height, angle.sin() * radius)),
+        );
+        let node = scene.node_mut(node);
+        node.drawables.push(Drawable::new(bulb, bulb_material));
+        node.light = Some(PointLight::new(Vec3::ZERO, color, 4.0).into());
+        orbits.push((orbit, 0.1 + (i % 5) as f32 * 0.05));
+    }
+
+    let camera = scene.add_node("camera", None, Transform::IDENTITY);
+    scene.node_mut(camera).camera = Some(Camera::new(45f32.to_radians(), 0.1, 100.0));
+
+    let mut renderer = Renderer::new();
+    // Filled in each frame when rendering forward, which only gets the first few lights.
+    let lights = UniformBuffer::new(
+        &Lights::default(),
+        Usage::Dynamic,
+        harness.uniform_bindings(),
+    )?;
+    lights.attach(&forward_program, "Lights")?;
+
+    let mut path = Path::Deferred(LightingMode::LightVolumes);
+    let mut frame = 0u64;
+    while !window.should_close() {
+        let previous = path;
+        handle_events(
+            &events_rx,
+            &mut window,
+            &mut harness,
+            &mut deferred,
+            &mut path,
+        )?;
+        if path != previous {
+            set_path(
+                &mut scene,
+                &mut deferred,
+                material,
+                phong,
+                forward_program,
+                path,
+            );
+        }
+
+        let time = glfw_obj.get_time() as f32;
+        let eye = vec3((time * 0.1).sin() * 16.0, 7.0, (time * 0.1).cos() * 16.0);
+        scene.set_transform(camera, Transform::looking_at(eye, Vec3::ZERO, Vec3::Y));
+        for (orbit, speed) in &orbits {
+            scene.set_transform(
+                *orbit,
+                Transform::from_rotation(Quat::from_rotation_y(time * speed)),
+            );
+        }
+        scene.update_world_transforms();
+
+        let (width, height) = window.get_framebuffer_size();
+        let camera_matrices = scene.camera_matrices(camera, width as f32 / height.max(1) as f32)?;
+        matrices.update(&camera_matrices);
+        let mut lighting = scene.lighting(Shading::BlinnPhong);
+
+        state.clear(&Clear::all([0.02, 0.02, 0.03, 1.0]));
+        {
+            let _scene = harness.profiler().scope("scene");
+            match path {
+                Path::Deferred(_) => deferred.render(
+                    &mut renderer,
+                    &mut scene,
+                    &mut state,
+                    &camera_matrices.view,
+                    &lighting,
+                )?,
+                Path::Forward => {
+                    lighting.point.truncate(MAX_POINT_LIGHTS);
+                    lights.update(&lighting.block(eye)?);
+                    renderer.render(&mut scene, &mut state, &camera_matrices.view)?;
+                }
+            }
+        }
+
+        renderer.end_frame();
+        if frame.is_multiple_of(60) {
+            window.set_title(&format!(
+                "{TITLE} ({}, ssao {}) - {}",
+                path.describe(),
+                if deferred.ssao_enabled() { "on" } else { "off" },
+                renderer.last_frame_stats()
+            ));
+        }
+        frame += 1;
+
+        harness.end_frame(&mut window)?;
+        window.swap_buffers();
+        glfw_obj.poll_events();
+    }
+
+    harness.finish()
+}
+
+/// Switches the opaque material between the G-buffer and forward programs.
+fn set_path(
+    scene: &mut Scene,
+    deferred: &mut DeferredRenderer,
+    material: MaterialId,
+    phong: PhongMaterial,
+    forward_program: Program,
+    path: Path,
+) {
+    let program = match path {
+        Path::Deferred(mode) => {
+            deferred.set_mode(mode);
+            deferred.geometry_program()
+        }
+        Path::Forward => forward_program,
+    };
+    *scene.material_mut(material) = phong.into_material(program);
+}
+
+/// Fully saturated color of the given hue in `[0, 1]`.
+fn hue_to_rgb(hue: f32) -> Vec3 {
+    let channel = |offset: f32| {
+        let h = (hue + offset).fract() * 6.0;
+        (2.0 - (h - 3.0).abs()).clamp(0.0, 1.0)
+    };
+    vec3(channel(0.0), channel(2.0 / 3.0), channel(1.0 / 3.0))
+}
+
+fn try_into(size: i32) -> Result<u32> {
+    u32::try_from(size).map_err(|e| format_err!("invalid framebuffer size {size}: {e}"))
+}
+
+fn handle_events(
+    events_rx: &GlfwReceiver<(f64, WindowEvent)>,
+    window: &mut PWindow,
+    harness: &mut Harness,
+    deferred: &mut DeferredRenderer,
+    path: &mut Path,
+) -> Result<()> {
+    for (_, event) in glfw::flush_messages(events_rx) {
+        harness.handle_event(&event);
+        deferred.handle_event(&event)?;
+
+        match event {
+            WindowEvent::Key(key, _, Action::Press, modifier) => match (modifier, key) {
+                (Modifiers::Super, Key::W) | (_, Key::Escape) => window.set_should_close(true),
+                (_, Key::R) => *path = path.next(),
+                (_, Key::O) => deferred.set_ssao_enabled(!deferred.ssao_enabled()),
+                _ => (),
+            },
+            WindowEvent::FramebufferSize(width, height) => unsafe {
+                gl::Viewport(0, 0, width, height);
+            },
+            _ => (),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,4 @@
+pub mod _3_shadow_mapping;
+pub mod _5_parallax_mapping;
+pub mod _7_bloom;
+pub mod _8_deferred_shading;
@@ -0,0 +1,371 @@
+use crate::{
+    cli::Args,
+    glutils::{
+        lighting::{PointLight, Shading},
+        mesh::{shapes, MeshOptions},
+        model::{usage::Usage, Model},
+        pbr::{
+            flat_normal_texture,
+            ibl::{Ibl, IblBaker},
+            PbrMaterial,
+        },
+        postprocess::{
+            effect::{Effect, Tonemapper},
+            PostProcessor,
+        },
+        render_state::{Clear, StateTracker},
+        scene::{
+            camera::Camera, renderer::Renderer, transform::Transform, Drawable, MaterialId, Scene,
+        },
+        shader::{
+            program::{Linker, Program},
+            Shader, ShaderType,
+        },
+        skybox::Skybox,
+        texture::{Texture, TextureBuilder},
+        uniform_buffer::{Matrices, UniformBuffer},
+    },
+    harness::{self, Harness},
+};
+use anyhow::{format_err, Result};
+use glam::{vec3, Quat, Vec3};
+use glfw::{
+    Action, Context, GlfwReceiver, Key, Modifiers, OpenGlProfileHint, PWindow, WindowEvent,
+    WindowHint, WindowMode,
+};
+use image::Rgb32FImage;
+use std::{f32::consts::PI, path::PathBuf};
+
+const TITLE: &str = "Image Based Lighting";
+
+/// Equirectangular HDR panorama to light the scene with. Without it, a procedural sky is baked
+/// every run instead.
+const PANORAMA: &str = "textures/hdr/environment.hdr";
+
+/// Spheres per side of the grid, from smooth to rough across and from dielectric to metal up.
+const GRID_SIZE: usize = 7;
+
+/// A grid of spheres sweeping through metalness and roughness, and a bumpy textured one, lit by
+/// a few point lights and by the environment around them. The environment's diffuse and
+/// specular light is baked once into cube maps and cached under `cache/`. I toggles the
+/// environment's light.
+pub fn run(args: &Args) -> Result<()> {
+    let mut glfw_obj = glfw::init_no_callbacks()?;
+    glfw_obj.window_hint(WindowHint::OpenGlProfile(OpenGlProfileHint::Core));
+    glfw_obj.window_hint(WindowHint::ContextVersion(3, 3));
+    harness::window_hints(&mut glfw_obj, args);
+
+    #[cfg(target_os = "macos")]
+    glfw_obj.window_hint(WindowHint::OpenGlForwardCompat(true));
+
+    let (mut window, events_rx) = glfw_obj
+        .create_window(1280, 720, TITLE, WindowMode::Windowed)
+        .ok_or_else(|| format_err!("failed to create window"))?;
+
+    glfw_obj.make_context_current(Some(&window));
+    gl::load_with(|sym| window.get_proc_address(sym));
+
+    window.set_key_polling(true);
+    window.set_framebuffer_size_polling(true);
+
+    let mut harness = Harness::new(args)?;
+    let mut state = StateTracker::new();
+
+    let baker = IblBaker::new();
+    let ibl = if PathBuf::from(PANORAMA).exists() {
+        baker.bake_from_path(&mut state, PANORAMA)?
+    } else {
+        log::info!("'{PANORAMA}' not found, lighting with a procedural sky");
+        baker.bake_from_image(&mut state, &procedural_sky(1024, 512))?
+    };
+
+    let shaders_dir = PathBuf::new().join("shaders").join("_6_pbr");
+    let link = |fragment: &str| -> Result<_> {
+        let vs = Shader::new(
+            shaders_dir.join("_2_image_based_lighting_vertex_shader.glsl"),
+            ShaderType::Vertex,
+        )?;
+        let fs = Shader::new(shaders_dir.join(fragment), ShaderType::Fragment)?;
+        Linker::new().attach_shader(vs).attach_shader(fs).link()
+    };
+    let ibl_program = link("_2_image_based_lighting_fragment_shader.glsl")?;
+    let plain_program = link("_2_image_based_lighting_no_ibl_fragment_shader.glsl")?;
+
+    let matrices = UniformBuffer::new(
+        &Matrices::default(),
+        Usage::Dynamic,
+        harness.uniform_bindings(),
+    )?;
+    let mut skybox = Skybox::new(ibl.environment, &matrices)?;
+
+    let mut scene = Scene::new();
+    let sphere = scene.add_model(Model::from_mesh(
+        &shapes::uv_sphere(0.45, 48, 24, MeshOptions::default()),
+        ibl_program,
+        Usage::Static,
+    )?);
+
+    // Each material is kept around so that it can be rebuilt for either program.
+    let mut materials = vec![];
+    for row in 0..GRID_SIZE {
+        for column in 0..GRID_SIZE {
+            let metallic = row as f32 / (GRID_SIZE - 1) as f32;
+            // Perfectly smooth surfaces reflect lights as single points, which look off.
+            let roughness = (column as f32 / (GRID_SIZE - 1) as f32).clamp(0.05, 1.0);
+            let pbr = PbrMaterial::uniform(vec3(0.5, 0.0, 0.0), metallic, roughness)?;
+            let material = scene.add_material(pbr.into_material(ibl_program, Some(&ibl)));
+            materials.push((material, pbr));
+
+            let offset = (GRID_SIZE - 1) as f32 * 0.5;
+            let node = scene.add_node(
+                &format!("sphere {row} {column}"),
+                None,
+                Transform::from_translation(vec3(
+                    (column as f32 - offset) * 1.1,
+                    (row as f32 - offset) * 1.1,
+                    0.0,
+                )),
+            );
+            scene
+                .node_mut(node)
+                .drawables
+                .push(Drawable::new(sphere, material));
+        }
+    }
+
+    let textured = PbrMaterial {
+        albedo: TextureBuilder::new()
+            .flip_vertically(true)
+            .build_from_path(PathBuf::new().join("textures").join("wall.jpg"))?,
+        normal: bumps_normal_texture(256, 8)?,
+        ..PbrMaterial::uniform(Vec3::ONE, 0.0, 0.6)?
+    };
+    let textured_material = scene.add_material(textured.into_material(ibl_program, Some(&ibl)));
+    materials.push((textured_material, textured));
+    let textured_node = scene.add_node(
+        "textured sphere",
+        None,
+        Transform::from_translation(vec3(5.5, 0.0, 0.0)).with_scale(Vec3::splat(3.0)),
+    );
+    scene
+        .node_mut(textured_node)
+        .drawables
+        .push(Drawable::new(sphere, textured_material));
+
+    for (i, position) in [
+        vec3(-6.0, 6.0, 8.0),
+        vec3(6.0, 6.0, 8.0),
+        vec3(-6.0, -6.0, 8.0),
+        vec3(6.0, -6.0, 8.0),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let node = scene.add_node(
+            &format!("light {i}"),
+            None,
+            Transform::from_translation(position),
+        );
+        scene.node_mut(node).light =
+            Some(PointLight::new(Vec3::ZERO, Vec3::splat(30.0), 40.0).into());
+    }
+
+    let camera = scene.add_node("camera", None, Transform::IDENTITY);
+    scene.node_mut(camera).camera = Some(Camera::new(45f32.to_radians(), 0.1, 100.0));
+
+    let mut renderer = Renderer::new();
+    let lights = UniformBuffer::new(
+        &scene.lighting(Shading::BlinnPhong).block(Vec3::ZERO)?,
+        Usage::Dynamic,
+        harness.uniform_bindings(),
+    )?;
+    for program in [ibl_program, plain_program] {
+        matrices.attach(&program, "Matrices")?;
+        lights.attach(&program, "Lights")?;
+    }
+
+    // Lighting is computed in linear HDR, so it needs tonemapping and gamma correction on the
+    // way to the screen.
+    let effects = if args.post.is_empty() {
+        vec![
+            Effect::Tonemap {
+                exposure: 1.0,
+                operator: Tonemapper::Aces,
+            },
+            Effect::Gamma(2.2),
+        ]
+    } else {
+        args.post.clone()
+    };
+    let (width, height) = window.get_framebuffer_size();
+    let mut post_processor =
+        PostProcessor::new(u32::try_from(width)?, u32::try_from(height)?, &effects)?;
+
+    let mut use_ibl = true;
+    while !window.should_close() {
+        let previous = use_ibl;
+        handle_events(
+            &events_rx,
+            &mut window,
+            &mut harness,
+            &mut post_processor,
+            &mut use_ibl,
+        )?;
+        if use_ibl != previous {
+            set_ibl(
+                &mut scene,
+                &materials,
+                ibl_program,
+                plain_program,
+                &ibl,
+                use_ibl,
+            );
+            window.set_title(&format!(
+                "{TITLE} - environment light {}",
+                if use_ibl { "on" } else { "off" }
+            ));
+        }
+
+        let time = glfw_obj.get_time() as f32;
+        let eye = vec3((time * 0.1).sin() * 12.0, 1.0, (time * 0.1).cos() * 12.0);
+        scene.set_transform(camera, Transform::looking_at(eye, Vec3::ZERO, Vec3::Y));
+        scene.set_transform(
+            textured_node,
+            scene
+                .node(textured_node)
+                .transform()
+                .with_rotation(Quat::from_rotation_y(time * 0.2)),
+        );
+        scene.update_world_transforms();
+
+        let (width, height) = window.get_framebuffer_size();
+        let camera_matrices = scene.camera_matrices(camera, width as f32 / height.max(1) as f32)?;
+        matrices.update(&camera_matrices);
+        lights.update(&scene.lighting(Shading::BlinnPhong).block(eye)?);
+
+        {
+            let _scene = harness.profiler().scope("scene");
+            post_processor.begin();
+            state.clear(&Clear::new().depth(1.0));
+            renderer.render(&mut scene, &mut state, &camera_matrices.view)?;
+            skybox.draw(&mut state)?;
+        }
+        renderer.end_frame();
+
+        {
+            let _post = harness.profiler().scope("post-processing");
+            post_processor.apply(&mut state, width, height)?;
+        }
+
+        harness.end_frame(&mut window)?;
+        window.swap_buffers();
+        glfw_obj.poll_events();
+    }
+
+    harness.finish()
+}
+
+/// Rebuilds every material for the program with or without environment lighting.
+fn set_ibl(
+    scene: &mut Scene,
+    materials: &[(MaterialId, PbrMaterial)],
+    ibl_program: Program,
+    plain_program: Program,
+    ibl: &Ibl,
+    use_ibl: bool,
+) {
+    for (id, pbr) in materials {
+        *scene.material_mut(*id) = if use_ibl {
+            pbr.into_material(ibl_program, Some(ibl))
+        } else {
+            pbr.into_material(plain_program, None)
+        };
+    }
+}
+
+/// An equirectangular panorama of a clear sky over dark ground, with a sun bright enough to
+/// stand out after tonemapping.
+fn procedural_sky(width: u32, height: u32) -> Rgb32FImage {
+    let sun = vec3(0.6, 0.5, -0.6).normalize();
+    Rgb32FImage::from_fn(width, height, |x, y| {
+        // The inverse of the lookup in `equirectangular_fragment_shader.glsl`.
+        let longitude = ((x as f32 + 0.5) / width as f32 - 0.5) * 2.0 * PI;
+        let latitude = (y as f32 + 0.5) / height as f32 * PI;
+        let direction = vec3(
+            latitude.sin() * longitude.cos(),
+            latitude.cos(),
+            latitude.sin() * longitude.sin(),
+        );
+
+        let color = if direction.y >= 0.0 {
+            let horizon = vec3(1.2, 1.1, 1.0);
+            let zenith = vec3(0.2, 0.4, 1.0);
+            let sky = horizon.lerp(zenith, direction.y.sqrt());
+            let to_sun = direction.dot(sun).max(0.0);
+            let glow = vec3(4.0, 3.0, 2.0) * to_sun.powf(64.0);
+            let disc = if to_sun > 0.9995 {
+                vec3(200.0, 180.0, 150.0)
+            } else {
+                Vec3::ZERO
+            };
+            sky + glow + disc
+        } else {
+            vec3(0.15, 0.12, 0.1)
+        };
+        image::Rgb(color.to_array())
+    })
+}
+
+/// A normal map of a `cells` by `cells` grid of round bumps, `size` texels square.
+fn bumps_normal_texture(size: u32, cells: u32) -> Result<Texture> {
+    if cells == 0 {
+        return flat_normal_texture();
+    }
+    let cell_size = size as f32 / cells as f32;
+    let mut texels = Vec::with_capacity(usize::try_from(size * size * 4)?);
+    for y in 0..size {
+        for x in 0..size {
+            // Position within the cell in [-1, 1], from which the bump's slope follows.
+            let dx = ((x as f32 + 0.5) / cell_size).fract() * 2.0 - 1.0;
+            let dy = ((y as f32 + 0.5) / cell_size).fract() * 2.0 - 1.0;
+            let radius = 0.8;
+            let r2 = (dx * dx + dy * dy) / (radius * radius);
+            let normal = if r2 < 1.0 {
+                vec3(dx, dy, (1.0 - r2).sqrt() * radius).normalize()
+            } else {
+                Vec3::Z
+            };
+            let [r, g, b] = (normal * 0.5 + 0.5)
+                .to_array()
+                .map(|c| (c * 255.0).round() as u8);
+            texels.extend_from_slice(&[r, g, b, 255]);
+        }
+    }
+    TextureBuilder::new().build_from_rgba(size, size, &texels)
+}
+
+fn handle_events(
+    events_rx: &GlfwReceiver<(f64, WindowEvent)>,
+    window: &mut PWindow,
+    harness: &mut Harness,
+    post_processor: &mut PostProcessor,
+    use_ibl: &mut bool,
+) -> Result<()> {
+    for (_, event) in glfw::flush_messages(events_rx) {
+        harness.handle_event(&event);
+        post_processor.handle_event(&event)?;
+
+        match event {
+            WindowEvent::Key(key, _, Action::Press, modifier) => match (modifier, key) {
+                (Modifiers::Super, Key::W) | (_, Key::Escape) => window.set_should_close(true),
+                (_, Key::I) => *use_ibl = !*use_ibl,
+                _ => (),
+            },
+            WindowEvent::FramebufferSize(width, height) => unsafe {
+                gl::Viewport(0, 0, width, height);
+            },
+            _ => (),
+        }
+    }
+    Ok(())
+}
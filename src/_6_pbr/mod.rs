@@ -0,0 +1 @@
+pub mod _2_image_based_lighting;
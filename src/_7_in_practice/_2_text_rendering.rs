@@ -0,0 +1,151 @@
+use crate::{
+    cli::Args,
+    glutils::{
+        render_state::{Clear, StateTracker},
+        text::{TextRenderer, TextStyle},
+    },
+    harness::{self, Harness},
+};
+use anyhow::{format_err, Result};
+use glam::{vec2, vec4};
+use glfw::{
+    Action, Context, GlfwReceiver, Key, Modifiers, OpenGlProfileHint, PWindow, WindowEvent,
+    WindowHint, WindowMode,
+};
+use std::path::PathBuf;
+
+const TITLE: &str = "Text Rendering";
+
+const PARAGRAPH: &str = "Glyphs are rasterized once into an atlas texture and drawn as \
+textured quads, so a whole screen of text takes a single draw call. This paragraph wraps at \
+half the window's width; resize the window to reflow it, or press Up and Down to change its \
+size.\nLines also break at newlines.";
+
+/// Screen space text over a plain background: a title, a frame rate counter, a paragraph that
+/// wraps to the window and a few lines in other colors and sizes. Up and Down scale the
+/// paragraph.
+pub fn run(args: &Args) -> Result<()> {
+    let mut glfw_obj = glfw::init_no_callbacks()?;
+    glfw_obj.window_hint(WindowHint::OpenGlProfile(OpenGlProfileHint::Core));
+    glfw_obj.window_hint(WindowHint::ContextVersion(3, 3));
+    harness::window_hints(&mut glfw_obj, args);
+
+    #[cfg(target_os = "macos")]
+    glfw_obj.window_hint(WindowHint::OpenGlForwardCompat(true));
+
+    let (mut window, events_rx) = glfw_obj
+        .create_window(1280, 720, TITLE, WindowMode::Windowed)
+        .ok_or_else(|| format_err!("failed to create window"))?;
+
+    glfw_obj.make_context_current(Some(&window));
+    gl::load_with(|sym| window.get_proc_address(sym));
+
+    window.set_key_polling(true);
+    window.set_framebuffer_size_polling(true);
+
+    let mut harness = Harness::new(args)?;
+    let mut state = StateTracker::new();
+
+    let mut text = TextRenderer::new(PathBuf::new().join("fonts").join("DejaVuSans.ttf"), 48.0)?;
+
+    let mut paragraph_size = 22.0;
+    let (mut frames, mut last_second, mut fps) = (0, glfw_obj.get_time(), 0);
+    while !window.should_close() {
+        handle_events(&events_rx, &mut window, &mut harness, &mut paragraph_size);
+
+        frames += 1;
+        let time = glfw_obj.get_time();
+        if time - last_second >= 1.0 {
+            fps = frames;
+            frames = 0;
+            last_second = time;
+        }
+
+        let (width, height) = window.get_framebuffer_size();
+        let margin = 24.0;
+        let mut cursor = vec2(margin, margin);
+
+        let title = TextStyle {
+            size: 48.0,
+            ..TextStyle::default()
+        };
+        cursor.y += text.draw(TITLE, cursor, &title).y;
+
+        let fps_text = format!("{fps} FPS");
+        let small = TextStyle {
+            color: vec4(0.6, 0.9, 0.6, 1.0),
+            size: 16.0,
+            ..TextStyle::default()
+        };
+        let fps_size = text.measure(&fps_text, &small);
+        text.draw(
+            &fps_text,
+            vec2(width as f32 - margin - fps_size.x, margin),
+            &small,
+        );
+
+        let paragraph = TextStyle {
+            color: vec4(0.85, 0.85, 0.85, 1.0),
+            size: paragraph_size,
+            max_width: Some(width as f32 / 2.0 - margin),
+        };
+        cursor.y += text.draw(PARAGRAPH, cursor + vec2(0.0, 16.0), &paragraph).y + 40.0;
+
+        // Kerning pulls pairs like AV and To together.
+        for (i, (line, color, size)) in [
+            ("AVATAR WAVE Today", vec4(1.0, 0.55, 0.2, 1.0), 40.0),
+            ("Ça coûte 5 £, garçon ½", vec4(0.4, 0.7, 1.0, 1.0), 28.0),
+            ("Half transparent", vec4(1.0, 1.0, 1.0, 0.5), 32.0),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let pulse = 1.0 + 0.05 * ((time as f32 * 2.0) + i as f32).sin();
+            let style = TextStyle {
+                color,
+                size: size * pulse,
+                ..TextStyle::default()
+            };
+            cursor.y += text.draw(line, cursor, &style).y;
+        }
+
+        {
+            let _text = harness.profiler().scope("text");
+            state.clear(&Clear::all([0.1, 0.1, 0.12, 1.0]));
+            text.flush(&mut state, width, height)?;
+        }
+
+        harness.end_frame(&mut window)?;
+        window.swap_buffers();
+        glfw_obj.poll_events();
+    }
+
+    text.delete();
+    harness.finish()
+}
+
+fn handle_events(
+    events_rx: &GlfwReceiver<(f64, WindowEvent)>,
+    window: &mut PWindow,
+    harness: &mut Harness,
+    paragraph_size: &mut f32,
+) {
+    for (_, event) in glfw::flush_messages(events_rx) {
+        harness.handle_event(&event);
+
+        match event {
+            WindowEvent::Key(key, _, Action::Press | Action::Repeat, modifier) => {
+                match (modifier, key) {
+                    (Modifiers::Super, Key::W) | (_, Key::Escape) => window.set_should_close(true),
+                    (_, Key::Up) => *paragraph_size = (*paragraph_size + 2.0).min(64.0),
+                    (_, Key::Down) => *paragraph_size = (*paragraph_size - 2.0).max(8.0),
+                    _ => (),
+                }
+            }
+            WindowEvent::FramebufferSize(width, height) => unsafe {
+                gl::Viewport(0, 0, width, height);
+            },
+            _ => (),
+        }
+    }
+}
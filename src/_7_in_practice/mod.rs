@@ -0,0 +1 @@
+pub mod _2_text_rendering;
@@ -0,0 +1,195 @@
+use crate::glutils::{capture, postprocess::effect::Effect};
+use anyhow::{format_err, Result};
+use std::path::PathBuf;
+
+const USAGE: &str = "usage: rust_learn_opengl <lesson> [--post <effect>[,<effect>...]] \
+[--screenshot <path> [--after-frames <n>]] [--record <dir>] [--profile] [--trace <path>] \
+[--params <path>] [--set <name>=<value>]...";
+
+/// Command-line arguments shared by every lesson. Lessons ignore options they don't support,
+/// except for lesson parameters, which are an error for lessons that don't declare any.
+pub struct Args {
+    pub lesson: usize,
+    /// Post-processing effects applied in order, e.g. `--post grayscale,sharpen,gamma=2.2`.
+    pub post: Vec<Effect>,
+    /// Saves a PNG or JPEG of the frame given by `after_frames` and then exits.
+    pub screenshot: Option<PathBuf>,
+    pub after_frames: Option<u64>,
+    /// Dumps every frame as a numbered PNG into this directory.
+    pub record: Option<PathBuf>,
+    /// Times GPU work and logs a summary on exit.
+    pub profile: bool,
+    /// Writes GPU timings as a Chrome trace to this JSON file. Implies `profile`.
+    pub trace: Option<PathBuf>,
+    /// TOML file of lesson parameters, applied over the ones saved by the last run.
+    pub params: Option<PathBuf>,
+    /// Lesson parameters given with `--set name=value`, applied last.
+    pub set: Vec<(String, String)>,
+}
+
+impl Args {
+    pub fn parse() -> Result<Self> {
+        Self::parse_from(std::env::args().skip(1))
+    }
+
+    pub fn parse_from<I: IntoIterator<Item = String>>(args: I) -> Result<Self> {
+        let mut args = args.into_iter();
+
+        let lesson = args
+            .next()
+            .ok_or_else(|| format_err!("expected number argument\n{USAGE}"))?
+            .parse::<usize>()?;
+
+        let mut post = vec![];
+        let mut screenshot = None;
+        let mut after_frames = None;
+        let mut record = None;
+        let mut profile = false;
+        let mut trace = None;
+        let mut params = None;
+        let mut set = vec![];
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format_err!("expected a value after '{arg}'\n{USAGE}"))
+            };
+
+            match arg.as_str() {
+                "--post" => {
+                    for effect in value()?.split(',').filter(|e| !e.is_empty()) {
+                        post.push(effect.parse()?);
+                    }
+                }
+                "--screenshot" => {
+                    let path = PathBuf::from(value()?);
+                    if !capture::is_supported_format(&path) {
+                        return Err(format_err!(
+                            "screenshot '{}' should have a .png, .jpg or .jpeg extension",
+                            path.display()
+                        ));
+                    }
+                    screenshot = Some(path);
+                }
+                "--after-frames" => {
+                    let frames = value()?.parse::<u64>()?;
+                    if frames == 0 {
+                        return Err(format_err!("'--after-frames' should be at least 1"));
+                    }
+                    after_frames = Some(frames);
+                }
+                "--record" => record = Some(PathBuf::from(value()?)),
+                "--profile" => profile = true,
+                "--trace" => {
+                    trace = Some(PathBuf::from(value()?));
+                    profile = true;
+                }
+                "--params" => params = Some(PathBuf::from(value()?)),
+                "--set" => {
+                    let assignment = value()?;
+                    let (name, value) = assignment.split_once('=').ok_or_else(|| {
+                        format_err!("expected '<name>=<value>' after '--set'\n{USAGE}")
+                    })?;
+                    set.push((name.trim().to_owned(), value.trim().to_owned()));
+                }
+                _ => return Err(format_err!("unrecognized argument '{arg}'\n{USAGE}")),
+            }
+        }
+
+        if after_frames.is_some() && screenshot.is_none() {
+            return Err(format_err!(
+                "'--after-frames' requires '--screenshot'\n{USAGE}"
+            ));
+        }
+
+        Ok(Self {
+            lesson,
+            post,
+            screenshot,
+            after_frames,
+            record,
+            profile,
+            trace,
+            params,
+            set,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::glutils::postprocess::effect::Tonemapper;
+
+    fn parse(args: &str) -> Result<Args> {
+        Args::parse_from(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn parses_lesson_alone() {
+        let args = parse("4").unwrap();
+        assert_eq!(args.lesson, 4);
+        assert!(args.post.is_empty() && args.screenshot.is_none() && !args.profile);
+        assert!(parse("").is_err());
+        assert!(parse("four").is_err());
+    }
+
+    #[test]
+    fn parses_post_effects_in_order() {
+        let args = parse("6 --post grayscale,,aces=1.5, --post gamma=2.2").unwrap();
+        assert_eq!(
+            args.post,
+            [
+                Effect::Grayscale,
+                Effect::Tonemap {
+                    exposure: 1.5,
+                    operator: Tonemapper::Aces,
+                },
+                Effect::Gamma(2.2),
+            ]
+        );
+        assert!(parse("6 --post blur=3").is_err());
+        assert!(parse("6 --post sepia").is_err());
+        assert!(parse("6 --post").is_err());
+    }
+
+    #[test]
+    fn after_frames_requires_screenshot() {
+        let err = parse("4 --after-frames 10").err().unwrap();
+        assert!(err.to_string().contains("requires '--screenshot'"), "{err}");
+
+        let args = parse("4 --screenshot out.png --after-frames 10").unwrap();
+        assert_eq!(args.screenshot, Some(PathBuf::from("out.png")));
+        assert_eq!(args.after_frames, Some(10));
+        assert!(parse("4 --screenshot out.png --after-frames 0").is_err());
+        assert!(parse("4 --screenshot out.bmp").is_err());
+    }
+
+    #[test]
+    fn trace_implies_profile() {
+        let args = parse("9 --trace trace.json").unwrap();
+        assert!(args.profile);
+        assert_eq!(args.trace, Some(PathBuf::from("trace.json")));
+    }
+
+    #[test]
+    fn parses_parameters() {
+        let args =
+            parse("4 --params p.toml --set pulse=false --set clear_color=0.2,0.3,0.3").unwrap();
+        assert_eq!(args.params, Some(PathBuf::from("p.toml")));
+        assert_eq!(
+            args.set,
+            [
+                ("pulse".to_owned(), "false".to_owned()),
+                ("clear_color".to_owned(), "0.2,0.3,0.3".to_owned()),
+            ]
+        );
+        assert!(parse("4 --set pulse").is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_arguments() {
+        let err = parse("4 --fullscreen").err().unwrap();
+        assert!(err.to_string().contains("unrecognized argument"), "{err}");
+    }
+}
@@ -0,0 +1,61 @@
+use super::try_into;
+use anyhow::{format_err, Result};
+use image::{imageops, RgbImage};
+use std::{ffi::c_void, fs, path::Path};
+
+/// Reads the window's back buffer, i.e. the frame that the next `swap_buffers` will present.
+/// Hidden windows are read the same way, although some drivers leave their contents undefined.
+/// There's no headless backend to read from instead yet.
+pub fn read_back_buffer(width: u32, height: u32) -> RgbImage {
+    let mut pixels = vec![0_u8; usize::try_from(width * height * 3).unwrap()];
+
+    unsafe {
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+        gl::ReadBuffer(gl::BACK);
+        // Rows of RGB pixels aren't necessarily 4-byte aligned.
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::ReadPixels(
+            0,
+            0,
+            try_into!(width),
+            try_into!(height),
+            gl::RGB,
+            gl::UNSIGNED_BYTE,
+            pixels.as_mut_ptr() as *mut c_void,
+        );
+        // Back to GL's default, which everything else assumes.
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 4);
+    }
+
+    let mut image = RgbImage::from_raw(width, height, pixels).unwrap();
+    // OpenGL's first row is the bottom of the image whereas image formats start at the top.
+    imageops::flip_vertical_in_place(&mut image);
+    image
+}
+
+/// Whether `path` has an extension that [`save_image`] can encode.
+pub fn is_supported_format<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref()
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| matches!(ext.to_ascii_lowercase().as_str(), "png" | "jpg" | "jpeg"))
+}
+
+/// Saves a capture as PNG or JPEG depending on the file extension, creating any missing parent
+/// directories.
+pub fn save_image<P: AsRef<Path>>(image: &RgbImage, path: P) -> Result<()> {
+    let path = path.as_ref();
+    if !is_supported_format(path) {
+        return Err(format_err!(
+            "can't save '{}': expected a .png, .jpg or .jpeg extension",
+            path.display()
+        ));
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .map_err(|e| format_err!("failed to create '{}': {e}", parent.display()))?;
+    }
+    image
+        .save(path)
+        .map_err(|e| format_err!("failed to save '{}': {e}", path.display()))
+}
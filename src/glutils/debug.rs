@@ -0,0 +1,157 @@
+use log::Level;
+use std::{
+    ffi::{c_void, CStr},
+    ptr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Target of every log record emitted on behalf of the driver, so that GL output can be filtered
+/// with e.g. `RUST_LOG=gl=debug`.
+pub const LOG_TARGET: &str = "gl";
+
+static DEBUG_OUTPUT_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Routes driver messages into `log` records through `glDebugMessageCallback`. This requires a
+/// debug context and either OpenGL 4.3 or the `KHR_debug` extension; returns whether it worked.
+/// Messages are delivered synchronously so that a breakpoint in the callback lands on the
+/// offending call.
+pub fn enable_debug_output() -> bool {
+    if !gl::DebugMessageCallback::is_loaded() {
+        log::info!(target: LOG_TARGET, "KHR_debug is unavailable, falling back to glGetError");
+        return false;
+    }
+
+    unsafe {
+        let mut flags = 0;
+        gl::GetIntegerv(gl::CONTEXT_FLAGS, &mut flags);
+        if flags as gl::types::GLuint & gl::CONTEXT_FLAG_DEBUG_BIT == 0 {
+            log::info!(target: LOG_TARGET, "not a debug context, falling back to glGetError");
+            return false;
+        }
+
+        gl::Enable(gl::DEBUG_OUTPUT);
+        gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+        gl::DebugMessageCallback(Some(debug_message_callback), ptr::null());
+        gl::DebugMessageControl(
+            gl::DONT_CARE,
+            gl::DONT_CARE,
+            gl::DONT_CARE,
+            0,
+            ptr::null(),
+            gl::TRUE,
+        );
+    }
+
+    DEBUG_OUTPUT_ENABLED.store(true, Ordering::Relaxed);
+    true
+}
+
+pub fn debug_output_enabled() -> bool {
+    DEBUG_OUTPUT_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Logs every error flag raised since the last check, naming the wrapper call that raised it.
+/// Only does anything in debug builds when the debug callback isn't installed, since the
+/// callback already reports errors as they happen.
+pub fn check_errors(call: &str) {
+    if !cfg!(debug_assertions) || debug_output_enabled() {
+        return;
+    }
+
+    loop {
+        let error = unsafe { gl::GetError() };
+        if error == gl::NO_ERROR {
+            break;
+        }
+        log::error!(target: LOG_TARGET, "{} after {call}", error_name(error));
+    }
+}
+
+/// Attaches a human-readable name to a GL object, which the driver then uses in its debug
+/// messages. `identifier` is the object's namespace, e.g. `gl::PROGRAM` or `gl::BUFFER`.
+pub fn label(identifier: gl::types::GLenum, name: gl::types::GLuint, label: &str) {
+    if !gl::ObjectLabel::is_loaded() {
+        return;
+    }
+
+    let mut max_length = 0;
+    unsafe { gl::GetIntegerv(gl::MAX_LABEL_LENGTH, &mut max_length) };
+    // The limit includes the null terminator that GL would otherwise add.
+    let length = label
+        .len()
+        .min(usize::try_from(max_length - 1).unwrap_or(0));
+
+    unsafe {
+        gl::ObjectLabel(
+            identifier,
+            name,
+            gl::types::GLsizei::try_from(length).unwrap(),
+            label.as_ptr() as *const gl::types::GLchar,
+        );
+    }
+}
+
+extern "system" fn debug_message_callback(
+    source: gl::types::GLenum,
+    gltype: gl::types::GLenum,
+    id: gl::types::GLuint,
+    severity: gl::types::GLenum,
+    _length: gl::types::GLsizei,
+    message: *const gl::types::GLchar,
+    _user_param: *mut c_void,
+) {
+    let message = unsafe { CStr::from_ptr(message) }.to_string_lossy();
+    let level = match (gltype, severity) {
+        (gl::DEBUG_TYPE_ERROR, _) | (_, gl::DEBUG_SEVERITY_HIGH) => Level::Error,
+        (_, gl::DEBUG_SEVERITY_MEDIUM) => Level::Warn,
+        (_, gl::DEBUG_SEVERITY_LOW) => Level::Info,
+        _ => Level::Debug,
+    };
+
+    log::log!(
+        target: LOG_TARGET,
+        level,
+        "[{} {} {id}] {}",
+        source_name(source),
+        type_name(gltype),
+        message.trim_end()
+    );
+}
+
+fn source_name(source: gl::types::GLenum) -> &'static str {
+    match source {
+        gl::DEBUG_SOURCE_API => "api",
+        gl::DEBUG_SOURCE_WINDOW_SYSTEM => "window system",
+        gl::DEBUG_SOURCE_SHADER_COMPILER => "shader compiler",
+        gl::DEBUG_SOURCE_THIRD_PARTY => "third party",
+        gl::DEBUG_SOURCE_APPLICATION => "application",
+        _ => "other",
+    }
+}
+
+fn type_name(gltype: gl::types::GLenum) -> &'static str {
+    match gltype {
+        gl::DEBUG_TYPE_ERROR => "error",
+        gl::DEBUG_TYPE_DEPRECATED_BEHAVIOR => "deprecated behavior",
+        gl::DEBUG_TYPE_UNDEFINED_BEHAVIOR => "undefined behavior",
+        gl::DEBUG_TYPE_PORTABILITY => "portability",
+        gl::DEBUG_TYPE_PERFORMANCE => "performance",
+        gl::DEBUG_TYPE_MARKER => "marker",
+        gl::DEBUG_TYPE_PUSH_GROUP => "push group",
+        gl::DEBUG_TYPE_POP_GROUP => "pop group",
+        _ => "other",
+    }
+}
+
+fn error_name(error: gl::types::GLenum) -> &'static str {
+    match error {
+        gl::INVALID_ENUM => "GL_INVALID_ENUM",
+        gl::INVALID_VALUE => "GL_INVALID_VALUE",
+        gl::INVALID_OPERATION => "GL_INVALID_OPERATION",
+        gl::INVALID_FRAMEBUFFER_OPERATION => "GL_INVALID_FRAMEBUFFER_OPERATION",
+        gl::OUT_OF_MEMORY => "GL_OUT_OF_MEMORY",
+        gl::STACK_UNDERFLOW => "GL_STACK_UNDERFLOW",
+        gl::STACK_OVERFLOW => "GL_STACK_OVERFLOW",
+        _ => "unknown GL error",
+    }
+}
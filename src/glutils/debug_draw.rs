@@ -0,0 +1,191 @@
+use super::{
+    material::Material,
+    mesh::{bounds::Aabb, Mesh},
+    model::{primitives::Primitive, stream::VertexStream},
+    render_state::{comparison::Comparison, RenderState, StateTracker},
+    shader::{program::Linker, Shader, ShaderType},
+    uniform_buffer::{Matrices, UniformBuffer},
+};
+use anyhow::Result;
+use glam::{vec4, Mat3, Mat4, Vec3, Vec4};
+use std::{f32::consts::TAU, path::PathBuf};
+
+/// Segments in a full circle, as drawn by [`DebugDraw::circle`] and [`DebugDraw::sphere`].
+const CIRCLE_SEGMENTS: usize = 32;
+
+/// Pairs of corners joined by the edges of a box, indexed as in [`Aabb::corners`].
+const BOX_EDGES: [(usize, usize); 12] = [
+    (0, 1),
+    (2, 3),
+    (4, 5),
+    (6, 7),
+    (0, 2),
+    (1, 3),
+    (4, 6),
+    (5, 7),
+    (0, 4),
+    (1, 5),
+    (2, 6),
+    (3, 7),
+];
+
+/// Immediate mode lines in world space for visualizing things that aren't otherwise visible,
+/// such as light positions, bounding volumes or frusta. Shapes are queued every frame and drawn
+/// together by [`DebugDraw::flush`] in a single draw call.
+pub struct DebugDraw {
+    material: Material,
+    stream: VertexStream,
+    vertices: Vec<f32>,
+}
+
+impl DebugDraw {
+    /// The program reads the camera from `matrices`.
+    pub fn new(matrices: &UniformBuffer<Matrices>) -> Result<Self> {
+        let shaders_dir = PathBuf::new().join("shaders").join("debug");
+        let vs = Shader::new(
+            shaders_dir.join("debug_draw_vertex_shader.glsl"),
+            ShaderType::Vertex,
+        )?;
+        let fs = Shader::new(
+            shaders_dir.join("debug_draw_fragment_shader.glsl"),
+            ShaderType::Fragment,
+        )?;
+        let program = Linker::new().attach_shader(vs).attach_shader(fs).link()?;
+        matrices.attach(&program, "Matrices")?;
+        let stream = VertexStream::new(&program, &[("aPos", 3), ("aCol", 4)], "debug draw")?;
+
+        let mut material = Material::new(program);
+        material.set_render_state(Self::render_state(true));
+
+        Ok(Self {
+            material,
+            stream,
+            vertices: vec![],
+        })
+    }
+
+    /// Whether lines are hidden behind the geometry drawn before them, which is the default.
+    /// Without the depth test they are drawn over everything.
+    pub fn set_depth_test(&mut self, depth_test: bool) {
+        self.material
+            .set_render_state(Self::render_state(depth_test));
+    }
+
+    pub fn line(&mut self, from: Vec3, to: Vec3, color: Vec4) {
+        for point in [from, to] {
+            self.vertices.extend_from_slice(&point.to_array());
+            self.vertices.extend_from_slice(&color.to_array());
+        }
+    }
+
+    /// The edges of `aabb`.
+    pub fn aabb(&mut self, aabb: &Aabb, color: Vec4) {
+        self.box_corners(aabb.corners(), color);
+    }
+
+    /// The edges of the cube from -1 to 1 on each axis once transformed by `transform`, which
+    /// can be any box, or a frustum with a perspective projection.
+    pub fn transformed_cube(&mut self, transform: Mat4, color: Vec4) {
+        let cube = Aabb::new(Vec3::NEG_ONE, Vec3::ONE);
+        self.box_corners(cube.corners().map(|c| transform.project_point3(c)), color);
+    }
+
+    /// The volume seen through `view_projection`, e.g. a camera's or a shadow map's.
+    pub fn frustum(&mut self, view_projection: Mat4, color: Vec4) {
+        self.transformed_cube(view_projection.inverse(), color);
+    }
+
+    /// A circle of `radius` around `center` in the plane facing `normal`.
+    pub fn circle(&mut self, center: Vec3, normal: Vec3, radius: f32, color: Vec4) {
+        let (u, v) = normal.normalize_or(Vec3::Y).any_orthonormal_pair();
+        let point = |i: usize| {
+            let angle = i as f32 / CIRCLE_SEGMENTS as f32 * TAU;
+            center + (u * angle.cos() + v * angle.sin()) * radius
+        };
+        for i in 0..CIRCLE_SEGMENTS {
+            self.line(point(i), point(i + 1), color);
+        }
+    }
+
+    /// Three circles around `center`, one in each axis plane.
+    pub fn sphere(&mut self, center: Vec3, radius: f32, color: Vec4) {
+        for axis in [Vec3::X, Vec3::Y, Vec3::Z] {
+            self.circle(center, axis, radius, color);
+        }
+    }
+
+    /// The axes of `transform`'s space, `size` long in it, in red, green and blue for x, y
+    /// and z.
+    pub fn axes(&mut self, transform: Mat4, size: f32) {
+        let origin = transform.transform_point3(Vec3::ZERO);
+        for (axis, color) in [
+            (Vec3::X, vec4(1.0, 0.0, 0.0, 1.0)),
+            (Vec3::Y, vec4(0.0, 1.0, 0.0, 1.0)),
+            (Vec3::Z, vec4(0.0, 0.0, 1.0, 1.0)),
+        ] {
+            self.line(origin, transform.transform_point3(axis * size), color);
+        }
+    }
+
+    /// A square grid in the XZ plane centered on `center`, with `cells` cells of `cell_size`
+    /// along each side.
+    pub fn grid(&mut self, center: Vec3, cell_size: f32, cells: u32, color: Vec4) {
+        let half = cells as f32 * cell_size / 2.0;
+        for i in 0..=cells {
+            let offset = i as f32 * cell_size - half;
+            self.line(
+                center + Vec3::new(offset, 0.0, -half),
+                center + Vec3::new(offset, 0.0, half),
+                color,
+            );
+            self.line(
+                center + Vec3::new(-half, 0.0, offset),
+                center + Vec3::new(half, 0.0, offset),
+                color,
+            );
+        }
+    }
+
+    /// A line `length` long along the normal of each of `mesh`'s vertices, once transformed by
+    /// `transform`. Does nothing for meshes without normals.
+    pub fn normals(&mut self, mesh: &Mesh, transform: Mat4, length: f32, color: Vec4) {
+        let Some(normals) = &mesh.normals else {
+            return;
+        };
+        let normal_matrix = Mat3::from_mat4(transform).inverse().transpose();
+        for (position, normal) in mesh.positions.iter().zip(normals) {
+            let position = transform.transform_point3(Vec3::from_array(*position));
+            let normal = (normal_matrix * Vec3::from_array(*normal)).normalize_or_zero();
+            self.line(position, position + normal * length, color);
+        }
+    }
+
+    /// Draws everything queued since the last flush into the bound framebuffer.
+    pub fn flush(&mut self, state: &mut StateTracker) -> Result<()> {
+        self.material.bind(state)?;
+        self.stream.draw(&self.vertices, Primitive::Lines);
+        self.vertices.clear();
+        Ok(())
+    }
+
+    pub fn delete(self) {
+        self.stream.delete();
+    }
+
+    fn box_corners(&mut self, corners: [Vec3; 8], color: Vec4) {
+        for (a, b) in BOX_EDGES {
+            self.line(corners[a], corners[b], color);
+        }
+    }
+
+    fn render_state(depth_test: bool) -> RenderState {
+        if depth_test {
+            RenderState {
+                depth_test: Some(Comparison::LessEqual),
+                ..RenderState::OVERLAY
+            }
+        } else {
+            RenderState::OVERLAY
+        }
+    }
+}
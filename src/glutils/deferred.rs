@@ -0,0 +1,402 @@
+use super::{
+    framebuffer::{
+        attachment::{ColorFormat, DepthFormat, DepthStencilAttachment},
+        BufferMask, Framebuffer, FramebufferBuilder,
+    },
+    lighting::{
+        solid_texture, Lighting, Lights, PointLight, SpotLight, MAX_DIRECTIONAL_LIGHTS,
+        MAX_POINT_LIGHTS, MAX_SPOT_LIGHTS,
+    },
+    material::Material,
+    mesh::{shapes, MeshOptions},
+    model::{primitives::Primitive, usage::Usage, Model},
+    render_state::{
+        blend::Blend, comparison::Comparison, raster::CullMode, Clear, RenderState, StateTracker,
+    },
+    scene::{renderer::Renderer, Scene},
+    shader::{
+        program::{Linker, Program},
+        Shader, ShaderType,
+    },
+    ssao::{Ssao, SsaoSettings},
+    texture::Texture,
+    try_into,
+    uniform_buffer::{Matrices, UniformBindings, UniformBuffer},
+};
+use anyhow::Result;
+use glam::{Mat4, Quat, Vec3};
+use glfw::WindowEvent;
+use std::path::PathBuf;
+
+/// Samplers of the lighting programs that read the G-buffer, in attachment order.
+const GBUFFER_SAMPLERS: [&str; 4] = ["gPosition", "gNormal", "gAlbedoSpec", "gAmbient"];
+
+/// Lights the G-buffer's surfaces without touching the depth buffer. Passes after the first
+/// add their light to it.
+const FULL_SCREEN_STATE: RenderState = RenderState {
+    depth_write: false,
+    ..RenderState::DEFAULT
+};
+
+/// Only the back faces of light volumes are drawn, so that they're still drawn when the camera
+/// is inside of one, and only where they're behind a surface that they might light.
+const VOLUME_STATE: RenderState = RenderState {
+    depth_test: Some(Comparison::GreaterEqual),
+    depth_write: false,
+    blend: Some(Blend::ADDITIVE),
+    cull_mode: Some(CullMode::Front),
+    ..RenderState::DEFAULT
+};
+
+/// Radius of the sphere that a light volume is drawn with, relative to the light's range. The
+/// sphere's faces lie inside of its vertices, so it needs to be a bit larger than the range.
+const VOLUME_SCALE: f32 = 1.1;
+
+/// How the deferred lighting pass covers the screen.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LightingMode {
+    /// Every light is evaluated at every pixel by full-screen passes, as many as it takes to go
+    /// through the lights a `Lights` block at a time.
+    FullScreen,
+    /// Directional lights are evaluated by full-screen passes but point and spot lights are
+    /// drawn as spheres the size of their range, so that each only costs the pixels it can
+    /// reach. Scales to many small lights.
+    #[default]
+    LightVolumes,
+}
+
+/// Shades opaque geometry in two steps: a geometry pass writes the surface properties of the
+/// closest fragment at each pixel to a G-buffer, and lighting passes then shade each pixel once
+/// per light, no matter how much geometry was drawn there. Materials that go through it use the
+/// program from [`DeferredRenderer::geometry_program`] with a
+/// [`PhongMaterial`](super::lighting::PhongMaterial).
+///
+/// Everything else, such as transparent materials or unlit light bulbs, is drawn with the
+/// forward renderer afterwards, on top of the G-buffer's depth.
+pub struct DeferredRenderer {
+    gbuffer: Framebuffer,
+    geometry_program: Program,
+    lights: UniformBuffer<Lights>,
+    quad: Model,
+    full_screen_material: Material,
+    sphere: Model,
+    volume_material: Material,
+    mode: LightingMode,
+    ssao: Ssao,
+    ssao_enabled: bool,
+    /// Stands in for the occlusion while SSAO is off.
+    no_occlusion: Texture,
+}
+
+impl DeferredRenderer {
+    /// Makes a G-buffer of `width` by `height` pixels, which should be the size of the viewport
+    /// that's rendered to. Programs read the camera from `matrices`.
+    pub fn new(
+        width: u32,
+        height: u32,
+        matrices: &UniformBuffer<Matrices>,
+        bindings: &mut UniformBindings,
+    ) -> Result<Self> {
+        // World space positions need more precision than normals, but half floats are enough at
+        // the scale of the lessons.
+        let gbuffer = FramebufferBuilder::new(width, height)
+            .color_attachment(ColorFormat::Rgba16F)
+            .color_attachment(ColorFormat::Rgba16F)
+            .color_attachment(ColorFormat::Rgba8)
+            .color_attachment(ColorFormat::Rgba8)
+            .depth_stencil(DepthStencilAttachment::Renderbuffer(
+                DepthFormat::Depth24Stencil8,
+            ))
+            .build()?;
+
+        let shaders_dir = PathBuf::new().join("shaders").join("deferred");
+        let link = |vertex: PathBuf, fragment: &str| -> Result<Program> {
+            let vs = Shader::new(vertex, ShaderType::Vertex)?;
+            let fs = Shader::new(shaders_dir.join(fragment), ShaderType::Fragment)?;
+            Linker::new().attach_shader(vs).attach_shader(fs).link()
+        };
+        let geometry_program = link(
+            shaders_dir.join("geometry_vertex_shader.glsl"),
+            "geometry_fragment_shader.glsl",
+        )?;
+        let full_screen_program = link(
+            PathBuf::new()
+                .join("shaders")
+                .join("postprocess")
+                .join("fullscreen_vertex_shader.glsl"),
+            "lighting_fragment_shader.glsl",
+        )?;
+        let volume_program = link(
+            shaders_dir.join("volume_vertex_shader.glsl"),
+            "volume_fragment_shader.glsl",
+        )?;
+
+        let lights = UniformBuffer::new(&Lights::default(), Usage::Dynamic, bindings)?;
+        for program in [geometry_program, volume_program] {
+            matrices.attach(&program, "Matrices")?;
+        }
+        for program in [full_screen_program, volume_program] {
+            lights.attach(&program, "Lights")?;
+        }
+
+        let quad = Model::from_mesh(
+            &shapes::fullscreen_quad(MeshOptions {
+                normals: false,
+                tangents: false,
+                uvs: true,
+            }),
+            full_screen_program,
+            Usage::Static,
+        )?;
+        let sphere = Model::from_mesh(
+            &shapes::icosphere(
+                VOLUME_SCALE,
+                1,
+                MeshOptions {
+                    normals: false,
+                    tangents: false,
+                    uvs: false,
+                },
+            ),
+            volume_program,
+            Usage::Static,
+        )?;
+
+        Ok(Self {
+            gbuffer,
+            geometry_program,
+            lights,
+            quad,
+            full_screen_material: Material::new(full_screen_program),
+            sphere,
+            volume_material: Material::new(volume_program),
+            mode: LightingMode::default(),
+            ssao: Ssao::new(width, height, SsaoSettings::default(), matrices)?,
+            ssao_enabled: false,
+            no_occlusion: solid_texture(Vec3::ONE)?,
+        })
+    }
+
+    /// The program that materials drawn into the G-buffer need to use.
+    pub fn geometry_program(&self) -> Program {
+        self.geometry_program
+    }
+
+    /// Holds the world space position, the normal and shininess, the diffuse color and specular
+    /// intensity, and the ambient color of the last frame's opaque surfaces.
+    pub fn gbuffer(&self) -> &Framebuffer {
+        &self.gbuffer
+    }
+
+    pub fn mode(&self) -> LightingMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: LightingMode) {
+        self.mode = mode;
+    }
+
+    pub fn ssao(&self) -> &Ssao {
+        &self.ssao
+    }
+
+    /// For tweaking the occlusion's settings, which take effect on the next frame.
+    pub fn ssao_mut(&mut self) -> &mut Ssao {
+        &mut self.ssao
+    }
+
+    pub fn ssao_enabled(&self) -> bool {
+        self.ssao_enabled
+    }
+
+    pub fn set_ssao_enabled(&mut self, enabled: bool) {
+        self.ssao_enabled = enabled;
+    }
+
+    /// Keeps the G-buffer the same size as the window.
+    pub fn handle_event(&mut self, event: &WindowEvent) -> Result<()> {
+        self.gbuffer.handle_event(event)?;
+        self.ssao.handle_event(event)
+    }
+
+    /// Renders the scene as seen through `view` into the bound framebuffer, which needs a depth
+    /// buffer of the same format as the G-buffer's so that it can be copied over for the forward
+    /// pass. Its color should be cleared beforehand, since pixels without opaque geometry are
+    /// left alone. Shadows aren't supported.
+    pub fn render(
+        &mut self,
+        renderer: &mut Renderer,
+        scene: &mut Scene,
+        state: &mut StateTracker,
+        view: &Mat4,
+        lighting: &Lighting,
+    ) -> Result<()> {
+        let mut target = 0;
+        let mut viewport = [0; 4];
+        unsafe {
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut target);
+            gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+        }
+        let target = try_into!(target);
+        let [x, y, width, height] = viewport;
+
+        self.gbuffer.bind();
+        state.clear(&Clear::all([0.0; 4]));
+        let geometry_program = self.geometry_program;
+        renderer.submit_scene_where(scene, |material| material.program() == geometry_program);
+        renderer.flush(scene, state, view)?;
+        let occlusion = if self.ssao_enabled {
+            self.ssao.render(state, &self.gbuffer)?
+        } else {
+            self.no_occlusion
+        };
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, target);
+            gl::Viewport(x, y, width, height);
+        }
+        for (sampler, texture) in GBUFFER_SAMPLERS.iter().zip(self.gbuffer.color_textures()) {
+            self.full_screen_material.set_texture(sampler, *texture);
+            self.volume_material.set_texture(sampler, *texture);
+        }
+        self.full_screen_material.set_texture("ssao", occlusion);
+        self.volume_material.set_texture("ssao", occlusion);
+
+        let view_position = view.inverse().w_axis.truncate();
+        let full_screen_lights = match self.mode {
+            LightingMode::FullScreen => lighting.clone(),
+            LightingMode::LightVolumes => Lighting {
+                point: vec![],
+                spot: vec![],
+                ..lighting.clone()
+            },
+        };
+        for (i, chunk) in chunks(&full_screen_lights).iter().enumerate() {
+            self.lights.update(&chunk.block(view_position)?);
+            self.full_screen_material.set_render_state(RenderState {
+                blend: (i > 0).then_some(Blend::ADDITIVE),
+                ..FULL_SCREEN_STATE
+            });
+            self.quad
+                .draw(&self.full_screen_material, state, Primitive::Triangles)?;
+        }
+
+        self.gbuffer
+            .blit_to(target, width, height, BufferMask::DEPTH);
+        unsafe { gl::Viewport(x, y, width, height) };
+
+        if self.mode == LightingMode::LightVolumes
+            && !(lighting.point.is_empty() && lighting.spot.is_empty())
+        {
+            self.draw_volumes(state, lighting, [x, y, width, height])?;
+        }
+
+        renderer.submit_scene_where(scene, |material| material.program() != geometry_program);
+        renderer.flush(scene, state, view)
+    }
+
+    fn draw_volumes(
+        &mut self,
+        state: &mut StateTracker,
+        lighting: &Lighting,
+        viewport: [i32; 4],
+    ) -> Result<()> {
+        self.volume_material.set_render_state(VOLUME_STATE);
+        self.volume_material.bind(state)?;
+        let program = self.volume_material.program();
+        program.set_uniform_4f(
+            "viewport",
+            viewport[0] as f32,
+            viewport[1] as f32,
+            viewport[2] as f32,
+            viewport[3] as f32,
+        )?;
+
+        self.sphere.bind();
+        let sphere = &mut self.sphere;
+        let result = (|| -> Result<()> {
+            for light in &lighting.point {
+                set_point_light(&program, light)?;
+                sphere.try_draw_arrays(Primitive::Triangles)?;
+            }
+            for light in &lighting.spot {
+                set_spot_light(&program, light)?;
+                sphere.try_draw_arrays(Primitive::Triangles)?;
+            }
+            Ok(())
+        })();
+        self.sphere.unbind();
+        result
+    }
+}
+
+/// Splits the lights into groups that each fit in a `Lights` block. There's always at least one
+/// group, so that surfaces get shaded even without lights.
+fn chunks(lighting: &Lighting) -> Vec<Lighting> {
+    let num_chunks = [
+        lighting.directional.len().div_ceil(MAX_DIRECTIONAL_LIGHTS),
+        lighting.point.len().div_ceil(MAX_POINT_LIGHTS),
+        lighting.spot.len().div_ceil(MAX_SPOT_LIGHTS),
+    ]
+    .into_iter()
+    .max()
+    .unwrap()
+    .max(1);
+
+    (0..num_chunks)
+        .map(|i| Lighting {
+            directional: chunk(&lighting.directional, i, MAX_DIRECTIONAL_LIGHTS),
+            point: chunk(&lighting.point, i, MAX_POINT_LIGHTS),
+            spot: chunk(&lighting.spot, i, MAX_SPOT_LIGHTS),
+            shading: lighting.shading,
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn chunk<T: Copy>(lights: &[T], index: usize, size: usize) -> Vec<T> {
+    lights.chunks(size).nth(index).unwrap_or_default().to_vec()
+}
+
+/// Scales the volume to the light's range and sets the light for the volume shader.
+fn set_point_light(program: &Program, light: &PointLight) -> Result<()> {
+    let model = Mat4::from_scale_rotation_translation(
+        Vec3::splat(light.range()),
+        Quat::IDENTITY,
+        light.position,
+    );
+    program.set_uniform_matrix_4fv("model", &model)?;
+    program.set_uniform_1i("spot", 0)?;
+    set_vec3(program, "pointLight.position", light.position)?;
+    set_vec3(program, "pointLight.ambient", light.ambient)?;
+    set_vec3(program, "pointLight.diffuse", light.diffuse)?;
+    set_vec3(program, "pointLight.specular", light.specular)?;
+    program.set_uniform_1f("pointLight.constant", light.constant)?;
+    program.set_uniform_1f("pointLight.linear", light.linear)?;
+    program.set_uniform_1f("pointLight.quadratic", light.quadratic)
+}
+
+/// Like [`set_point_light`], with a sphere around the whole cone rather than a cone.
+fn set_spot_light(program: &Program, light: &SpotLight) -> Result<()> {
+    let model = Mat4::from_scale_rotation_translation(
+        Vec3::splat(light.range()),
+        Quat::IDENTITY,
+        light.position,
+    );
+    program.set_uniform_matrix_4fv("model", &model)?;
+    program.set_uniform_1i("spot", 1)?;
+    set_vec3(program, "spotLight.position", light.position)?;
+    set_vec3(program, "spotLight.direction", light.direction)?;
+    set_vec3(program, "spotLight.ambient", light.ambient)?;
+    set_vec3(program, "spotLight.diffuse", light.diffuse)?;
+    set_vec3(program, "spotLight.specular", light.specular)?;
+    program.set_uniform_1f("spotLight.inner_cutoff", light.inner_cutoff)?;
+    program.set_uniform_1f("spotLight.outer_cutoff", light.outer_cutoff)?;
+    program.set_uniform_1f("spotLight.constant", light.constant)?;
+    program.set_uniform_1f("spotLight.linear", light.linear)?;
+    program.set_uniform_1f("spotLight.quadratic", light.quadratic)
+}
+
+fn set_vec3(program: &Program, uniform: &str, value: Vec3) -> Result<()> {
+    program.set_uniform_3f(uniform, value.x, value.y, value.z)
+}
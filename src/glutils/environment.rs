@@ -0,0 +1,55 @@
+use super::{
+    material::Material,
+    shader::program::Program,
+    texture::{target::Target, Texture},
+};
+use anyhow::{format_err, Result};
+
+/// Indices of refraction of common materials, relative to a vacuum.
+pub mod refractive_index {
+    pub const AIR: f32 = 1.00;
+    pub const WATER: f32 = 1.33;
+    pub const ICE: f32 = 1.309;
+    pub const GLASS: f32 = 1.52;
+    pub const DIAMOND: f32 = 2.42;
+}
+
+/// How a surface shows its environment.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EnvironmentMapping {
+    /// Mirrors the environment like polished metal.
+    Reflection,
+    /// Shows the environment bent through a transparent surface of the given index of
+    /// refraction, as seen from air. Only the entry into the surface is accounted for.
+    Refraction(f32),
+}
+
+/// Surface properties for `environment.glsl`: a cube map and whether it's reflected or refracted.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EnvironmentMaterial {
+    pub environment: Texture,
+    pub mapping: EnvironmentMapping,
+}
+
+impl EnvironmentMaterial {
+    pub fn new(environment: Texture, mapping: EnvironmentMapping) -> Result<Self> {
+        if environment.target != Target::CubeMap {
+            return Err(format_err!("environment mapping needs a cube map texture"));
+        }
+        Ok(Self {
+            environment,
+            mapping,
+        })
+    }
+
+    pub fn into_material(self, program: Program) -> Material {
+        let refraction_ratio = match self.mapping {
+            EnvironmentMapping::Reflection => 0.0,
+            EnvironmentMapping::Refraction(index) => refractive_index::AIR / index,
+        };
+        let mut material = Material::new(program);
+        material.set_texture("environment", self.environment);
+        material.set("refractionRatio", refraction_ratio);
+        material
+    }
+}
@@ -0,0 +1,79 @@
+/// Storage formats for color attachments.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColorFormat {
+    R8,
+    Rg8,
+    Rgb8,
+    Rgba8,
+    R16F,
+    Rg16F,
+    Rgb16F,
+    Rgba16F,
+    R32F,
+    Rgba32F,
+    /// Packed unsigned floats without alpha, half the size of `Rgba16F` for HDR color that
+    /// never goes negative.
+    Rg11B10F,
+}
+
+/// Storage formats for depth and depth-stencil attachments.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DepthFormat {
+    Depth24,
+    Depth32F,
+    Depth24Stencil8,
+}
+
+/// Renderbuffers are cheaper when the depth values are never sampled; textures are needed when
+/// they are, e.g. for shadow maps.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DepthStencilAttachment {
+    Renderbuffer(DepthFormat),
+    Texture(DepthFormat),
+}
+
+impl ColorFormat {
+    /// Internal format, pixel format and pixel type as passed to `glTexImage2D`.
+    pub fn gl_formats(&self) -> (gl::types::GLenum, gl::types::GLenum, gl::types::GLenum) {
+        match self {
+            Self::R8 => (gl::R8, gl::RED, gl::UNSIGNED_BYTE),
+            Self::Rg8 => (gl::RG8, gl::RG, gl::UNSIGNED_BYTE),
+            Self::Rgb8 => (gl::RGB8, gl::RGB, gl::UNSIGNED_BYTE),
+            Self::Rgba8 => (gl::RGBA8, gl::RGBA, gl::UNSIGNED_BYTE),
+            Self::R16F => (gl::R16F, gl::RED, gl::FLOAT),
+            Self::Rg16F => (gl::RG16F, gl::RG, gl::FLOAT),
+            Self::Rgb16F => (gl::RGB16F, gl::RGB, gl::FLOAT),
+            Self::Rgba16F => (gl::RGBA16F, gl::RGBA, gl::FLOAT),
+            Self::R32F => (gl::R32F, gl::RED, gl::FLOAT),
+            Self::Rgba32F => (gl::RGBA32F, gl::RGBA, gl::FLOAT),
+            Self::Rg11B10F => (gl::R11F_G11F_B10F, gl::RGB, gl::FLOAT),
+        }
+    }
+}
+
+impl DepthFormat {
+    /// Internal format, pixel format and pixel type as passed to `glTexImage2D`.
+    pub fn gl_formats(&self) -> (gl::types::GLenum, gl::types::GLenum, gl::types::GLenum) {
+        match self {
+            Self::Depth24 => (gl::DEPTH_COMPONENT24, gl::DEPTH_COMPONENT, gl::UNSIGNED_INT),
+            Self::Depth32F => (gl::DEPTH_COMPONENT32F, gl::DEPTH_COMPONENT, gl::FLOAT),
+            Self::Depth24Stencil8 => (
+                gl::DEPTH24_STENCIL8,
+                gl::DEPTH_STENCIL,
+                gl::UNSIGNED_INT_24_8,
+            ),
+        }
+    }
+
+    pub fn has_stencil(&self) -> bool {
+        matches!(self, Self::Depth24Stencil8)
+    }
+
+    pub fn attachment_point(&self) -> gl::types::GLenum {
+        if self.has_stencil() {
+            gl::DEPTH_STENCIL_ATTACHMENT
+        } else {
+            gl::DEPTH_ATTACHMENT
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use super::{
+    debug,
+    texture::{target::Target, Texture},
+    try_into,
+};
+use anyhow::{format_err, Result};
+use glfw::WindowEvent;
+use std::{ops::BitOr, ptr};
+
+pub mod attachment;
+use attachment::{ColorFormat, DepthStencilAttachment};
+
+/// An off-screen render target. Attachments are owned by the framebuffer and are recreated
+/// whenever it's resized, so textures handed out by [`Framebuffer::color_texture`] shouldn't be
+/// held onto across a resize.
+pub struct Framebuffer {
+    pub gl_object_id: gl::types::GLuint,
+    width: u32,
+    height: u32,
+    color_formats: Vec<ColorFormat>,
+    depth_stencil: Option<DepthStencilAttachment>,
+    color_textures: Vec<Texture>,
+    depth_texture: Option<Texture>,
+    depth_renderbuffer: Option<gl::types::GLuint>,
+}
+
+pub struct FramebufferBuilder {
+    width: u32,
+    height: u32,
+    color_formats: Vec<ColorFormat>,
+    depth_stencil: Option<DepthStencilAttachment>,
+}
+
+/// Selects which buffers an operation such as a blit or a clear applies to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BufferMask(gl::types::GLbitfield);
+
+impl BufferMask {
+    pub const NONE: Self = Self(0);
+    pub const COLOR: Self = Self(gl::COLOR_BUFFER_BIT);
+    pub const DEPTH: Self = Self(gl::DEPTH_BUFFER_BIT);
+    pub const STENCIL: Self = Self(gl::STENCIL_BUFFER_BIT);
+    pub const ALL: Self =
+        Self(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT);
+
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for BufferMask {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl From<BufferMask> for gl::types::GLbitfield {
+    fn from(value: BufferMask) -> Self {
+        value.0
+    }
+}
+
+impl Framebuffer {
+    /// Makes this the target of subsequent draws and sets the viewport to cover it.
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.gl_object_id);
+            gl::Viewport(0, 0, try_into!(self.width), try_into!(self.height));
+        }
+    }
+
+    /// Switches back to the window's framebuffer with a viewport of the given size.
+    pub fn bind_default(width: i32, height: i32) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::Viewport(0, 0, width, height);
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn color_texture(&self, index: usize) -> Option<&Texture> {
+        self.color_textures.get(index)
+    }
+
+    pub fn color_textures(&self) -> &[Texture] {
+        &self.color_textures
+    }
+
+    /// Only present if the depth attachment was requested as a texture.
+    pub fn depth_texture(&self) -> Option<&Texture> {
+        self.depth_texture.as_ref()
+    }
+
+    /// Recreates every attachment at the new size. Zero sized requests, e.g. from a minimized
+    /// window, are ignored.
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<()> {
+        if width == 0 || height == 0 || (width, height) == (self.width, self.height) {
+            return Ok(());
+        }
+        unsafe {
+            self.delete_attachments_impl();
+            self.width = width;
+            self.height = height;
+            self.attach_impl()
+        }
+    }
+
+    /// Keeps the framebuffer the same size as the window.
+    pub fn handle_event(&mut self, event: &WindowEvent) -> Result<()> {
+        if let WindowEvent::FramebufferSize(width, height) = event {
+            self.resize(try_into!(*width), try_into!(*height))?;
+        }
+        Ok(())
+    }
+
+    /// Copies the first color attachment and, if asked for, the depth and stencil buffers to the
+    /// window's framebuffer, scaling to `width` by `height`.
+    pub fn blit_to_default(&self, width: i32, height: i32, mask: BufferMask) {
+        self.blit_to(0, width, height, mask);
+    }
+
+    /// Same as [`Framebuffer::blit_to_default`] but into the framebuffer with the given id, e.g.
+    /// to share this one's depth buffer with it, which is left bound. Depth and stencil can only
+    /// be copied between buffers of the same format.
+    pub fn blit_to(&self, target: gl::types::GLuint, width: i32, height: i32, mask: BufferMask) {
+        // Depth and stencil can only be copied with nearest filtering.
+        let filter = if mask == BufferMask::COLOR {
+            gl::LINEAR
+        } else {
+            gl::NEAREST
+        };
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.gl_object_id);
+            gl::ReadBuffer(gl::COLOR_ATTACHMENT0);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, target);
+            gl::BlitFramebuffer(
+                0,
+                0,
+                try_into!(self.width),
+                try_into!(self.height),
+                0,
+                0,
+                width,
+                height,
+                mask.into(),
+                filter,
+            );
+            gl::BindFramebuffer(gl::FRAMEBUFFER, target);
+        }
+    }
+
+    pub fn delete(mut self) {
+        unsafe {
+            self.delete_attachments_impl();
+            gl::DeleteFramebuffers(1, &self.gl_object_id);
+        }
+    }
+
+    unsafe fn delete_attachments_impl(&mut self) {
+        for texture in self.color_textures.drain(..) {
+            texture.delete();
+        }
+        if let Some(texture) = self.depth_texture.take() {
+            texture.delete();
+        }
+        if let Some(rbo) = self.depth_renderbuffer.take() {
+            gl::DeleteRenderbuffers(1, &rbo);
+        }
+    }
+
+    unsafe fn attach_impl(&mut self) -> Result<()> {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, self.gl_object_id);
+
+        let (width, height) = (try_into!(self.width), try_into!(self.height));
+
+        let mut draw_buffers = Vec::with_capacity(self.color_formats.len());
+
+        for (i, format) in self.color_formats.iter().enumerate() {
+            let (internal_format, pixel_format, pixel_type) = format.gl_formats();
+            let attachment = gl::COLOR_ATTACHMENT0 + u32::try_from(i).unwrap();
+            let texture = new_texture(width, height, internal_format, pixel_format, pixel_type);
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, attachment, gl::TEXTURE_2D, texture, 0);
+            draw_buffers.push(attachment);
+            self.color_textures.push(Texture {
+                gl_object_id: texture,
+                width: self.width,
+                height: self.height,
+                target: Target::Texture2D,
+            });
+        }
+
+        if draw_buffers.is_empty() {
+            // Depth-only targets such as shadow maps have nothing to draw or read color into.
+            gl::DrawBuffer(gl::NONE);
+            gl::ReadBuffer(gl::NONE);
+        } else {
+            gl::DrawBuffers(try_into!(draw_buffers.len()), draw_buffers.as_ptr());
+        }
+
+        match self.depth_stencil {
+            Some(DepthStencilAttachment::Renderbuffer(format)) => {
+                let (internal_format, _, _) = format.gl_formats();
+                let mut rbo = 0;
+                gl::GenRenderbuffers(1, &mut rbo);
+                gl::BindRenderbuffer(gl::RENDERBUFFER, rbo);
+                gl::RenderbufferStorage(gl::RENDERBUFFER, internal_format, width, height);
+                gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+                gl::FramebufferRenderbuffer(
+                    gl::FRAMEBUFFER,
+                    format.attachment_point(),
+                    gl::RENDERBUFFER,
+                    rbo,
+                );
+                self.depth_renderbuffer = Some(rbo);
+            }
+            Some(DepthStencilAttachment::Texture(format)) => {
+                let (internal_format, pixel_format, pixel_type) = format.gl_formats();
+                let texture = new_texture(width, height, internal_format, pixel_format, pixel_type);
+                gl::FramebufferTexture2D(
+                    gl::FRAMEBUFFER,
+                    format.attachment_point(),
+                    gl::TEXTURE_2D,
+                    texture,
+                    0,
+                );
+                self.depth_texture = Some(Texture {
+                    gl_object_id: texture,
+                    width: self.width,
+                    height: self.height,
+                    target: Target::Texture2D,
+                });
+            }
+            None => (),
+        }
+
+        let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            return Err(format_err!(
+                "framebuffer is incomplete: {}",
+                describe_status(status)
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl FramebufferBuilder {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            color_formats: vec![],
+            depth_stencil: None,
+        }
+    }
+
+    /// Adds a color attachment. Attachments are bound to `GL_COLOR_ATTACHMENT0 + n` and fragment
+    /// shader outputs in the order they're added.
+    pub fn color_attachment(mut self, format: ColorFormat) -> Self {
+        self.color_formats.push(format);
+        self
+    }
+
+    pub fn depth_stencil(mut self, attachment: DepthStencilAttachment) -> Self {
+        self.depth_stencil = Some(attachment);
+        self
+    }
+
+    pub fn build(self) -> Result<Framebuffer> {
+        if self.width == 0 || self.height == 0 {
+            return Err(format_err!("framebuffer dimensions must be non-zero"));
+        }
+        if self.color_formats.is_empty() && self.depth_stencil.is_none() {
+            return Err(format_err!("framebuffer needs at least one attachment"));
+        }
+        let framebuffer = unsafe { self.build_impl() };
+        debug::check_errors("FramebufferBuilder::build");
+        framebuffer
+    }
+
+    unsafe fn build_impl(self) -> Result<Framebuffer> {
+        let mut max_color_attachments = 0;
+        gl::GetIntegerv(gl::MAX_COLOR_ATTACHMENTS, &mut max_color_attachments);
+        if self.color_formats.len() > try_into!(max_color_attachments) {
+            return Err(format_err!(
+                "requested {} color attachments but at most {max_color_attachments} are supported",
+                self.color_formats.len()
+            ));
+        }
+
+        let mut fbo = 0;
+        gl::GenFramebuffers(1, &mut fbo);
+
+        let mut framebuffer = Framebuffer {
+            gl_object_id: fbo,
+            width: self.width,
+            height: self.height,
+            color_formats: self.color_formats,
+            depth_stencil: self.depth_stencil,
+            color_textures: vec![],
+            depth_texture: None,
+            depth_renderbuffer: None,
+        };
+
+        if let Err(e) = framebuffer.attach_impl() {
+            framebuffer.delete();
+            return Err(e);
+        }
+        Ok(framebuffer)
+    }
+}
+
+unsafe fn new_texture(
+    width: gl::types::GLsizei,
+    height: gl::types::GLsizei,
+    internal_format: gl::types::GLenum,
+    pixel_format: gl::types::GLenum,
+    pixel_type: gl::types::GLenum,
+) -> gl::types::GLuint {
+    let mut texture = 0;
+    gl::GenTextures(1, &mut texture);
+    gl::BindTexture(gl::TEXTURE_2D, texture);
+    gl::TexImage2D(
+        gl::TEXTURE_2D,
+        0,
+        try_into!(internal_format),
+        width,
+        height,
+        0,
+        pixel_format,
+        pixel_type,
+        ptr::null(),
+    );
+    gl::TexParameteri(
+        gl::TEXTURE_2D,
+        gl::TEXTURE_MIN_FILTER,
+        try_into!(gl::LINEAR),
+    );
+    gl::TexParameteri(
+        gl::TEXTURE_2D,
+        gl::TEXTURE_MAG_FILTER,
+        try_into!(gl::LINEAR),
+    );
+    gl::TexParameteri(
+        gl::TEXTURE_2D,
+        gl::TEXTURE_WRAP_S,
+        try_into!(gl::CLAMP_TO_EDGE),
+    );
+    gl::TexParameteri(
+        gl::TEXTURE_2D,
+        gl::TEXTURE_WRAP_T,
+        try_into!(gl::CLAMP_TO_EDGE),
+    );
+    gl::BindTexture(gl::TEXTURE_2D, 0);
+    texture
+}
+
+fn describe_status(status: gl::types::GLenum) -> String {
+    let reason = match status {
+        gl::FRAMEBUFFER_UNDEFINED => "GL_FRAMEBUFFER_UNDEFINED (the default framebuffer doesn't exist)",
+        gl::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => {
+            "GL_FRAMEBUFFER_INCOMPLETE_ATTACHMENT (an attachment is incomplete or has an unrenderable format)"
+        }
+        gl::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => {
+            "GL_FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT (no images are attached)"
+        }
+        gl::FRAMEBUFFER_INCOMPLETE_DRAW_BUFFER => {
+            "GL_FRAMEBUFFER_INCOMPLETE_DRAW_BUFFER (a draw buffer has no attachment)"
+        }
+        gl::FRAMEBUFFER_INCOMPLETE_READ_BUFFER => {
+            "GL_FRAMEBUFFER_INCOMPLETE_READ_BUFFER (the read buffer has no attachment)"
+        }
+        gl::FRAMEBUFFER_UNSUPPORTED => {
+            "GL_FRAMEBUFFER_UNSUPPORTED (the combination of attachment formats isn't supported)"
+        }
+        gl::FRAMEBUFFER_INCOMPLETE_MULTISAMPLE => {
+            "GL_FRAMEBUFFER_INCOMPLETE_MULTISAMPLE (attachments have mismatched sample counts)"
+        }
+        gl::FRAMEBUFFER_INCOMPLETE_LAYER_TARGETS => {
+            "GL_FRAMEBUFFER_INCOMPLETE_LAYER_TARGETS (attachments are a mix of layered and non-layered)"
+        }
+        _ => return format!("unknown status 0x{status:X}"),
+    };
+    reason.to_string()
+}
@@ -0,0 +1,123 @@
+use crate::glutils::texture::{filter::Filter, wrap::Wrap};
+
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AlphaMode {
+    #[default]
+    Opaque,
+    Mask,
+    Blend,
+}
+
+/// Reference from a material to one of [`GltfScene::textures`](super::GltfScene).
+#[derive(Copy, Clone, Debug)]
+pub struct TextureRef {
+    pub texture: usize,
+    pub tex_coord: u32,
+}
+
+/// The metallic-roughness material model of the core glTF 2.0 specification.
+#[derive(Clone, Debug)]
+pub struct GltfMaterial {
+    pub name: Option<String>,
+    pub base_color_factor: [f32; 4],
+    pub base_color_texture: Option<TextureRef>,
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+    pub metallic_roughness_texture: Option<TextureRef>,
+    pub normal_texture: Option<TextureRef>,
+    pub normal_scale: f32,
+    pub occlusion_texture: Option<TextureRef>,
+    pub occlusion_strength: f32,
+    pub emissive_factor: [f32; 3],
+    pub emissive_texture: Option<TextureRef>,
+    pub alpha_mode: AlphaMode,
+    pub alpha_cutoff: f32,
+    pub double_sided: bool,
+}
+
+/// A texture is an index into [`GltfScene::images`](super::GltfScene) paired with sampler state.
+/// Filters left unspecified by the asset are up to the application.
+#[derive(Copy, Clone, Debug)]
+pub struct GltfTexture {
+    pub image: usize,
+    pub wrap_s: Wrap,
+    pub wrap_t: Wrap,
+    pub min_filter: Option<Filter>,
+    pub mag_filter: Option<Filter>,
+}
+
+impl From<::gltf::Material<'_>> for GltfMaterial {
+    fn from(material: ::gltf::Material<'_>) -> Self {
+        let pbr = material.pbr_metallic_roughness();
+        let normal = material.normal_texture();
+        let occlusion = material.occlusion_texture();
+
+        Self {
+            name: material.name().map(String::from),
+            base_color_factor: pbr.base_color_factor(),
+            base_color_texture: pbr.base_color_texture().map(|info| TextureRef {
+                texture: info.texture().index(),
+                tex_coord: info.tex_coord(),
+            }),
+            metallic_factor: pbr.metallic_factor(),
+            roughness_factor: pbr.roughness_factor(),
+            metallic_roughness_texture: pbr.metallic_roughness_texture().map(|info| TextureRef {
+                texture: info.texture().index(),
+                tex_coord: info.tex_coord(),
+            }),
+            normal_scale: normal.as_ref().map_or(1.0, |n| n.scale()),
+            normal_texture: normal.map(|n| TextureRef {
+                texture: n.texture().index(),
+                tex_coord: n.tex_coord(),
+            }),
+            occlusion_strength: occlusion.as_ref().map_or(1.0, |o| o.strength()),
+            occlusion_texture: occlusion.map(|o| TextureRef {
+                texture: o.texture().index(),
+                tex_coord: o.tex_coord(),
+            }),
+            emissive_factor: material.emissive_factor(),
+            emissive_texture: material.emissive_texture().map(|info| TextureRef {
+                texture: info.texture().index(),
+                tex_coord: info.tex_coord(),
+            }),
+            alpha_mode: match material.alpha_mode() {
+                ::gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+                ::gltf::material::AlphaMode::Mask => AlphaMode::Mask,
+                ::gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+            },
+            alpha_cutoff: material.alpha_cutoff().unwrap_or(0.5),
+            double_sided: material.double_sided(),
+        }
+    }
+}
+
+impl From<::gltf::Texture<'_>> for GltfTexture {
+    fn from(texture: ::gltf::Texture<'_>) -> Self {
+        use ::gltf::texture::{MagFilter, MinFilter, WrappingMode};
+
+        let wrap = |mode| match mode {
+            WrappingMode::ClampToEdge => Wrap::ClampToEdge,
+            WrappingMode::MirroredRepeat => Wrap::MirroredRepeat,
+            WrappingMode::Repeat => Wrap::Repeat,
+        };
+        let sampler = texture.sampler();
+
+        Self {
+            image: texture.source().index(),
+            wrap_s: wrap(sampler.wrap_s()),
+            wrap_t: wrap(sampler.wrap_t()),
+            min_filter: sampler.min_filter().map(|filter| match filter {
+                MinFilter::Nearest => Filter::Nearest,
+                MinFilter::Linear => Filter::Linear,
+                MinFilter::NearestMipmapNearest => Filter::NearestMipmapNearest,
+                MinFilter::LinearMipmapNearest => Filter::LinearMipmapNearest,
+                MinFilter::NearestMipmapLinear => Filter::NearestMipmapLinear,
+                MinFilter::LinearMipmapLinear => Filter::LinearMipmapLinear,
+            }),
+            mag_filter: sampler.mag_filter().map(|filter| match filter {
+                MagFilter::Nearest => Filter::Nearest,
+                MagFilter::Linear => Filter::Linear,
+            }),
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use crate::glutils::{mesh::Mesh, model::primitives::Primitive};
+
+pub struct GltfMesh {
+    pub name: Option<String>,
+    pub primitives: Vec<GltfPrimitive>,
+}
+
+/// A single draw's worth of geometry. Non-indexed primitives are given sequential indices so that
+/// every primitive can be treated as a [`Mesh`], whose indices describe `mode` primitives. Only
+/// apply [`Mesh`]'s triangle operations when `mode` is [`Primitive::Triangles`].
+pub struct GltfPrimitive {
+    pub mode: Primitive,
+    pub mesh: Mesh,
+    pub material: Option<usize>,
+}
+
+impl From<::gltf::mesh::Mode> for Primitive {
+    fn from(value: ::gltf::mesh::Mode) -> Self {
+        use ::gltf::mesh::Mode;
+
+        match value {
+            Mode::Points => Primitive::Points,
+            Mode::Lines => Primitive::Lines,
+            Mode::LineLoop => Primitive::LineLoop,
+            Mode::LineStrip => Primitive::LineStrip,
+            Mode::Triangles => Primitive::Triangles,
+            Mode::TriangleStrip => Primitive::TriangleStrip,
+            Mode::TriangleFan => Primitive::TriangleFan,
+        }
+    }
+}
@@ -0,0 +1,399 @@
+use crate::glutils::{
+    mesh::Mesh,
+    model::{usage::Usage, Model},
+    shader::program::Program,
+    texture::{filter::Filter, Texture, TextureBuilder},
+};
+use anyhow::{format_err, Result};
+use glam::Mat4;
+use image::RgbaImage;
+use std::{fs, path::Path};
+
+pub mod material;
+use material::{GltfMaterial, GltfTexture};
+
+pub mod mesh;
+use mesh::{GltfMesh, GltfPrimitive};
+
+/// Required extensions that the importer knows how to handle. Assets requiring anything else are
+/// rejected up front rather than silently rendering incorrectly.
+pub const SUPPORTED_EXTENSIONS: &[&str] = &[];
+
+/// A glTF 2.0 asset decoded into CPU-side data. Nodes, meshes, materials, textures and images
+/// are stored in flat arrays and reference each other by index just as they do in the file.
+pub struct GltfScene {
+    pub nodes: Vec<GltfNode>,
+    pub roots: Vec<usize>,
+    pub meshes: Vec<GltfMesh>,
+    pub materials: Vec<GltfMaterial>,
+    pub textures: Vec<GltfTexture>,
+    pub images: Vec<RgbaImage>,
+}
+
+pub struct GltfNode {
+    pub name: Option<String>,
+    /// Transform relative to the parent node.
+    pub local_transform: Mat4,
+    pub mesh: Option<usize>,
+    pub children: Vec<usize>,
+}
+
+impl GltfScene {
+    /// Imports a `.gltf` or `.glb` file. Buffers and images may be embedded as base64 data URIs,
+    /// stored in the GLB binary chunk, or live in external files relative to `path`.
+    pub fn import<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes =
+            fs::read(path).map_err(|e| format_err!("failed to load '{}': {e}", path.display()))?;
+
+        let ::gltf::Gltf { document, blob } =
+            ::gltf::Gltf::from_slice_without_validation(&bytes)
+                .map_err(|e| format_err!("failed to parse '{}': {e}", path.display()))?;
+
+        let unsupported = document
+            .extensions_required()
+            .filter(|ext| !SUPPORTED_EXTENSIONS.contains(ext))
+            .collect::<Vec<_>>();
+
+        if !unsupported.is_empty() {
+            return Err(format_err!(
+                "'{}' requires unsupported glTF extension(s): {}",
+                path.display(),
+                unsupported.join(", ")
+            ));
+        }
+
+        let document = ::gltf::Document::from_json(document.into_json())
+            .map_err(|e| format_err!("invalid glTF in '{}': {e}", path.display()))?;
+
+        let base = path.parent();
+        let buffers = ::gltf::import_buffers(&document, base, blob)
+            .map_err(|e| format_err!("failed to load buffers of '{}': {e}", path.display()))?;
+        let images = ::gltf::import_images(&document, base, &buffers)
+            .map_err(|e| format_err!("failed to load images of '{}': {e}", path.display()))?;
+
+        let meshes = document
+            .meshes()
+            .map(|mesh| Self::import_mesh(mesh, &buffers))
+            .collect::<Result<Vec<_>>>()?;
+
+        let nodes = document
+            .nodes()
+            .map(|node| GltfNode {
+                name: node.name().map(String::from),
+                local_transform: Mat4::from_cols_array_2d(&node.transform().matrix()),
+                mesh: node.mesh().map(|m| m.index()),
+                children: node.children().map(|c| c.index()).collect(),
+            })
+            .collect::<Vec<_>>();
+
+        let roots = match document
+            .default_scene()
+            .or_else(|| document.scenes().next())
+        {
+            Some(scene) => scene.nodes().map(|n| n.index()).collect(),
+            None => (0..nodes.len())
+                .filter(|i| !nodes.iter().any(|n| n.children.contains(i)))
+                .collect(),
+        };
+
+        Ok(Self {
+            nodes,
+            roots,
+            meshes,
+            materials: document.materials().map(GltfMaterial::from).collect(),
+            textures: document.textures().map(GltfTexture::from).collect(),
+            images: images
+                .into_iter()
+                .map(decode_image)
+                .collect::<Result<Vec<_>>>()?,
+        })
+    }
+
+    /// Builds one [`Model`] per primitive, grouped by mesh.
+    pub fn build_models(&self, program: Program, usage: Usage) -> Result<Vec<Vec<Model>>> {
+        self.meshes
+            .iter()
+            .map(|mesh| {
+                mesh.primitives
+                    .iter()
+                    .map(|prim| prim.mesh.model_builder(prim.mode, program, usage)?.build())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Uploads every texture, applying the asset's sampler state. Images are flipped to match the
+    /// texture coordinates, which are converted to the OpenGL convention on import. Indices into
+    /// the returned vector match [`GltfScene::textures`].
+    pub fn build_textures(&self) -> Result<Vec<Texture>> {
+        self.textures
+            .iter()
+            .map(|tex| {
+                let image = self
+                    .images
+                    .get(tex.image)
+                    .ok_or_else(|| format_err!("texture references missing image {}", tex.image))?;
+
+                TextureBuilder::new()
+                    .wrap(tex.wrap_s, tex.wrap_t)
+                    .filter(
+                        tex.min_filter.unwrap_or(Filter::LinearMipmapLinear),
+                        tex.mag_filter.unwrap_or(Filter::Linear),
+                    )?
+                    .build_from_rgba(
+                        image.width(),
+                        image.height(),
+                        image::imageops::flip_vertical(image).as_raw(),
+                    )
+            })
+            .collect()
+    }
+
+    /// Computes the world transform of every node by walking the hierarchy from the roots.
+    pub fn world_transforms(&self) -> Vec<Mat4> {
+        let mut world = vec![Mat4::IDENTITY; self.nodes.len()];
+        let mut stack = self
+            .roots
+            .iter()
+            .map(|root| (*root, Mat4::IDENTITY))
+            .collect::<Vec<_>>();
+
+        while let Some((index, parent)) = stack.pop() {
+            let node = &self.nodes[index];
+            world[index] = parent * node.local_transform;
+            stack.extend(node.children.iter().map(|c| (*c, world[index])));
+        }
+        world
+    }
+
+    fn import_mesh(mesh: ::gltf::Mesh<'_>, buffers: &[::gltf::buffer::Data]) -> Result<GltfMesh> {
+        let primitives = mesh
+            .primitives()
+            .map(|prim| {
+                let reader = prim.reader(|buffer| buffers.get(buffer.index()).map(|d| &d.0[..]));
+
+                let positions = reader
+                    .read_positions()
+                    .ok_or_else(|| {
+                        format_err!(
+                            "primitive {} of mesh '{}' has no POSITION attribute",
+                            prim.index(),
+                            mesh.name().unwrap_or("<unnamed>")
+                        )
+                    })?
+                    .collect::<Vec<_>>();
+
+                let indices = match reader.read_indices() {
+                    Some(indices) => indices.into_u32().collect(),
+                    None => (0..u32::try_from(positions.len()).unwrap()).collect(),
+                };
+
+                Ok(GltfPrimitive {
+                    mode: prim.mode().into(),
+                    mesh: Mesh {
+                        positions,
+                        normals: reader.read_normals().map(Iterator::collect),
+                        tangents: reader.read_tangents().map(Iterator::collect),
+                        // glTF puts `v = 0` at the top of the image.
+                        uvs: reader
+                            .read_tex_coords(0)
+                            .map(|t| t.into_f32().map(|[u, v]| [u, 1.0 - v]).collect()),
+                        colors: reader.read_colors(0).map(|c| c.into_rgba_f32().collect()),
+                        indices,
+                    },
+                    material: prim.material().index(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(GltfMesh {
+            name: mesh.name().map(String::from),
+            primitives,
+        })
+    }
+}
+
+/// glTF images may decode to a handful of channel layouts and bit depths; everything is
+/// normalized to 8-bit RGBA so it can be handed straight to [`TextureBuilder`].
+fn decode_image(data: ::gltf::image::Data) -> Result<RgbaImage> {
+    use ::gltf::image::Format;
+
+    let (channels, channel_size) = match data.format {
+        Format::R8 => (1, 1),
+        Format::R8G8 => (2, 1),
+        Format::R8G8B8 => (3, 1),
+        Format::R8G8B8A8 => (4, 1),
+        Format::R16 => (1, 2),
+        Format::R16G16 => (2, 2),
+        Format::R16G16B16 => (3, 2),
+        Format::R16G16B16A16 => (4, 2),
+        Format::R32G32B32FLOAT => (3, 4),
+        Format::R32G32B32A32FLOAT => (4, 4),
+    };
+
+    let mut rgba = Vec::with_capacity(data.pixels.len() / (channels * channel_size) * 4);
+
+    for texel in data.pixels.chunks_exact(channels * channel_size) {
+        let mut values = texel.chunks_exact(channel_size).map(|c| match c {
+            [v] => *v,
+            [a, b] => (u16::from_ne_bytes([*a, *b]) >> 8) as u8,
+            [a, b, c, d] => (f32::from_ne_bytes([*a, *b, *c, *d]).clamp(0.0, 1.0) * 255.0) as u8,
+            _ => unreachable!(),
+        });
+
+        match channels {
+            // Luminance and luminance-alpha.
+            1 | 2 => {
+                let luma = values.next().unwrap();
+                let alpha = values.next().unwrap_or(u8::MAX);
+                rgba.extend_from_slice(&[luma, luma, luma, alpha]);
+            }
+            _ => {
+                let mut texel = [0, 0, 0, u8::MAX];
+                texel.iter_mut().zip(values).for_each(|(t, v)| *t = v);
+                rgba.extend_from_slice(&texel);
+            }
+        }
+    }
+
+    RgbaImage::from_raw(data.width, data.height, rgba).ok_or_else(|| {
+        format_err!(
+            "image data doesn't match its {}x{} size",
+            data.width,
+            data.height
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::glutils::{model::primitives::Primitive, texture::wrap::Wrap};
+    use glam::{vec3, Vec3};
+    use std::path::PathBuf;
+
+    const TRIANGLE_POSITIONS: [[f32; 3]; 3] =
+        [[0.5, -0.5, 0.0], [-0.5, -0.5, 0.0], [0.0, 0.5, 0.0]];
+
+    fn models_dir() -> PathBuf {
+        PathBuf::new().join("models").join("gltf")
+    }
+
+    fn assert_triangle(scene: &GltfScene) {
+        assert_eq!(scene.roots, [0]);
+        assert_eq!(scene.nodes.len(), 1);
+        assert_eq!(scene.nodes[0].name.as_deref(), Some("Triangle"));
+        assert_eq!(scene.nodes[0].mesh, Some(0));
+        assert_eq!(scene.meshes.len(), 1);
+
+        let primitive = &scene.meshes[0].primitives[0];
+        assert_eq!(primitive.mode, Primitive::Triangles);
+        assert_eq!(primitive.material, None);
+        let mesh = &primitive.mesh;
+        assert_eq!(mesh.positions, TRIANGLE_POSITIONS);
+        assert_eq!(
+            mesh.colors.as_deref(),
+            Some(
+                &[
+                    [1.0, 0.0, 0.0, 1.0],
+                    [0.0, 1.0, 0.0, 1.0],
+                    [0.0, 0.0, 1.0, 1.0]
+                ][..]
+            )
+        );
+        assert_eq!(mesh.indices, [0, 1, 2]);
+        assert!(mesh.normals.is_none() && mesh.uvs.is_none());
+        assert!(scene.materials.is_empty() && scene.textures.is_empty());
+    }
+
+    #[test]
+    fn imports_external_buffer() {
+        assert_triangle(
+            &GltfScene::import(models_dir().join("triangle").join("triangle.gltf")).unwrap(),
+        );
+    }
+
+    #[test]
+    fn imports_base64_buffer() {
+        let path = models_dir().join("triangle").join("triangle_embedded.gltf");
+        assert_triangle(&GltfScene::import(path).unwrap());
+    }
+
+    #[test]
+    fn imports_binary_chunk() {
+        assert_triangle(
+            &GltfScene::import(models_dir().join("triangle").join("triangle.glb")).unwrap(),
+        );
+    }
+
+    #[test]
+    fn imports_hierarchy_materials_and_textures() {
+        let path = models_dir()
+            .join("textured_quad")
+            .join("textured_quad.gltf");
+        let scene = GltfScene::import(path).unwrap();
+
+        assert_eq!(scene.roots, [0]);
+        let names = scene
+            .nodes
+            .iter()
+            .map(|n| n.name.as_deref().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Root", "Left", "Right"]);
+        assert_eq!(scene.nodes[0].children, [1, 2]);
+        assert_eq!(scene.nodes[0].mesh, None);
+        assert_eq!(scene.nodes[1].mesh, Some(0));
+        assert_eq!(scene.nodes[2].mesh, Some(0));
+
+        let world = scene.world_transforms();
+        let left = world[1].transform_point3(Vec3::ZERO);
+        assert!(left.abs_diff_eq(vec3(-0.5, 0.25, 0.0), 1e-6), "{left}");
+        let corner = world[1].transform_point3(vec3(0.5, 0.5, 0.0));
+        assert!(corner.abs_diff_eq(vec3(-0.25, 0.5, 0.0), 1e-6), "{corner}");
+
+        let primitive = &scene.meshes[0].primitives[0];
+        assert_eq!(primitive.material, Some(0));
+        let mesh = &primitive.mesh;
+        assert_eq!(mesh.num_vertices(), 4);
+        assert_eq!(mesh.indices, [0, 1, 3, 1, 2, 3]);
+        assert_eq!(mesh.normals.as_ref().unwrap().len(), 4);
+        // Flipped from glTF's `v = 0` at the top.
+        assert_eq!(
+            mesh.uvs.as_deref(),
+            Some(&[[1.0, 1.0], [1.0, 0.0], [0.0, 0.0], [0.0, 1.0]][..])
+        );
+
+        let material = &scene.materials[0];
+        assert_eq!(material.name.as_deref(), Some("Wall"));
+        assert_eq!(material.base_color_texture.unwrap().texture, 0);
+        assert_eq!(material.metallic_factor, 0.0);
+        assert!(material.double_sided);
+
+        let texture = &scene.textures[0];
+        assert_eq!(texture.image, 0);
+        assert_eq!(
+            (texture.wrap_s, texture.wrap_t),
+            (Wrap::Repeat, Wrap::Repeat)
+        );
+        assert_eq!(texture.min_filter, Some(Filter::LinearMipmapLinear));
+        assert_eq!(texture.mag_filter, Some(Filter::Linear));
+        assert_eq!(scene.images.len(), 1);
+        assert!(scene.images[0].width() > 0);
+    }
+
+    #[test]
+    fn rejects_unsupported_required_extensions() {
+        let path = models_dir().join("unsupported").join("draco_required.gltf");
+        let err = GltfScene::import(path).err().unwrap();
+        assert!(
+            err.to_string().contains("KHR_draco_mesh_compression"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn reports_missing_files() {
+        assert!(GltfScene::import(models_dir().join("missing.gltf")).is_err());
+    }
+}
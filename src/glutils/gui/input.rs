@@ -0,0 +1,56 @@
+use egui::{Key, Modifiers, PointerButton};
+
+/// The egui key for a GLFW key, for the keys text fields and their shortcuts use.
+pub fn key(key: glfw::Key) -> Option<Key> {
+    Some(match key {
+        glfw::Key::Left => Key::ArrowLeft,
+        glfw::Key::Right => Key::ArrowRight,
+        glfw::Key::Up => Key::ArrowUp,
+        glfw::Key::Down => Key::ArrowDown,
+        glfw::Key::Home => Key::Home,
+        glfw::Key::End => Key::End,
+        glfw::Key::PageUp => Key::PageUp,
+        glfw::Key::PageDown => Key::PageDown,
+        glfw::Key::Backspace => Key::Backspace,
+        glfw::Key::Delete => Key::Delete,
+        glfw::Key::Insert => Key::Insert,
+        glfw::Key::Enter | glfw::Key::KpEnter => Key::Enter,
+        glfw::Key::Tab => Key::Tab,
+        glfw::Key::Escape => Key::Escape,
+        glfw::Key::Space => Key::Space,
+        glfw::Key::A => Key::A,
+        glfw::Key::C => Key::C,
+        glfw::Key::V => Key::V,
+        glfw::Key::X => Key::X,
+        glfw::Key::Y => Key::Y,
+        glfw::Key::Z => Key::Z,
+        _ => return None,
+    })
+}
+
+pub fn pointer_button(button: glfw::MouseButton) -> Option<PointerButton> {
+    Some(match button {
+        glfw::MouseButton::Button1 => PointerButton::Primary,
+        glfw::MouseButton::Button2 => PointerButton::Secondary,
+        glfw::MouseButton::Button3 => PointerButton::Middle,
+        glfw::MouseButton::Button4 => PointerButton::Extra1,
+        glfw::MouseButton::Button5 => PointerButton::Extra2,
+        _ => return None,
+    })
+}
+
+pub fn modifiers(modifiers: glfw::Modifiers) -> Modifiers {
+    let ctrl = modifiers.contains(glfw::Modifiers::Control);
+    let mac_cmd = cfg!(target_os = "macos") && modifiers.contains(glfw::Modifiers::Super);
+    Modifiers {
+        alt: modifiers.contains(glfw::Modifiers::Alt),
+        ctrl,
+        shift: modifiers.contains(glfw::Modifiers::Shift),
+        mac_cmd,
+        command: if cfg!(target_os = "macos") {
+            mac_cmd
+        } else {
+            ctrl
+        },
+    }
+}
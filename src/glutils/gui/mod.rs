@@ -0,0 +1,315 @@
+use super::{
+    material::Material,
+    model::{primitives::Primitive, stream::VertexStream},
+    render_state::{blend::Blend, raster::Scissor, RenderState, StateTracker},
+    shader::{program::Linker, Shader, ShaderType},
+    texture::{filter::Filter, wrap::Wrap, Texture, TextureBuilder},
+};
+use anyhow::{format_err, Result};
+use egui::{
+    epaint::{ClippedPrimitive, ImageDelta, Primitive as EguiPrimitive},
+    pos2, vec2, Context, Event, ImageData, MouseWheelUnit, RawInput, Rect, TextureFilter,
+    TextureId, TextureWrapMode, ViewportId,
+};
+use glfw::{Action, PWindow, WindowEvent};
+use std::{collections::HashMap, path::PathBuf};
+
+mod input;
+
+/// Floats per vertex: position, texture coordinates and color.
+const VERTEX_SIZE: usize = 8;
+
+/// An in-window immediate mode GUI built with [`egui`], for tweaking a lesson's parameters while
+/// it runs. Every frame, lessons feed it the window's events with [`Gui::handle_event`], build
+/// their widgets in [`Gui::run`] and draw them over the frame with [`Gui::draw`].
+pub struct Gui {
+    context: Context,
+    input: RawInput,
+    /// Last cursor position in points, which egui wants with every button press.
+    pointer: egui::Pos2,
+    pixels_per_point: f32,
+    primitives: Vec<ClippedPrimitive>,
+    textures: HashMap<TextureId, Texture>,
+    /// egui's textures to delete once the current primitives are drawn.
+    to_free: Vec<TextureId>,
+    material: Material,
+    stream: VertexStream,
+    vertices: Vec<f32>,
+}
+
+impl Gui {
+    /// Also turns on polling of the mouse and text events the GUI needs on `window`.
+    pub fn new(window: &mut PWindow) -> Result<Self> {
+        window.set_cursor_pos_polling(true);
+        window.set_cursor_enter_polling(true);
+        window.set_mouse_button_polling(true);
+        window.set_scroll_polling(true);
+        window.set_char_polling(true);
+        window.set_focus_polling(true);
+
+        let shaders_dir = PathBuf::new().join("shaders").join("gui");
+        let vs = Shader::new(
+            shaders_dir.join("gui_vertex_shader.glsl"),
+            ShaderType::Vertex,
+        )?;
+        let fs = Shader::new(
+            shaders_dir.join("gui_fragment_shader.glsl"),
+            ShaderType::Fragment,
+        )?;
+        let program = Linker::new().attach_shader(vs).attach_shader(fs).link()?;
+        let stream = VertexStream::new(
+            &program,
+            &[("aPos", 2), ("aTexCoord", 2), ("aCol", 4)],
+            "gui",
+        )?;
+
+        let mut max_texture_size = 0;
+        unsafe { gl::GetIntegerv(gl::MAX_TEXTURE_SIZE, &mut max_texture_size) };
+
+        Ok(Self {
+            context: Context::default(),
+            input: RawInput {
+                max_texture_side: Some(usize::try_from(max_texture_size)?),
+                focused: true,
+                ..RawInput::default()
+            },
+            pointer: egui::Pos2::ZERO,
+            pixels_per_point: 1.0,
+            primitives: vec![],
+            textures: HashMap::new(),
+            to_free: vec![],
+            material: Material::new(program),
+            stream,
+            vertices: vec![],
+        })
+    }
+
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Whether the GUI is using the keyboard, e.g. to type into a text field, so the lesson
+    /// shouldn't react to keys.
+    pub fn wants_keyboard(&self) -> bool {
+        self.context.wants_keyboard_input()
+    }
+
+    /// Whether the pointer is over the GUI or dragging one of its widgets, so the lesson
+    /// shouldn't react to the mouse.
+    pub fn wants_pointer(&self) -> bool {
+        self.context.wants_pointer_input()
+    }
+
+    /// Queues the input in `event` for the next [`Gui::run`].
+    pub fn handle_event(&mut self, event: &WindowEvent) {
+        let event = match *event {
+            WindowEvent::CursorPos(x, y) => {
+                self.pointer = pos2(x as f32, y as f32);
+                Event::PointerMoved(self.pointer)
+            }
+            WindowEvent::CursorEnter(false) => Event::PointerGone,
+            WindowEvent::MouseButton(button, action, modifiers) => {
+                let Some(button) = input::pointer_button(button) else {
+                    return;
+                };
+                Event::PointerButton {
+                    pos: self.pointer,
+                    button,
+                    pressed: action != Action::Release,
+                    modifiers: input::modifiers(modifiers),
+                }
+            }
+            WindowEvent::Scroll(x, y) => Event::MouseWheel {
+                unit: MouseWheelUnit::Line,
+                delta: vec2(x as f32, y as f32),
+                modifiers: self.input.modifiers,
+            },
+            WindowEvent::Key(key, _, action, modifiers) => {
+                self.input.modifiers = input::modifiers(modifiers);
+                let Some(key) = input::key(key) else {
+                    return;
+                };
+                Event::Key {
+                    key,
+                    physical_key: None,
+                    pressed: action != Action::Release,
+                    repeat: action == Action::Repeat,
+                    modifiers: self.input.modifiers,
+                }
+            }
+            WindowEvent::Char(c) if !c.is_control() => Event::Text(c.to_string()),
+            WindowEvent::Focus(focused) => {
+                self.input.focused = focused;
+                Event::WindowFocused(focused)
+            }
+            _ => return,
+        };
+        self.input.events.push(event);
+    }
+
+    /// Builds this frame's widgets with `build` from the input queued since the last call, at
+    /// `time` seconds. They're drawn by the next [`Gui::draw`].
+    pub fn run<F>(&mut self, window: &PWindow, time: f64, build: F) -> Result<()>
+    where
+        F: FnMut(&Context),
+    {
+        let (width, height) = window.get_size();
+        let (framebuffer_width, _) = window.get_framebuffer_size();
+        self.pixels_per_point = framebuffer_width as f32 / width.max(1) as f32;
+
+        let mut input = self.input.take();
+        input.time = Some(time);
+        input.screen_rect = Some(Rect::from_min_size(
+            egui::Pos2::ZERO,
+            vec2(width as f32, height as f32),
+        ));
+        input
+            .viewports
+            .entry(ViewportId::ROOT)
+            .or_default()
+            .native_pixels_per_point = Some(self.pixels_per_point);
+
+        let output = self.context.run(input, build);
+        for (id, delta) in output.textures_delta.set {
+            self.set_texture(id, &delta)?;
+        }
+        self.to_free.extend(output.textures_delta.free);
+        self.primitives = self
+            .context
+            .tessellate(output.shapes, output.pixels_per_point);
+        self.pixels_per_point = output.pixels_per_point;
+        Ok(())
+    }
+
+    /// Draws the widgets built by the last [`Gui::run`] over the bound framebuffer, which is
+    /// `width` by `height` pixels.
+    pub fn draw(&mut self, state: &mut StateTracker, width: i32, height: i32) -> Result<()> {
+        let ppp = self.pixels_per_point;
+        self.material.set(
+            "screenSize",
+            glam::vec2(width as f32 / ppp, height as f32 / ppp),
+        );
+
+        for ClippedPrimitive {
+            clip_rect,
+            primitive,
+        } in &self.primitives
+        {
+            let EguiPrimitive::Mesh(mesh) = primitive else {
+                // Callbacks are for other renderers' custom painting, which nothing here uses.
+                continue;
+            };
+            let texture = self.textures.get(&mesh.texture_id).ok_or_else(|| {
+                format_err!("GUI mesh uses unknown texture {:?}", mesh.texture_id)
+            })?;
+
+            // Clip rectangles are in points from the top left, scissor boxes in pixels from
+            // the bottom left.
+            let min_x = (clip_rect.min.x * ppp).round().clamp(0.0, width as f32) as i32;
+            let max_x = (clip_rect.max.x * ppp).round().clamp(0.0, width as f32) as i32;
+            let min_y = (clip_rect.min.y * ppp).round().clamp(0.0, height as f32) as i32;
+            let max_y = (clip_rect.max.y * ppp).round().clamp(0.0, height as f32) as i32;
+            if max_x <= min_x || max_y <= min_y {
+                continue;
+            }
+
+            self.vertices.clear();
+            for index in &mesh.indices {
+                let vertex = &mesh.vertices[usize::try_from(*index)?];
+                let color = vertex.color.to_array().map(|c| c as f32 / 255.0);
+                self.vertices.extend_from_slice(&[
+                    vertex.pos.x,
+                    vertex.pos.y,
+                    vertex.uv.x,
+                    vertex.uv.y,
+                ]);
+                self.vertices.extend_from_slice(&color);
+            }
+            debug_assert!(self.vertices.len().is_multiple_of(VERTEX_SIZE));
+
+            self.material.set_texture("image", *texture);
+            self.material.set_render_state(RenderState {
+                depth_write: false,
+                blend: Some(Blend::PREMULTIPLIED_ALPHA),
+                scissor: Some(Scissor {
+                    x: min_x,
+                    y: height - max_y,
+                    width: max_x - min_x,
+                    height: max_y - min_y,
+                }),
+                ..RenderState::DEFAULT
+            });
+            self.material.bind(state)?;
+            self.stream.draw(&self.vertices, Primitive::Triangles);
+        }
+
+        // Otherwise the last scissor box would also limit the next frame's clear.
+        state.apply(&RenderState::DEFAULT);
+
+        for id in self.to_free.drain(..) {
+            if let Some(texture) = self.textures.remove(&id) {
+                texture.delete();
+            }
+        }
+        Ok(())
+    }
+
+    /// Makes `texture` available to widgets such as [`egui::Image`]. The GUI doesn't take
+    /// ownership of it.
+    pub fn register_texture(&mut self, texture: Texture) -> TextureId {
+        let id = TextureId::User(u64::from(texture.gl_object_id));
+        self.textures.insert(id, texture);
+        id
+    }
+
+    pub fn delete(self) {
+        for (id, texture) in self.textures {
+            if matches!(id, TextureId::Managed(_)) {
+                texture.delete();
+            }
+        }
+        self.stream.delete();
+    }
+
+    /// Creates or patches one of egui's own textures, such as its font atlas.
+    fn set_texture(&mut self, id: TextureId, delta: &ImageDelta) -> Result<()> {
+        let ImageData::Color(image) = &delta.image;
+        let [width, height] = image.size.map(|s| u32::try_from(s).unwrap());
+        let texels: Vec<u8> = image.pixels.iter().flat_map(|c| c.to_array()).collect();
+
+        if let Some([x, y]) = delta.pos {
+            let texture = self
+                .textures
+                .get(&id)
+                .ok_or_else(|| format_err!("GUI patches unknown texture {id:?}"))?;
+            return texture.update_rgba(
+                u32::try_from(x)?,
+                u32::try_from(y)?,
+                width,
+                height,
+                &texels,
+            );
+        }
+
+        let filter = |filter| match filter {
+            TextureFilter::Nearest => Filter::Nearest,
+            TextureFilter::Linear => Filter::Linear,
+        };
+        let wrap = match delta.options.wrap_mode {
+            TextureWrapMode::ClampToEdge => Wrap::ClampToEdge,
+            TextureWrapMode::Repeat => Wrap::Repeat,
+            TextureWrapMode::MirroredRepeat => Wrap::MirroredRepeat,
+        };
+        let texture = TextureBuilder::new()
+            .wrap(wrap, wrap)
+            .filter(
+                filter(delta.options.minification),
+                filter(delta.options.magnification),
+            )?
+            .build_from_rgba(width, height, &texels)?;
+        if let Some(old) = self.textures.insert(id, texture) {
+            old.delete();
+        }
+        Ok(())
+    }
+}
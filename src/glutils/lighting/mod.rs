@@ -0,0 +1,378 @@
+use super::{
+    material::Material,
+    shader::program::Program,
+    texture::{Texture, TextureBuilder},
+    uniform_buffer::std140,
+};
+use anyhow::{format_err, Result};
+use glam::Vec3;
+
+/// Array sizes of the `Lights` uniform block declared in `shaders/include/lights.glsl`.
+pub const MAX_DIRECTIONAL_LIGHTS: usize = 4;
+pub const MAX_POINT_LIGHTS: usize = 8;
+pub const MAX_SPOT_LIGHTS: usize = 4;
+
+std140::uniform_block! {
+    /// Light infinitely far away shining in `direction`, like the sun.
+    #[derive(Copy, Clone, Debug, Default, PartialEq)]
+    pub struct DirectionalLight {
+        pub direction: Vec3,
+        pub ambient: Vec3,
+        pub diffuse: Vec3,
+        pub specular: Vec3,
+    }
+}
+
+std140::uniform_block! {
+    /// Light shining in every direction from `position`, fading with distance as
+    /// `1 / (constant + linear * d + quadratic * d²)`.
+    #[derive(Copy, Clone, Debug, Default, PartialEq)]
+    pub struct PointLight {
+        pub position: Vec3,
+        pub ambient: Vec3,
+        pub diffuse: Vec3,
+        pub specular: Vec3,
+        pub constant: f32,
+        pub linear: f32,
+        pub quadratic: f32,
+    }
+}
+
+std140::uniform_block! {
+    /// Point light restricted to a cone around `direction`. The cutoffs are the cosines of the
+    /// angles at which the light starts to fade out and is fully off.
+    #[derive(Copy, Clone, Debug, Default, PartialEq)]
+    pub struct SpotLight {
+        pub position: Vec3,
+        pub direction: Vec3,
+        pub ambient: Vec3,
+        pub diffuse: Vec3,
+        pub specular: Vec3,
+        pub inner_cutoff: f32,
+        pub outer_cutoff: f32,
+        pub constant: f32,
+        pub linear: f32,
+        pub quadratic: f32,
+    }
+}
+
+std140::uniform_block! {
+    /// Contents of the `Lights` uniform block, built with [`Lighting::block`]. The `*_shadow`
+    /// members are the index of the light of each kind that has a shadow map, or -1.
+    #[derive(Copy, Clone, Debug, Default, PartialEq)]
+    pub struct Lights {
+        pub directional_lights: [DirectionalLight; MAX_DIRECTIONAL_LIGHTS],
+        pub point_lights: [PointLight; MAX_POINT_LIGHTS],
+        pub spot_lights: [SpotLight; MAX_SPOT_LIGHTS],
+        pub num_directional_lights: i32,
+        pub num_point_lights: i32,
+        pub num_spot_lights: i32,
+        pub directional_shadow: i32,
+        pub spot_shadow: i32,
+        pub point_shadow: i32,
+        pub blinn_phong: bool,
+        pub view_position: Vec3,
+    }
+}
+
+/// How specular highlights are computed.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Shading {
+    /// Reflects the light direction around the normal and compares it with the view direction,
+    /// which cuts highlights off sharply once the angle between them exceeds 90°.
+    Phong,
+    /// Compares the normal with the vector halfway between the light and view directions. Needs
+    /// a higher shininess than Phong for highlights of the same size.
+    #[default]
+    BlinnPhong,
+}
+
+/// Constant, linear and quadratic attenuation terms that fade a light out over roughly `range`
+/// units, fitted to the commonly used table of values.
+pub fn attenuation(range: f32) -> (f32, f32, f32) {
+    (1.0, 4.5 / range, 75.0 / (range * range))
+}
+
+/// Distance at which light with the given attenuation terms has faded to 1/256 of its
+/// intensity, past which it can't make a visible difference to 8-bit colors.
+pub fn falloff_distance(constant: f32, linear: f32, quadratic: f32) -> f32 {
+    let c = constant - 256.0;
+    if quadratic > 0.0 {
+        (-linear + (linear * linear - 4.0 * quadratic * c).sqrt()) / (2.0 * quadratic)
+    } else if linear > 0.0 {
+        -c / linear
+    } else {
+        f32::INFINITY
+    }
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Vec3, color: Vec3) -> Self {
+        Self {
+            direction: direction.normalize(),
+            ambient: color * 0.05,
+            diffuse: color * 0.4,
+            specular: color * 0.5,
+        }
+    }
+}
+
+impl PointLight {
+    pub fn new(position: Vec3, color: Vec3, range: f32) -> Self {
+        let (constant, linear, quadratic) = attenuation(range);
+        Self {
+            position,
+            ambient: color * 0.05,
+            diffuse: color * 0.8,
+            specular: color,
+            constant,
+            linear,
+            quadratic,
+        }
+    }
+
+    /// See [`falloff_distance`].
+    pub fn range(&self) -> f32 {
+        falloff_distance(self.constant, self.linear, self.quadratic)
+    }
+}
+
+impl SpotLight {
+    /// `inner_angle` and `outer_angle` are in radians, measured from `direction`.
+    pub fn new(
+        position: Vec3,
+        direction: Vec3,
+        color: Vec3,
+        inner_angle: f32,
+        outer_angle: f32,
+        range: f32,
+    ) -> Self {
+        let (constant, linear, quadratic) = attenuation(range);
+        Self {
+            position,
+            direction: direction.normalize(),
+            ambient: Vec3::ZERO,
+            diffuse: color,
+            specular: color,
+            inner_cutoff: inner_angle.cos(),
+            outer_cutoff: outer_angle.cos(),
+            constant,
+            linear,
+            quadratic,
+        }
+    }
+
+    /// See [`falloff_distance`].
+    pub fn range(&self) -> f32 {
+        falloff_distance(self.constant, self.linear, self.quadratic)
+    }
+
+    /// Angle in radians between the light's direction and the edge of its cone.
+    pub fn outer_angle(&self) -> f32 {
+        self.outer_cutoff.clamp(-1.0, 1.0).acos()
+    }
+}
+
+/// The lights of a scene, uploaded to programs that include `lights.glsl` through a
+/// [`UniformBuffer<Lights>`](super::uniform_buffer::UniformBuffer).
+#[derive(Clone, Debug, Default)]
+pub struct Lighting {
+    pub directional: Vec<DirectionalLight>,
+    pub point: Vec<PointLight>,
+    pub spot: Vec<SpotLight>,
+    pub shading: Shading,
+    /// Index of the light of each kind that [`ShadowMaps`](super::shadow::ShadowMaps) renders a
+    /// shadow map for.
+    pub directional_shadow: Option<usize>,
+    pub spot_shadow: Option<usize>,
+    pub point_shadow: Option<usize>,
+}
+
+impl Lighting {
+    /// Packs the lights into the uniform block, seen from a camera at `view_position`.
+    pub fn block(&self, view_position: Vec3) -> Result<Lights> {
+        let mut block = Lights {
+            num_directional_lights: i32::try_from(self.directional.len())?,
+            num_point_lights: i32::try_from(self.point.len())?,
+            num_spot_lights: i32::try_from(self.spot.len())?,
+            directional_shadow: shadow_index(self.directional_shadow, self.directional.len())?,
+            spot_shadow: shadow_index(self.spot_shadow, self.spot.len())?,
+            point_shadow: shadow_index(self.point_shadow, self.point.len())?,
+            blinn_phong: self.shading == Shading::BlinnPhong,
+            view_position,
+            ..Default::default()
+        };
+        copy_lights(
+            &mut block.directional_lights,
+            &self.directional,
+            "directional",
+        )?;
+        copy_lights(&mut block.point_lights, &self.point, "point")?;
+        copy_lights(&mut block.spot_lights, &self.spot, "spot")?;
+        Ok(block)
+    }
+}
+
+fn shadow_index(index: Option<usize>, num_lights: usize) -> Result<i32> {
+    match index {
+        Some(index) if index >= num_lights => Err(format_err!(
+            "shadow of light {index} given but there are only {num_lights} lights of its kind"
+        )),
+        Some(index) => Ok(i32::try_from(index)?),
+        None => Ok(-1),
+    }
+}
+
+fn copy_lights<T: Copy>(dst: &mut [T], src: &[T], kind: &str) -> Result<()> {
+    if src.len() > dst.len() {
+        return Err(format_err!(
+            "{} {kind} lights exceed the maximum of {}",
+            src.len(),
+            dst.len()
+        ));
+    }
+    dst[..src.len()].copy_from_slice(src);
+    Ok(())
+}
+
+/// Surface properties for `lighting.glsl`: the ambient and diffuse maps hold the surface's
+/// color, the specular map how strongly each texel reflects highlights, and `shininess` how
+/// tight those highlights are. The normal and height maps are optional, see
+/// [`PhongMaterial::features`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PhongMaterial {
+    pub ambient: Texture,
+    pub diffuse: Texture,
+    pub specular: Texture,
+    pub shininess: f32,
+    /// Tangent space normals, which need meshes with tangents.
+    pub normal: Option<Texture>,
+    pub height: Option<HeightMap>,
+}
+
+/// Heights in the red channel, white being the top of the surface and black `scale` texture
+/// units below it, which displace texture coordinates so that flat geometry looks carved.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct HeightMap {
+    pub texture: Texture,
+    pub scale: f32,
+    pub mode: ParallaxMode,
+}
+
+/// How a [`HeightMap`] displaces texture coordinates, from cheapest to most convincing at
+/// grazing angles.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ParallaxMode {
+    /// Shifts each fragment by the height under it, which falls apart on steep slopes.
+    Simple,
+    /// Marches the view ray through layers of depth until it's below the height map.
+    Steep,
+    /// Steep parallax mapping that interpolates between the last two layers, hiding their
+    /// steps.
+    Occlusion,
+}
+
+impl ParallaxMode {
+    pub const ALL: [Self; 3] = [Self::Simple, Self::Steep, Self::Occlusion];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Simple => "parallax",
+            Self::Steep => "steep parallax",
+            Self::Occlusion => "parallax occlusion",
+        }
+    }
+
+    fn define(self) -> &'static str {
+        match self {
+            Self::Simple => "PARALLAX_MAPPING",
+            Self::Steep => "STEEP_PARALLAX_MAPPING",
+            Self::Occlusion => "PARALLAX_OCCLUSION_MAPPING",
+        }
+    }
+}
+
+/// The optional parts of a [`PhongMaterial`] in use. `lighting.glsl` leaves each of them out
+/// unless its define is set, so a program can only draw materials with the features it was
+/// compiled for.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MaterialFeatures {
+    pub normal_map: bool,
+    pub parallax: Option<ParallaxMode>,
+}
+
+impl MaterialFeatures {
+    /// Defines to compile both shaders of a program with, see
+    /// [`Shader::with_defines`](super::shader::Shader::with_defines).
+    pub fn defines(self) -> Vec<&'static str> {
+        let mut defines = vec![];
+        if self.normal_map {
+            defines.push("NORMAL_MAPPING");
+        }
+        if let Some(mode) = self.parallax {
+            defines.push(mode.define());
+        }
+        defines
+    }
+}
+
+impl PhongMaterial {
+    /// Uses the diffuse map for ambient light too, which is what it usually is.
+    pub fn new(diffuse: Texture, specular: Texture, shininess: f32) -> Self {
+        Self {
+            ambient: diffuse,
+            diffuse,
+            specular,
+            shininess,
+            normal: None,
+            height: None,
+        }
+    }
+
+    pub fn with_normal_map(mut self, normal: Texture) -> Self {
+        self.normal = Some(normal);
+        self
+    }
+
+    pub fn with_height_map(mut self, texture: Texture, scale: f32, mode: ParallaxMode) -> Self {
+        self.height = Some(HeightMap {
+            texture,
+            scale,
+            mode,
+        });
+        self
+    }
+
+    pub fn features(&self) -> MaterialFeatures {
+        MaterialFeatures {
+            normal_map: self.normal.is_some(),
+            parallax: self.height.map(|height| height.mode),
+        }
+    }
+
+    /// The program needs to have been compiled with the defines of [`PhongMaterial::features`].
+    pub fn into_material(self, program: Program) -> Material {
+        let mut material = Material::new(program);
+        material.set_texture("material.ambient", self.ambient);
+        material.set_texture("material.diffuse", self.diffuse);
+        material.set_texture("material.specular", self.specular);
+        material.set("material.shininess", self.shininess);
+        if let Some(normal) = self.normal {
+            material.set_texture("material.normal", normal);
+        }
+        if let Some(height) = self.height {
+            material.set_texture("material.height", height.texture);
+            material.set("material.heightScale", height.scale);
+        }
+        material
+    }
+}
+
+/// A 1x1 texture of a single color, for materials whose maps would be uniform anyway.
+pub fn solid_texture(color: Vec3) -> Result<Texture> {
+    let [r, g, b] = color
+        .clamp(Vec3::ZERO, Vec3::ONE)
+        .to_array()
+        .map(|c| (c * 255.0).round() as u8);
+    TextureBuilder::new().build_from_rgba(1, 1, &[r, g, b, 255])
+}
@@ -0,0 +1,105 @@
+use super::{
+    render_state::{RenderState, StateTracker},
+    shader::program::Program,
+    texture::Texture,
+};
+use anyhow::Result;
+
+pub mod uniform;
+use uniform::UniformValue;
+
+/// Everything a draw call needs besides geometry: a program, the textures bound to its samplers,
+/// values for its uniforms and the render state. Several materials can share one program with
+/// different parameters.
+#[derive(Clone, Debug)]
+pub struct Material {
+    program: Program,
+    textures: Vec<(String, Texture)>,
+    uniforms: Vec<(String, UniformValue)>,
+    render_state: RenderState,
+}
+
+impl Material {
+    /// A material with no textures or uniform values that draws with [`RenderState::OPAQUE`].
+    pub fn new(program: Program) -> Self {
+        Self {
+            program,
+            textures: vec![],
+            uniforms: vec![],
+            render_state: RenderState::OPAQUE,
+        }
+    }
+
+    pub fn program(&self) -> Program {
+        self.program
+    }
+
+    /// Binds `texture` to the sampler uniform `sampler`. Samplers are assigned texture units in
+    /// the order they're first set.
+    pub fn set_texture(&mut self, sampler: &str, texture: Texture) {
+        match self.textures.iter_mut().find(|(name, _)| name == sampler) {
+            Some((_, slot)) => *slot = texture,
+            None => self.textures.push((sampler.to_string(), texture)),
+        }
+    }
+
+    pub fn texture(&self, sampler: &str) -> Option<&Texture> {
+        self.textures
+            .iter()
+            .find(|(name, _)| name == sampler)
+            .map(|(_, texture)| texture)
+    }
+
+    /// Textures with the samplers they're bound to, in texture unit order.
+    pub fn textures(&self) -> impl Iterator<Item = (&str, &Texture)> {
+        self.textures
+            .iter()
+            .map(|(sampler, texture)| (sampler.as_str(), texture))
+    }
+
+    pub fn set<V: Into<UniformValue>>(&mut self, uniform: &str, value: V) {
+        let value = value.into();
+        match self.uniforms.iter_mut().find(|(name, _)| name == uniform) {
+            Some((_, slot)) => *slot = value,
+            None => self.uniforms.push((uniform.to_string(), value)),
+        }
+    }
+
+    pub fn get(&self, uniform: &str) -> Option<&UniformValue> {
+        self.uniforms
+            .iter()
+            .find(|(name, _)| name == uniform)
+            .map(|(_, value)| value)
+    }
+
+    pub fn render_state(&self) -> &RenderState {
+        &self.render_state
+    }
+
+    pub fn set_render_state(&mut self, render_state: RenderState) {
+        self.render_state = render_state;
+    }
+
+    /// Uses the program and sets every texture, uniform and render state of the material.
+    pub fn bind(&self, state: &mut StateTracker) -> Result<()> {
+        self.program.use_program();
+        state.apply(&self.render_state);
+
+        for (unit, (sampler, texture)) in self.textures.iter().enumerate() {
+            let unit = u32::try_from(unit).unwrap();
+            texture.bind(unit);
+            self.program
+                .set_uniform_1i(sampler, i32::try_from(unit).unwrap())?;
+        }
+        self.set_uniforms()
+    }
+
+    /// Sets the material's uniform values, leaving samplers alone. The program needs to be in
+    /// use.
+    pub fn set_uniforms(&self) -> Result<()> {
+        for (uniform, value) in &self.uniforms {
+            value.set(&self.program, uniform)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use crate::glutils::shader::program::Program;
+use anyhow::Result;
+use glam::{Mat3, Mat4, Vec2, Vec3, Vec4};
+
+/// A value for a plain (non-block, non-sampler) uniform.
+#[derive(Clone, Debug, PartialEq)]
+pub enum UniformValue {
+    Int(i32),
+    Float(f32),
+    Vec2(Vec2),
+    Vec3(Vec3),
+    Vec4(Vec4),
+    Mat3(Mat3),
+    Mat4(Mat4),
+    FloatArray(Vec<f32>),
+}
+
+impl UniformValue {
+    /// Sets the uniform on `program`, which needs to be in use.
+    pub fn set(&self, program: &Program, uniform: &str) -> Result<()> {
+        match self {
+            Self::Int(v) => program.set_uniform_1i(uniform, *v),
+            Self::Float(v) => program.set_uniform_1f(uniform, *v),
+            Self::Vec2(v) => program.set_uniform_2f(uniform, v.x, v.y),
+            Self::Vec3(v) => program.set_uniform_3f(uniform, v.x, v.y, v.z),
+            Self::Vec4(v) => program.set_uniform_4f(uniform, v.x, v.y, v.z, v.w),
+            Self::Mat3(m) => program.set_uniform_matrix_3fv(uniform, m),
+            Self::Mat4(m) => program.set_uniform_matrix_4fv(uniform, m),
+            Self::FloatArray(v) => program.set_uniform_1fv(uniform, v),
+        }
+    }
+}
+
+impl From<i32> for UniformValue {
+    fn from(value: i32) -> Self {
+        Self::Int(value)
+    }
+}
+
+/// GLSL booleans are set like integers.
+impl From<bool> for UniformValue {
+    fn from(value: bool) -> Self {
+        Self::Int(i32::from(value))
+    }
+}
+
+impl From<f32> for UniformValue {
+    fn from(value: f32) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<Vec2> for UniformValue {
+    fn from(value: Vec2) -> Self {
+        Self::Vec2(value)
+    }
+}
+
+impl From<Vec3> for UniformValue {
+    fn from(value: Vec3) -> Self {
+        Self::Vec3(value)
+    }
+}
+
+impl From<Vec4> for UniformValue {
+    fn from(value: Vec4) -> Self {
+        Self::Vec4(value)
+    }
+}
+
+impl From<Mat3> for UniformValue {
+    fn from(value: Mat3) -> Self {
+        Self::Mat3(value)
+    }
+}
+
+impl From<Mat4> for UniformValue {
+    fn from(value: Mat4) -> Self {
+        Self::Mat4(value)
+    }
+}
+
+impl From<Vec<f32>> for UniformValue {
+    fn from(value: Vec<f32>) -> Self {
+        Self::FloatArray(value)
+    }
+}
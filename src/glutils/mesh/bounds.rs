@@ -0,0 +1,175 @@
+use glam::{Mat4, Vec3};
+
+/// Axis-aligned bounding box.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl Aabb {
+    /// Smallest box containing every point, or `None` if there are no points.
+    pub fn from_points<I: IntoIterator<Item = Vec3>>(points: I) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(Self::new(first, first), |aabb, p| aabb.expand(p)))
+    }
+
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn extents(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn expand(self, point: Vec3) -> Self {
+        Self::new(self.min.min(point), self.max.max(point))
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    pub fn contains(&self, point: Vec3) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+
+    /// The eight corners, ordered by the bits of the index: x for bit 0, y for bit 1, z for bit 2.
+    pub fn corners(&self) -> [Vec3; 8] {
+        std::array::from_fn(|i| {
+            Vec3::new(
+                if i & 1 == 0 { self.min.x } else { self.max.x },
+                if i & 2 == 0 { self.min.y } else { self.max.y },
+                if i & 4 == 0 { self.min.z } else { self.max.z },
+            )
+        })
+    }
+
+    /// Box containing this one after it has been transformed.
+    pub fn transformed(&self, matrix: Mat4) -> Self {
+        Self::from_points(self.corners().map(|c| matrix.transform_point3(c))).unwrap()
+    }
+}
+
+impl BoundingSphere {
+    /// Approximate minimal sphere using Ritter's algorithm, or `None` if there are no points.
+    pub fn from_points(points: &[Vec3]) -> Option<Self> {
+        let first = *points.first()?;
+        let farthest_from = |origin: Vec3| {
+            points
+                .iter()
+                .copied()
+                .max_by(|a, b| {
+                    a.distance_squared(origin)
+                        .total_cmp(&b.distance_squared(origin))
+                })
+                .unwrap()
+        };
+
+        let a = farthest_from(first);
+        let b = farthest_from(a);
+        let mut sphere = Self {
+            center: (a + b) / 2.0,
+            radius: a.distance(b) / 2.0,
+        };
+
+        for p in points {
+            let dist = p.distance(sphere.center);
+            if dist > sphere.radius {
+                let radius = (sphere.radius + dist) / 2.0;
+                sphere.center += (*p - sphere.center) * ((radius - sphere.radius) / dist);
+                sphere.radius = radius;
+            }
+        }
+        Some(sphere)
+    }
+
+    pub fn contains(&self, point: Vec3) -> bool {
+        point.distance_squared(self.center) <= self.radius * self.radius
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POINTS: [Vec3; 4] = [
+        Vec3::new(-1.0, 0.0, 2.0),
+        Vec3::new(3.0, -2.0, 0.0),
+        Vec3::new(0.0, 4.0, 1.0),
+        Vec3::new(1.0, 1.0, 1.0),
+    ];
+
+    #[test]
+    fn aabb_from_points() {
+        let aabb = Aabb::from_points(POINTS).unwrap();
+        assert_eq!(aabb.min, Vec3::new(-1.0, -2.0, 0.0));
+        assert_eq!(aabb.max, Vec3::new(3.0, 4.0, 2.0));
+        assert_eq!(aabb.center(), Vec3::new(1.0, 1.0, 1.0));
+        assert_eq!(aabb.extents(), Vec3::new(4.0, 6.0, 2.0));
+        assert!(POINTS.iter().all(|p| aabb.contains(*p)));
+        assert!(!aabb.contains(Vec3::new(0.0, 0.0, 3.0)));
+        assert_eq!(Aabb::from_points([]), None);
+    }
+
+    #[test]
+    fn aabb_corners_and_union() {
+        let aabb = Aabb::new(Vec3::ZERO, Vec3::ONE);
+        let corners = aabb.corners();
+        assert_eq!(corners[0], Vec3::ZERO);
+        assert_eq!(corners[5], Vec3::new(1.0, 0.0, 1.0));
+        assert_eq!(corners[7], Vec3::ONE);
+
+        let other = Aabb::new(Vec3::splat(-1.0), Vec3::splat(0.5));
+        assert_eq!(aabb.union(other), Aabb::new(Vec3::splat(-1.0), Vec3::ONE));
+    }
+
+    #[test]
+    fn aabb_transformed_encloses_rotated_box() {
+        let aabb = Aabb::new(Vec3::splat(-1.0), Vec3::ONE);
+        let rotated = aabb.transformed(Mat4::from_rotation_z(std::f32::consts::FRAC_PI_4));
+        let half_diagonal = 2.0_f32.sqrt();
+        assert!((rotated.max.x - half_diagonal).abs() < 1e-5);
+        assert!((rotated.min.y + half_diagonal).abs() < 1e-5);
+        assert!((rotated.max.z - 1.0).abs() < 1e-5);
+
+        let moved = aabb.transformed(Mat4::from_translation(Vec3::X));
+        assert_eq!(
+            moved,
+            Aabb::new(Vec3::new(0.0, -1.0, -1.0), Vec3::new(2.0, 1.0, 1.0))
+        );
+    }
+
+    #[test]
+    fn bounding_sphere_contains_every_point() {
+        let sphere = BoundingSphere::from_points(&POINTS).unwrap();
+        for p in POINTS {
+            assert!(p.distance(sphere.center) <= sphere.radius + 1e-5);
+        }
+        // Ritter's algorithm is within a few percent of the minimal sphere, which has to be at
+        // least half the largest distance between two points.
+        let diameter = POINTS[1].distance(POINTS[2]);
+        assert!(sphere.radius >= diameter / 2.0 - 1e-5);
+        assert!(sphere.radius <= diameter / 2.0 * 1.1);
+        assert_eq!(BoundingSphere::from_points(&[]), None);
+    }
+
+    #[test]
+    fn bounding_sphere_of_one_point() {
+        let sphere = BoundingSphere::from_points(&[Vec3::ONE]).unwrap();
+        assert_eq!(sphere.center, Vec3::ONE);
+        assert_eq!(sphere.radius, 0.0);
+        assert!(sphere.contains(Vec3::ONE));
+    }
+}
@@ -0,0 +1,185 @@
+use crate::glutils::{
+    model::{primitives::Primitive, usage::Usage, ModelBuilder, VertexAttribute},
+    shader::program::Program,
+};
+use anyhow::{format_err, Result};
+
+pub mod bounds;
+pub mod ops;
+pub mod shapes;
+
+/// Attribute names that vertex streams are bound to. Streams whose attribute the program doesn't
+/// declare are skipped.
+pub const POSITION_ATTRIB: &str = "aPos";
+pub const COLOR_ATTRIB: &str = "aCol";
+pub const NORMAL_ATTRIB: &str = "aNormal";
+pub const TEX_COORD_ATTRIB: &str = "aTexCoord";
+pub const TANGENT_ATTRIB: &str = "aTangent";
+
+/// The standard attributes, in the order of the locations every program binds them to.
+pub const ATTRIBS: [&str; 5] = [
+    POSITION_ATTRIB,
+    COLOR_ATTRIB,
+    NORMAL_ATTRIB,
+    TEX_COORD_ATTRIB,
+    TANGENT_ATTRIB,
+];
+
+/// Which optional vertex streams a generated mesh should carry.
+#[derive(Copy, Clone, Debug)]
+pub struct MeshOptions {
+    pub normals: bool,
+    pub tangents: bool,
+    pub uvs: bool,
+}
+
+impl Default for MeshOptions {
+    fn default() -> Self {
+        Self {
+            normals: true,
+            tangents: false,
+            uvs: true,
+        }
+    }
+}
+
+/// CPU-side indexed vertex data, independent of any GL state so that it can be inspected and
+/// processed before being uploaded with [`Model::from_mesh`](crate::glutils::model::Model).
+/// Indices usually form a triangle list, which the processing operations in [`ops`] assume, but
+/// may describe any [`Primitive`] when uploaded with [`Mesh::model_builder`]. Texture coordinates
+/// follow the OpenGL convention of `v = 0` being the bottom of the image, and tangents carry the
+/// bitangent's handedness in `w`.
+#[derive(Clone, Debug, Default)]
+pub struct Mesh {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Option<Vec<[f32; 3]>>,
+    pub tangents: Option<Vec<[f32; 4]>>,
+    pub uvs: Option<Vec<[f32; 2]>>,
+    pub colors: Option<Vec<[f32; 4]>>,
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    pub fn num_vertices(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn num_triangles(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// Checks that every vertex stream has one entry per position, that every index refers to
+    /// an existing vertex and, for triangle lists, that the indices form whole triangles.
+    pub fn validate(&self, primitive: Primitive) -> Result<()> {
+        let num_vertices = self.num_vertices();
+        let streams = [
+            ("normal", self.normals.as_ref().map(Vec::len)),
+            ("tangent", self.tangents.as_ref().map(Vec::len)),
+            ("uv", self.uvs.as_ref().map(Vec::len)),
+            ("color", self.colors.as_ref().map(Vec::len)),
+        ];
+        for (stream, len) in streams {
+            if len.is_some_and(|len| len != num_vertices) {
+                return Err(format_err!(
+                    "number of {stream} vertices should match number of position vertices"
+                ));
+            }
+        }
+        if primitive == Primitive::Triangles && !self.indices.len().is_multiple_of(3) {
+            return Err(format_err!("number of indices should be divisible by 3"));
+        }
+        if self.indices.iter().any(|i| *i as usize >= num_vertices) {
+            return Err(format_err!("index value exceeds number of vertices"));
+        }
+        Ok(())
+    }
+
+    /// Drops the vertex streams that weren't asked for.
+    pub fn with_options(mut self, options: MeshOptions) -> Self {
+        if !options.normals {
+            self.normals = None;
+        }
+        if !options.tangents {
+            self.tangents = None;
+        }
+        if !options.uvs {
+            self.uvs = None;
+        }
+        self
+    }
+
+    /// Prepares a [`ModelBuilder`] with every vertex stream the program consumes, for indices
+    /// that describe `primitive`s.
+    pub fn model_builder(
+        &self,
+        primitive: Primitive,
+        program: Program,
+        usage: Usage,
+    ) -> Result<ModelBuilder> {
+        self.validate(primitive)?;
+
+        let mut builder = ModelBuilder::new(
+            program,
+            usage,
+            VertexAttribute::new(POSITION_ATTRIB, flatten(&self.positions), 3, false),
+        )?;
+
+        if let Some(colors) = self
+            .colors
+            .as_ref()
+            .filter(|_| program.has_attrib(COLOR_ATTRIB))
+        {
+            builder = builder.color_attributes(VertexAttribute::new(
+                COLOR_ATTRIB,
+                flatten(colors),
+                4,
+                false,
+            ))?;
+        }
+
+        if let Some(normals) = self
+            .normals
+            .as_ref()
+            .filter(|_| program.has_attrib(NORMAL_ATTRIB))
+        {
+            builder = builder.attribute(VertexAttribute::new(
+                NORMAL_ATTRIB,
+                flatten(normals),
+                3,
+                false,
+            ))?;
+        }
+
+        if let Some(uvs) = self
+            .uvs
+            .as_ref()
+            .filter(|_| program.has_attrib(TEX_COORD_ATTRIB))
+        {
+            builder = builder.attribute(VertexAttribute::new(
+                TEX_COORD_ATTRIB,
+                flatten(uvs),
+                2,
+                false,
+            ))?;
+        }
+
+        if let Some(tangents) = self
+            .tangents
+            .as_ref()
+            .filter(|_| program.has_attrib(TANGENT_ATTRIB))
+        {
+            builder = builder.attribute(VertexAttribute::new(
+                TANGENT_ATTRIB,
+                flatten(tangents),
+                4,
+                false,
+            ))?;
+        }
+
+        builder.indices(self.indices.clone())
+    }
+}
+
+pub(crate) fn flatten<const N: usize>(values: &[[f32; N]]) -> Vec<f32> {
+    values.iter().flatten().copied().collect()
+}
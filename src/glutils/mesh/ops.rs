@@ -0,0 +1,433 @@
+use super::{
+    bounds::{Aabb, BoundingSphere},
+    Mesh,
+};
+use crate::glutils::model::primitives::Primitive;
+use anyhow::{format_err, Result};
+use glam::{Mat3, Mat4, Vec2, Vec3, Vec4, Vec4Swizzles};
+use std::collections::HashMap;
+
+impl Mesh {
+    /// Replaces the normals with smooth per-vertex normals, weighting each triangle's
+    /// contribution by its area. Vertices shared by no triangle get a zero normal.
+    pub fn compute_normals(&mut self) -> Result<()> {
+        self.validate(Primitive::Triangles)?;
+        let mut normals = vec![Vec3::ZERO; self.num_vertices()];
+
+        for tri in self.indices.chunks_exact(3) {
+            let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| i as usize);
+            let [pa, pb, pc] = [a, b, c].map(|i| Vec3::from(self.positions[i]));
+            // The cross product's length is twice the triangle's area.
+            let face_normal = (pb - pa).cross(pc - pa);
+            for i in [a, b, c] {
+                normals[i] += face_normal;
+            }
+        }
+
+        self.normals = Some(
+            normals
+                .into_iter()
+                .map(|n| n.normalize_or_zero().to_array())
+                .collect(),
+        );
+        Ok(())
+    }
+
+    /// Generates per-vertex tangents from the texture coordinates so that normal maps can be
+    /// sampled in tangent space. The bitangent can be recovered in a shader with
+    /// `cross(normal, tangent.xyz) * tangent.w`.
+    pub fn compute_tangents(&mut self) -> Result<()> {
+        self.validate(Primitive::Triangles)?;
+        let (Some(normals), Some(uvs)) = (self.normals.as_ref(), self.uvs.as_ref()) else {
+            return Err(format_err!(
+                "tangent generation requires normals and texture coordinates"
+            ));
+        };
+
+        let mut tangents = vec![Vec3::ZERO; self.num_vertices()];
+        let mut bitangents = vec![Vec3::ZERO; self.num_vertices()];
+
+        for tri in self.indices.chunks_exact(3) {
+            let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| i as usize);
+            let [pa, pb, pc] = [a, b, c].map(|i| Vec3::from(self.positions[i]));
+            let [ta, tb, tc] = [a, b, c].map(|i| Vec2::from(uvs[i]));
+
+            let (edge1, edge2) = (pb - pa, pc - pa);
+            let (duv1, duv2) = (tb - ta, tc - ta);
+
+            let det = duv1.perp_dot(duv2);
+            if det.abs() <= f32::EPSILON {
+                continue;
+            }
+
+            let tangent = (edge1 * duv2.y - edge2 * duv1.y) / det;
+            let bitangent = (edge2 * duv1.x - edge1 * duv2.x) / det;
+            for i in [a, b, c] {
+                tangents[i] += tangent;
+                bitangents[i] += bitangent;
+            }
+        }
+
+        self.tangents = Some(
+            normals
+                .iter()
+                .zip(tangents.into_iter().zip(bitangents))
+                .map(|(normal, (tangent, bitangent))| {
+                    let normal = Vec3::from(*normal);
+                    // Gram-Schmidt orthogonalize against the normal.
+                    let tangent = (tangent - normal * normal.dot(tangent))
+                        .try_normalize()
+                        .unwrap_or_else(|| normal.any_orthonormal_vector());
+                    let handedness = if normal.cross(tangent).dot(bitangent) < 0.0 {
+                        -1.0
+                    } else {
+                        1.0
+                    };
+                    tangent.extend(handedness).to_array()
+                })
+                .collect(),
+        );
+
+        Ok(())
+    }
+
+    /// Merges vertices whose attributes are all within `epsilon` of each other and drops any
+    /// triangles that become degenerate as a result. Returns the number of vertices removed.
+    pub fn weld(&mut self, epsilon: f32) -> Result<usize> {
+        if epsilon.is_nan() || epsilon <= 0.0 {
+            return Err(format_err!(
+                "weld epsilon should be positive, not {epsilon}"
+            ));
+        }
+        self.validate(Primitive::Triangles)?;
+        let quantize = |key: &mut Vec<i64>, v: &[f32]| {
+            key.extend(v.iter().map(|c| (c / epsilon).round() as i64));
+        };
+        let num_vertices = self.num_vertices();
+
+        let mut unique = HashMap::<Vec<i64>, u32>::new();
+        let mut remap = Vec::with_capacity(num_vertices);
+        let mut kept = Vec::new();
+
+        for i in 0..num_vertices {
+            let mut key = Vec::new();
+            quantize(&mut key, &self.positions[i]);
+            if let Some(normals) = &self.normals {
+                quantize(&mut key, &normals[i]);
+            }
+            if let Some(tangents) = &self.tangents {
+                quantize(&mut key, &tangents[i]);
+            }
+            if let Some(uvs) = &self.uvs {
+                quantize(&mut key, &uvs[i]);
+            }
+            if let Some(colors) = &self.colors {
+                quantize(&mut key, &colors[i]);
+            }
+
+            let index = *unique.entry(key).or_insert_with(|| {
+                kept.push(i);
+                u32::try_from(kept.len() - 1).unwrap()
+            });
+            remap.push(index);
+        }
+
+        fn retain<T: Copy>(stream: &mut Vec<T>, kept: &[usize]) {
+            *stream = kept.iter().map(|i| stream[*i]).collect();
+        }
+
+        retain(&mut self.positions, &kept);
+        if let Some(normals) = self.normals.as_mut() {
+            retain(normals, &kept);
+        }
+        if let Some(tangents) = self.tangents.as_mut() {
+            retain(tangents, &kept);
+        }
+        if let Some(uvs) = self.uvs.as_mut() {
+            retain(uvs, &kept);
+        }
+        if let Some(colors) = self.colors.as_mut() {
+            retain(colors, &kept);
+        }
+
+        self.indices = self
+            .indices
+            .chunks_exact(3)
+            .map(|tri| [tri[0], tri[1], tri[2]].map(|i| remap[i as usize]))
+            .filter(|[a, b, c]| a != b && b != c && c != a)
+            .flatten()
+            .collect();
+
+        Ok(num_vertices - kept.len())
+    }
+
+    pub fn bounding_box(&self) -> Option<Aabb> {
+        Aabb::from_points(self.positions.iter().copied().map(Vec3::from))
+    }
+
+    pub fn bounding_sphere(&self) -> Option<BoundingSphere> {
+        let points = self
+            .positions
+            .iter()
+            .copied()
+            .map(Vec3::from)
+            .collect::<Vec<_>>();
+        BoundingSphere::from_points(&points)
+    }
+
+    /// Transforms positions by `matrix`, normals by its inverse transpose and tangents by its
+    /// upper 3x3. Mirroring transforms also flip the winding order so that front faces stay
+    /// front-facing.
+    pub fn transform(&mut self, matrix: Mat4) {
+        let linear = Mat3::from_mat4(matrix);
+        let normal_matrix = linear.inverse().transpose();
+        let mirrored = linear.determinant() < 0.0;
+
+        for p in self.positions.iter_mut() {
+            *p = matrix.transform_point3(Vec3::from(*p)).to_array();
+        }
+
+        if let Some(normals) = self.normals.as_mut() {
+            for n in normals.iter_mut() {
+                *n = (normal_matrix * Vec3::from(*n))
+                    .normalize_or_zero()
+                    .to_array();
+            }
+        }
+
+        if let Some(tangents) = self.tangents.as_mut() {
+            for t in tangents.iter_mut() {
+                let tangent = Vec4::from(*t);
+                let handedness = if mirrored { -tangent.w } else { tangent.w };
+                *t = (linear * tangent.xyz())
+                    .normalize_or_zero()
+                    .extend(handedness)
+                    .to_array();
+            }
+        }
+
+        if mirrored {
+            for tri in self.indices.chunks_exact_mut(3) {
+                tri.swap(1, 2);
+            }
+        }
+    }
+
+    /// Appends another mesh's vertices and triangles. Both meshes need to carry the same vertex
+    /// streams.
+    pub fn append(&mut self, other: &Mesh) -> Result<()> {
+        let same_streams = self.normals.is_some() == other.normals.is_some()
+            && self.tangents.is_some() == other.tangents.is_some()
+            && self.uvs.is_some() == other.uvs.is_some()
+            && self.colors.is_some() == other.colors.is_some();
+
+        if !same_streams {
+            return Err(format_err!(
+                "meshes need the same vertex streams in order to be merged"
+            ));
+        }
+
+        let offset = u32::try_from(self.num_vertices()).unwrap();
+
+        self.positions.extend_from_slice(&other.positions);
+        if let (Some(dst), Some(src)) = (self.normals.as_mut(), other.normals.as_ref()) {
+            dst.extend_from_slice(src);
+        }
+        if let (Some(dst), Some(src)) = (self.tangents.as_mut(), other.tangents.as_ref()) {
+            dst.extend_from_slice(src);
+        }
+        if let (Some(dst), Some(src)) = (self.uvs.as_mut(), other.uvs.as_ref()) {
+            dst.extend_from_slice(src);
+        }
+        if let (Some(dst), Some(src)) = (self.colors.as_mut(), other.colors.as_ref()) {
+            dst.extend_from_slice(src);
+        }
+        self.indices
+            .extend(other.indices.iter().map(|i| i + offset));
+
+        Ok(())
+    }
+
+    /// Combines meshes into one so that they can be drawn with a single call.
+    pub fn merge<'a, I: IntoIterator<Item = &'a Mesh>>(meshes: I) -> Result<Mesh> {
+        let mut meshes = meshes.into_iter();
+        let Some(first) = meshes.next() else {
+            return Ok(Mesh::default());
+        };
+
+        let mut merged = first.clone();
+        for mesh in meshes {
+            merged.append(mesh)?;
+        }
+        Ok(merged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A unit quad in the XY plane facing +Z, split into two triangles that don't share
+    /// vertices.
+    fn split_quad() -> Mesh {
+        Mesh {
+            positions: vec![
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [1.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0],
+                [1.0, 1.0, 0.0],
+                [0.0, 1.0, 0.0],
+            ],
+            uvs: Some(vec![
+                [0.0, 0.0],
+                [1.0, 0.0],
+                [1.0, 1.0],
+                [0.0, 0.0],
+                [1.0, 1.0],
+                [0.0, 1.0],
+            ]),
+            indices: vec![0, 1, 2, 3, 4, 5],
+            ..Mesh::default()
+        }
+    }
+
+    fn assert_near(a: [f32; 3], b: [f32; 3]) {
+        assert!(
+            Vec3::from(a).abs_diff_eq(Vec3::from(b), 1e-5),
+            "{a:?} != {b:?}"
+        );
+    }
+
+    #[test]
+    fn weld_merges_shared_vertices() {
+        let mut mesh = split_quad();
+        assert_eq!(mesh.weld(1e-4).unwrap(), 2);
+        assert_eq!(mesh.num_vertices(), 4);
+        assert_eq!(mesh.uvs.as_ref().unwrap().len(), 4);
+        assert_eq!(mesh.indices, [0, 1, 2, 0, 2, 3]);
+        mesh.validate(Primitive::Triangles).unwrap();
+    }
+
+    #[test]
+    fn weld_keeps_vertices_with_different_attributes() {
+        let mut mesh = split_quad();
+        mesh.uvs.as_mut().unwrap()[3] = [0.5, 0.5];
+        assert_eq!(mesh.weld(1e-4).unwrap(), 1);
+        assert_eq!(mesh.num_vertices(), 5);
+    }
+
+    #[test]
+    fn weld_drops_degenerate_triangles() {
+        let mut mesh = split_quad();
+        mesh.positions[2] = mesh.positions[1];
+        mesh.uvs.as_mut().unwrap()[2] = [1.0, 0.0];
+        mesh.weld(1e-4).unwrap();
+        assert_eq!(mesh.num_triangles(), 1);
+    }
+
+    #[test]
+    fn weld_rejects_bad_epsilon() {
+        for epsilon in [0.0, -1.0, f32::NAN] {
+            assert!(split_quad().weld(epsilon).is_err());
+        }
+    }
+
+    #[test]
+    fn ops_reject_out_of_range_indices() {
+        let mut mesh = split_quad();
+        mesh.indices[4] = 6;
+        assert!(mesh.compute_normals().is_err());
+        mesh.normals = Some(vec![[0.0, 0.0, 1.0]; 6]);
+        assert!(mesh.compute_tangents().is_err());
+        assert!(mesh.weld(1e-4).is_err());
+    }
+
+    #[test]
+    fn normals_follow_winding() {
+        let mut mesh = split_quad();
+        mesh.compute_normals().unwrap();
+        for normal in mesh.normals.unwrap() {
+            assert_near(normal, [0.0, 0.0, 1.0]);
+        }
+    }
+
+    #[test]
+    fn tangents_follow_uvs() {
+        let mut mesh = split_quad();
+        mesh.compute_normals().unwrap();
+        mesh.compute_tangents().unwrap();
+        for tangent in mesh.tangents.unwrap() {
+            assert_near([tangent[0], tangent[1], tangent[2]], [1.0, 0.0, 0.0]);
+            // cross(+Z, +X) is +Y, which is the direction of increasing v.
+            assert_eq!(tangent[3], 1.0);
+        }
+    }
+
+    #[test]
+    fn tangents_flip_handedness_with_mirrored_uvs() {
+        let mut mesh = split_quad();
+        for uv in mesh.uvs.as_mut().unwrap() {
+            uv[1] = 1.0 - uv[1];
+        }
+        mesh.compute_normals().unwrap();
+        mesh.compute_tangents().unwrap();
+        assert!(mesh.tangents.unwrap().iter().all(|t| t[3] == -1.0));
+    }
+
+    #[test]
+    fn tangents_require_normals_and_uvs() {
+        assert!(split_quad().compute_tangents().is_err());
+    }
+
+    #[test]
+    fn mirrored_transform_flips_winding_and_handedness() {
+        let mut mesh = split_quad();
+        mesh.compute_normals().unwrap();
+        mesh.compute_tangents().unwrap();
+        mesh.transform(Mat4::from_scale(Vec3::new(-1.0, 1.0, 1.0)));
+
+        assert_eq!(mesh.indices, [0, 2, 1, 3, 5, 4]);
+        assert_near(mesh.positions[1], [-1.0, 0.0, 0.0]);
+        for normal in mesh.normals.as_ref().unwrap() {
+            assert_near(*normal, [0.0, 0.0, 1.0]);
+        }
+        for tangent in mesh.tangents.as_ref().unwrap() {
+            assert_near([tangent[0], tangent[1], tangent[2]], [-1.0, 0.0, 0.0]);
+            assert_eq!(tangent[3], -1.0);
+        }
+
+        // The recomputed normals of the re-wound triangles still face the same way.
+        let mut recomputed = mesh.clone();
+        recomputed.compute_normals().unwrap();
+        assert_eq!(recomputed.normals, mesh.normals);
+    }
+
+    #[test]
+    fn transform_uses_inverse_transpose_for_normals() {
+        let mut mesh = Mesh {
+            positions: vec![[0.0; 3]],
+            normals: Some(vec![[1.0, 1.0, 0.0]]),
+            ..Mesh::default()
+        };
+        mesh.transform(Mat4::from_scale(Vec3::new(2.0, 1.0, 1.0)));
+        let expected = Vec3::new(0.5, 1.0, 0.0).normalize().to_array();
+        assert_near(mesh.normals.unwrap()[0], expected);
+    }
+
+    #[test]
+    fn merge_offsets_indices() {
+        let merged = Mesh::merge([&split_quad(), &split_quad()]).unwrap();
+        assert_eq!(merged.num_vertices(), 12);
+        assert_eq!(merged.uvs.as_ref().unwrap().len(), 12);
+        assert_eq!(&merged.indices[6..], [6, 7, 8, 9, 10, 11]);
+    }
+
+    #[test]
+    fn merge_rejects_different_streams() {
+        let mut without_uvs = split_quad();
+        without_uvs.uvs = None;
+        assert!(Mesh::merge([&split_quad(), &without_uvs]).is_err());
+        assert_eq!(Mesh::merge([]).unwrap().num_vertices(), 0);
+    }
+}
@@ -0,0 +1,516 @@
+//! Generators for common primitives. Every shape is centered on the origin with +Y up and has
+//! counter-clockwise front faces.
+use super::{Mesh, MeshOptions};
+use std::{collections::HashMap, f32::consts::PI};
+
+/// Axis-aligned cube with flat-shaded faces, i.e. 4 vertices per face.
+pub fn cube(size: f32, options: MeshOptions) -> Mesh {
+    let half = size / 2.0;
+
+    // (normal, u axis, v axis) with u x v = normal so that faces wind counter-clockwise.
+    let faces: [([f32; 3], [f32; 3], [f32; 3]); 6] = [
+        ([1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+        ([-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+        ([0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+        ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+        ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+        ([0.0, 0.0, -1.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ];
+
+    let mut mesh = Mesh::with_capacity(24, 36);
+
+    for (normal, u, v) in faces {
+        let base = mesh.next_index();
+        for (su, sv) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+            let position = [0, 1, 2].map(|i| (normal[i] + su * u[i] + sv * v[i]) * half);
+            let uv = [(su + 1.0) / 2.0, (sv + 1.0) / 2.0];
+            mesh.push_vertex(position, normal, [u[0], u[1], u[2], 1.0], uv);
+        }
+        mesh.push_quad(base, base + 1, base + 2, base + 3);
+    }
+
+    mesh.with_options(options)
+}
+
+/// Flat plane in the XZ plane facing +Y.
+pub fn plane(width: f32, depth: f32, options: MeshOptions) -> Mesh {
+    grid(width, depth, 1, 1, options)
+}
+
+/// Plane in the XZ plane facing +Y, subdivided into `cols` by `rows` quads. `u` runs along +X and
+/// `v` along -Z.
+pub fn grid(width: f32, depth: f32, cols: u32, rows: u32, options: MeshOptions) -> Mesh {
+    let (cols, rows) = (cols.max(1), rows.max(1));
+    let num_vertices = usize::try_from((cols + 1) * (rows + 1)).unwrap();
+    let mut mesh = Mesh::with_capacity(num_vertices, usize::try_from(cols * rows * 6).unwrap());
+
+    for row in 0..=rows {
+        for col in 0..=cols {
+            let (u, v) = (col as f32 / cols as f32, row as f32 / rows as f32);
+            mesh.push_vertex(
+                [width * (u - 0.5), 0.0, depth * (0.5 - v)],
+                [0.0, 1.0, 0.0],
+                [1.0, 0.0, 0.0, 1.0],
+                [u, v],
+            );
+        }
+    }
+    mesh.push_grid(0, cols, rows);
+
+    mesh.with_options(options)
+}
+
+/// Sphere tessellated along lines of longitude (`sectors`) and latitude (`stacks`). The texture
+/// seam lies on the +Z meridian.
+pub fn uv_sphere(radius: f32, sectors: u32, stacks: u32, options: MeshOptions) -> Mesh {
+    let (sectors, stacks) = (sectors.max(3), stacks.max(2));
+    let num_vertices = usize::try_from((sectors + 1) * (stacks + 1)).unwrap();
+    let mut mesh = Mesh::with_capacity(num_vertices, num_vertices * 6);
+
+    // Rows are laid out from the south pole upwards.
+    for stack in 0..=stacks {
+        let v = stack as f32 / stacks as f32;
+        let phi = PI * (v - 0.5);
+        for sector in 0..=sectors {
+            let u = sector as f32 / sectors as f32;
+            let theta = 2.0 * PI * u;
+            let normal = [phi.cos() * theta.sin(), phi.sin(), phi.cos() * theta.cos()];
+            mesh.push_vertex(
+                normal.map(|n| n * radius),
+                normal,
+                [theta.cos(), 0.0, -theta.sin(), 1.0],
+                [u, v],
+            );
+        }
+    }
+
+    for stack in 0..stacks {
+        for sector in 0..sectors {
+            let a = stack * (sectors + 1) + sector;
+            let d = a + sectors + 1;
+            // Skip the triangles that would collapse onto a pole.
+            if stack != 0 {
+                mesh.indices.extend_from_slice(&[a, a + 1, d + 1]);
+            }
+            if stack != stacks - 1 {
+                mesh.indices.extend_from_slice(&[a, d + 1, d]);
+            }
+        }
+    }
+
+    mesh.with_options(options)
+}
+
+/// Sphere made by repeatedly subdividing an icosahedron, giving evenly sized triangles.
+/// Texture coordinates are spherically projected, so triangles straddling the seam on the +Z
+/// meridian will stretch the texture.
+pub fn icosphere(radius: f32, subdivisions: u32, options: MeshOptions) -> Mesh {
+    let t = (1.0 + 5.0_f32.sqrt()) / 2.0;
+
+    let mut directions: Vec<[f32; 3]> = [
+        [-1.0, t, 0.0],
+        [1.0, t, 0.0],
+        [-1.0, -t, 0.0],
+        [1.0, -t, 0.0],
+        [0.0, -1.0, t],
+        [0.0, 1.0, t],
+        [0.0, -1.0, -t],
+        [0.0, 1.0, -t],
+        [t, 0.0, -1.0],
+        [t, 0.0, 1.0],
+        [-t, 0.0, -1.0],
+        [-t, 0.0, 1.0],
+    ]
+    .into_iter()
+    .map(normalize)
+    .collect();
+
+    let mut triangles: Vec<[u32; 3]> = vec![
+        [0, 11, 5],
+        [0, 5, 1],
+        [0, 1, 7],
+        [0, 7, 10],
+        [0, 10, 11],
+        [1, 5, 9],
+        [5, 11, 4],
+        [11, 10, 2],
+        [10, 7, 6],
+        [7, 1, 8],
+        [3, 9, 4],
+        [3, 4, 2],
+        [3, 2, 6],
+        [3, 6, 8],
+        [3, 8, 9],
+        [4, 9, 5],
+        [2, 4, 11],
+        [6, 2, 10],
+        [8, 6, 7],
+        [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        let mut midpoints = HashMap::<(u32, u32), u32>::new();
+        let mut midpoint = |a: u32, b: u32| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                let (pa, pb) = (directions[a as usize], directions[b as usize]);
+                directions.push(normalize([0, 1, 2].map(|i| (pa[i] + pb[i]) / 2.0)));
+                u32::try_from(directions.len() - 1).unwrap()
+            })
+        };
+
+        triangles = triangles
+            .into_iter()
+            .flat_map(|[a, b, c]| {
+                let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    let mut mesh = Mesh::with_capacity(directions.len(), triangles.len() * 3);
+
+    for normal @ [x, y, z] in directions {
+        let theta = x.atan2(z);
+        let tangent = match (z * z + x * x).sqrt() {
+            rho if rho > f32::EPSILON => [z / rho, 0.0, -x / rho, 1.0],
+            _ => [1.0, 0.0, 0.0, 1.0],
+        };
+        let uv = [
+            (theta / (2.0 * PI)).rem_euclid(1.0),
+            0.5 + y.clamp(-1.0, 1.0).asin() / PI,
+        ];
+        mesh.push_vertex(normal.map(|n| n * radius), normal, tangent, uv);
+    }
+    mesh.indices = triangles.into_iter().flatten().collect();
+
+    mesh.with_options(options)
+}
+
+/// Capped cylinder along the Y axis.
+pub fn cylinder(radius: f32, height: f32, sectors: u32, options: MeshOptions) -> Mesh {
+    let sectors = sectors.max(3);
+    let half = height / 2.0;
+    let mut mesh = Mesh::default();
+
+    for (row, y) in [-half, half].into_iter().enumerate() {
+        for sector in 0..=sectors {
+            let u = sector as f32 / sectors as f32;
+            let theta = 2.0 * PI * u;
+            let normal = [theta.sin(), 0.0, theta.cos()];
+            mesh.push_vertex(
+                [radius * normal[0], y, radius * normal[2]],
+                normal,
+                [theta.cos(), 0.0, -theta.sin(), 1.0],
+                [u, row as f32],
+            );
+        }
+    }
+    mesh.push_grid(0, sectors, 1);
+
+    mesh.push_cap(radius, half, sectors, true);
+    mesh.push_cap(radius, -half, sectors, false);
+
+    mesh.with_options(options)
+}
+
+/// Capped cone along the Y axis with its apex at `+height / 2`.
+pub fn cone(radius: f32, height: f32, sectors: u32, options: MeshOptions) -> Mesh {
+    let sectors = sectors.max(3);
+    let half = height / 2.0;
+    let slant = (radius * radius + height * height).sqrt();
+    let cone_normal = |theta: f32| {
+        [
+            height * theta.sin() / slant,
+            radius / slant,
+            height * theta.cos() / slant,
+        ]
+    };
+
+    let mut mesh = Mesh::default();
+
+    for sector in 0..=sectors {
+        let u = sector as f32 / sectors as f32;
+        let theta = 2.0 * PI * u;
+        mesh.push_vertex(
+            [radius * theta.sin(), -half, radius * theta.cos()],
+            cone_normal(theta),
+            [theta.cos(), 0.0, -theta.sin(), 1.0],
+            [u, 0.0],
+        );
+    }
+
+    // The apex is duplicated per sector so that each side triangle gets a normal facing outwards
+    // from the middle of its sector.
+    let apex_base = mesh.next_index();
+    for sector in 0..sectors {
+        let u = (sector as f32 + 0.5) / sectors as f32;
+        let theta = 2.0 * PI * u;
+        mesh.push_vertex(
+            [0.0, half, 0.0],
+            cone_normal(theta),
+            [theta.cos(), 0.0, -theta.sin(), 1.0],
+            [u, 1.0],
+        );
+    }
+
+    for sector in 0..sectors {
+        mesh.indices
+            .extend_from_slice(&[sector, sector + 1, apex_base + sector]);
+    }
+
+    mesh.push_cap(radius, -half, sectors, false);
+
+    mesh.with_options(options)
+}
+
+/// Torus lying in the XZ plane. `u` runs around the ring and `v` around the tube.
+pub fn torus(
+    major_radius: f32,
+    minor_radius: f32,
+    major_segments: u32,
+    minor_segments: u32,
+    options: MeshOptions,
+) -> Mesh {
+    let (major_segments, minor_segments) = (major_segments.max(3), minor_segments.max(3));
+    let num_vertices = usize::try_from((major_segments + 1) * (minor_segments + 1)).unwrap();
+    let mut mesh = Mesh::with_capacity(num_vertices, num_vertices * 6);
+
+    for minor in 0..=minor_segments {
+        let v = minor as f32 / minor_segments as f32;
+        let phi = 2.0 * PI * v;
+        for major in 0..=major_segments {
+            let u = major as f32 / major_segments as f32;
+            let theta = 2.0 * PI * u;
+            let outward = [theta.sin(), 0.0, theta.cos()];
+            let normal = [phi.cos() * outward[0], phi.sin(), phi.cos() * outward[2]];
+            mesh.push_vertex(
+                [0, 1, 2].map(|i| major_radius * outward[i] + minor_radius * normal[i]),
+                normal,
+                [theta.cos(), 0.0, -theta.sin(), 1.0],
+                [u, v],
+            );
+        }
+    }
+    mesh.push_grid(0, major_segments, minor_segments);
+
+    mesh.with_options(options)
+}
+
+/// Quad covering the whole viewport in normalized device coordinates, for full-screen passes.
+pub fn fullscreen_quad(options: MeshOptions) -> Mesh {
+    let mut mesh = Mesh::with_capacity(4, 6);
+    for (x, y) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+        mesh.push_vertex(
+            [x, y, 0.0],
+            [0.0, 0.0, 1.0],
+            [1.0, 0.0, 0.0, 1.0],
+            [(x + 1.0) / 2.0, (y + 1.0) / 2.0],
+        );
+    }
+    mesh.push_quad(0, 1, 2, 3);
+    mesh.with_options(options)
+}
+
+impl Mesh {
+    fn with_capacity(num_vertices: usize, num_indices: usize) -> Self {
+        Self {
+            positions: Vec::with_capacity(num_vertices),
+            normals: Some(Vec::with_capacity(num_vertices)),
+            tangents: Some(Vec::with_capacity(num_vertices)),
+            uvs: Some(Vec::with_capacity(num_vertices)),
+            colors: None,
+            indices: Vec::with_capacity(num_indices),
+        }
+    }
+
+    fn next_index(&self) -> u32 {
+        u32::try_from(self.positions.len()).unwrap()
+    }
+
+    fn push_vertex(
+        &mut self,
+        position: [f32; 3],
+        normal: [f32; 3],
+        tangent: [f32; 4],
+        uv: [f32; 2],
+    ) {
+        self.positions.push(position);
+        self.normals.get_or_insert_with(Vec::new).push(normal);
+        self.tangents.get_or_insert_with(Vec::new).push(tangent);
+        self.uvs.get_or_insert_with(Vec::new).push(uv);
+    }
+
+    /// Two triangles for a quad whose corners are given counter-clockwise.
+    fn push_quad(&mut self, a: u32, b: u32, c: u32, d: u32) {
+        self.indices.extend_from_slice(&[a, b, c, a, c, d]);
+    }
+
+    /// Indexes a `(cols + 1) * (rows + 1)` block of vertices starting at `base`, laid out row by
+    /// row with `u` increasing along a row and `v` increasing between rows.
+    fn push_grid(&mut self, base: u32, cols: u32, rows: u32) {
+        for row in 0..rows {
+            for col in 0..cols {
+                let a = base + row * (cols + 1) + col;
+                let d = a + cols + 1;
+                self.push_quad(a, a + 1, d + 1, d);
+            }
+        }
+    }
+
+    /// Disk closing off a cylinder or cone at height `y`, facing up or down.
+    fn push_cap(&mut self, radius: f32, y: f32, sectors: u32, facing_up: bool) {
+        let (normal, dir) = if facing_up {
+            ([0.0, 1.0, 0.0], -1.0)
+        } else {
+            ([0.0, -1.0, 0.0], 1.0)
+        };
+
+        let center = self.next_index();
+        self.push_vertex([0.0, y, 0.0], normal, [1.0, 0.0, 0.0, 1.0], [0.5, 0.5]);
+
+        for sector in 0..=sectors {
+            let theta = 2.0 * PI * sector as f32 / sectors as f32;
+            let (sin, cos) = theta.sin_cos();
+            self.push_vertex(
+                [radius * sin, y, radius * cos],
+                normal,
+                [1.0, 0.0, 0.0, 1.0],
+                [0.5 + 0.5 * sin, 0.5 + dir * 0.5 * cos],
+            );
+        }
+
+        for sector in 0..sectors {
+            let (a, b) = (center + 1 + sector, center + 2 + sector);
+            let triangle = if facing_up {
+                [center, a, b]
+            } else {
+                [center, b, a]
+            };
+            self.indices.extend_from_slice(&triangle);
+        }
+    }
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let len = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    v.map(|c| c / len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::glutils::model::primitives::Primitive;
+    use glam::Vec3;
+
+    const ALL: MeshOptions = MeshOptions {
+        normals: true,
+        tangents: true,
+        uvs: true,
+    };
+
+    /// Checks what every generator promises and returns the mesh for shape-specific checks.
+    /// `outward` gives the point that a vertex's normal should point away from.
+    fn check(mesh: Mesh, vertices: usize, indices: usize, outward: impl Fn(Vec3) -> Vec3) -> Mesh {
+        assert_eq!(mesh.num_vertices(), vertices);
+        assert_eq!(mesh.indices.len(), indices);
+        mesh.validate(Primitive::Triangles).unwrap();
+
+        let normals = mesh.normals.as_ref().unwrap();
+        for (position, normal) in mesh.positions.iter().zip(normals) {
+            let (position, normal) = (Vec3::from(*position), Vec3::from(*normal));
+            assert!((normal.length() - 1.0).abs() < 1e-5, "{normal}");
+            let away = position - outward(position);
+            assert!(normal.dot(away) > 0.0, "{normal} at {position}");
+        }
+
+        for tri in mesh.indices.chunks_exact(3) {
+            let [a, b, c] =
+                [tri[0], tri[1], tri[2]].map(|i| Vec3::from(mesh.positions[i as usize]));
+            let face = (b - a).cross(c - a);
+            let normal = tri
+                .iter()
+                .map(|i| Vec3::from(normals[*i as usize]))
+                .sum::<Vec3>();
+            assert!(face.dot(normal) > 0.0, "triangle {tri:?} winds clockwise");
+        }
+
+        for uv in mesh.uvs.as_ref().unwrap() {
+            assert!(uv.iter().all(|c| (0.0..=1.0).contains(c)), "{uv:?}");
+        }
+        mesh
+    }
+
+    fn center(_: Vec3) -> Vec3 {
+        Vec3::ZERO
+    }
+
+    /// A point below a planar mesh facing `normal`.
+    fn behind(normal: Vec3) -> impl Fn(Vec3) -> Vec3 {
+        move |p| p - normal
+    }
+
+    #[test]
+    fn cube() {
+        let mesh = check(super::cube(2.0, ALL), 24, 36, center);
+        assert!(mesh.positions.iter().flatten().all(|c| c.abs() == 1.0));
+    }
+
+    #[test]
+    fn grid() {
+        let mesh = check(super::grid(2.0, 4.0, 3, 2, ALL), 12, 36, behind(Vec3::Y));
+        let aabb = mesh.bounding_box().unwrap();
+        assert_eq!(aabb.min, Vec3::new(-1.0, 0.0, -2.0));
+        assert_eq!(aabb.max, Vec3::new(1.0, 0.0, 2.0));
+        check(super::plane(1.0, 1.0, ALL), 4, 6, behind(Vec3::Y));
+    }
+
+    #[test]
+    fn uv_sphere() {
+        let mesh = check(super::uv_sphere(2.0, 8, 6, ALL), 9 * 7, 6 * 8 * 5, center);
+        for p in &mesh.positions {
+            assert!((Vec3::from(*p).length() - 2.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn icosphere() {
+        check(super::icosphere(1.0, 0, ALL), 12, 60, center);
+        let mesh = check(super::icosphere(1.5, 2, ALL), 162, 320 * 3, center);
+        for p in &mesh.positions {
+            assert!((Vec3::from(*p).length() - 1.5).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn cylinder() {
+        check(
+            super::cylinder(1.0, 2.0, 12, ALL),
+            4 * 12 + 6,
+            12 * 12,
+            center,
+        );
+    }
+
+    #[test]
+    fn cone() {
+        check(super::cone(1.0, 2.0, 12, ALL), 3 * 12 + 3, 6 * 12, center);
+    }
+
+    #[test]
+    fn torus() {
+        let ring = |p: Vec3| Vec3::new(p.x, 0.0, p.z).normalize() * 2.0;
+        check(super::torus(2.0, 0.5, 16, 8, ALL), 17 * 9, 6 * 16 * 8, ring);
+    }
+
+    #[test]
+    fn fullscreen_quad() {
+        let mesh = check(super::fullscreen_quad(ALL), 4, 6, behind(Vec3::Z));
+        assert_eq!(mesh.uvs.unwrap()[0], [0.0, 0.0]);
+    }
+
+    #[test]
+    fn options_drop_streams() {
+        let mesh = super::cube(1.0, MeshOptions::default());
+        assert!(mesh.normals.is_some() && mesh.uvs.is_some() && mesh.tangents.is_none());
+    }
+}
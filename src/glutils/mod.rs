@@ -1,5 +1,27 @@
+pub mod capture;
+pub mod debug;
+pub mod debug_draw;
+pub mod deferred;
+pub mod environment;
+pub mod framebuffer;
+pub mod gltf;
+pub mod gui;
+pub mod lighting;
+pub mod material;
+pub mod mesh;
 pub mod model;
+pub mod pbr;
+pub mod postprocess;
+pub mod profiler;
+pub mod render_state;
+pub mod scene;
 pub mod shader;
+pub mod shadow;
+pub mod skybox;
+pub mod ssao;
+pub mod text;
+pub mod texture;
+pub mod uniform_buffer;
 
 #[macro_export]
 macro_rules! try_into {
@@ -1,5 +1,7 @@
-use super::{as_gl_bool, try_into};
-use crate::glutils::shader::program::Program;
+use super::{as_gl_bool, debug, try_into};
+use crate::glutils::{
+    material::Material, mesh::Mesh, render_state::StateTracker, shader::program::Program,
+};
 use anyhow::{format_err, Result};
 use std::{ffi::c_void, mem, ptr};
 
@@ -9,6 +11,8 @@ use usage::Usage;
 pub mod primitives;
 use primitives::Primitive;
 
+pub mod stream;
+
 pub struct Model {
     vertex_array_object: gl::types::GLuint,
     element_buffer_object: Option<gl::types::GLuint>,
@@ -23,9 +27,11 @@ pub struct Model {
 pub struct ModelBuilder {
     position_attributes: VertexAttribute,
     color_attributes: Option<VertexAttribute>,
+    attributes: Vec<VertexAttribute>,
     indices: Option<Vec<u32>>,
     usage: Usage,
     program: Program,
+    label: Option<String>,
 
     vbo_num_elements: gl::types::GLsizei,
     stride: gl::types::GLsizei,
@@ -55,6 +61,13 @@ impl VertexAttribute {
 }
 
 impl Model {
+    /// Uploads a CPU-side triangle [`Mesh`], binding each of its vertex streams that the program
+    /// consumes.
+    pub fn from_mesh(mesh: &Mesh, program: Program, usage: Usage) -> Result<Self> {
+        mesh.model_builder(Primitive::Triangles, program, usage)?
+            .build()
+    }
+
     pub fn try_draw_arrays(&mut self, primitive: Primitive) -> Result<()> {
         if !self.vbo_bound && !self.program_active {
             return Err(format_err!(
@@ -62,21 +75,73 @@ impl Model {
             ));
         }
         unsafe { self.try_draw_arrays_impl(primitive) }
+        debug::check_errors("Model::try_draw_arrays");
         Ok(())
     }
 
+    /// Binds `material` and draws the model with it. The material's program needs to be the one
+    /// the model was built for, since vertex attribute locations are specific to a program.
+    pub fn draw(
+        &mut self,
+        material: &Material,
+        state: &mut StateTracker,
+        primitive: Primitive,
+    ) -> Result<()> {
+        self.draw_with(material, state, primitive, |_| Ok(()))
+    }
+
+    /// Like [`Model::draw`], but calls `set_uniforms` once the material is bound so that per-draw
+    /// values such as the model matrix can be set without a material per instance.
+    pub fn draw_with<F>(
+        &mut self,
+        material: &Material,
+        state: &mut StateTracker,
+        primitive: Primitive,
+        set_uniforms: F,
+    ) -> Result<()>
+    where
+        F: FnOnce(&Program) -> Result<()>,
+    {
+        if material.program() != self.program {
+            return Err(format_err!(
+                "material uses a different program than the model was built for"
+            ));
+        }
+        material.bind(state)?;
+        set_uniforms(&self.program)?;
+        self.program_active = true;
+        self.bind();
+        let result = self.try_draw_arrays(primitive);
+        self.unbind();
+        result
+    }
+
+    pub fn program(&self) -> Program {
+        self.program
+    }
+
     pub fn use_program(&mut self) {
         unsafe { self.use_program_impl() }
+        debug::check_errors("Model::use_program");
     }
 
     pub fn bind(&mut self) {
         unsafe { self.bind_impl() }
+        debug::check_errors("Model::bind");
     }
 
     pub fn unbind(&mut self) {
         unsafe { self.unbind_impl() }
     }
 
+    /// Marks the model as no longer bound, without any GL calls, once another vertex array has
+    /// been bound in its place.
+    pub fn release_binding(&mut self) {
+        self.vbo_bound = false;
+        self.ebo_bound = false;
+        self.program_active = false;
+    }
+
     unsafe fn try_draw_arrays_impl(&mut self, primitive: Primitive) {
         if self.element_buffer_object.is_some_and(|_| self.ebo_bound) {
             gl::DrawElements(
@@ -135,6 +200,8 @@ impl ModelBuilder {
             indices: None,
             vbo_num_elements: num_values,
             color_attributes: None,
+            attributes: vec![],
+            label: None,
         })
     }
 
@@ -169,8 +236,43 @@ impl ModelBuilder {
         Ok(self)
     }
 
+    /// Adds an additional interleaved attribute such as normals or texture coordinates. Attributes
+    /// are laid out in the VBO after position and color, in the order they were added.
+    pub fn attribute(mut self, vertices: VertexAttribute) -> Result<Self> {
+        let num_values = gl::types::GLsizei::try_from(vertices.values.len()).unwrap();
+        if num_values % vertices.component_size != 0 {
+            return Err(format_err!(
+                "number of values for '{}' attribute should be divisible by component size",
+                vertices.name
+            ));
+        }
+        self.vbo_num_elements += num_values;
+        self.stride +=
+            gl::types::GLsizei::try_from(mem::size_of::<f32>()).unwrap() * vertices.component_size;
+        self.attributes.push(vertices);
+        Ok(self)
+    }
+
+    /// Name shown for the model's vertex array and buffers in GL debug messages. Defaults to a
+    /// summary of the vertex attributes.
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
     pub fn build(self) -> Result<Model> {
-        unsafe { self.build_impl() }
+        let model = unsafe { self.build_impl() };
+        debug::check_errors("ModelBuilder::build");
+        model
+    }
+
+    fn default_label(&self) -> String {
+        let attributes = std::iter::once(&self.position_attributes)
+            .chain(self.color_attributes.as_ref())
+            .chain(&self.attributes)
+            .map(|attr| attr.name.as_str())
+            .collect::<Vec<_>>();
+        format!("model ({})", attributes.join(", "))
     }
 
     unsafe fn build_impl(&self) -> Result<Model> {
@@ -194,6 +296,17 @@ impl ModelBuilder {
             attribute_data_iters.push(color_attrs.values.chunks(col_component_size));
         }
 
+        for attr in &self.attributes {
+            let component_size = usize::try_from(attr.component_size).unwrap();
+            if attr.values.len() / component_size != num_vertices {
+                return Err(format_err!(
+                    "number of '{}' vertices should match number of position vertices",
+                    attr.name
+                ));
+            }
+            attribute_data_iters.push(attr.values.chunks(component_size));
+        }
+
         let vbo_num_elements = usize::try_from(self.vbo_num_elements).unwrap();
         let mut buffer = Vec::with_capacity(vbo_num_elements);
 
@@ -215,7 +328,7 @@ impl ModelBuilder {
         gl::GenVertexArrays(1, &mut vao);
         gl::BindVertexArray(vao);
 
-        let byte_offset = mem::size_of::<f32>() * pos_component_size;
+        let mut byte_offset = mem::size_of::<f32>() * pos_component_size;
 
         /*
          * Position attribute
@@ -257,8 +370,30 @@ impl ModelBuilder {
                 byte_offset as *const c_void,
             );
             gl::EnableVertexAttribArray(col_attr_loc);
-            // For when we texture coordinates are supported
-            // byte_offset += mem::size_of::<f32>() * component_size;
+            byte_offset += mem::size_of::<f32>() * usize::try_from(*component_size).unwrap();
+        }
+
+        /*
+         * Additional attributes
+         */
+        for VertexAttribute {
+            name,
+            component_size,
+            normalized,
+            ..
+        } in &self.attributes
+        {
+            let attr_loc = self.program.get_attrib_loc(name)?;
+            gl::VertexAttribPointer(
+                attr_loc,
+                *component_size,
+                gl::FLOAT,
+                as_gl_bool(*normalized),
+                self.stride,
+                byte_offset as *const c_void,
+            );
+            gl::EnableVertexAttribArray(attr_loc);
+            byte_offset += mem::size_of::<f32>() * usize::try_from(*component_size).unwrap();
         }
 
         /*
@@ -283,6 +418,13 @@ impl ModelBuilder {
         gl::BindBuffer(gl::ARRAY_BUFFER, 0);
         gl::BindVertexArray(0);
 
+        let label = self.label.clone().unwrap_or_else(|| self.default_label());
+        debug::label(gl::VERTEX_ARRAY, vao, &label);
+        debug::label(gl::BUFFER, vbo, &format!("{label} vertices"));
+        if let Some(ebo) = element_buffer_object {
+            debug::label(gl::BUFFER, ebo, &format!("{label} indices"));
+        }
+
         Ok(Model {
             program: self.program,
             vertex_array_object: vao,
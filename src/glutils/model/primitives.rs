@@ -1,11 +1,25 @@
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Primitive {
+    Points,
+    Lines,
+    LineLoop,
+    LineStrip,
+    #[default]
     Triangles,
+    TriangleStrip,
+    TriangleFan,
 }
 
 impl From<Primitive> for gl::types::GLenum {
     fn from(value: Primitive) -> Self {
         match value {
+            Primitive::Points => gl::POINTS,
+            Primitive::Lines => gl::LINES,
+            Primitive::LineLoop => gl::LINE_LOOP,
+            Primitive::LineStrip => gl::LINE_STRIP,
             Primitive::Triangles => gl::TRIANGLES,
+            Primitive::TriangleStrip => gl::TRIANGLE_STRIP,
+            Primitive::TriangleFan => gl::TRIANGLE_FAN,
         }
     }
 }
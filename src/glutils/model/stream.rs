@@ -0,0 +1,96 @@
+use super::primitives::Primitive;
+use crate::glutils::{debug, shader::program::Program, try_into};
+use anyhow::Result;
+use std::{ffi::c_void, mem, ptr};
+
+/// A vertex array whose vertices are replaced every time it's drawn, for geometry that's rebuilt
+/// each frame such as text or debug lines. Vertices are interleaved floats, with the attributes
+/// in the order they were given to [`VertexStream::new`].
+pub struct VertexStream {
+    vertex_array_object: gl::types::GLuint,
+    vertex_buffer_object: gl::types::GLuint,
+    /// Floats per vertex.
+    stride: usize,
+}
+
+impl VertexStream {
+    /// `attributes` are the names and component counts of the vertex attributes, which need to
+    /// be active in `program`.
+    pub fn new(program: &Program, attributes: &[(&str, usize)], label: &str) -> Result<Self> {
+        let stride = attributes.iter().map(|(_, size)| size).sum::<usize>();
+        let locations = attributes
+            .iter()
+            .map(|(name, _)| program.get_attrib_loc(name))
+            .collect::<Result<Vec<_>>>()?;
+
+        let stream = unsafe {
+            let mut vao = 0;
+            gl::GenVertexArrays(1, &mut vao);
+            gl::BindVertexArray(vao);
+            let mut vbo = 0;
+            gl::GenBuffers(1, &mut vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+
+            let mut offset = 0;
+            for (location, (_, size)) in locations.into_iter().zip(attributes) {
+                gl::VertexAttribPointer(
+                    location,
+                    try_into!(*size),
+                    gl::FLOAT,
+                    gl::FALSE,
+                    try_into!(stride * mem::size_of::<f32>()),
+                    (offset * mem::size_of::<f32>()) as *const c_void,
+                );
+                gl::EnableVertexAttribArray(location);
+                offset += size;
+            }
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::BindVertexArray(0);
+            debug::label(gl::VERTEX_ARRAY, vao, label);
+            debug::label(gl::BUFFER, vbo, &format!("{label} vertices"));
+            Self {
+                vertex_array_object: vao,
+                vertex_buffer_object: vbo,
+                stride,
+            }
+        };
+        debug::check_errors("VertexStream::new");
+        Ok(stream)
+    }
+
+    /// Uploads `vertices` and draws them with the program in use. Leaves no vertex array bound.
+    pub fn draw(&mut self, vertices: &[f32], primitive: Primitive) {
+        if vertices.is_empty() {
+            return;
+        }
+        unsafe {
+            gl::BindVertexArray(self.vertex_array_object);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vertex_buffer_object);
+            // Orphans last draw's storage rather than waiting for the GPU to be done with it.
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                try_into!(mem::size_of_val(vertices)),
+                ptr::null(),
+                gl::STREAM_DRAW,
+            );
+            gl::BufferSubData(
+                gl::ARRAY_BUFFER,
+                0,
+                try_into!(mem::size_of_val(vertices)),
+                vertices.as_ptr() as *const c_void,
+            );
+            gl::DrawArrays(primitive.into(), 0, try_into!(vertices.len() / self.stride));
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::BindVertexArray(0);
+        }
+        debug::check_errors("VertexStream::draw");
+    }
+
+    pub fn delete(self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.vertex_array_object);
+            gl::DeleteBuffers(1, &self.vertex_buffer_object);
+        }
+    }
+}
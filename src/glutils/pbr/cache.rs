@@ -0,0 +1,319 @@
+//! On-disk cache of baked [`Ibl`] maps. Texels are stored as the half floats they are on the
+//! GPU, in native byte order, since the cache is only meant for the machine that baked it.
+
+use super::ibl::{faces, half_float_formats, new_half_float_texture, Ibl};
+use crate::glutils::{
+    debug,
+    texture::{target::Target, Texture},
+    try_into,
+};
+use anyhow::{format_err, Result};
+use std::{
+    collections::hash_map::DefaultHasher,
+    ffi::c_void,
+    fs,
+    hash::{Hash, Hasher},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+const MAGIC: &[u8; 8] = b"IBLCACHE";
+/// Bumped whenever the layout or the way maps are baked changes, which invalidates old caches.
+const VERSION: u32 = 1;
+
+/// Where maps baked from `source` with the given sizes are cached in `dir`. Editing or
+/// replacing the source changes the path, so stale maps are never loaded.
+pub(super) fn path(dir: &Path, source: &Path, sizes: [u32; 4]) -> Result<PathBuf> {
+    let metadata = fs::metadata(source)
+        .map_err(|e| format_err!("failed to read '{}': {e}", source.display()))?;
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    let mut hasher = DefaultHasher::new();
+    VERSION.hash(&mut hasher);
+    source.hash(&mut hasher);
+    metadata.len().hash(&mut hasher);
+    modified.hash(&mut hasher);
+    sizes.hash(&mut hasher);
+
+    let stem = source
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    Ok(dir.join(format!("{stem}-{:016x}.ibl", hasher.finish())))
+}
+
+/// Uploads the maps cached at `path`, or returns `None` if nothing is cached there yet.
+pub(super) fn load(path: &Path) -> Result<Option<Ibl>> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let ([environment, irradiance, prefiltered, brdf_lut], prefiltered_levels) =
+        decode(&bytes).map_err(|e| format_err!("failed to load '{}': {e}", path.display()))?;
+    Ok(Some(Ibl {
+        environment: environment.upload(),
+        irradiance: irradiance.upload(),
+        prefiltered: prefiltered.upload(),
+        brdf_lut: brdf_lut.upload(),
+        prefiltered_levels,
+    }))
+}
+
+/// Reads the maps back from the GPU and writes them to `path`.
+pub(super) fn store(path: &Path, ibl: &Ibl) -> Result<()> {
+    let environment_levels = ibl.environment.width.ilog2() + 1;
+    let maps = [
+        Map::download(ibl.environment, environment_levels, 3),
+        Map::download(ibl.irradiance, 1, 3),
+        Map::download(ibl.prefiltered, ibl.prefiltered_levels, 3),
+        Map::download(ibl.brdf_lut, 1, 2),
+    ];
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, encode(&maps, ibl.prefiltered_levels))?;
+    Ok(())
+}
+
+/// The environment, irradiance, prefiltered and BRDF maps, followed by the number of
+/// prefiltered levels the shaders sample.
+fn encode(maps: &[Map; 4], prefiltered_levels: u32) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&VERSION.to_ne_bytes());
+    for map in maps {
+        map.write_to(&mut bytes);
+    }
+    bytes.extend_from_slice(&prefiltered_levels.to_ne_bytes());
+    bytes
+}
+
+fn decode(bytes: &[u8]) -> Result<([Map; 4], u32)> {
+    let mut reader = Reader { bytes };
+    if reader.take(MAGIC.len())? != MAGIC || reader.u32()? != VERSION {
+        return Err(format_err!("not a cache of this version"));
+    }
+
+    let maps = [
+        Map::read_from(&mut reader)?,
+        Map::read_from(&mut reader)?,
+        Map::read_from(&mut reader)?,
+        Map::read_from(&mut reader)?,
+    ];
+    let prefiltered_levels = reader.u32()?;
+    if !reader.bytes.is_empty() {
+        return Err(format_err!("cache has trailing data"));
+    }
+    Ok((maps, prefiltered_levels))
+}
+
+/// Texels of every level and face of a square half float map.
+#[derive(Debug, PartialEq)]
+struct Map {
+    target: Target,
+    size: u32,
+    levels: u32,
+    channels: u32,
+    texels: Vec<u16>,
+}
+
+impl Map {
+    fn num_texels(target: Target, size: u32, levels: u32, channels: u32) -> usize {
+        let faces = faces(target).len();
+        (0..levels)
+            .map(|level| {
+                let level_size = (size >> level).max(1);
+                usize::try_from(level_size * level_size * channels).unwrap() * faces
+            })
+            .sum()
+    }
+
+    fn download(texture: Texture, levels: u32, channels: u32) -> Self {
+        let (_, format) = half_float_formats(channels);
+        let mut texels =
+            vec![0u16; Self::num_texels(texture.target, texture.width, levels, channels)];
+        unsafe {
+            gl::BindTexture(texture.target.into(), texture.gl_object_id);
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 2);
+            let mut offset = 0;
+            for level in 0..levels {
+                let level_size = (texture.width >> level).max(1);
+                for face in faces(texture.target) {
+                    gl::GetTexImage(
+                        face,
+                        try_into!(level),
+                        format,
+                        gl::HALF_FLOAT,
+                        texels[offset..].as_mut_ptr() as *mut c_void,
+                    );
+                    offset += usize::try_from(level_size * level_size * channels).unwrap();
+                }
+            }
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 4);
+            gl::BindTexture(texture.target.into(), 0);
+        }
+        debug::check_errors("cache::Map::download");
+        Self {
+            target: texture.target,
+            size: texture.width,
+            levels,
+            channels,
+            texels,
+        }
+    }
+
+    fn upload(&self) -> Texture {
+        let texture = unsafe {
+            new_half_float_texture(
+                self.target,
+                self.size,
+                self.levels,
+                self.channels,
+                Some(&self.texels),
+            )
+        };
+        debug::check_errors("cache::Map::upload");
+        texture
+    }
+
+    fn write_to(&self, bytes: &mut Vec<u8>) {
+        let cube_map = u32::from(self.target == Target::CubeMap);
+        for value in [cube_map, self.size, self.levels, self.channels] {
+            bytes.extend_from_slice(&value.to_ne_bytes());
+        }
+        bytes.extend_from_slice(bytemuck::cast_slice(&self.texels));
+    }
+
+    fn read_from(reader: &mut Reader) -> Result<Self> {
+        let target = match reader.u32()? {
+            0 => Target::Texture2D,
+            1 => Target::CubeMap,
+            other => return Err(format_err!("unknown map target {other}")),
+        };
+        let size = reader.u32()?;
+        let levels = reader.u32()?;
+        let channels = reader.u32()?;
+        if size == 0 || levels == 0 || levels > size.ilog2() + 1 || !(2..=3).contains(&channels) {
+            return Err(format_err!(
+                "invalid map of size {size} with {levels} levels and {channels} channels"
+            ));
+        }
+        let len = Self::num_texels(target, size, levels, channels);
+        let texels = reader
+            .take(len * 2)?
+            .chunks_exact(2)
+            .map(|bytes| u16::from_ne_bytes([bytes[0], bytes[1]]))
+            .collect();
+        Ok(Self {
+            target,
+            size,
+            levels,
+            channels,
+            texels,
+        })
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(format_err!("cache is truncated"));
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_ne_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(target: Target, size: u32, levels: u32, channels: u32) -> Map {
+        let len = Map::num_texels(target, size, levels, channels);
+        Map {
+            target,
+            size,
+            levels,
+            channels,
+            texels: (0..len).map(|i| i as u16).collect(),
+        }
+    }
+
+    fn maps() -> [Map; 4] {
+        [
+            map(Target::CubeMap, 8, 4, 3),
+            map(Target::CubeMap, 2, 1, 3),
+            map(Target::CubeMap, 4, 2, 3),
+            map(Target::Texture2D, 4, 1, 2),
+        ]
+    }
+
+    fn decode_err(bytes: &[u8]) -> String {
+        decode(bytes).err().unwrap().to_string()
+    }
+
+    #[test]
+    fn maps_round_trip() {
+        let maps = maps();
+        // Levels stop halving at one texel.
+        assert_eq!(maps[0].texels.len(), (64 + 16 + 4 + 1) * 3 * 6);
+
+        let (decoded, levels) = decode(&encode(&maps, 2)).unwrap();
+        assert_eq!(decoded, maps);
+        assert_eq!(levels, 2);
+    }
+
+    #[test]
+    fn rejects_other_files_and_versions() {
+        let mut bytes = encode(&maps(), 2);
+        bytes[0] = b'X';
+        assert!(decode_err(&bytes).contains("this version"));
+
+        let mut bytes = encode(&maps(), 2);
+        bytes[MAGIC.len()..][..4].copy_from_slice(&(VERSION + 1).to_ne_bytes());
+        assert!(decode_err(&bytes).contains("this version"));
+    }
+
+    #[test]
+    fn rejects_truncated_and_trailing_data() {
+        let bytes = encode(&maps(), 2);
+        for len in [0, 4, MAGIC.len() + 4, bytes.len() / 2, bytes.len() - 1] {
+            assert!(decode_err(&bytes[..len]).contains("truncated"), "{len}");
+        }
+
+        let mut bytes = bytes;
+        bytes.push(0);
+        assert!(decode_err(&bytes).contains("trailing data"));
+    }
+
+    #[test]
+    fn rejects_invalid_maps() {
+        for (size, levels, channels) in [(8, 0, 3), (8, 5, 3), (0, 1, 3), (8, 4, 4), (8, 4, 1)] {
+            let mut maps = maps();
+            maps[0].size = size;
+            maps[0].levels = levels;
+            maps[0].channels = channels;
+            let err = decode_err(&encode(&maps, 2));
+            assert!(err.contains("invalid map"), "{err}");
+        }
+
+        let mut bytes = encode(&maps(), 2);
+        bytes[MAGIC.len() + 4..][..4].copy_from_slice(&2u32.to_ne_bytes());
+        assert!(decode_err(&bytes).contains("unknown map target"));
+    }
+}
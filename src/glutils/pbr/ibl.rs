@@ -0,0 +1,476 @@
+use super::cache;
+use crate::glutils::{
+    debug,
+    material::Material,
+    mesh::{shapes, MeshOptions},
+    model::{primitives::Primitive, usage::Usage, Model},
+    render_state::{RenderState, StateTracker},
+    shader::{program::Linker, Shader, ShaderType},
+    texture::{cubemap::CubeFace, target::Target, Texture},
+    try_into,
+};
+use anyhow::{format_err, Result};
+use glam::{camera::rh::proj::opengl, Vec3};
+use image::Rgb32FImage;
+use std::{
+    f32::consts::FRAC_PI_2,
+    ffi::c_void,
+    path::{Path, PathBuf},
+    ptr,
+};
+
+/// Mip levels of the prefiltered map, from perfectly smooth to fully rough. Rougher levels are
+/// blurry enough that they don't need many texels.
+const MAX_PREFILTERED_LEVELS: u32 = 5;
+
+/// Every pass draws a cube around the camera, or a full-screen quad, seen from inside.
+const BAKE_STATE: RenderState = RenderState {
+    depth_write: false,
+    ..RenderState::DEFAULT
+};
+
+/// Maps that light surfaces with their surroundings, baked from an HDR environment by
+/// [`IblBaker`]. Programs that include `pbr.glsl` with `IBL` defined read them once set on their
+/// materials with [`Ibl::apply`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Ibl {
+    /// The environment as a cube map, for drawing as a skybox.
+    pub environment: Texture,
+    /// Light arriving at a surface facing each direction, for diffuse ambient light.
+    pub irradiance: Texture,
+    /// The environment as reflected by increasingly rough surfaces down its mip levels.
+    pub prefiltered: Texture,
+    /// Scale and bias to a surface's reflectance by view angle across and roughness up.
+    pub brdf_lut: Texture,
+    pub prefiltered_levels: u32,
+}
+
+impl Ibl {
+    /// Mip level of the prefiltered map for fully rough surfaces.
+    pub fn max_reflection_lod(&self) -> f32 {
+        (self.prefiltered_levels - 1) as f32
+    }
+
+    pub fn apply(&self, material: &mut Material) {
+        material.set_texture("irradianceMap", self.irradiance);
+        material.set_texture("prefilteredMap", self.prefiltered);
+        material.set_texture("brdfLut", self.brdf_lut);
+        material.set("maxReflectionLod", self.max_reflection_lod());
+    }
+
+    pub fn delete(self) {
+        for texture in [
+            self.environment,
+            self.irradiance,
+            self.prefiltered,
+            self.brdf_lut,
+        ] {
+            texture.delete();
+        }
+    }
+}
+
+/// Bakes [`Ibl`] maps on the GPU. Baking takes a noticeable while, so maps baked from a file
+/// are cached to disk, keyed by the file's path, size, modification time and the map sizes.
+pub struct IblBaker {
+    environment_size: u32,
+    irradiance_size: u32,
+    prefiltered_size: u32,
+    brdf_lut_size: u32,
+    cache_dir: Option<PathBuf>,
+}
+
+impl Default for IblBaker {
+    fn default() -> Self {
+        Self {
+            environment_size: 512,
+            irradiance_size: 32,
+            prefiltered_size: 128,
+            brdf_lut_size: 512,
+            cache_dir: Some(PathBuf::new().join("cache").join("ibl")),
+        }
+    }
+}
+
+impl IblBaker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Size of a face of the environment cube map the panorama is projected onto.
+    pub fn environment_size(mut self, size: u32) -> Self {
+        self.environment_size = size;
+        self
+    }
+
+    pub fn irradiance_size(mut self, size: u32) -> Self {
+        self.irradiance_size = size;
+        self
+    }
+
+    /// Size of a face of the prefiltered map's sharpest level.
+    pub fn prefiltered_size(mut self, size: u32) -> Self {
+        self.prefiltered_size = size;
+        self
+    }
+
+    pub fn brdf_lut_size(mut self, size: u32) -> Self {
+        self.brdf_lut_size = size;
+        self
+    }
+
+    /// Directory baked maps are cached in, or `None` to always bake.
+    pub fn cache_dir<P: AsRef<Path>>(mut self, dir: Option<P>) -> Self {
+        self.cache_dir = dir.map(|dir| dir.as_ref().to_path_buf());
+        self
+    }
+
+    fn prefiltered_levels(&self) -> u32 {
+        MAX_PREFILTERED_LEVELS.min(self.prefiltered_size.ilog2() + 1)
+    }
+
+    /// Sizes of the maps, which baked maps are only valid for.
+    pub(super) fn sizes(&self) -> [u32; 4] {
+        [
+            self.environment_size,
+            self.irradiance_size,
+            self.prefiltered_size,
+            self.brdf_lut_size,
+        ]
+    }
+
+    /// Loads an equirectangular HDR panorama, with longitude across and latitude down the
+    /// image, and bakes maps from it unless they're cached already.
+    pub fn bake_from_path<P: AsRef<Path>>(&self, state: &mut StateTracker, path: P) -> Result<Ibl> {
+        let path = path.as_ref();
+        let cache_path = match &self.cache_dir {
+            Some(dir) => Some(cache::path(dir, path, self.sizes())?),
+            None => None,
+        };
+        if let Some(cache_path) = &cache_path {
+            match cache::load(cache_path) {
+                Ok(Some(ibl)) => return Ok(ibl),
+                Ok(None) => (),
+                Err(e) => log::warn!("rebaking '{}': {e}", path.display()),
+            }
+        }
+
+        let img = image::open(path)
+            .map_err(|e| format_err!("failed to load '{}': {e}", path.display()))?
+            .into_rgb32f();
+        let ibl = self.bake_from_image(state, &img)?;
+        if let Some(cache_path) = &cache_path {
+            // Baking again next time is better than not running at all.
+            if let Err(e) = cache::store(cache_path, &ibl) {
+                log::warn!("failed to cache '{}': {e}", cache_path.display());
+            }
+        }
+        Ok(ibl)
+    }
+
+    /// Bakes maps from an equirectangular HDR panorama without caching them. The bound
+    /// framebuffer and viewport are restored afterwards.
+    pub fn bake_from_image(&self, state: &mut StateTracker, img: &Rgb32FImage) -> Result<Ibl> {
+        if self.sizes().contains(&0) {
+            return Err(format_err!("IBL map sizes must be non-zero"));
+        }
+
+        let shaders_dir = PathBuf::new().join("shaders").join("ibl");
+        let cube = shapes::cube(
+            2.0,
+            MeshOptions {
+                normals: false,
+                tangents: false,
+                uvs: false,
+            },
+        );
+        let cube_pass = |fragment: &str| -> Result<(Model, Material)> {
+            let vs = Shader::new(
+                shaders_dir.join("cube_vertex_shader.glsl"),
+                ShaderType::Vertex,
+            )?;
+            let fs = Shader::new(shaders_dir.join(fragment), ShaderType::Fragment)?;
+            let program = Linker::new().attach_shader(vs).attach_shader(fs).link()?;
+            let mut material = Material::new(program);
+            material.set_render_state(BAKE_STATE);
+            Ok((Model::from_mesh(&cube, program, Usage::Static)?, material))
+        };
+        let mut equirectangular = cube_pass("equirectangular_fragment_shader.glsl")?;
+        let mut irradiance = cube_pass("irradiance_fragment_shader.glsl")?;
+        let mut prefilter = cube_pass("prefilter_fragment_shader.glsl")?;
+
+        let brdf_program = {
+            let vs = Shader::new(
+                PathBuf::new()
+                    .join("shaders")
+                    .join("postprocess")
+                    .join("fullscreen_vertex_shader.glsl"),
+                ShaderType::Vertex,
+            )?;
+            let fs = Shader::new(
+                shaders_dir.join("brdf_fragment_shader.glsl"),
+                ShaderType::Fragment,
+            )?;
+            Linker::new().attach_shader(vs).attach_shader(fs).link()?
+        };
+        let quad = shapes::fullscreen_quad(MeshOptions {
+            normals: false,
+            tangents: false,
+            uvs: true,
+        });
+        let mut brdf = (
+            Model::from_mesh(&quad, brdf_program, Usage::Static)?,
+            Material::new(brdf_program),
+        );
+        brdf.1.set_render_state(BAKE_STATE);
+
+        let environment_levels = self.environment_size.ilog2() + 1;
+        let prefiltered_levels = self.prefiltered_levels();
+        let (panorama, framebuffer, ibl) = unsafe {
+            let panorama = upload_panorama(img);
+            let ibl = Ibl {
+                environment: new_half_float_texture(
+                    Target::CubeMap,
+                    self.environment_size,
+                    environment_levels,
+                    3,
+                    None,
+                ),
+                irradiance: new_half_float_texture(
+                    Target::CubeMap,
+                    self.irradiance_size,
+                    1,
+                    3,
+                    None,
+                ),
+                prefiltered: new_half_float_texture(
+                    Target::CubeMap,
+                    self.prefiltered_size,
+                    prefiltered_levels,
+                    3,
+                    None,
+                ),
+                brdf_lut: new_half_float_texture(Target::Texture2D, self.brdf_lut_size, 1, 2, None),
+                prefiltered_levels,
+            };
+            let mut framebuffer = 0;
+            gl::GenFramebuffers(1, &mut framebuffer);
+            (panorama, framebuffer, ibl)
+        };
+        debug::check_errors("IblBaker::bake_from_image");
+
+        let mut previous_framebuffer = 0;
+        let mut previous_viewport = [0; 4];
+        unsafe {
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut previous_framebuffer);
+            gl::GetIntegerv(gl::VIEWPORT, previous_viewport.as_mut_ptr());
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
+        }
+
+        let result = (|| -> Result<()> {
+            equirectangular
+                .1
+                .set_texture("equirectangularMap", panorama);
+            draw_cube(state, &mut equirectangular, ibl.environment, 0)?;
+            // Prefiltering reads blurrier levels for the samples that stand for more of the
+            // environment.
+            unsafe {
+                ibl.environment.bind(0);
+                gl::GenerateMipmap(gl::TEXTURE_CUBE_MAP);
+            }
+
+            irradiance.1.set_texture("environmentMap", ibl.environment);
+            draw_cube(state, &mut irradiance, ibl.irradiance, 0)?;
+
+            prefilter.1.set_texture("environmentMap", ibl.environment);
+            prefilter
+                .1
+                .set("environmentSize", self.environment_size as f32);
+            for level in 0..prefiltered_levels {
+                let roughness = level as f32 / (prefiltered_levels - 1).max(1) as f32;
+                prefilter.1.set("roughness", roughness);
+                draw_cube(state, &mut prefilter, ibl.prefiltered, level)?;
+            }
+
+            attach(gl::TEXTURE_2D, ibl.brdf_lut, 0);
+            brdf.0.draw(&brdf.1, state, Primitive::Triangles)
+        })();
+
+        unsafe {
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, 0, 0);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, try_into!(previous_framebuffer));
+            gl::DeleteFramebuffers(1, &framebuffer);
+            let [x, y, width, height] = previous_viewport;
+            gl::Viewport(x, y, width, height);
+        }
+        panorama.delete();
+        debug::check_errors("IblBaker::bake_from_image");
+
+        match result {
+            Ok(()) => Ok(ibl),
+            Err(e) => {
+                ibl.delete();
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Renders every face of level `level` of `target` with a cube pass, from the cube's center.
+fn draw_cube(
+    state: &mut StateTracker,
+    (model, material): &mut (Model, Material),
+    target: Texture,
+    level: u32,
+) -> Result<()> {
+    let projection = opengl::perspective(FRAC_PI_2, 1.0, 0.1, 10.0);
+    for face in CubeFace::ALL {
+        attach(face.into(), target, level);
+        model.draw_with(material, state, Primitive::Triangles, |program| {
+            program.set_uniform_matrix_4fv("projection", &projection)?;
+            program.set_uniform_matrix_4fv("view", &face.view(Vec3::ZERO))
+        })?;
+    }
+    Ok(())
+}
+
+/// Draws into level `level` of `image`, a face of a cube map or a 2D texture.
+fn attach(image: gl::types::GLenum, texture: Texture, level: u32) {
+    let size = (texture.width >> level).max(1);
+    unsafe {
+        gl::FramebufferTexture2D(
+            gl::FRAMEBUFFER,
+            gl::COLOR_ATTACHMENT0,
+            image,
+            texture.gl_object_id,
+            try_into!(level),
+        );
+        gl::Viewport(0, 0, try_into!(size), try_into!(size));
+    }
+}
+
+/// The panorama as a float texture that wraps around horizontally. Its rows stay top first,
+/// which the projection accounts for.
+unsafe fn upload_panorama(img: &Rgb32FImage) -> Texture {
+    let mut texture = 0;
+    gl::GenTextures(1, &mut texture);
+    gl::BindTexture(gl::TEXTURE_2D, texture);
+    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, try_into!(gl::REPEAT));
+    gl::TexParameteri(
+        gl::TEXTURE_2D,
+        gl::TEXTURE_WRAP_T,
+        try_into!(gl::CLAMP_TO_EDGE),
+    );
+    gl::TexParameteri(
+        gl::TEXTURE_2D,
+        gl::TEXTURE_MIN_FILTER,
+        try_into!(gl::LINEAR),
+    );
+    gl::TexParameteri(
+        gl::TEXTURE_2D,
+        gl::TEXTURE_MAG_FILTER,
+        try_into!(gl::LINEAR),
+    );
+    gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+    gl::TexImage2D(
+        gl::TEXTURE_2D,
+        0,
+        try_into!(gl::RGB32F),
+        try_into!(img.width()),
+        try_into!(img.height()),
+        0,
+        gl::RGB,
+        gl::FLOAT,
+        img.as_raw().as_ptr() as *const c_void,
+    );
+    gl::BindTexture(gl::TEXTURE_2D, 0);
+    Texture {
+        gl_object_id: texture,
+        width: img.width(),
+        height: img.height(),
+        target: Target::Texture2D,
+    }
+}
+
+/// Internal format and pixel format of half float maps with `channels` channels.
+pub(super) fn half_float_formats(channels: u32) -> (gl::types::GLenum, gl::types::GLenum) {
+    match channels {
+        2 => (gl::RG16F, gl::RG),
+        _ => (gl::RGB16F, gl::RGB),
+    }
+}
+
+/// Faces of a texture of the given target, the only one of a 2D texture.
+pub(super) fn faces(target: Target) -> Vec<gl::types::GLenum> {
+    match target {
+        Target::CubeMap => CubeFace::ALL.map(Into::into).to_vec(),
+        _ => vec![gl::TEXTURE_2D],
+    }
+}
+
+/// A square map of `size` texels with `levels` mip levels, filled with `texels` if given. Those
+/// are laid out level by level and then face by face.
+pub(super) unsafe fn new_half_float_texture(
+    target: Target,
+    size: u32,
+    levels: u32,
+    channels: u32,
+    texels: Option<&[u16]>,
+) -> Texture {
+    let gl_target = target.into();
+    let (internal_format, format) = half_float_formats(channels);
+    let mut texture = 0;
+    gl::GenTextures(1, &mut texture);
+    gl::BindTexture(gl_target, texture);
+
+    // Rows of half floats are always 2-byte aligned.
+    gl::PixelStorei(gl::UNPACK_ALIGNMENT, 2);
+    let mut offset = 0;
+    for level in 0..levels {
+        let level_size = (size >> level).max(1);
+        for face in faces(target) {
+            let data = match texels {
+                Some(texels) => {
+                    let data = texels[offset..].as_ptr() as *const c_void;
+                    offset += usize::try_from(level_size * level_size * channels).unwrap();
+                    data
+                }
+                None => ptr::null(),
+            };
+            gl::TexImage2D(
+                face,
+                try_into!(level),
+                try_into!(internal_format),
+                try_into!(level_size),
+                try_into!(level_size),
+                0,
+                format,
+                gl::HALF_FLOAT,
+                data,
+            );
+        }
+    }
+
+    for wrap in [gl::TEXTURE_WRAP_S, gl::TEXTURE_WRAP_T, gl::TEXTURE_WRAP_R] {
+        gl::TexParameteri(gl_target, wrap, try_into!(gl::CLAMP_TO_EDGE));
+    }
+    let min_filter = if levels > 1 {
+        gl::LINEAR_MIPMAP_LINEAR
+    } else {
+        gl::LINEAR
+    };
+    gl::TexParameteri(gl_target, gl::TEXTURE_MIN_FILTER, try_into!(min_filter));
+    gl::TexParameteri(gl_target, gl::TEXTURE_MAG_FILTER, try_into!(gl::LINEAR));
+    gl::TexParameteri(gl_target, gl::TEXTURE_MAX_LEVEL, try_into!(levels - 1));
+    if target == Target::CubeMap {
+        gl::Enable(gl::TEXTURE_CUBE_MAP_SEAMLESS);
+    }
+    gl::BindTexture(gl_target, 0);
+
+    Texture {
+        gl_object_id: texture,
+        width: size,
+        height: size,
+        target,
+    }
+}
@@ -0,0 +1,54 @@
+use super::{
+    lighting::solid_texture, material::Material, shader::program::Program, texture::Texture,
+};
+use anyhow::Result;
+use glam::{vec3, Vec3};
+
+mod cache;
+pub mod ibl;
+use ibl::Ibl;
+
+/// Surface properties for `pbr.glsl`'s metallic-roughness model: the albedo map holds the
+/// surface's base color in sRGB, the metallic, roughness and ambient occlusion maps one linear
+/// value each in their red channel, and the normal map tangent space normals.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PbrMaterial {
+    pub albedo: Texture,
+    pub normal: Texture,
+    pub metallic: Texture,
+    pub roughness: Texture,
+    pub ao: Texture,
+}
+
+impl PbrMaterial {
+    /// A flat surface of a single linear `albedo`, `metallic` and `roughness`, with nothing
+    /// occluding its ambient light.
+    pub fn uniform(albedo: Vec3, metallic: f32, roughness: f32) -> Result<Self> {
+        Ok(Self {
+            albedo: solid_texture(albedo.powf(1.0 / 2.2))?,
+            normal: flat_normal_texture()?,
+            metallic: solid_texture(Vec3::splat(metallic))?,
+            roughness: solid_texture(Vec3::splat(roughness))?,
+            ao: solid_texture(Vec3::ONE)?,
+        })
+    }
+
+    /// The program needs to include `pbr.glsl` with `IBL` defined exactly when `ibl` is given.
+    pub fn into_material(self, program: Program, ibl: Option<&Ibl>) -> Material {
+        let mut material = Material::new(program);
+        material.set_texture("material.albedo", self.albedo);
+        material.set_texture("material.normal", self.normal);
+        material.set_texture("material.metallic", self.metallic);
+        material.set_texture("material.roughness", self.roughness);
+        material.set_texture("material.ao", self.ao);
+        if let Some(ibl) = ibl {
+            ibl.apply(&mut material);
+        }
+        material
+    }
+}
+
+/// A 1x1 normal map that leaves normals as they are, for materials without one.
+pub fn flat_normal_texture() -> Result<Texture> {
+    solid_texture(vec3(0.5, 0.5, 1.0))
+}
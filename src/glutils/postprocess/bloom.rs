@@ -0,0 +1,244 @@
+use crate::glutils::{
+    framebuffer::{attachment::ColorFormat, Framebuffer, FramebufferBuilder},
+    mesh::{shapes, MeshOptions},
+    model::{primitives::Primitive, usage::Usage, Model},
+    shader::{
+        program::{Linker, Program},
+        Shader, ShaderType,
+    },
+    texture::Texture,
+};
+use anyhow::Result;
+use std::path::PathBuf;
+
+/// How many times the bloom buffer can be halved for the dual Kawase blur.
+pub const MAX_LEVELS: usize = 6;
+
+/// How the bright parts of the image are spread out.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BloomBlur {
+    /// A separable Gaussian at half resolution, repeated `passes` times for a wider glow.
+    Gaussian { passes: u32 },
+    /// Dual Kawase filtering down `levels` successively halved buffers and back up again, which
+    /// spreads much further than a Gaussian of the same cost. At most [`MAX_LEVELS`].
+    DualKawase { levels: u32 },
+}
+
+impl BloomBlur {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Gaussian { .. } => "gaussian",
+            Self::DualKawase { .. } => "dual kawase",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BloomSettings {
+    /// Brightness, as the largest of the color's channels, past which pixels start to glow.
+    pub threshold: f32,
+    /// How far below the threshold the glow fades in, so that it doesn't switch on abruptly.
+    pub knee: f32,
+    /// How strongly the glow is added back onto the scene.
+    pub intensity: f32,
+    pub blur: BloomBlur,
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        Self {
+            threshold: 1.0,
+            knee: 0.5,
+            intensity: 0.8,
+            blur: BloomBlur::DualKawase { levels: 5 },
+        }
+    }
+}
+
+/// Makes bright parts of an HDR image bleed into their surroundings: pixels above a threshold
+/// are extracted into a half resolution buffer, blurred and then added back onto the image. All
+/// of it happens before tonemapping, so only colors brighter than white bloom with the default
+/// threshold.
+pub struct Bloom {
+    settings: BloomSettings,
+    threshold: Model,
+    gaussian: Model,
+    kawase_down: Model,
+    kawase_up: Model,
+    composite: Model,
+    /// Half the size of the source, then each half the size of the one before.
+    levels: Vec<Framebuffer>,
+    /// Holds the horizontally blurred image between the two halves of a Gaussian pass.
+    scratch: Framebuffer,
+}
+
+impl Bloom {
+    /// Bloom for images of `width` by `height` pixels.
+    pub fn new(width: u32, height: u32, settings: BloomSettings) -> Result<Self> {
+        let shaders_dir = PathBuf::new().join("shaders").join("postprocess");
+        let quad = shapes::fullscreen_quad(MeshOptions {
+            normals: false,
+            tangents: false,
+            uvs: true,
+        });
+        let pass = |fragment_shader: &str| -> Result<Model> {
+            let vs = Shader::new(
+                shaders_dir.join("fullscreen_vertex_shader.glsl"),
+                ShaderType::Vertex,
+            )?;
+            let fs = Shader::new(shaders_dir.join(fragment_shader), ShaderType::Fragment)?;
+            let program = Linker::new().attach_shader(vs).attach_shader(fs).link()?;
+            Model::from_mesh(&quad, program, Usage::Static)
+        };
+
+        let levels = (0..MAX_LEVELS)
+            .map(|level| {
+                let (width, height) = level_size(width, height, level);
+                FramebufferBuilder::new(width, height)
+                    .color_attachment(ColorFormat::Rg11B10F)
+                    .build()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let (scratch_width, scratch_height) = level_size(width, height, 0);
+        let scratch = FramebufferBuilder::new(scratch_width, scratch_height)
+            .color_attachment(ColorFormat::Rg11B10F)
+            .build()?;
+
+        Ok(Self {
+            settings,
+            threshold: pass("bloom_threshold_fragment_shader.glsl")?,
+            gaussian: pass("gaussian_blur_fragment_shader.glsl")?,
+            kawase_down: pass("kawase_down_fragment_shader.glsl")?,
+            kawase_up: pass("kawase_up_fragment_shader.glsl")?,
+            composite: pass("bloom_composite_fragment_shader.glsl")?,
+            levels,
+            scratch,
+        })
+    }
+
+    pub fn settings(&self) -> &BloomSettings {
+        &self.settings
+    }
+
+    pub fn settings_mut(&mut self) -> &mut BloomSettings {
+        &mut self.settings
+    }
+
+    /// Resizes the buffers for a source image of `width` by `height` pixels.
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<()> {
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+        for (level, fb) in self.levels.iter_mut().enumerate() {
+            let (width, height) = level_size(width, height, level);
+            fb.resize(width, height)?;
+        }
+        let (width, height) = level_size(width, height, 0);
+        self.scratch.resize(width, height)
+    }
+
+    /// Extracts and blurs the bright parts of `source`, returning the half resolution result.
+    /// Leaves some framebuffer of its own bound, with a viewport to match.
+    pub fn render(&mut self, source: &Texture) -> Result<Texture> {
+        let settings = self.settings;
+
+        self.levels[0].bind();
+        draw(&mut self.threshold, &[*source], |program| {
+            program.set_uniform_2f(
+                "texelSize",
+                1.0 / source.width as f32,
+                1.0 / source.height as f32,
+            )?;
+            program.set_uniform_1f("threshold", settings.threshold)?;
+            program.set_uniform_1f("knee", settings.knee.max(0.0))
+        })?;
+
+        match settings.blur {
+            BloomBlur::Gaussian { passes } => {
+                for _ in 0..passes {
+                    let image = *self.levels[0].color_texture(0).unwrap();
+                    self.scratch.bind();
+                    draw(&mut self.gaussian, &[image], |program| {
+                        program.set_uniform_2f("direction", 1.0 / image.width as f32, 0.0)
+                    })?;
+
+                    let image = *self.scratch.color_texture(0).unwrap();
+                    self.levels[0].bind();
+                    draw(&mut self.gaussian, &[image], |program| {
+                        program.set_uniform_2f("direction", 0.0, 1.0 / image.height as f32)
+                    })?;
+                }
+            }
+            BloomBlur::DualKawase { levels } => {
+                let levels = usize::try_from(levels).unwrap().clamp(1, MAX_LEVELS);
+                for level in 1..levels {
+                    self.kawase_pass(level - 1, level, false)?;
+                }
+                for level in (1..levels).rev() {
+                    self.kawase_pass(level, level - 1, true)?;
+                }
+            }
+        }
+        Ok(*self.levels[0].color_texture(0).unwrap())
+    }
+
+    /// Draws `scene` with `bloom`, as returned by [`Bloom::render`], added on top into the bound
+    /// framebuffer.
+    pub fn composite(&mut self, scene: &Texture, bloom: &Texture) -> Result<()> {
+        let intensity = self.settings.intensity;
+        draw(&mut self.composite, &[*scene, *bloom], |program| {
+            program.set_uniform_1i("bloomTexture", 1)?;
+            program.set_uniform_1f("intensity", intensity)
+        })
+    }
+
+    fn kawase_pass(&mut self, from: usize, to: usize, up: bool) -> Result<()> {
+        let image = *self.levels[from].color_texture(0).unwrap();
+        self.levels[to].bind();
+        let model = if up {
+            &mut self.kawase_up
+        } else {
+            &mut self.kawase_down
+        };
+        draw(model, &[image], |program| {
+            program.set_uniform_2f(
+                "halfTexel",
+                0.5 / image.width as f32,
+                0.5 / image.height as f32,
+            )
+        })
+    }
+
+    pub fn delete(self) {
+        for fb in self.levels {
+            fb.delete();
+        }
+        self.scratch.delete();
+    }
+}
+
+/// Size of the buffer at `level` of the chain for a source of `width` by `height` pixels.
+fn level_size(width: u32, height: u32, level: usize) -> (u32, u32) {
+    (
+        (width >> (level + 1)).max(1),
+        (height >> (level + 1)).max(1),
+    )
+}
+
+/// Draws a full-screen pass into the bound framebuffer with `inputs` bound to consecutive
+/// texture units. Samplers default to unit 0, so only those of later inputs need setting.
+fn draw(
+    model: &mut Model,
+    inputs: &[Texture],
+    set_uniforms: impl FnOnce(&Program) -> Result<()>,
+) -> Result<()> {
+    model.use_program();
+    for (unit, texture) in inputs.iter().enumerate() {
+        texture.bind(u32::try_from(unit).unwrap());
+    }
+    set_uniforms(&model.program())?;
+    model.bind();
+    model.try_draw_arrays(Primitive::Triangles)?;
+    model.unbind();
+    Ok(())
+}
@@ -0,0 +1,203 @@
+use crate::glutils::shader::program::Program;
+use anyhow::{format_err, Result};
+use std::str::FromStr;
+
+/// A single full-screen pass. Effects that take parameters can be given them on the command line
+/// as `name=value`, e.g. `gamma=2.2`. Each tonemapper is named after its [`Tonemapper::name`] and
+/// takes an exposure, e.g. `aces=1.5`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Effect {
+    Inversion,
+    Grayscale,
+    Sharpen,
+    Blur,
+    EdgeDetect,
+    Gamma(f32),
+    /// Maps HDR color scaled by `exposure` into `[0, 1]`.
+    Tonemap {
+        exposure: f32,
+        operator: Tonemapper,
+    },
+    Fxaa,
+}
+
+/// Curves that compress HDR color into displayable range, in the order their shader numbers
+/// them.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Tonemapper {
+    /// `1 - e^-x`, which approaches white smoothly and never quite reaches it.
+    #[default]
+    Exponential,
+    /// `x / (1 + x)`, which desaturates bright colors the least but flattens contrast.
+    Reinhard,
+    /// Narkowicz's fit of the ACES filmic curve, with a toe that deepens shadows and a shoulder
+    /// that rolls highlights off to white.
+    Aces,
+    /// Hable's filmic curve from Uncharted 2, with a softer shoulder than ACES.
+    Uncharted2,
+}
+
+impl Tonemapper {
+    pub const ALL: [Self; 4] = [
+        Self::Exponential,
+        Self::Reinhard,
+        Self::Aces,
+        Self::Uncharted2,
+    ];
+
+    /// The next operator, wrapping around, for cycling through them at runtime.
+    pub fn next(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Exponential => "tonemap",
+            Self::Reinhard => "reinhard",
+            Self::Aces => "aces",
+            Self::Uncharted2 => "uncharted2",
+        }
+    }
+}
+
+const SHARPEN_KERNEL: [f32; 9] = [-1.0, -1.0, -1.0, -1.0, 9.0, -1.0, -1.0, -1.0, -1.0];
+
+const BLUR_KERNEL: [f32; 9] = [
+    1.0 / 16.0,
+    2.0 / 16.0,
+    1.0 / 16.0,
+    2.0 / 16.0,
+    4.0 / 16.0,
+    2.0 / 16.0,
+    1.0 / 16.0,
+    2.0 / 16.0,
+    1.0 / 16.0,
+];
+
+const EDGE_DETECT_KERNEL: [f32; 9] = [1.0, 1.0, 1.0, 1.0, -8.0, 1.0, 1.0, 1.0, 1.0];
+
+impl Effect {
+    /// File name of the fragment shader under `shaders/postprocess`.
+    pub fn fragment_shader(&self) -> &'static str {
+        match self {
+            Self::Inversion => "inversion_fragment_shader.glsl",
+            Self::Grayscale => "grayscale_fragment_shader.glsl",
+            Self::Sharpen | Self::Blur | Self::EdgeDetect => "kernel_fragment_shader.glsl",
+            Self::Gamma(_) => "gamma_fragment_shader.glsl",
+            Self::Tonemap { .. } => "tonemap_fragment_shader.glsl",
+            Self::Fxaa => "fxaa_fragment_shader.glsl",
+        }
+    }
+
+    /// Uploads the effect's parameters to its program, which needs to be in use.
+    pub fn set_uniforms(&self, program: &Program, texel_size: (f32, f32)) -> Result<()> {
+        program.set_uniform_1i("screenTexture", 0)?;
+
+        match self {
+            Self::Inversion | Self::Grayscale => (),
+            Self::Sharpen | Self::Blur | Self::EdgeDetect => {
+                let kernel = match self {
+                    Self::Sharpen => &SHARPEN_KERNEL,
+                    Self::Blur => &BLUR_KERNEL,
+                    _ => &EDGE_DETECT_KERNEL,
+                };
+                program.set_uniform_1fv("kernel", kernel)?;
+                program.set_uniform_2f("texelSize", texel_size.0, texel_size.1)?;
+            }
+            Self::Gamma(gamma) => program.set_uniform_1f("gamma", *gamma)?,
+            Self::Tonemap { exposure, operator } => {
+                program.set_uniform_1f("exposure", *exposure)?;
+                program.set_uniform_1i("tonemapper", *operator as i32)?;
+            }
+            Self::Fxaa => program.set_uniform_2f("texelSize", texel_size.0, texel_size.1)?,
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Effect {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, value) = match s.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (s, None),
+        };
+
+        let parse_value = |default: f32| -> Result<f32> {
+            value.map_or(Ok(default), |v| {
+                v.parse::<f32>()
+                    .map_err(|e| format_err!("invalid value for effect '{name}': {e}"))
+            })
+        };
+
+        let effect = match name {
+            "invert" | "inversion" => Self::Inversion,
+            "grayscale" => Self::Grayscale,
+            "sharpen" => Self::Sharpen,
+            "blur" => Self::Blur,
+            "edge" => Self::EdgeDetect,
+            "gamma" => Self::Gamma(parse_value(2.2)?),
+            "fxaa" => Self::Fxaa,
+            _ => match Tonemapper::ALL.into_iter().find(|op| op.name() == name) {
+                Some(operator) => Self::Tonemap {
+                    exposure: parse_value(1.0)?,
+                    operator,
+                },
+                None => return Err(format_err!("unknown post-processing effect '{name}'")),
+            },
+        };
+
+        if value.is_some() && !matches!(effect, Self::Gamma(_) | Self::Tonemap { .. }) {
+            return Err(format_err!("effect '{name}' doesn't take a value"));
+        }
+        Ok(effect)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_values() {
+        assert_eq!("gamma=2.2".parse::<Effect>().unwrap(), Effect::Gamma(2.2));
+        assert_eq!("gamma".parse::<Effect>().unwrap(), Effect::Gamma(2.2));
+        assert_eq!(
+            "aces=1.5".parse::<Effect>().unwrap(),
+            Effect::Tonemap {
+                exposure: 1.5,
+                operator: Tonemapper::Aces,
+            }
+        );
+    }
+
+    #[test]
+    fn tonemappers_default_to_unit_exposure() {
+        assert_eq!(
+            "reinhard".parse::<Effect>().unwrap(),
+            Effect::Tonemap {
+                exposure: 1.0,
+                operator: Tonemapper::Reinhard,
+            }
+        );
+        for operator in Tonemapper::ALL {
+            let effect = operator.name().parse::<Effect>().unwrap();
+            assert!(matches!(effect, Effect::Tonemap { operator: op, .. } if op == operator));
+        }
+    }
+
+    #[test]
+    fn rejects_values_for_effects_without_parameters() {
+        let err = "blur=3".parse::<Effect>().unwrap_err();
+        assert!(err.to_string().contains("doesn't take a value"), "{err}");
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        let err = "sepia".parse::<Effect>().unwrap_err();
+        assert!(err.to_string().contains("unknown"), "{err}");
+        assert!("gamma=bright".parse::<Effect>().is_err());
+        assert!("".parse::<Effect>().is_err());
+    }
+}
@@ -0,0 +1,206 @@
+use crate::glutils::{
+    framebuffer::{
+        attachment::{ColorFormat, DepthFormat, DepthStencilAttachment},
+        BufferMask, Framebuffer, FramebufferBuilder,
+    },
+    mesh::{shapes, MeshOptions},
+    model::{primitives::Primitive, usage::Usage, Model},
+    render_state::{RenderState, StateTracker},
+    shader::{program::Linker, Shader, ShaderType},
+};
+use anyhow::{format_err, Result};
+use glfw::WindowEvent;
+use std::path::PathBuf;
+
+pub mod bloom;
+use bloom::{Bloom, BloomSettings};
+
+pub mod effect;
+use effect::Effect;
+
+/// Renders the scene into an off-screen framebuffer and then runs it through a sequence of
+/// full-screen passes, ping-ponging between two intermediate framebuffers. The final pass draws
+/// straight to the window. Bloom, if enabled, is added to the scene before the first pass so that
+/// it's tonemapped along with everything else.
+pub struct PostProcessor {
+    scene: Framebuffer,
+    ping_pong: [Framebuffer; 2],
+    passes: Vec<Pass>,
+    bloom: Option<Bloom>,
+}
+
+struct Pass {
+    effect: Effect,
+    /// The shader the pass was built with, which the effect has to keep using.
+    fragment_shader: &'static str,
+    model: Model,
+}
+
+impl PostProcessor {
+    pub fn new(width: u32, height: u32, effects: &[Effect]) -> Result<Self> {
+        // Half floats so that HDR values survive until a tonemapping pass.
+        let scene = FramebufferBuilder::new(width, height)
+            .color_attachment(ColorFormat::Rgba16F)
+            .depth_stencil(DepthStencilAttachment::Renderbuffer(
+                DepthFormat::Depth24Stencil8,
+            ))
+            .build()?;
+
+        let ping_pong = [
+            FramebufferBuilder::new(width, height)
+                .color_attachment(ColorFormat::Rgba16F)
+                .build()?,
+            FramebufferBuilder::new(width, height)
+                .color_attachment(ColorFormat::Rgba16F)
+                .build()?,
+        ];
+
+        let shaders_dir = PathBuf::new().join("shaders").join("postprocess");
+        let quad = shapes::fullscreen_quad(MeshOptions {
+            normals: false,
+            tangents: false,
+            uvs: true,
+        });
+
+        let passes = effects
+            .iter()
+            .map(|effect| {
+                let vs = Shader::new(
+                    shaders_dir.join("fullscreen_vertex_shader.glsl"),
+                    ShaderType::Vertex,
+                )?;
+                let fs = Shader::new(
+                    shaders_dir.join(effect.fragment_shader()),
+                    ShaderType::Fragment,
+                )?;
+                let program = Linker::new().attach_shader(vs).attach_shader(fs).link()?;
+                let model = Model::from_mesh(&quad, program, Usage::Static)?;
+                Ok(Pass {
+                    effect: *effect,
+                    fragment_shader: effect.fragment_shader(),
+                    model,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            scene,
+            ping_pong,
+            passes,
+            bloom: None,
+        })
+    }
+
+    pub fn effects(&self) -> impl Iterator<Item = &Effect> {
+        self.passes.iter().map(|p| &p.effect)
+    }
+
+    /// For tweaking the effects' parameters at runtime. An effect can't be replaced by one that
+    /// needs a different shader, e.g. a tonemapper can become another tonemapper but not a blur.
+    pub fn effects_mut(&mut self) -> impl Iterator<Item = &mut Effect> {
+        self.passes.iter_mut().map(|p| &mut p.effect)
+    }
+
+    pub fn bloom(&self) -> Option<&Bloom> {
+        self.bloom.as_ref()
+    }
+
+    pub fn bloom_mut(&mut self) -> Option<&mut Bloom> {
+        self.bloom.as_mut()
+    }
+
+    /// Enables bloom with the given settings, or disables it with `None`. Its buffers are freed
+    /// when it's disabled and created again when it's enabled again.
+    pub fn set_bloom(&mut self, settings: Option<BloomSettings>) -> Result<()> {
+        match (settings, &mut self.bloom) {
+            (Some(settings), Some(bloom)) => *bloom.settings_mut() = settings,
+            (Some(settings), None) => {
+                self.bloom = Some(Bloom::new(
+                    self.scene.width(),
+                    self.scene.height(),
+                    settings,
+                )?)
+            }
+            (None, bloom) => {
+                if let Some(bloom) = bloom.take() {
+                    bloom.delete();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Redirects subsequent draws into the scene framebuffer.
+    pub fn begin(&self) {
+        self.scene.bind();
+    }
+
+    /// Runs every pass and presents the result to the window's framebuffer, which is `width` by
+    /// `height` pixels. With no effects configured the scene is blitted as-is. The passes are
+    /// drawn with the default render state, which is left applied.
+    pub fn apply(&mut self, state: &mut StateTracker, width: i32, height: i32) -> Result<()> {
+        state.apply(&RenderState::DEFAULT);
+
+        let mut source = *self.scene.color_texture(0).unwrap();
+        // With bloom the scene is composited into the second ping-pong buffer, so that the
+        // passes can start from the first.
+        if let Some(bloom) = &mut self.bloom {
+            let glow = bloom.render(&source)?;
+            if self.passes.is_empty() {
+                Framebuffer::bind_default(width, height);
+            } else {
+                self.ping_pong[1].bind();
+            }
+            bloom.composite(&source, &glow)?;
+            source = *self.ping_pong[1].color_texture(0).unwrap();
+        } else if self.passes.is_empty() {
+            self.scene.blit_to_default(width, height, BufferMask::COLOR);
+            return Ok(());
+        }
+
+        let texel_size = (
+            1.0 / self.scene.width() as f32,
+            1.0 / self.scene.height() as f32,
+        );
+        let num_passes = self.passes.len();
+
+        for (i, pass) in self.passes.iter_mut().enumerate() {
+            if pass.effect.fragment_shader() != pass.fragment_shader {
+                return Err(format_err!(
+                    "effect {:?} needs a different shader than the pass it was put in",
+                    pass.effect
+                ));
+            }
+
+            let target = &self.ping_pong[i % 2];
+            if i == num_passes - 1 {
+                Framebuffer::bind_default(width, height);
+            } else {
+                target.bind();
+            }
+
+            pass.model.use_program();
+            source.bind(0);
+            pass.effect
+                .set_uniforms(&pass.model.program(), texel_size)?;
+            pass.model.bind();
+            pass.model.try_draw_arrays(Primitive::Triangles)?;
+            pass.model.unbind();
+
+            source = *target.color_texture(0).unwrap();
+        }
+        Ok(())
+    }
+
+    /// Keeps every framebuffer the same size as the window.
+    pub fn handle_event(&mut self, event: &WindowEvent) -> Result<()> {
+        self.scene.handle_event(event)?;
+        for fb in self.ping_pong.iter_mut() {
+            fb.handle_event(event)?;
+        }
+        if let Some(bloom) = &mut self.bloom {
+            bloom.resize(self.scene.width(), self.scene.height())?;
+        }
+        Ok(())
+    }
+}
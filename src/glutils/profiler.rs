@@ -0,0 +1,414 @@
+use anyhow::{format_err, Result};
+use serde_json::json;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Path of the scope that covers the whole frame. Every other scope is nested inside it.
+const FRAME_SCOPE: &str = "frame";
+
+/// Measures how long the GPU spends on named, possibly nested, sections of each frame.
+///
+/// A frame is timed as a whole with a `GL_TIME_ELAPSED` query, while scopes within it are timed
+/// by a pair of `GL_TIMESTAMP` queries since elapsed-time queries can't be nested. Results are
+/// read back `frames_in_flight` frames later, by which time the GPU has normally caught up, so
+/// that reading them doesn't stall the pipeline.
+///
+/// ```ignore
+/// {
+///     let _scene = profiler.scope("scene");
+///     // draw calls...
+/// }
+/// profiler.end_frame();
+/// ```
+pub struct Profiler {
+    state: Option<RefCell<State>>,
+}
+
+/// Closes its scope when dropped.
+#[must_use = "the scope is closed as soon as the guard is dropped"]
+pub struct Scope<'a> {
+    state: Option<&'a RefCell<State>>,
+}
+
+struct State {
+    frames: Vec<FrameQueries>,
+    current: usize,
+    frame_open: bool,
+    open_scopes: Vec<usize>,
+    stats: Vec<ScopeStats>,
+    stats_index: HashMap<String, usize>,
+    trace_path: Option<PathBuf>,
+    trace_events: Vec<TraceEvent>,
+    trace_origin: Option<u64>,
+}
+
+/// Queries issued during a single frame. The query objects are reused once the frame's results
+/// have been read back.
+struct FrameQueries {
+    elapsed_query: gl::types::GLuint,
+    timestamp_queries: Vec<gl::types::GLuint>,
+    used_timestamps: usize,
+    start_timestamp: usize,
+    scopes: Vec<ScopeQueries>,
+    pending: bool,
+}
+
+struct ScopeQueries {
+    path: String,
+    name: String,
+    depth: usize,
+    begin: usize,
+    end: Option<usize>,
+}
+
+struct ScopeStats {
+    path: String,
+    name: String,
+    depth: usize,
+    calls: u64,
+    total_ns: u64,
+    min_ns: u64,
+    max_ns: u64,
+}
+
+struct TraceEvent {
+    name: String,
+    start_ns: u64,
+    duration_ns: u64,
+}
+
+impl Profiler {
+    /// Starts timing the first frame. `frames_in_flight` is clamped to at least 2; 3 leaves the
+    /// driver room to queue up an extra frame. With `trace_path` set, every timed scope is also
+    /// written there as Chrome trace events by [`Profiler::finish`].
+    pub fn new(frames_in_flight: usize, trace_path: Option<PathBuf>) -> Self {
+        let frames = (0..frames_in_flight.max(2))
+            .map(|_| FrameQueries::new())
+            .collect();
+        let mut state = State {
+            frames,
+            current: 0,
+            frame_open: false,
+            open_scopes: vec![],
+            stats: vec![],
+            stats_index: HashMap::new(),
+            trace_path,
+            trace_events: vec![],
+            trace_origin: None,
+        };
+        state.begin_frame();
+
+        Self {
+            state: Some(RefCell::new(state)),
+        }
+    }
+
+    /// A profiler that issues no queries, so that code can be instrumented unconditionally.
+    pub fn disabled() -> Self {
+        Self { state: None }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.state.is_some()
+    }
+
+    /// Times everything submitted until the returned guard is dropped. Scopes opened while
+    /// another is open are reported as its children.
+    pub fn scope(&self, name: &str) -> Scope<'_> {
+        if let Some(state) = self.state.as_ref() {
+            state.borrow_mut().begin_scope(name);
+        }
+        Scope {
+            state: self.state.as_ref(),
+        }
+    }
+
+    /// Closes the current frame, collects the results of the oldest frame in flight and starts
+    /// timing the next one. Call it once per frame, before swapping buffers.
+    pub fn end_frame(&self) {
+        if let Some(state) = self.state.as_ref() {
+            let mut state = state.borrow_mut();
+            state.end_frame();
+            state.begin_frame();
+        }
+    }
+
+    /// Waits for every outstanding query, logs a summary of all scopes and writes the trace
+    /// file if one was requested.
+    pub fn finish(self) -> Result<()> {
+        let Some(state) = self.state else {
+            return Ok(());
+        };
+        let mut state = state.into_inner();
+        state.end_frame();
+        state.collect_all();
+
+        log::info!("{}", state.report());
+        if let Some(path) = state.trace_path.as_ref() {
+            state.write_trace(path)?;
+            log::info!("saved GPU trace to '{}'", path.display());
+        }
+        state.delete_queries();
+        Ok(())
+    }
+}
+
+impl Drop for Scope<'_> {
+    fn drop(&mut self) {
+        if let Some(state) = self.state {
+            state.borrow_mut().end_scope();
+        }
+    }
+}
+
+impl State {
+    fn begin_frame(&mut self) {
+        if self.frames[self.current].pending {
+            // Written `frames_in_flight` frames ago, so the results should be ready by now.
+            self.collect(self.current);
+        }
+
+        let frame = &mut self.frames[self.current];
+        frame.used_timestamps = 0;
+        frame.scopes.clear();
+        frame.start_timestamp = frame.query_timestamp();
+        unsafe { gl::BeginQuery(gl::TIME_ELAPSED, frame.elapsed_query) };
+        frame.pending = true;
+        self.frame_open = true;
+    }
+
+    fn end_frame(&mut self) {
+        if !self.frame_open {
+            return;
+        }
+        if !self.open_scopes.is_empty() {
+            log::warn!(
+                "{} GPU profiler scope(s) still open at the end of the frame",
+                self.open_scopes.len()
+            );
+            while !self.open_scopes.is_empty() {
+                self.end_scope();
+            }
+        }
+        unsafe { gl::EndQuery(gl::TIME_ELAPSED) };
+        self.frame_open = false;
+        self.current = (self.current + 1) % self.frames.len();
+    }
+
+    fn begin_scope(&mut self, name: &str) {
+        let frame = &mut self.frames[self.current];
+        let (path, depth) = match self.open_scopes.last() {
+            Some(parent) => {
+                let parent = &frame.scopes[*parent];
+                (format!("{}/{name}", parent.path), parent.depth + 1)
+            }
+            None => (format!("{FRAME_SCOPE}/{name}"), 1),
+        };
+
+        let begin = frame.query_timestamp();
+        frame.scopes.push(ScopeQueries {
+            path,
+            name: name.to_string(),
+            depth,
+            begin,
+            end: None,
+        });
+        self.open_scopes.push(frame.scopes.len() - 1);
+    }
+
+    fn end_scope(&mut self) {
+        let Some(scope) = self.open_scopes.pop() else {
+            return;
+        };
+        let frame = &mut self.frames[self.current];
+        let end = frame.query_timestamp();
+        frame.scopes[scope].end = Some(end);
+    }
+
+    fn collect_all(&mut self) {
+        for i in 0..self.frames.len() {
+            // Oldest first, so that trace events stay in order.
+            let index = (self.current + i) % self.frames.len();
+            if self.frames[index].pending {
+                self.collect(index);
+            }
+        }
+    }
+
+    /// Reads back the results of a frame's queries, blocking if they aren't available yet.
+    fn collect(&mut self, index: usize) {
+        let frame = &self.frames[index];
+        let elapsed = query_result(frame.elapsed_query);
+        let start = query_result(frame.timestamp_queries[frame.start_timestamp]);
+        let scopes = frame
+            .scopes
+            .iter()
+            .filter_map(|scope| {
+                let begin = query_result(frame.timestamp_queries[scope.begin]);
+                let end = query_result(frame.timestamp_queries[scope.end?]);
+                Some((
+                    scope.path.clone(),
+                    scope.name.clone(),
+                    scope.depth,
+                    begin,
+                    end.saturating_sub(begin),
+                ))
+            })
+            .collect::<Vec<_>>();
+        self.frames[index].pending = false;
+
+        let origin = *self.trace_origin.get_or_insert(start);
+        self.record(FRAME_SCOPE, FRAME_SCOPE, 0, elapsed);
+        self.record_trace(FRAME_SCOPE, start, elapsed, origin);
+        for (path, name, depth, begin, duration) in scopes {
+            self.record(&path, &name, depth, duration);
+            self.record_trace(&name, begin, duration, origin);
+        }
+    }
+
+    fn record(&mut self, path: &str, name: &str, depth: usize, duration_ns: u64) {
+        let index = *self.stats_index.entry(path.to_string()).or_insert_with(|| {
+            self.stats.push(ScopeStats {
+                path: path.to_string(),
+                name: name.to_string(),
+                depth,
+                calls: 0,
+                total_ns: 0,
+                min_ns: u64::MAX,
+                max_ns: 0,
+            });
+            self.stats.len() - 1
+        });
+
+        let stats = &mut self.stats[index];
+        stats.calls += 1;
+        stats.total_ns += duration_ns;
+        stats.min_ns = stats.min_ns.min(duration_ns);
+        stats.max_ns = stats.max_ns.max(duration_ns);
+    }
+
+    fn record_trace(&mut self, name: &str, start_ns: u64, duration_ns: u64, origin: u64) {
+        if self.trace_path.is_some() {
+            self.trace_events.push(TraceEvent {
+                name: name.to_string(),
+                start_ns: start_ns.saturating_sub(origin),
+                duration_ns,
+            });
+        }
+    }
+
+    /// Scopes are listed depth-first, each under its parent with siblings sorted by name.
+    fn report(&self) -> String {
+        let mut order = (0..self.stats.len()).collect::<Vec<_>>();
+        order.sort_by(|a, b| {
+            let (a, b) = (&self.stats[*a], &self.stats[*b]);
+            a.path.split('/').cmp(b.path.split('/'))
+        });
+
+        let name_width = order
+            .iter()
+            .map(|i| self.stats[*i].depth * 2 + self.stats[*i].name.len())
+            .max()
+            .unwrap_or(0)
+            .max("scope".len());
+
+        let ms = |ns: u64| ns as f64 / 1_000_000.0;
+        let mut report = format!(
+            "GPU timings (ms)\n{:name_width$}  {:>8}  {:>8}  {:>8}  {:>8}",
+            "scope", "calls", "avg", "min", "max"
+        );
+        for stats in order.iter().map(|i| &self.stats[*i]) {
+            let name = format!("{}{}", "  ".repeat(stats.depth), stats.name);
+            report.push_str(&format!(
+                "\n{name:name_width$}  {:>8}  {:>8.3}  {:>8.3}  {:>8.3}",
+                stats.calls,
+                ms(stats.total_ns) / stats.calls as f64,
+                ms(stats.min_ns),
+                ms(stats.max_ns),
+            ));
+        }
+        report
+    }
+
+    /// Writes the events in the Trace Event Format understood by `chrome://tracing` and
+    /// Perfetto. Timestamps are in microseconds relative to the first frame.
+    fn write_trace(&self, path: &Path) -> Result<()> {
+        let events = self
+            .trace_events
+            .iter()
+            .map(|event| {
+                json!({
+                    "name": event.name,
+                    "cat": "gpu",
+                    "ph": "X",
+                    "ts": event.start_ns as f64 / 1000.0,
+                    "dur": event.duration_ns as f64 / 1000.0,
+                    "pid": 0,
+                    "tid": 0,
+                })
+            })
+            .collect::<Vec<_>>();
+        let trace = json!({
+            "traceEvents": events,
+            "displayTimeUnit": "ms",
+        });
+
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)
+                .map_err(|e| format_err!("failed to create '{}': {e}", parent.display()))?;
+        }
+        fs::write(path, trace.to_string())
+            .map_err(|e| format_err!("failed to write '{}': {e}", path.display()))
+    }
+
+    fn delete_queries(&mut self) {
+        for frame in &self.frames {
+            unsafe {
+                gl::DeleteQueries(1, &frame.elapsed_query);
+                gl::DeleteQueries(
+                    gl::types::GLsizei::try_from(frame.timestamp_queries.len()).unwrap(),
+                    frame.timestamp_queries.as_ptr(),
+                );
+            }
+        }
+    }
+}
+
+impl FrameQueries {
+    fn new() -> Self {
+        let mut elapsed_query = 0;
+        unsafe { gl::GenQueries(1, &mut elapsed_query) };
+        Self {
+            elapsed_query,
+            timestamp_queries: vec![],
+            used_timestamps: 0,
+            start_timestamp: 0,
+            scopes: vec![],
+            pending: false,
+        }
+    }
+
+    /// Records the GPU time once all previously submitted commands have completed and returns
+    /// the index of the query holding it.
+    fn query_timestamp(&mut self) -> usize {
+        if self.used_timestamps == self.timestamp_queries.len() {
+            let mut query = 0;
+            unsafe { gl::GenQueries(1, &mut query) };
+            self.timestamp_queries.push(query);
+        }
+        let index = self.used_timestamps;
+        unsafe { gl::QueryCounter(self.timestamp_queries[index], gl::TIMESTAMP) };
+        self.used_timestamps += 1;
+        index
+    }
+}
+
+fn query_result(query: gl::types::GLuint) -> u64 {
+    let mut result = 0;
+    unsafe { gl::GetQueryObjectui64v(query, gl::QUERY_RESULT, &mut result) };
+    result
+}
@@ -0,0 +1,85 @@
+/// How the fragment's color is combined with the color already in the framebuffer:
+/// `equation(src * src_factor, dst * dst_factor)`, with separate factors for alpha.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Blend {
+    pub equation: BlendEquation,
+    pub src_color: BlendFactor,
+    pub dst_color: BlendFactor,
+    pub src_alpha: BlendFactor,
+    pub dst_alpha: BlendFactor,
+}
+
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BlendEquation {
+    #[default]
+    Add,
+    Subtract,
+    ReverseSubtract,
+    Min,
+    Max,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BlendFactor {
+    Zero,
+    One,
+    SrcColor,
+    OneMinusSrcColor,
+    DstColor,
+    OneMinusDstColor,
+    SrcAlpha,
+    OneMinusSrcAlpha,
+    DstAlpha,
+    OneMinusDstAlpha,
+    SrcAlphaSaturate,
+}
+
+impl Blend {
+    /// Conventional transparency with straight (non-premultiplied) alpha.
+    pub const ALPHA: Self = Self::new(BlendFactor::SrcAlpha, BlendFactor::OneMinusSrcAlpha);
+    pub const PREMULTIPLIED_ALPHA: Self =
+        Self::new(BlendFactor::One, BlendFactor::OneMinusSrcAlpha);
+    /// Accumulates light, e.g. for particles or multi-pass lighting.
+    pub const ADDITIVE: Self = Self::new(BlendFactor::One, BlendFactor::One);
+
+    /// Adds the weighted source and destination, using the same factors for color and alpha.
+    pub const fn new(src: BlendFactor, dst: BlendFactor) -> Self {
+        Self {
+            equation: BlendEquation::Add,
+            src_color: src,
+            dst_color: dst,
+            src_alpha: src,
+            dst_alpha: dst,
+        }
+    }
+}
+
+impl From<BlendEquation> for gl::types::GLenum {
+    fn from(value: BlendEquation) -> Self {
+        match value {
+            BlendEquation::Add => gl::FUNC_ADD,
+            BlendEquation::Subtract => gl::FUNC_SUBTRACT,
+            BlendEquation::ReverseSubtract => gl::FUNC_REVERSE_SUBTRACT,
+            BlendEquation::Min => gl::MIN,
+            BlendEquation::Max => gl::MAX,
+        }
+    }
+}
+
+impl From<BlendFactor> for gl::types::GLenum {
+    fn from(value: BlendFactor) -> Self {
+        match value {
+            BlendFactor::Zero => gl::ZERO,
+            BlendFactor::One => gl::ONE,
+            BlendFactor::SrcColor => gl::SRC_COLOR,
+            BlendFactor::OneMinusSrcColor => gl::ONE_MINUS_SRC_COLOR,
+            BlendFactor::DstColor => gl::DST_COLOR,
+            BlendFactor::OneMinusDstColor => gl::ONE_MINUS_DST_COLOR,
+            BlendFactor::SrcAlpha => gl::SRC_ALPHA,
+            BlendFactor::OneMinusSrcAlpha => gl::ONE_MINUS_SRC_ALPHA,
+            BlendFactor::DstAlpha => gl::DST_ALPHA,
+            BlendFactor::OneMinusDstAlpha => gl::ONE_MINUS_DST_ALPHA,
+            BlendFactor::SrcAlphaSaturate => gl::SRC_ALPHA_SATURATE,
+        }
+    }
+}
@@ -0,0 +1,28 @@
+/// Test used by the depth and stencil stages to decide whether a fragment passes.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Never,
+    #[default]
+    Less,
+    Equal,
+    LessEqual,
+    Greater,
+    NotEqual,
+    GreaterEqual,
+    Always,
+}
+
+impl From<Comparison> for gl::types::GLenum {
+    fn from(value: Comparison) -> Self {
+        match value {
+            Comparison::Never => gl::NEVER,
+            Comparison::Less => gl::LESS,
+            Comparison::Equal => gl::EQUAL,
+            Comparison::LessEqual => gl::LEQUAL,
+            Comparison::Greater => gl::GREATER,
+            Comparison::NotEqual => gl::NOTEQUAL,
+            Comparison::GreaterEqual => gl::GEQUAL,
+            Comparison::Always => gl::ALWAYS,
+        }
+    }
+}
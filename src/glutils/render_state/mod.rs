@@ -0,0 +1,341 @@
+use super::{as_gl_bool, debug, framebuffer::BufferMask};
+use std::ops::BitOr;
+
+pub mod blend;
+use blend::{Blend, BlendFactor};
+
+pub mod comparison;
+use comparison::Comparison;
+
+pub mod raster;
+use raster::{CullMode, FrontFace, PolygonMode, Scissor};
+
+pub mod stencil;
+use stencil::{Stencil, StencilOp};
+
+/// Fixed-function pipeline configuration for a draw call. `None` disables the corresponding
+/// test or stage. The default matches the state of a freshly created context.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RenderState {
+    pub depth_test: Option<Comparison>,
+    pub depth_write: bool,
+    pub blend: Option<Blend>,
+    pub cull_mode: Option<CullMode>,
+    pub front_face: FrontFace,
+    pub stencil: Option<Stencil>,
+    pub polygon_mode: PolygonMode,
+    pub scissor: Option<Scissor>,
+}
+
+impl RenderState {
+    pub const DEFAULT: Self = Self {
+        depth_test: None,
+        depth_write: true,
+        blend: None,
+        cull_mode: None,
+        front_face: FrontFace::CounterClockwise,
+        stencil: None,
+        polygon_mode: PolygonMode::Fill,
+        scissor: None,
+    };
+
+    /// Depth tested and written, with back faces culled.
+    pub const OPAQUE: Self = Self {
+        depth_test: Some(Comparison::Less),
+        cull_mode: Some(CullMode::Back),
+        ..Self::DEFAULT
+    };
+
+    /// Alpha blended on top of opaque geometry without occluding what's drawn after it.
+    pub const TRANSPARENT: Self = Self {
+        depth_test: Some(Comparison::Less),
+        depth_write: false,
+        blend: Some(Blend::ALPHA),
+        ..Self::DEFAULT
+    };
+
+    /// Alpha blended over everything without touching depth, for screen space overlays such as
+    /// text.
+    pub const OVERLAY: Self = Self {
+        depth_write: false,
+        blend: Some(Blend::ALPHA),
+        ..Self::DEFAULT
+    };
+}
+
+impl Default for RenderState {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Values to clear the bound framebuffer's buffers to. Only the buffers that are given a value
+/// are cleared.
+#[derive(Default, Copy, Clone, Debug, PartialEq)]
+pub struct Clear {
+    pub color: Option<[f32; 4]>,
+    pub depth: Option<f32>,
+    pub stencil: Option<i32>,
+}
+
+impl Clear {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Clears color to `color`, depth to the far plane and stencil to zero.
+    pub fn all(color: [f32; 4]) -> Self {
+        Self::new().color(color).depth(1.0).stencil(0)
+    }
+
+    pub fn color(mut self, color: [f32; 4]) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn depth(mut self, depth: f32) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    pub fn stencil(mut self, stencil: i32) -> Self {
+        self.stencil = Some(stencil);
+        self
+    }
+
+    pub fn mask(&self) -> BufferMask {
+        [
+            self.color.map(|_| BufferMask::COLOR),
+            self.depth.map(|_| BufferMask::DEPTH),
+            self.stencil.map(|_| BufferMask::STENCIL),
+        ]
+        .into_iter()
+        .flatten()
+        .fold(BufferMask::NONE, BitOr::bitor)
+    }
+}
+
+/// Mirrors the GL state set through it so that applying a [`RenderState`] only issues calls for
+/// what actually changed. It assumes a fresh context's defaults when created; code that changes
+/// state behind its back needs to call [`StateTracker::invalidate`] afterwards.
+pub struct StateTracker {
+    gl: GlState,
+}
+
+/// The GL state as last set, with `None` for values that aren't known and so have to be set the
+/// next time they're needed. Parameters of disabled stages are kept, since GL keeps them too.
+#[derive(Default)]
+struct GlState {
+    depth_test: Option<bool>,
+    depth_func: Option<Comparison>,
+    depth_write: Option<bool>,
+    blend: Option<bool>,
+    blend_func: Option<Blend>,
+    cull_face: Option<bool>,
+    cull_mode: Option<CullMode>,
+    front_face: Option<FrontFace>,
+    stencil_test: Option<bool>,
+    stencil_func: Option<(Comparison, i32, u32)>,
+    stencil_ops: Option<(StencilOp, StencilOp, StencilOp)>,
+    stencil_write_mask: Option<u32>,
+    polygon_mode: Option<PolygonMode>,
+    scissor_test: Option<bool>,
+    scissor_box: Option<Scissor>,
+    clear_color: Option<[f32; 4]>,
+    clear_depth: Option<f32>,
+    clear_stencil: Option<i32>,
+}
+
+impl GlState {
+    fn fresh_context() -> Self {
+        Self {
+            depth_test: Some(false),
+            depth_func: Some(Comparison::Less),
+            depth_write: Some(true),
+            blend: Some(false),
+            blend_func: Some(Blend::new(BlendFactor::One, BlendFactor::Zero)),
+            cull_face: Some(false),
+            cull_mode: Some(CullMode::Back),
+            front_face: Some(FrontFace::CounterClockwise),
+            stencil_test: Some(false),
+            stencil_func: Some((Comparison::Always, 0, !0)),
+            stencil_ops: Some((StencilOp::Keep, StencilOp::Keep, StencilOp::Keep)),
+            stencil_write_mask: Some(!0),
+            polygon_mode: Some(PolygonMode::Fill),
+            scissor_test: Some(false),
+            // The initial scissor box is the window's size, which isn't known here.
+            scissor_box: None,
+            clear_color: Some([0.0; 4]),
+            clear_depth: Some(1.0),
+            clear_stencil: Some(0),
+        }
+    }
+}
+
+impl StateTracker {
+    pub fn new() -> Self {
+        Self {
+            gl: GlState::fresh_context(),
+        }
+    }
+
+    /// Forgets the cached state so that every value is set again the next time a
+    /// [`StateTracker::apply`] or [`StateTracker::clear`] needs it.
+    pub fn invalidate(&mut self) {
+        self.gl = GlState::default();
+    }
+
+    pub fn apply(&mut self, state: &RenderState) {
+        unsafe { self.apply_impl(state) }
+        debug::check_errors("StateTracker::apply");
+    }
+
+    /// Clears the buffers of the bound framebuffer that `clear` has values for, in a single call.
+    /// Depth and stencil writes are enabled as needed. Like any clear, it's limited to the
+    /// scissor box when the scissor test is enabled.
+    pub fn clear(&mut self, clear: &Clear) {
+        unsafe { self.clear_impl(clear) }
+        debug::check_errors("StateTracker::clear");
+    }
+
+    unsafe fn apply_impl(&mut self, state: &RenderState) {
+        let gl_state = &mut self.gl;
+
+        set_enabled(
+            gl::DEPTH_TEST,
+            &mut gl_state.depth_test,
+            state.depth_test.is_some(),
+        );
+        if let Some(func) = state.depth_test {
+            if update(&mut gl_state.depth_func, func) {
+                gl::DepthFunc(func.into());
+            }
+        }
+        if update(&mut gl_state.depth_write, state.depth_write) {
+            gl::DepthMask(as_gl_bool(state.depth_write));
+        }
+
+        set_enabled(gl::BLEND, &mut gl_state.blend, state.blend.is_some());
+        if let Some(blend) = state.blend {
+            let previous = gl_state.blend_func;
+            if update(&mut gl_state.blend_func, blend) {
+                if previous.is_none_or(|previous| previous.equation != blend.equation) {
+                    gl::BlendEquation(blend.equation.into());
+                }
+                gl::BlendFuncSeparate(
+                    blend.src_color.into(),
+                    blend.dst_color.into(),
+                    blend.src_alpha.into(),
+                    blend.dst_alpha.into(),
+                );
+            }
+        }
+
+        set_enabled(
+            gl::CULL_FACE,
+            &mut gl_state.cull_face,
+            state.cull_mode.is_some(),
+        );
+        if let Some(mode) = state.cull_mode {
+            if update(&mut gl_state.cull_mode, mode) {
+                gl::CullFace(mode.into());
+            }
+        }
+        if update(&mut gl_state.front_face, state.front_face) {
+            gl::FrontFace(state.front_face.into());
+        }
+
+        set_enabled(
+            gl::STENCIL_TEST,
+            &mut gl_state.stencil_test,
+            state.stencil.is_some(),
+        );
+        if let Some(stencil) = state.stencil {
+            let func = (stencil.func, stencil.reference, stencil.read_mask);
+            if update(&mut gl_state.stencil_func, func) {
+                gl::StencilFunc(func.0.into(), func.1, func.2);
+            }
+            let ops = (stencil.stencil_fail, stencil.depth_fail, stencil.pass);
+            if update(&mut gl_state.stencil_ops, ops) {
+                gl::StencilOp(ops.0.into(), ops.1.into(), ops.2.into());
+            }
+            if update(&mut gl_state.stencil_write_mask, stencil.write_mask) {
+                gl::StencilMask(stencil.write_mask);
+            }
+        }
+
+        if update(&mut gl_state.polygon_mode, state.polygon_mode) {
+            // Core profiles only accept both faces.
+            gl::PolygonMode(gl::FRONT_AND_BACK, state.polygon_mode.into());
+        }
+
+        set_enabled(
+            gl::SCISSOR_TEST,
+            &mut gl_state.scissor_test,
+            state.scissor.is_some(),
+        );
+        if let Some(scissor) = state.scissor {
+            if update(&mut gl_state.scissor_box, scissor) {
+                gl::Scissor(scissor.x, scissor.y, scissor.width, scissor.height);
+            }
+        }
+    }
+
+    unsafe fn clear_impl(&mut self, clear: &Clear) {
+        let gl_state = &mut self.gl;
+
+        if let Some(color) = clear.color {
+            if update(&mut gl_state.clear_color, color) {
+                gl::ClearColor(color[0], color[1], color[2], color[3]);
+            }
+        }
+        if let Some(depth) = clear.depth {
+            if update(&mut gl_state.depth_write, true) {
+                gl::DepthMask(gl::TRUE);
+            }
+            if update(&mut gl_state.clear_depth, depth) {
+                gl::ClearDepth(depth.into());
+            }
+        }
+        if let Some(stencil) = clear.stencil {
+            if update(&mut gl_state.stencil_write_mask, !0) {
+                gl::StencilMask(!0);
+            }
+            if update(&mut gl_state.clear_stencil, stencil) {
+                gl::ClearStencil(stencil);
+            }
+        }
+
+        let mask = clear.mask();
+        if mask != BufferMask::NONE {
+            gl::Clear(mask.into());
+        }
+    }
+}
+
+impl Default for StateTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Stores `value` and returns whether GL needs to be told about it, either because it changed or
+/// because the cached value isn't known.
+fn update<T: PartialEq>(cached: &mut Option<T>, value: T) -> bool {
+    if cached.as_ref() == Some(&value) {
+        false
+    } else {
+        *cached = Some(value);
+        true
+    }
+}
+
+unsafe fn set_enabled(capability: gl::types::GLenum, cached: &mut Option<bool>, enabled: bool) {
+    if update(cached, enabled) {
+        if enabled {
+            gl::Enable(capability);
+        } else {
+            gl::Disable(capability);
+        }
+    }
+}
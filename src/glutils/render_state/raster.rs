@@ -0,0 +1,63 @@
+/// Which faces are discarded before rasterization.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CullMode {
+    Front,
+    Back,
+    FrontAndBack,
+}
+
+/// Winding order, as seen on screen, of the faces considered to be facing the camera.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FrontFace {
+    #[default]
+    CounterClockwise,
+    Clockwise,
+}
+
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PolygonMode {
+    #[default]
+    Fill,
+    /// Wireframe.
+    Line,
+    Point,
+}
+
+/// Window-space rectangle outside of which fragments are discarded, with the origin in the
+/// bottom-left corner.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Scissor {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl From<CullMode> for gl::types::GLenum {
+    fn from(value: CullMode) -> Self {
+        match value {
+            CullMode::Front => gl::FRONT,
+            CullMode::Back => gl::BACK,
+            CullMode::FrontAndBack => gl::FRONT_AND_BACK,
+        }
+    }
+}
+
+impl From<FrontFace> for gl::types::GLenum {
+    fn from(value: FrontFace) -> Self {
+        match value {
+            FrontFace::CounterClockwise => gl::CCW,
+            FrontFace::Clockwise => gl::CW,
+        }
+    }
+}
+
+impl From<PolygonMode> for gl::types::GLenum {
+    fn from(value: PolygonMode) -> Self {
+        match value {
+            PolygonMode::Fill => gl::FILL,
+            PolygonMode::Line => gl::LINE,
+            PolygonMode::Point => gl::POINT,
+        }
+    }
+}
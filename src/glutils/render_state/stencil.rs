@@ -0,0 +1,75 @@
+use super::comparison::Comparison;
+
+/// Stencil test and update applied to both front and back faces. A fragment passes when
+/// `func(reference & read_mask, stored & read_mask)` holds; the stored value is then updated by
+/// one of the three operations, limited to the bits in `write_mask`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Stencil {
+    pub func: Comparison,
+    pub reference: i32,
+    pub read_mask: u32,
+    pub write_mask: u32,
+    /// Applied when the stencil test fails.
+    pub stencil_fail: StencilOp,
+    /// Applied when the stencil test passes but the depth test fails.
+    pub depth_fail: StencilOp,
+    /// Applied when both tests pass.
+    pub pass: StencilOp,
+}
+
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StencilOp {
+    #[default]
+    Keep,
+    Zero,
+    Replace,
+    Increment,
+    IncrementWrap,
+    Decrement,
+    DecrementWrap,
+    Invert,
+}
+
+impl Stencil {
+    /// Writes `reference` wherever something is drawn, e.g. to mark an object for outlining.
+    pub fn write(reference: i32) -> Self {
+        Self {
+            func: Comparison::Always,
+            reference,
+            read_mask: !0,
+            write_mask: !0,
+            stencil_fail: StencilOp::Keep,
+            depth_fail: StencilOp::Keep,
+            pass: StencilOp::Replace,
+        }
+    }
+
+    /// Only draws where the stored value compares to `reference` with `func`, leaving the
+    /// stencil buffer untouched.
+    pub fn test(func: Comparison, reference: i32) -> Self {
+        Self {
+            func,
+            reference,
+            read_mask: !0,
+            write_mask: 0,
+            stencil_fail: StencilOp::Keep,
+            depth_fail: StencilOp::Keep,
+            pass: StencilOp::Keep,
+        }
+    }
+}
+
+impl From<StencilOp> for gl::types::GLenum {
+    fn from(value: StencilOp) -> Self {
+        match value {
+            StencilOp::Keep => gl::KEEP,
+            StencilOp::Zero => gl::ZERO,
+            StencilOp::Replace => gl::REPLACE,
+            StencilOp::Increment => gl::INCR,
+            StencilOp::IncrementWrap => gl::INCR_WRAP,
+            StencilOp::Decrement => gl::DECR,
+            StencilOp::DecrementWrap => gl::DECR_WRAP,
+            StencilOp::Invert => gl::INVERT,
+        }
+    }
+}
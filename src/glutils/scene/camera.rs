@@ -0,0 +1,28 @@
+use glam::{camera::rh::proj::opengl, Mat4};
+
+/// Perspective projection of a camera node. The camera sits at its node's origin and looks
+/// down the node's -Z axis with +Y up, as glTF cameras do.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Camera {
+    /// Vertical field of view in radians.
+    pub fov_y: f32,
+    pub near: f32,
+    pub far: f32,
+}
+
+impl Camera {
+    pub fn new(fov_y: f32, near: f32, far: f32) -> Self {
+        Self { fov_y, near, far }
+    }
+
+    /// Projection for a viewport of the given width to height ratio.
+    pub fn projection(&self, aspect_ratio: f32) -> Mat4 {
+        opengl::perspective(self.fov_y, aspect_ratio, self.near, self.far)
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new(45f32.to_radians(), 0.1, 100.0)
+    }
+}
@@ -0,0 +1,50 @@
+use crate::glutils::lighting::{DirectionalLight, PointLight, SpotLight};
+use glam::Mat4;
+
+/// A light attached to a node. Positions and directions are in the node's local space, so a
+/// light moves and turns with its node.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Light {
+    Directional(DirectionalLight),
+    Point(PointLight),
+    Spot(SpotLight),
+}
+
+impl Light {
+    /// The light moved into world space by its node's world transform.
+    pub fn to_world(self, world: &Mat4) -> Self {
+        match self {
+            Self::Directional(light) => Self::Directional(DirectionalLight {
+                direction: world.transform_vector3(light.direction).normalize(),
+                ..light
+            }),
+            Self::Point(light) => Self::Point(PointLight {
+                position: world.transform_point3(light.position),
+                ..light
+            }),
+            Self::Spot(light) => Self::Spot(SpotLight {
+                position: world.transform_point3(light.position),
+                direction: world.transform_vector3(light.direction).normalize(),
+                ..light
+            }),
+        }
+    }
+}
+
+impl From<DirectionalLight> for Light {
+    fn from(light: DirectionalLight) -> Self {
+        Self::Directional(light)
+    }
+}
+
+impl From<PointLight> for Light {
+    fn from(light: PointLight) -> Self {
+        Self::Point(light)
+    }
+}
+
+impl From<SpotLight> for Light {
+    fn from(light: SpotLight) -> Self {
+        Self::Spot(light)
+    }
+}
@@ -0,0 +1,477 @@
+use super::{
+    gltf::{material::GltfMaterial, GltfScene},
+    lighting::{Lighting, Shading},
+    material::Material,
+    model::{primitives::Primitive, usage::Usage, Model},
+    shader::program::Program,
+    uniform_buffer::Matrices,
+};
+use anyhow::{format_err, Result};
+use glam::{Mat4, Vec3};
+use std::collections::HashMap;
+
+pub mod camera;
+use camera::Camera;
+
+pub mod light;
+use light::Light;
+
+pub mod renderer;
+
+pub mod transform;
+use transform::Transform;
+
+/// Handle to a node of a [`Scene`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+/// Handle to a model owned by a [`Scene`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ModelId(usize);
+
+/// Handle to a material owned by a [`Scene`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MaterialId(usize);
+
+/// A model drawn with a material at the world transform of the node it's attached to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Drawable {
+    pub model: ModelId,
+    pub material: MaterialId,
+    pub primitive: Primitive,
+    /// Whether the model is drawn into shadow maps. Worth turning off for things like the
+    /// visible bulb of a light, which would otherwise shadow everything around it.
+    pub casts_shadow: bool,
+}
+
+impl Drawable {
+    pub fn new(model: ModelId, material: MaterialId) -> Self {
+        Self {
+            model,
+            material,
+            primitive: Primitive::Triangles,
+            casts_shadow: true,
+        }
+    }
+}
+
+pub struct Node {
+    pub name: String,
+    pub drawables: Vec<Drawable>,
+    pub light: Option<Light>,
+    /// Whether the node's light casts shadows. Only the first light of each kind that does gets
+    /// a shadow map.
+    pub shadows: bool,
+    pub camera: Option<Camera>,
+    transform: Transform,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    world: Mat4,
+    /// Whether `world` is stale because the transform of this node changed. Descendants are
+    /// recomputed along with it without being flagged themselves.
+    dirty: bool,
+}
+
+impl Node {
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+    /// World transform as of the last [`Scene::update_world_transforms`].
+    pub fn world_transform(&self) -> Mat4 {
+        self.world
+    }
+
+    pub fn world_position(&self) -> Vec3 {
+        self.world.w_axis.truncate()
+    }
+}
+
+/// A hierarchy of nodes, each with a transform relative to its parent, along with the models and
+/// materials drawn at them. World transforms are cached and only recomputed for the subtrees
+/// whose transforms changed since the last update. Nodes live as long as the scene.
+#[derive(Default)]
+pub struct Scene {
+    nodes: Vec<Node>,
+    roots: Vec<NodeId>,
+    models: Vec<Model>,
+    materials: Vec<Material>,
+    dirty: bool,
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a node as the last child of `parent`, or as a root when there's none.
+    pub fn add_node(&mut self, name: &str, parent: Option<NodeId>, transform: Transform) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(Node {
+            name: name.to_string(),
+            drawables: vec![],
+            light: None,
+            shadows: false,
+            camera: None,
+            transform,
+            parent,
+            children: vec![],
+            world: Mat4::IDENTITY,
+            dirty: true,
+        });
+        match parent {
+            Some(parent) => self.nodes[parent.0].children.push(id),
+            None => self.roots.push(id),
+        }
+        self.dirty = true;
+        id
+    }
+
+    pub fn add_model(&mut self, model: Model) -> ModelId {
+        self.models.push(model);
+        ModelId(self.models.len() - 1)
+    }
+
+    pub fn add_material(&mut self, material: Material) -> MaterialId {
+        self.materials.push(material);
+        MaterialId(self.materials.len() - 1)
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0]
+    }
+
+    /// Gives access to a node's attachments. Its transform and parent are changed through the
+    /// scene so that world transforms can be kept up to date.
+    pub fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        &mut self.nodes[id.0]
+    }
+
+    pub fn model_mut(&mut self, id: ModelId) -> &mut Model {
+        &mut self.models[id.0]
+    }
+
+    pub fn material(&self, id: MaterialId) -> &Material {
+        &self.materials[id.0]
+    }
+
+    pub fn material_mut(&mut self, id: MaterialId) -> &mut Material {
+        &mut self.materials[id.0]
+    }
+
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    /// The first node named `name`, in depth-first order.
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.depth_first()
+            .into_iter()
+            .find(|id| self.node(*id).name == name)
+    }
+
+    /// Nodes in depth-first order, parents before their children and siblings in the order they
+    /// were added.
+    pub fn depth_first(&self) -> Vec<NodeId> {
+        let mut order = Vec::with_capacity(self.nodes.len());
+        let mut stack = self.roots.iter().rev().copied().collect::<Vec<_>>();
+        while let Some(id) = stack.pop() {
+            order.push(id);
+            stack.extend(self.node(id).children.iter().rev());
+        }
+        order
+    }
+
+    pub fn set_transform(&mut self, id: NodeId, transform: Transform) {
+        let node = &mut self.nodes[id.0];
+        if node.transform != transform {
+            node.transform = transform;
+            node.dirty = true;
+            self.dirty = true;
+        }
+    }
+
+    /// Moves a node, along with its descendants, under `parent` or to the roots. Its local
+    /// transform is kept, so it moves in world space unless the old and new parents coincide.
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) -> Result<()> {
+        let mut ancestor = parent;
+        while let Some(a) = ancestor {
+            if a == id {
+                return Err(format_err!(
+                    "node '{}' can't be moved under its own descendant",
+                    self.node(id).name
+                ));
+            }
+            ancestor = self.node(a).parent;
+        }
+
+        match self.nodes[id.0].parent {
+            Some(old) => self.nodes[old.0].children.retain(|c| *c != id),
+            None => self.roots.retain(|r| *r != id),
+        }
+        match parent {
+            Some(new) => self.nodes[new.0].children.push(id),
+            None => self.roots.push(id),
+        }
+
+        let node = &mut self.nodes[id.0];
+        node.parent = parent;
+        node.dirty = true;
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Recomputes the world transforms of nodes whose own transform or an ancestor's changed.
+    pub fn update_world_transforms(&mut self) {
+        if !self.dirty {
+            return;
+        }
+
+        let mut stack = self
+            .roots
+            .iter()
+            .map(|root| (*root, Mat4::IDENTITY, false))
+            .collect::<Vec<_>>();
+
+        while let Some((id, parent_world, parent_changed)) = stack.pop() {
+            let node = &mut self.nodes[id.0];
+            let changed = parent_changed || node.dirty;
+            if changed {
+                node.world = parent_world * node.transform.to_mat4();
+                node.dirty = false;
+            }
+            let world = node.world;
+            stack.extend(node.children.iter().map(|c| (*c, world, changed)));
+        }
+        self.dirty = false;
+    }
+
+    /// The scene's lights in world space, as of the last [`Scene::update_world_transforms`].
+    pub fn lighting(&self, shading: Shading) -> Lighting {
+        let mut lighting = Lighting {
+            shading,
+            ..Default::default()
+        };
+        for id in self.depth_first() {
+            let node = self.node(id);
+            let (lights, shadow) = match node.light.map(|light| light.to_world(&node.world)) {
+                Some(Light::Directional(light)) => {
+                    lighting.directional.push(light);
+                    (lighting.directional.len(), &mut lighting.directional_shadow)
+                }
+                Some(Light::Point(light)) => {
+                    lighting.point.push(light);
+                    (lighting.point.len(), &mut lighting.point_shadow)
+                }
+                Some(Light::Spot(light)) => {
+                    lighting.spot.push(light);
+                    (lighting.spot.len(), &mut lighting.spot_shadow)
+                }
+                None => continue,
+            };
+            if node.shadows && shadow.is_none() {
+                *shadow = Some(lights - 1);
+            }
+        }
+        lighting
+    }
+
+    /// Projection and view matrices of the camera attached to `id`, for a viewport of the given
+    /// width to height ratio.
+    pub fn camera_matrices(&self, id: NodeId, aspect_ratio: f32) -> Result<Matrices> {
+        let node = self.node(id);
+        let camera = node
+            .camera
+            .ok_or_else(|| format_err!("node '{}' has no camera", node.name))?;
+        Ok(Matrices {
+            projection: camera.projection(aspect_ratio),
+            view: node.world.inverse(),
+        })
+    }
+
+    /// Adds the node hierarchy of an imported glTF asset under a new node named `name`. Meshes are
+    /// uploaded for `program`, and `material` is called once per glTF material used, plus once
+    /// with `None` if some primitive has no material, to make what primitives are drawn with.
+    pub fn add_gltf<F>(
+        &mut self,
+        gltf: &GltfScene,
+        name: &str,
+        parent: Option<NodeId>,
+        program: Program,
+        usage: Usage,
+        mut material: F,
+    ) -> Result<NodeId>
+    where
+        F: FnMut(Option<&GltfMaterial>) -> Result<Material>,
+    {
+        let models = gltf
+            .build_models(program, usage)?
+            .into_iter()
+            .map(|models| {
+                models
+                    .into_iter()
+                    .map(|model| self.add_model(model))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let mut materials = HashMap::new();
+        let mut drawables = Vec::with_capacity(gltf.meshes.len());
+        for (mesh, mesh_models) in gltf.meshes.iter().zip(&models) {
+            let mut mesh_drawables = Vec::with_capacity(mesh.primitives.len());
+            for (primitive, model) in mesh.primitives.iter().zip(mesh_models) {
+                let material_id = match materials.get(&primitive.material) {
+                    Some(id) => *id,
+                    None => {
+                        let gltf_material = primitive
+                            .material
+                            .map(|i| {
+                                gltf.materials.get(i).ok_or_else(|| {
+                                    format_err!("primitive references missing material {i}")
+                                })
+                            })
+                            .transpose()?;
+                        let id = self.add_material(material(gltf_material)?);
+                        materials.insert(primitive.material, id);
+                        id
+                    }
+                };
+                mesh_drawables.push(Drawable {
+                    primitive: primitive.mode,
+                    ..Drawable::new(*model, material_id)
+                });
+            }
+            drawables.push(mesh_drawables);
+        }
+
+        let root = self.add_node(name, parent, Transform::IDENTITY);
+        let mut stack = gltf
+            .roots
+            .iter()
+            .rev()
+            .map(|index| (*index, root))
+            .collect::<Vec<_>>();
+
+        while let Some((index, parent)) = stack.pop() {
+            let gltf_node = gltf
+                .nodes
+                .get(index)
+                .ok_or_else(|| format_err!("glTF hierarchy references missing node {index}"))?;
+            let name = gltf_node
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("node {index}"));
+            let id = self.add_node(
+                &name,
+                Some(parent),
+                Transform::from_mat4(gltf_node.local_transform),
+            );
+            if let Some(mesh) = gltf_node.mesh {
+                self.nodes[id.0].drawables = drawables
+                    .get(mesh)
+                    .ok_or_else(|| format_err!("node '{name}' references missing mesh {mesh}"))?
+                    .clone();
+            }
+            stack.extend(gltf_node.children.iter().rev().map(|c| (*c, id)));
+        }
+        Ok(root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::vec3;
+
+    fn at(x: f32) -> Transform {
+        Transform::from_translation(vec3(x, 0.0, 0.0))
+    }
+
+    fn world_x(scene: &Scene, id: NodeId) -> f32 {
+        scene.node(id).world_position().x
+    }
+
+    /// A root with two children, the first of which has a child of its own.
+    fn hierarchy() -> (Scene, [NodeId; 4]) {
+        let mut scene = Scene::new();
+        let root = scene.add_node("root", None, at(1.0));
+        let a = scene.add_node("a", Some(root), at(10.0));
+        let b = scene.add_node("b", Some(root), at(100.0));
+        let leaf = scene.add_node("leaf", Some(a), at(1000.0));
+        scene.update_world_transforms();
+        (scene, [root, a, b, leaf])
+    }
+
+    #[test]
+    fn world_transforms_compose_parents() {
+        let (scene, [root, a, b, leaf]) = hierarchy();
+        assert_eq!(world_x(&scene, root), 1.0);
+        assert_eq!(world_x(&scene, a), 11.0);
+        assert_eq!(world_x(&scene, b), 101.0);
+        assert_eq!(world_x(&scene, leaf), 1011.0);
+        assert_eq!(scene.depth_first(), [root, a, leaf, b]);
+    }
+
+    #[test]
+    fn changes_propagate_to_descendants_only() {
+        let (mut scene, [root, a, b, leaf]) = hierarchy();
+        // Changed behind the scene's back, so it only shows up if `b` is recomputed.
+        scene.nodes[b.0].transform = at(200.0);
+
+        scene.set_transform(a, at(20.0));
+        scene.update_world_transforms();
+        assert_eq!(world_x(&scene, a), 21.0);
+        assert_eq!(world_x(&scene, leaf), 1021.0);
+        assert_eq!(world_x(&scene, b), 101.0);
+        assert_eq!(world_x(&scene, root), 1.0);
+
+        scene.set_transform(root, at(2.0));
+        scene.update_world_transforms();
+        assert_eq!(world_x(&scene, leaf), 1022.0);
+        assert_eq!(world_x(&scene, b), 202.0);
+    }
+
+    #[test]
+    fn unchanged_transforms_dont_dirty_the_scene() {
+        let (mut scene, [_, a, _, _]) = hierarchy();
+        scene.set_transform(a, at(10.0));
+        assert!(!scene.dirty && !scene.node(a).dirty);
+    }
+
+    #[test]
+    fn reparenting_moves_the_subtree() {
+        let (mut scene, [root, a, b, leaf]) = hierarchy();
+        scene.set_parent(a, Some(b)).unwrap();
+        assert_eq!(scene.node(root).children(), [b]);
+        assert_eq!(scene.node(b).children(), [a]);
+        assert_eq!(scene.node(a).parent(), Some(b));
+
+        scene.update_world_transforms();
+        assert_eq!(world_x(&scene, a), 111.0);
+        assert_eq!(world_x(&scene, leaf), 1111.0);
+
+        scene.set_parent(a, None).unwrap();
+        assert_eq!(scene.roots(), [root, a]);
+        scene.update_world_transforms();
+        assert_eq!(world_x(&scene, a), 10.0);
+        assert_eq!(world_x(&scene, leaf), 1010.0);
+        assert_eq!(scene.depth_first(), [root, b, a, leaf]);
+    }
+
+    #[test]
+    fn nodes_cant_be_moved_under_their_descendants() {
+        let (mut scene, [root, a, _, leaf]) = hierarchy();
+        assert!(scene.set_parent(a, Some(leaf)).is_err());
+        assert!(scene.set_parent(a, Some(a)).is_err());
+        assert!(scene.set_parent(root, Some(leaf)).is_err());
+        assert_eq!(scene.node(leaf).parent(), Some(a));
+    }
+}
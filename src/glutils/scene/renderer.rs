@@ -0,0 +1,459 @@
+use super::{Drawable, MaterialId, ModelId, Scene};
+use crate::glutils::{
+    material::Material,
+    model::primitives::Primitive,
+    render_state::{RenderState, StateTracker},
+    shader::program::Program,
+};
+use anyhow::Result;
+use glam::{Mat3, Mat4};
+use std::{cmp::Ordering, fmt};
+
+/// Uniform the renderer sets to each draw's world transform.
+pub const MODEL_UNIFORM: &str = "model";
+
+/// Uniform the renderer sets to the inverse transpose of the model matrix, for transforming
+/// normals, in programs that declare it.
+pub const NORMAL_MATRIX_UNIFORM: &str = "normalMatrix";
+
+/// A model of a [`Scene`] to draw with one of its materials.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DrawCall {
+    pub model: ModelId,
+    pub material: MaterialId,
+    pub primitive: Primitive,
+    pub transform: Mat4,
+}
+
+impl DrawCall {
+    pub fn new(drawable: Drawable, transform: Mat4) -> Self {
+        Self {
+            model: drawable.model,
+            material: drawable.material,
+            primitive: drawable.primitive,
+            transform,
+        }
+    }
+}
+
+/// Counts of what a frame's draws cost, for spotting redundant state changes.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FrameStats {
+    pub draw_calls: u32,
+    pub program_changes: u32,
+    pub material_changes: u32,
+    pub render_state_changes: u32,
+    pub texture_binds: u32,
+    pub vertex_array_binds: u32,
+}
+
+impl fmt::Display for FrameStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} draws, {} programs, {} materials, {} render states, {} textures, {} vertex arrays",
+            self.draw_calls,
+            self.program_changes,
+            self.material_changes,
+            self.render_state_changes,
+            self.texture_binds,
+            self.vertex_array_binds,
+        )
+    }
+}
+
+/// Queues up draws of a [`Scene`]'s models and issues them sorted to keep state changes down.
+/// Opaque draws are grouped by program, textures, material and model, and only ordered front to
+/// back among draws that share all of those, since a state change costs more than the overdraw
+/// it would save. Transparent draws, those whose material blends, come after and are strictly
+/// ordered back to front so that they blend correctly.
+///
+/// Camera matrices and lights are shared through uniform buffers and left to the caller, so that
+/// a scene can be rendered from several cameras.
+#[derive(Default)]
+pub struct Renderer {
+    queue: Vec<Submission>,
+    stats: FrameStats,
+    last_frame_stats: FrameStats,
+}
+
+struct Submission {
+    call: DrawCall,
+    /// Distance in front of the camera, along its view direction.
+    depth: f32,
+    transparent: bool,
+}
+
+/// What's currently bound, so that only differences are sent to GL.
+#[derive(Default)]
+struct Bound {
+    program: Option<Program>,
+    material: Option<MaterialId>,
+    render_state: Option<RenderState>,
+    textures: Vec<Option<u32>>,
+    model: Option<ModelId>,
+}
+
+impl Renderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn submit(&mut self, call: DrawCall) {
+        self.queue.push(Submission {
+            call,
+            depth: 0.0,
+            transparent: false,
+        });
+    }
+
+    /// Updates the scene's world transforms and submits every drawable in it.
+    pub fn submit_scene(&mut self, scene: &mut Scene) {
+        self.submit_scene_where(scene, |_| true);
+    }
+
+    /// Updates the scene's world transforms and submits the drawables whose material passes
+    /// `filter`, for splitting a scene across passes such as deferred and forward shading.
+    pub fn submit_scene_where<F: Fn(&Material) -> bool>(&mut self, scene: &mut Scene, filter: F) {
+        scene.update_world_transforms();
+        for id in scene.depth_first() {
+            let node = scene.node(id);
+            for drawable in &node.drawables {
+                if filter(&scene.materials[drawable.material.0]) {
+                    self.submit(DrawCall::new(*drawable, node.world));
+                }
+            }
+        }
+    }
+
+    /// Sorts and draws everything submitted since the last flush, as seen through `view`.
+    pub fn flush(
+        &mut self,
+        scene: &mut Scene,
+        state: &mut StateTracker,
+        view: &Mat4,
+    ) -> Result<()> {
+        for submission in &mut self.queue {
+            let material = &scene.materials[submission.call.material.0];
+            submission.transparent = material.render_state().blend.is_some();
+            submission.depth = -view
+                .transform_point3(submission.call.transform.w_axis.truncate())
+                .z;
+        }
+        self.queue.sort_by(|a, b| compare(scene, a, b));
+
+        let result = self.draw_queue(scene, state);
+        self.queue.clear();
+        result
+    }
+
+    /// Submits the scene and flushes it in one go.
+    pub fn render(
+        &mut self,
+        scene: &mut Scene,
+        state: &mut StateTracker,
+        view: &Mat4,
+    ) -> Result<()> {
+        self.submit_scene(scene);
+        self.flush(scene, state, view)
+    }
+
+    /// Stats of the draws flushed since the last [`Renderer::end_frame`].
+    pub fn stats(&self) -> FrameStats {
+        self.stats
+    }
+
+    /// Stats of the last complete frame.
+    pub fn last_frame_stats(&self) -> FrameStats {
+        self.last_frame_stats
+    }
+
+    pub fn end_frame(&mut self) {
+        self.last_frame_stats = std::mem::take(&mut self.stats);
+    }
+
+    fn draw_queue(&mut self, scene: &mut Scene, state: &mut StateTracker) -> Result<()> {
+        // Other code may have bound anything in between flushes.
+        let mut bound = Bound::default();
+        let stats = &mut self.stats;
+
+        for Submission { call, .. } in &self.queue {
+            let material = &scene.materials[call.material.0];
+            let program = material.program();
+
+            if bound.program != Some(program) {
+                program.use_program();
+                bound.program = Some(program);
+                stats.program_changes += 1;
+            }
+
+            if bound.material != Some(call.material) {
+                if bound.render_state.as_ref() != Some(material.render_state()) {
+                    state.apply(material.render_state());
+                    bound.render_state = Some(*material.render_state());
+                    stats.render_state_changes += 1;
+                }
+                for (unit, (sampler, texture)) in material.textures().enumerate() {
+                    if bound.textures.len() <= unit {
+                        bound.textures.resize(unit + 1, None);
+                    }
+                    if bound.textures[unit] != Some(texture.gl_object_id) {
+                        texture.bind(u32::try_from(unit).unwrap());
+                        bound.textures[unit] = Some(texture.gl_object_id);
+                        stats.texture_binds += 1;
+                    }
+                    program.set_uniform_1i(sampler, i32::try_from(unit).unwrap())?;
+                }
+                material.set_uniforms()?;
+                bound.material = Some(call.material);
+                stats.material_changes += 1;
+            }
+
+            program.set_uniform_matrix_4fv(MODEL_UNIFORM, &call.transform)?;
+            if program.has_uniform(NORMAL_MATRIX_UNIFORM) {
+                let normal_matrix = Mat3::from_mat4(call.transform).inverse().transpose();
+                program.set_uniform_matrix_3fv(NORMAL_MATRIX_UNIFORM, &normal_matrix)?;
+            }
+
+            draw_model(scene, &mut bound.model, call, stats)?;
+        }
+
+        if let Some(model) = bound.model {
+            scene.models[model.0].unbind();
+        }
+        Ok(())
+    }
+
+    /// Draws every opaque drawable of the scene that casts shadows with `material` in place of
+    /// its own, for passes that only need geometry such as shadow maps. Only the model matrix is
+    /// set per draw. Drawables whose material blends are skipped.
+    pub fn render_with(
+        &mut self,
+        scene: &mut Scene,
+        state: &mut StateTracker,
+        material: &Material,
+    ) -> Result<()> {
+        scene.update_world_transforms();
+        for id in scene.depth_first() {
+            let node = scene.node(id);
+            for drawable in &node.drawables {
+                let blends = scene.materials[drawable.material.0]
+                    .render_state()
+                    .blend
+                    .is_some();
+                if drawable.casts_shadow && !blends {
+                    self.submit(DrawCall::new(*drawable, node.world));
+                }
+            }
+        }
+        self.queue.sort_by_key(|submission| submission.call.model);
+
+        let result = self.draw_queue_with(scene, state, material);
+        self.queue.clear();
+        result
+    }
+
+    fn draw_queue_with(
+        &mut self,
+        scene: &mut Scene,
+        state: &mut StateTracker,
+        material: &Material,
+    ) -> Result<()> {
+        let stats = &mut self.stats;
+        material.bind(state)?;
+        stats.program_changes += 1;
+        stats.material_changes += 1;
+        stats.render_state_changes += 1;
+        stats.texture_binds += u32::try_from(material.textures().count()).unwrap();
+
+        let program = material.program();
+        let mut bound_model = None;
+        for Submission { call, .. } in &self.queue {
+            program.set_uniform_matrix_4fv(MODEL_UNIFORM, &call.transform)?;
+            draw_model(scene, &mut bound_model, call, stats)?;
+        }
+
+        if let Some(model) = bound_model {
+            scene.models[model.0].unbind();
+        }
+        Ok(())
+    }
+}
+
+/// Binds the call's model unless it's already bound, and draws it.
+fn draw_model(
+    scene: &mut Scene,
+    bound: &mut Option<ModelId>,
+    call: &DrawCall,
+    stats: &mut FrameStats,
+) -> Result<()> {
+    if *bound != Some(call.model) {
+        if let Some(previous) = *bound {
+            scene.models[previous.0].release_binding();
+        }
+        scene.models[call.model.0].bind();
+        *bound = Some(call.model);
+        stats.vertex_array_binds += 1;
+    }
+    scene.models[call.model.0].try_draw_arrays(call.primitive)?;
+    stats.draw_calls += 1;
+    Ok(())
+}
+
+fn compare(scene: &Scene, a: &Submission, b: &Submission) -> Ordering {
+    order(a, b, || {
+        compare_state(state_key(scene, &a.call), state_key(scene, &b.call))
+    })
+}
+
+/// Transparent draws after opaque ones. Opaque draws are grouped by state, as ordered by
+/// `by_state`, and then front to back; transparent ones are back to front, with state only
+/// breaking ties.
+fn order(a: &Submission, b: &Submission, by_state: impl FnOnce() -> Ordering) -> Ordering {
+    let by_depth = a.depth.total_cmp(&b.depth);
+    a.transparent.cmp(&b.transparent).then_with(|| {
+        if a.transparent {
+            by_depth.reverse().then_with(by_state)
+        } else {
+            by_state().then(by_depth)
+        }
+    })
+}
+
+/// What a draw needs bound, in the order of how costly it is to change.
+struct StateKey<I> {
+    program: gl::types::GLuint,
+    textures: I,
+    material: MaterialId,
+    model: ModelId,
+}
+
+fn state_key<'a>(
+    scene: &'a Scene,
+    call: &DrawCall,
+) -> StateKey<impl Iterator<Item = gl::types::GLuint> + 'a> {
+    let material = &scene.materials[call.material.0];
+    StateKey {
+        program: material.program().gl_object_id,
+        textures: texture_ids(material),
+        material: call.material,
+        model: call.model,
+    }
+}
+
+fn compare_state<I, J>(a: StateKey<I>, b: StateKey<J>) -> Ordering
+where
+    I: Iterator<Item = gl::types::GLuint>,
+    J: Iterator<Item = gl::types::GLuint>,
+{
+    a.program
+        .cmp(&b.program)
+        .then_with(|| a.textures.cmp(b.textures))
+        .then(a.material.cmp(&b.material))
+        .then(a.model.cmp(&b.model))
+}
+
+fn texture_ids(material: &Material) -> impl Iterator<Item = gl::types::GLuint> + '_ {
+    material.textures().map(|(_, texture)| texture.gl_object_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Program and textures of each material, standing in for the scene's materials.
+    const MATERIALS: [(gl::types::GLuint, &[gl::types::GLuint]); 4] =
+        [(2, &[]), (1, &[7]), (1, &[3, 9]), (2, &[])];
+
+    fn submission(material: usize, model: usize, depth: f32, transparent: bool) -> Submission {
+        Submission {
+            call: DrawCall {
+                model: ModelId(model),
+                material: MaterialId(material),
+                primitive: Primitive::Triangles,
+                transform: Mat4::IDENTITY,
+            },
+            depth,
+            transparent,
+        }
+    }
+
+    fn key(call: &DrawCall) -> StateKey<impl Iterator<Item = gl::types::GLuint>> {
+        let (program, textures) = MATERIALS[call.material.0];
+        StateKey {
+            program,
+            textures: textures.iter().copied(),
+            material: call.material,
+            model: call.model,
+        }
+    }
+
+    /// Sorts like the renderer and returns the (material, model, depth) of each draw.
+    fn sorted(mut queue: Vec<Submission>) -> Vec<(usize, usize, f32)> {
+        queue.sort_by(|a, b| order(a, b, || compare_state(key(&a.call), key(&b.call))));
+        queue
+            .iter()
+            .map(|s| (s.call.material.0, s.call.model.0, s.depth))
+            .collect()
+    }
+
+    #[test]
+    fn opaque_draws_are_grouped_by_state_then_front_to_back() {
+        let queue = vec![
+            submission(0, 0, 1.0, false),
+            submission(1, 0, 9.0, false),
+            submission(0, 1, 2.0, false),
+            submission(2, 0, 5.0, false),
+            submission(1, 0, 3.0, false),
+            submission(0, 0, 0.5, false),
+        ];
+        assert_eq!(
+            sorted(queue),
+            [
+                // Program 1, with textures [3, 9] before [7].
+                (2, 0, 5.0),
+                (1, 0, 3.0),
+                (1, 0, 9.0),
+                // Program 2, by material and then by model.
+                (0, 0, 0.5),
+                (0, 0, 1.0),
+                (0, 1, 2.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn transparent_draws_come_last_from_back_to_front() {
+        let queue = vec![
+            submission(1, 0, 2.0, true),
+            submission(0, 0, 20.0, false),
+            submission(3, 1, 8.0, true),
+            submission(1, 1, 5.0, true),
+            submission(0, 0, 1.0, false),
+            submission(2, 2, 8.0, true),
+        ];
+        assert_eq!(
+            sorted(queue),
+            [
+                (0, 0, 1.0),
+                (0, 0, 20.0),
+                // State only breaks ties between equally distant draws.
+                (2, 2, 8.0),
+                (3, 1, 8.0),
+                (1, 1, 5.0),
+                (1, 0, 2.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn state_is_ordered_by_program_textures_material_and_model() {
+        let call = |material, model| submission(material, model, 0.0, false).call;
+        let compare = |a: DrawCall, b: DrawCall| compare_state(key(&a), key(&b));
+        assert_eq!(compare(call(1, 0), call(0, 0)), Ordering::Less);
+        assert_eq!(compare(call(2, 0), call(1, 0)), Ordering::Less);
+        assert_eq!(compare(call(0, 5), call(3, 0)), Ordering::Less);
+        assert_eq!(compare(call(3, 0), call(3, 1)), Ordering::Less);
+        assert_eq!(compare(call(3, 1), call(3, 1)), Ordering::Equal);
+    }
+}
@@ -1,4 +1,4 @@
-use super::try_into;
+use super::{debug, try_into};
 use anyhow::{format_err, Result};
 use std::{
     convert::AsRef,
@@ -9,8 +9,15 @@ use std::{
 };
 
 pub mod program;
+pub mod variants;
 
-pub struct Shader(gl::types::GLuint);
+/// Guards against include cycles.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+pub struct Shader {
+    gl_object_id: gl::types::GLuint,
+    name: String,
+}
 
 pub enum ShaderType {
     Vertex,
@@ -27,16 +34,35 @@ impl From<ShaderType> for gl::types::GLenum {
 }
 
 impl Shader {
+    /// Compiles the shader at `src`. Lines of the form `#include "file.glsl"` are replaced with
+    /// the contents of that file, resolved relative to the file including it, so that GLSL
+    /// libraries can be shared between shaders.
     pub fn new<P: AsRef<Path>>(src: P, typ: ShaderType) -> Result<Self> {
+        Self::with_defines(src, typ, &[])
+    }
+
+    /// Like [`Shader::new`], with a `#define` for each of `defines` inserted right after the
+    /// `#version` directive, so that included files see them too. A define can carry a value,
+    /// e.g. `"MAX_LIGHTS 8"`.
+    pub fn with_defines<P: AsRef<Path>>(src: P, typ: ShaderType, defines: &[&str]) -> Result<Self> {
         let shader_path = src.as_ref();
-        let shader_src = fs::read_to_string(src.as_ref())
-            .map_err(|e| format_err!("failed to load '{}': {e}", shader_path.display()))?;
+        let shader_src = insert_defines(&load_source(shader_path, 0)?, defines);
         let file_name = shader_path
             .file_name()
             .map(OsStr::to_string_lossy)
             .ok_or(format_err!("expected shader to have a file name"))?;
-        let shader = Self::compile_src(&file_name, &shader_src, typ)?;
-        Ok(Self(shader))
+        let name = if defines.is_empty() {
+            file_name.into_owned()
+        } else {
+            format!("{file_name} [{}]", defines.join(", "))
+        };
+        let shader = Self::compile_src(&name, &shader_src, typ)?;
+        debug::label(gl::SHADER, shader, &name);
+        debug::check_errors("Shader::new");
+        Ok(Self {
+            gl_object_id: shader,
+            name,
+        })
     }
 
     fn compile_src(file_name: &str, src: &str, typ: ShaderType) -> Result<gl::types::GLuint> {
@@ -69,3 +95,57 @@ impl Shader {
         }
     }
 }
+
+fn load_source(path: &Path, depth: usize) -> Result<String> {
+    if depth > MAX_INCLUDE_DEPTH {
+        return Err(format_err!(
+            "includes nested too deeply at '{}', is there an include cycle?",
+            path.display()
+        ));
+    }
+    let src = fs::read_to_string(path)
+        .map_err(|e| format_err!("failed to load '{}': {e}", path.display()))?;
+
+    let mut resolved = String::with_capacity(src.len());
+    for line in src.lines() {
+        match line.trim_start().strip_prefix("#include") {
+            Some(include) => {
+                let file_name = include
+                    .trim()
+                    .strip_prefix('"')
+                    .and_then(|f| f.strip_suffix('"'))
+                    .ok_or_else(|| {
+                        format_err!("malformed include in '{}': {line}", path.display())
+                    })?;
+                let include_path = path.parent().unwrap_or(Path::new("")).join(file_name);
+                resolved.push_str(&load_source(&include_path, depth + 1)?);
+            }
+            None => resolved.push_str(line),
+        }
+        resolved.push('\n');
+    }
+    Ok(resolved)
+}
+
+/// `#version` has to stay the first directive, so defines go right after it.
+fn insert_defines(src: &str, defines: &[&str]) -> String {
+    if defines.is_empty() {
+        return src.to_owned();
+    }
+    let (version, rest) = match src.split_once('\n') {
+        Some((first, rest)) if first.trim_start().starts_with("#version") => (first, rest),
+        _ => ("", src),
+    };
+    let mut resolved = String::with_capacity(src.len());
+    if !version.is_empty() {
+        resolved.push_str(version);
+        resolved.push('\n');
+    }
+    for define in defines {
+        resolved.push_str("#define ");
+        resolved.push_str(define);
+        resolved.push('\n');
+    }
+    resolved.push_str(rest);
+    resolved
+}
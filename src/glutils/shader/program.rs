@@ -1,8 +1,10 @@
-use super::Shader;
+use super::{debug, try_into, Shader};
+use crate::glutils::mesh;
 use anyhow::{format_err, Result};
+use glam::{Mat3, Mat4};
 use std::{ffi::CString, mem, ptr};
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Program {
     pub gl_object_id: gl::types::GLuint,
 }
@@ -17,6 +19,7 @@ impl Program {
         unsafe {
             gl::UseProgram(self.gl_object_id);
         }
+        debug::check_errors("Program::use_program");
     }
 
     pub fn get_attrib_loc(&self, attrib: &str) -> Result<gl::types::GLuint> {
@@ -32,6 +35,15 @@ impl Program {
         }
     }
 
+    /// Whether the program has an active vertex attribute with the given name. Useful for
+    /// skipping attributes that the shaders don't consume rather than failing outright.
+    pub fn has_attrib(&self, attrib: &str) -> bool {
+        let Ok(c_attrib) = CString::new(attrib) else {
+            return false;
+        };
+        unsafe { gl::GetAttribLocation(self.gl_object_id, c_attrib.as_ptr()) != -1 }
+    }
+
     pub fn get_uniform_loc(&self, uniform: &str) -> Result<gl::types::GLint> {
         let c_uniform = CString::new(uniform)?;
         unsafe {
@@ -44,6 +56,165 @@ impl Program {
             Ok(loc)
         }
     }
+
+    /// Whether the program has an active uniform with the given name. Uniforms that the
+    /// shaders declare but never read are optimized out and count as missing.
+    pub fn has_uniform(&self, uniform: &str) -> bool {
+        let Ok(c_uniform) = CString::new(uniform) else {
+            return false;
+        };
+        unsafe { gl::GetUniformLocation(self.gl_object_id, c_uniform.as_ptr()) != -1 }
+    }
+
+    pub fn get_uniform_block_index(&self, block: &str) -> Result<gl::types::GLuint> {
+        let c_block = CString::new(block)?;
+        unsafe {
+            let index = gl::GetUniformBlockIndex(self.gl_object_id, c_block.as_ptr());
+            if index == gl::INVALID_INDEX {
+                return Err(format_err!(
+                    "failed to query index of uniform block '{block}'"
+                ));
+            }
+            Ok(index)
+        }
+    }
+
+    /// Size in bytes of a uniform block and the offset of each of its active members, as laid
+    /// out by the shader compiler. Member names are relative to the block, e.g. `lights[0].color`.
+    pub fn uniform_block_layout(&self, block: &str) -> Result<(usize, Vec<(String, usize)>)> {
+        let index = self.get_uniform_block_index(block)?;
+        unsafe {
+            let mut data_size = 0;
+            gl::GetActiveUniformBlockiv(
+                self.gl_object_id,
+                index,
+                gl::UNIFORM_BLOCK_DATA_SIZE,
+                &mut data_size,
+            );
+            let mut num_members = 0;
+            gl::GetActiveUniformBlockiv(
+                self.gl_object_id,
+                index,
+                gl::UNIFORM_BLOCK_ACTIVE_UNIFORMS,
+                &mut num_members,
+            );
+            let mut member_indices = vec![0; try_into!(num_members)];
+            gl::GetActiveUniformBlockiv(
+                self.gl_object_id,
+                index,
+                gl::UNIFORM_BLOCK_ACTIVE_UNIFORM_INDICES,
+                member_indices.as_mut_ptr(),
+            );
+            let member_indices = member_indices
+                .into_iter()
+                .map(|i| gl::types::GLuint::try_from(i).unwrap())
+                .collect::<Vec<_>>();
+
+            let mut offsets = vec![0; member_indices.len()];
+            gl::GetActiveUniformsiv(
+                self.gl_object_id,
+                num_members,
+                member_indices.as_ptr(),
+                gl::UNIFORM_OFFSET,
+                offsets.as_mut_ptr(),
+            );
+
+            let mut max_name_length = 0;
+            gl::GetProgramiv(
+                self.gl_object_id,
+                gl::ACTIVE_UNIFORM_MAX_LENGTH,
+                &mut max_name_length,
+            );
+            let prefix = format!("{block}.");
+            let members = member_indices
+                .iter()
+                .zip(offsets)
+                .map(|(member, offset)| {
+                    let mut name = vec![0u8; try_into!(max_name_length)];
+                    let mut length = 0;
+                    gl::GetActiveUniformName(
+                        self.gl_object_id,
+                        *member,
+                        max_name_length,
+                        &mut length,
+                        name.as_mut_ptr() as *mut gl::types::GLchar,
+                    );
+                    name.truncate(try_into!(length));
+                    let name = String::from_utf8_lossy(&name);
+                    let name = name.strip_prefix(&prefix).unwrap_or(&name).to_string();
+                    (name, try_into!(offset))
+                })
+                .collect();
+
+            Ok((try_into!(data_size), members))
+        }
+    }
+
+    /// Makes the program read the uniform block from the buffer bound to `binding`.
+    pub fn bind_uniform_block(&self, block: &str, binding: u32) -> Result<()> {
+        let index = self.get_uniform_block_index(block)?;
+        unsafe { gl::UniformBlockBinding(self.gl_object_id, index, binding) };
+        debug::check_errors("Program::bind_uniform_block");
+        Ok(())
+    }
+
+    /// The uniform setters below apply to the program currently in use, so
+    /// [`Program::use_program`] needs to have been called first.
+    pub fn set_uniform_1i(&self, uniform: &str, value: i32) -> Result<()> {
+        let loc = self.get_uniform_loc(uniform)?;
+        unsafe { gl::Uniform1i(loc, value) };
+        debug::check_errors("Program::set_uniform_1i");
+        Ok(())
+    }
+
+    pub fn set_uniform_1f(&self, uniform: &str, value: f32) -> Result<()> {
+        let loc = self.get_uniform_loc(uniform)?;
+        unsafe { gl::Uniform1f(loc, value) };
+        debug::check_errors("Program::set_uniform_1f");
+        Ok(())
+    }
+
+    pub fn set_uniform_2f(&self, uniform: &str, x: f32, y: f32) -> Result<()> {
+        let loc = self.get_uniform_loc(uniform)?;
+        unsafe { gl::Uniform2f(loc, x, y) };
+        debug::check_errors("Program::set_uniform_2f");
+        Ok(())
+    }
+
+    pub fn set_uniform_3f(&self, uniform: &str, x: f32, y: f32, z: f32) -> Result<()> {
+        let loc = self.get_uniform_loc(uniform)?;
+        unsafe { gl::Uniform3f(loc, x, y, z) };
+        debug::check_errors("Program::set_uniform_3f");
+        Ok(())
+    }
+
+    pub fn set_uniform_4f(&self, uniform: &str, x: f32, y: f32, z: f32, w: f32) -> Result<()> {
+        let loc = self.get_uniform_loc(uniform)?;
+        unsafe { gl::Uniform4f(loc, x, y, z, w) };
+        debug::check_errors("Program::set_uniform_4f");
+        Ok(())
+    }
+
+    pub fn set_uniform_matrix_3fv(&self, uniform: &str, matrix: &Mat3) -> Result<()> {
+        let loc = self.get_uniform_loc(uniform)?;
+        unsafe { gl::UniformMatrix3fv(loc, 1, gl::FALSE, matrix.as_ref().as_ptr()) };
+        debug::check_errors("Program::set_uniform_matrix_3fv");
+        Ok(())
+    }
+
+    pub fn set_uniform_matrix_4fv(&self, uniform: &str, matrix: &Mat4) -> Result<()> {
+        let loc = self.get_uniform_loc(uniform)?;
+        unsafe { gl::UniformMatrix4fv(loc, 1, gl::FALSE, matrix.as_ref().as_ptr()) };
+        debug::check_errors("Program::set_uniform_matrix_4fv");
+        Ok(())
+    }
+
+    pub fn set_uniform_1fv(&self, uniform: &str, values: &[f32]) -> Result<()> {
+        let loc = self.get_uniform_loc(uniform)?;
+        unsafe { gl::Uniform1fv(loc, try_into!(values.len()), values.as_ptr()) };
+        debug::check_errors("Program::set_uniform_1fv");
+        Ok(())
+    }
 }
 
 impl Linker {
@@ -60,8 +231,16 @@ impl Linker {
 
     pub fn link(self) -> Result<Program> {
         unsafe {
-            for Shader(shader) in &self.shaders {
-                gl::AttachShader(self.program, *shader);
+            for shader in &self.shaders {
+                gl::AttachShader(self.program, shader.gl_object_id);
+            }
+
+            // Giving the standard attributes the same locations in every program lets a model's
+            // vertex array be drawn with programs other than the one it was built for, such as
+            // depth-only passes.
+            for (location, attrib) in mesh::ATTRIBS.iter().enumerate() {
+                let c_attrib = CString::new(*attrib).unwrap();
+                gl::BindAttribLocation(self.program, try_into!(location), c_attrib.as_ptr());
             }
 
             gl::LinkProgram(self.program);
@@ -85,9 +264,18 @@ impl Linker {
                 ));
             }
 
-            for Shader(shader) in self.shaders {
-                gl::DeleteShader(shader);
+            let label = self
+                .shaders
+                .iter()
+                .map(|shader| shader.name.as_str())
+                .collect::<Vec<_>>()
+                .join(" + ");
+            debug::label(gl::PROGRAM, self.program, &label);
+
+            for shader in self.shaders {
+                gl::DeleteShader(shader.gl_object_id);
             }
+            debug::check_errors("Linker::link");
 
             Ok(Program {
                 gl_object_id: self.program,
@@ -0,0 +1,46 @@
+use super::{
+    program::{Linker, Program},
+    Shader, ShaderType,
+};
+use anyhow::Result;
+use std::{collections::HashMap, path::PathBuf};
+
+/// A vertex and fragment shader pair linked once for every set of defines it's asked for, e.g.
+/// once per combination of [`MaterialFeatures`](crate::glutils::lighting::MaterialFeatures) in
+/// use.
+pub struct ProgramVariants {
+    vertex: PathBuf,
+    fragment: PathBuf,
+    programs: HashMap<Vec<String>, Program>,
+}
+
+impl ProgramVariants {
+    pub fn new<P: Into<PathBuf>>(vertex: P, fragment: P) -> Self {
+        Self {
+            vertex: vertex.into(),
+            fragment: fragment.into(),
+            programs: HashMap::new(),
+        }
+    }
+
+    /// The program compiled with `defines`, which is linked on first use. The order of the
+    /// defines doesn't matter.
+    pub fn get(&mut self, defines: &[&str]) -> Result<Program> {
+        let mut key: Vec<String> = defines.iter().map(|define| define.to_string()).collect();
+        key.sort();
+        if let Some(program) = self.programs.get(&key) {
+            return Ok(*program);
+        }
+
+        let vs = Shader::with_defines(&self.vertex, ShaderType::Vertex, defines)?;
+        let fs = Shader::with_defines(&self.fragment, ShaderType::Fragment, defines)?;
+        let program = Linker::new().attach_shader(vs).attach_shader(fs).link()?;
+        self.programs.insert(key, program);
+        Ok(program)
+    }
+
+    /// Every variant linked so far.
+    pub fn programs(&self) -> impl Iterator<Item = Program> + '_ {
+        self.programs.values().copied()
+    }
+}
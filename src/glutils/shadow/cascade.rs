@@ -0,0 +1,167 @@
+use glam::{
+    camera::rh::{proj::opengl, view},
+    vec3, Mat4, Vec3, Vec3Swizzles,
+};
+
+/// Distances from the camera at which each of `count` cascades ends, splitting `near` to `far`
+/// with a blend of logarithmic and uniform splits. A `lambda` of 1 is fully logarithmic, which
+/// matches how perspective shrinks distant texels, while 0 is uniform, which wastes fewer texels
+/// right in front of the camera.
+pub fn split_distances(near: f32, far: f32, count: usize, lambda: f32) -> Vec<f32> {
+    (1..=count)
+        .map(|i| {
+            let fraction = i as f32 / count as f32;
+            let logarithmic = near * (far / near).powf(fraction);
+            let uniform = near + (far - near) * fraction;
+            lambda * logarithmic + (1.0 - lambda) * uniform
+        })
+        .collect()
+}
+
+/// World space corners of the slice of a camera's view frustum between `near` and `far`, for a
+/// camera with the given world transform, vertical field of view and aspect ratio.
+pub fn frustum_slice_corners(
+    camera_world: Mat4,
+    fov_y: f32,
+    aspect_ratio: f32,
+    near: f32,
+    far: f32,
+) -> [Vec3; 8] {
+    let tan_half_fov = (fov_y * 0.5).tan();
+    let mut corners = [Vec3::ZERO; 8];
+    for (i, corner) in corners.iter_mut().enumerate() {
+        let distance = if i < 4 { near } else { far };
+        let half_height = distance * tan_half_fov;
+        let half_width = half_height * aspect_ratio;
+        let x = if i & 1 == 0 { -half_width } else { half_width };
+        let y = if i & 2 == 0 {
+            -half_height
+        } else {
+            half_height
+        };
+        *corner = camera_world.transform_point3(vec3(x, y, -distance));
+    }
+    corners
+}
+
+/// Light space projection and view of a directional light shining in `direction` that covers
+/// `corners` with a `resolution` texels square map. The map covers a sphere around the corners
+/// rather than the corners themselves so that its size doesn't change as the camera turns, and
+/// it's moved in whole texels so that shadow edges don't shimmer as the camera moves. Its depth
+/// range reaches `caster_distance` further towards the light to catch casters outside of the
+/// slice.
+pub fn fit_directional(
+    corners: &[Vec3; 8],
+    direction: Vec3,
+    resolution: u32,
+    caster_distance: f32,
+) -> Mat4 {
+    let center = corners.iter().copied().sum::<Vec3>() / corners.len() as f32;
+    let radius = corners
+        .iter()
+        .map(|corner| corner.distance(center))
+        .fold(0.0, f32::max);
+    // Rounded up so that floating point noise doesn't change the texel size from frame to frame.
+    let radius = (radius * 16.0).ceil() / 16.0;
+
+    let direction = direction.normalize();
+    let up = if direction.y.abs() > 0.99 {
+        Vec3::Z
+    } else {
+        Vec3::Y
+    };
+    // Looking from the origin keeps light space fixed in the world, so that snapping the center
+    // to texels of light space keeps texels fixed in the world too.
+    let light_view = view::look_at_mat4(Vec3::ZERO, direction, up);
+    let texel_size = 2.0 * radius / resolution as f32;
+    let center = light_view.transform_point3(center);
+    let snapped = (center.xy() / texel_size).floor() * texel_size;
+
+    let projection = opengl::orthographic(
+        snapped.x - radius,
+        snapped.x + radius,
+        snapped.y - radius,
+        snapped.y + radius,
+        -center.z - radius - caster_distance,
+        -center.z + radius,
+    );
+    projection * light_view
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() <= 1e-4 * b.abs().max(1.0), "{a} != {b}");
+    }
+
+    #[test]
+    fn splits_end_at_far_and_increase() {
+        for lambda in [0.0, 0.5, 0.9, 1.0] {
+            let splits = split_distances(0.1, 100.0, 4, lambda);
+            assert_eq!(splits.len(), 4);
+            assert_close(*splits.last().unwrap(), 100.0);
+            assert!(splits[0] > 0.1);
+            assert!(splits.windows(2).all(|w| w[0] < w[1]), "{splits:?}");
+        }
+    }
+
+    #[test]
+    fn lambda_blends_uniform_and_logarithmic_splits() {
+        let uniform = split_distances(1.0, 9.0, 4, 0.0);
+        for (split, expected) in uniform.into_iter().zip([3.0, 5.0, 7.0, 9.0]) {
+            assert_close(split, expected);
+        }
+
+        let logarithmic = split_distances(1.0, 16.0, 4, 1.0);
+        for (split, expected) in logarithmic.into_iter().zip([2.0, 4.0, 8.0, 16.0]) {
+            assert_close(split, expected);
+        }
+    }
+
+    #[test]
+    fn slice_corners_follow_the_camera() {
+        let camera = Mat4::from_translation(vec3(1.0, 2.0, 3.0));
+        let fov_y = 90f32.to_radians();
+        let corners = frustum_slice_corners(camera, fov_y, 2.0, 1.0, 10.0);
+
+        assert!(corners[..4].iter().all(|c| (c.z - 2.0).abs() < 1e-5));
+        assert!(corners[4..].iter().all(|c| (c.z + 7.0).abs() < 1e-5));
+        assert!(corners[0].abs_diff_eq(vec3(-1.0, 1.0, 2.0), 1e-5));
+        assert!(corners[7].abs_diff_eq(vec3(21.0, 12.0, -7.0), 1e-5));
+    }
+
+    #[test]
+    fn fitted_projection_contains_the_slice() {
+        let cameras = [
+            Mat4::IDENTITY,
+            Mat4::from_rotation_y(1.0) * Mat4::from_translation(vec3(3.7, 1.3, -8.2)),
+            Mat4::from_translation(vec3(-40.0, 5.0, 12.5)) * Mat4::from_rotation_x(-0.6),
+        ];
+        let directions = [
+            vec3(-0.2, -1.0, -0.3),
+            vec3(0.0, -1.0, 0.0),
+            vec3(1.0, -0.5, 0.25),
+        ];
+        let splits = split_distances(0.1, 50.0, 3, 0.75);
+
+        for camera in cameras {
+            for direction in directions {
+                let mut near = 0.1;
+                for &far in &splits {
+                    let corners = frustum_slice_corners(camera, 45f32.to_radians(), 1.6, near, far);
+                    let light = fit_directional(&corners, direction, 1024, 10.0);
+                    for corner in corners {
+                        let ndc = light.project_point3(corner);
+                        assert!(
+                            ndc.abs().max_element() <= 1.0,
+                            "{corner} lands outside the map at {ndc}"
+                        );
+                    }
+                    near = far;
+                }
+            }
+        }
+    }
+}
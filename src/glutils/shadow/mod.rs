@@ -0,0 +1,632 @@
+use super::{
+    debug,
+    lighting::Lighting,
+    material::Material,
+    mesh::{shapes, MeshOptions},
+    model::{primitives::Primitive, usage::Usage, Model},
+    render_state::{comparison::Comparison, Clear, RenderState, StateTracker},
+    scene::{renderer::Renderer, NodeId, Scene},
+    shader::{
+        program::{Linker, Program},
+        Shader, ShaderType,
+    },
+    texture::{cubemap::CubeFace, target::Target, Texture},
+    try_into,
+    uniform_buffer::{std140, UniformBindings, UniformBuffer},
+};
+use anyhow::{format_err, Result};
+use glam::{camera::rh::proj::opengl, camera::rh::view, Mat4, Vec3, Vec4};
+use std::{f32::consts::FRAC_PI_2, path::PathBuf, ptr};
+
+pub mod cascade;
+
+/// Array size of the `Shadows` uniform block declared in `shaders/include/lights.glsl`.
+pub const MAX_CASCADES: usize = 4;
+
+/// Texture units the shadow maps stay bound to, out of the way of material textures.
+pub const DIRECTIONAL_SHADOW_UNIT: u32 = 13;
+pub const SPOT_SHADOW_UNIT: u32 = 14;
+pub const POINT_SHADOW_UNIT: u32 = 15;
+
+/// Closest distance from a spot or point light at which geometry casts shadows.
+const NEAR_PLANE: f32 = 0.05;
+
+/// Depth only, with nothing culled so that single sided geometry such as planes still casts
+/// shadows.
+const DEPTH_STATE: RenderState = RenderState {
+    depth_test: Some(Comparison::Less),
+    ..RenderState::DEFAULT
+};
+
+std140::uniform_block! {
+    /// Contents of the `Shadows` uniform block, declared in `shaders/include/lights.glsl` when
+    /// `SHADOWS` is defined. `cascade_splits` holds the distance along `camera_forward` at which
+    /// each cascade ends and `cascade_texel_sizes` the world space size of their texels. Spot
+    /// and point maps store distances to the light divided by `spot_far` and `point_far`, and
+    /// their texels are `*_texel_scale` times the distance to the light across.
+    #[derive(Copy, Clone, Debug, Default, PartialEq)]
+    pub struct Shadows {
+        pub cascade_matrices: [Mat4; MAX_CASCADES],
+        pub cascade_splits: Vec4,
+        pub cascade_texel_sizes: Vec4,
+        pub spot_matrix: Mat4,
+        pub camera_forward: Vec3,
+        pub num_cascades: i32,
+        pub spot_far: f32,
+        pub point_far: f32,
+        pub spot_texel_scale: f32,
+        pub point_texel_scale: f32,
+        pub bias: f32,
+        pub normal_bias: f32,
+        pub pcf_radius: i32,
+        pub show_cascades: bool,
+    }
+}
+
+/// How shadow maps are rendered and sampled.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ShadowSettings {
+    /// Texels along each side of the directional light's cascades and of the spot light's map.
+    pub resolution: u32,
+    /// Texels along each side of every face of the point light's cube map.
+    pub point_resolution: u32,
+    /// Number of cascades the camera's view is split into for the directional light, at most
+    /// [`MAX_CASCADES`].
+    pub cascades: usize,
+    /// Blend between uniform (0) and logarithmic (1) cascade splits, see
+    /// [`cascade::split_distances`].
+    pub split_lambda: f32,
+    /// Distance from the camera past which nothing is shadowed by the directional light, and
+    /// the furthest spot and point lights reach.
+    pub max_distance: f32,
+    /// World space distance by which surfaces are moved towards the light before being looked
+    /// up, to keep them from shadowing themselves.
+    pub bias: f32,
+    /// Same as `bias` but along the surface normal and in texels of the shadow map, which
+    /// handles surfaces at grazing angles to the light much better.
+    pub normal_bias: f32,
+    /// Texels around the looked up one that are averaged for soft edges, so 0 is a single
+    /// hardware filtered lookup and 1 averages 3x3 of them.
+    pub pcf_radius: i32,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        Self {
+            resolution: 2048,
+            point_resolution: 1024,
+            cascades: MAX_CASCADES,
+            split_lambda: 0.75,
+            max_distance: 50.0,
+            bias: 0.02,
+            normal_bias: 1.5,
+            pcf_radius: 1,
+        }
+    }
+}
+
+/// A shadow map to show with [`ShadowMaps::draw_overlay`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ShadowView {
+    Cascade(usize),
+    Spot,
+    Point(CubeFace),
+}
+
+/// Shadow maps for one directional, one spot and one point light of a scene, those that
+/// [`Lighting`] picks. The directional light's map is split into cascades that cover
+/// successively larger slices of the camera's view, so that nearby shadows stay sharp in large
+/// scenes. The spot light gets a single map and the point light a cube map. Programs that
+/// include `lights.glsl` with `SHADOWS` defined look them up once attached with
+/// [`ShadowMaps::attach`].
+pub struct ShadowMaps {
+    settings: ShadowSettings,
+    framebuffer: gl::types::GLuint,
+    directional: Texture,
+    spot: Texture,
+    point: Texture,
+    depth_material: Material,
+    distance_material: Material,
+    block: Shadows,
+    buffer: UniformBuffer<Shadows>,
+    overlay: Overlay,
+}
+
+struct Overlay {
+    model: Model,
+    /// Samples depths as they are, rather than comparing them like shadow lookups do.
+    sampler: gl::types::GLuint,
+}
+
+impl ShadowMaps {
+    pub fn new(settings: ShadowSettings, bindings: &mut UniformBindings) -> Result<Self> {
+        if settings.cascades == 0 || settings.cascades > MAX_CASCADES {
+            return Err(format_err!(
+                "shadows need between 1 and {MAX_CASCADES} cascades but {} were asked for",
+                settings.cascades
+            ));
+        }
+        if settings.resolution == 0 || settings.point_resolution == 0 {
+            return Err(format_err!("shadow map resolutions must be non-zero"));
+        }
+
+        let shaders_dir = PathBuf::new().join("shaders").join("shadow");
+        let link = |vertex: &str, fragment: &str| -> Result<Program> {
+            let vs = Shader::new(shaders_dir.join(vertex), ShaderType::Vertex)?;
+            let fs = Shader::new(shaders_dir.join(fragment), ShaderType::Fragment)?;
+            Linker::new().attach_shader(vs).attach_shader(fs).link()
+        };
+        let mut depth_material = Material::new(link(
+            "depth_vertex_shader.glsl",
+            "depth_fragment_shader.glsl",
+        )?);
+        depth_material.set_render_state(DEPTH_STATE);
+        let mut distance_material = Material::new(link(
+            "depth_vertex_shader.glsl",
+            "distance_fragment_shader.glsl",
+        )?);
+        distance_material.set_render_state(DEPTH_STATE);
+
+        let overlay_program = {
+            let vs = Shader::new(
+                PathBuf::new()
+                    .join("shaders")
+                    .join("postprocess")
+                    .join("fullscreen_vertex_shader.glsl"),
+                ShaderType::Vertex,
+            )?;
+            let fs = Shader::new(
+                shaders_dir.join("overlay_fragment_shader.glsl"),
+                ShaderType::Fragment,
+            )?;
+            Linker::new().attach_shader(vs).attach_shader(fs).link()?
+        };
+        let quad = shapes::fullscreen_quad(MeshOptions {
+            normals: false,
+            tangents: false,
+            uvs: true,
+        });
+        let model = Model::from_mesh(&quad, overlay_program, Usage::Static)?;
+
+        let (framebuffer, directional, spot, point, sampler) =
+            unsafe { Self::build_impl(&settings) };
+        debug::check_errors("ShadowMaps::new");
+
+        let block = Shadows::default();
+        let buffer = UniformBuffer::new(&block, Usage::Dynamic, bindings)?;
+
+        Ok(Self {
+            settings,
+            framebuffer,
+            directional,
+            spot,
+            point,
+            depth_material,
+            distance_material,
+            block,
+            buffer,
+            overlay: Overlay { model, sampler },
+        })
+    }
+
+    unsafe fn build_impl(
+        settings: &ShadowSettings,
+    ) -> (
+        gl::types::GLuint,
+        Texture,
+        Texture,
+        Texture,
+        gl::types::GLuint,
+    ) {
+        let resolution = try_into!(settings.resolution);
+        let point_resolution = try_into!(settings.point_resolution);
+
+        let directional = new_depth_texture(Target::Texture2DArray, |target| {
+            gl::TexImage3D(
+                target,
+                0,
+                try_into!(gl::DEPTH_COMPONENT32F),
+                resolution,
+                resolution,
+                try_into!(settings.cascades),
+                0,
+                gl::DEPTH_COMPONENT,
+                gl::FLOAT,
+                ptr::null(),
+            );
+        });
+        let spot = new_depth_texture(Target::Texture2D, |target| {
+            gl::TexImage2D(
+                target,
+                0,
+                try_into!(gl::DEPTH_COMPONENT32F),
+                resolution,
+                resolution,
+                0,
+                gl::DEPTH_COMPONENT,
+                gl::FLOAT,
+                ptr::null(),
+            );
+        });
+        let point = new_depth_texture(Target::CubeMap, |_| {
+            for face in CubeFace::ALL {
+                gl::TexImage2D(
+                    face.into(),
+                    0,
+                    try_into!(gl::DEPTH_COMPONENT32F),
+                    point_resolution,
+                    point_resolution,
+                    0,
+                    gl::DEPTH_COMPONENT,
+                    gl::FLOAT,
+                    ptr::null(),
+                );
+            }
+        });
+
+        // Depth only, so there's no color to draw or read. Attachments change with every pass.
+        let mut framebuffer = 0;
+        gl::GenFramebuffers(1, &mut framebuffer);
+        gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
+        gl::DrawBuffer(gl::NONE);
+        gl::ReadBuffer(gl::NONE);
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        debug::label(gl::FRAMEBUFFER, framebuffer, "shadow maps");
+
+        let mut sampler = 0;
+        gl::GenSamplers(1, &mut sampler);
+        gl::SamplerParameteri(sampler, gl::TEXTURE_COMPARE_MODE, try_into!(gl::NONE));
+        gl::SamplerParameteri(sampler, gl::TEXTURE_MIN_FILTER, try_into!(gl::NEAREST));
+        gl::SamplerParameteri(sampler, gl::TEXTURE_MAG_FILTER, try_into!(gl::NEAREST));
+        for wrap in [gl::TEXTURE_WRAP_S, gl::TEXTURE_WRAP_T, gl::TEXTURE_WRAP_R] {
+            gl::SamplerParameteri(sampler, wrap, try_into!(gl::CLAMP_TO_EDGE));
+        }
+
+        let directional = Texture {
+            gl_object_id: directional,
+            width: settings.resolution,
+            height: settings.resolution,
+            target: Target::Texture2DArray,
+        };
+        let spot = Texture {
+            gl_object_id: spot,
+            width: settings.resolution,
+            height: settings.resolution,
+            target: Target::Texture2D,
+        };
+        let point = Texture {
+            gl_object_id: point,
+            width: settings.point_resolution,
+            height: settings.point_resolution,
+            target: Target::CubeMap,
+        };
+        (framebuffer, directional, spot, point, sampler)
+    }
+
+    pub fn settings(&self) -> &ShadowSettings {
+        &self.settings
+    }
+
+    /// Changes how shadows are sampled. The resolutions and number of cascades are fixed when
+    /// the maps are created, so changes to them are ignored.
+    pub fn set_settings(&mut self, settings: ShadowSettings) {
+        self.settings = ShadowSettings {
+            resolution: self.settings.resolution,
+            point_resolution: self.settings.point_resolution,
+            cascades: self.settings.cascades,
+            ..settings
+        };
+    }
+
+    /// Tints everything lit by the directional light by the cascade it's looked up in.
+    pub fn set_show_cascades(&mut self, show: bool) {
+        self.block.show_cascades = show;
+    }
+
+    pub fn show_cascades(&self) -> bool {
+        self.block.show_cascades
+    }
+
+    /// The uniform values last uploaded, by [`ShadowMaps::render`].
+    pub fn block(&self) -> &Shadows {
+        &self.block
+    }
+
+    /// Makes `program` read the shadow maps and the `Shadows` block.
+    pub fn attach(&self, program: &Program) -> Result<()> {
+        self.buffer.attach(program, "Shadows")?;
+        program.use_program();
+        for (sampler, unit) in [
+            ("directionalShadowMap", DIRECTIONAL_SHADOW_UNIT),
+            ("spotShadowMap", SPOT_SHADOW_UNIT),
+            ("pointShadowMap", POINT_SHADOW_UNIT),
+        ] {
+            program.set_uniform_1i(sampler, try_into!(unit))?;
+        }
+        Ok(())
+    }
+
+    /// Renders the shadow maps of the lights `lighting` picked, as seen by the camera attached
+    /// to `camera` with the given viewport aspect ratio, then binds them to their texture units.
+    /// The bound framebuffer and viewport are restored afterwards.
+    pub fn render(
+        &mut self,
+        renderer: &mut Renderer,
+        scene: &mut Scene,
+        state: &mut StateTracker,
+        lighting: &Lighting,
+        camera: NodeId,
+        aspect_ratio: f32,
+    ) -> Result<()> {
+        scene.update_world_transforms();
+        let camera_node = scene.node(camera);
+        let camera_world = camera_node.world_transform();
+        let camera_lens = camera_node
+            .camera
+            .ok_or_else(|| format_err!("node '{}' has no camera", camera_node.name))?;
+
+        let mut previous_framebuffer = 0;
+        let mut previous_viewport = [0; 4];
+        unsafe {
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut previous_framebuffer);
+            gl::GetIntegerv(gl::VIEWPORT, previous_viewport.as_mut_ptr());
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.framebuffer);
+        }
+
+        let settings = self.settings;
+        let block = &mut self.block;
+        block.num_cascades = 0;
+        block.spot_far = 0.0;
+        block.point_far = 0.0;
+        block.bias = settings.bias;
+        block.normal_bias = settings.normal_bias;
+        block.pcf_radius = settings.pcf_radius.max(0);
+        block.camera_forward = camera_world.transform_vector3(Vec3::NEG_Z).normalize();
+
+        let result = (|| -> Result<()> {
+            if let Some(light) = lighting.directional_shadow.map(|i| lighting.directional[i]) {
+                let far = camera_lens.far.min(settings.max_distance);
+                let splits = cascade::split_distances(
+                    camera_lens.near,
+                    far,
+                    settings.cascades,
+                    settings.split_lambda,
+                );
+                let mut near = camera_lens.near;
+                for (i, split) in splits.iter().enumerate() {
+                    let corners = cascade::frustum_slice_corners(
+                        camera_world,
+                        camera_lens.fov_y,
+                        aspect_ratio,
+                        near,
+                        *split,
+                    );
+                    let light_space = cascade::fit_directional(
+                        &corners,
+                        light.direction,
+                        settings.resolution,
+                        settings.max_distance,
+                    );
+                    block.cascade_matrices[i] = light_space;
+                    block.cascade_splits[i] = *split;
+                    // The projection scales the covered width down to the 2 units of [-1, 1].
+                    let width = 2.0 / light_space.row(0).truncate().length();
+                    block.cascade_texel_sizes[i] = width / settings.resolution as f32;
+                    near = *split;
+
+                    unsafe {
+                        gl::FramebufferTextureLayer(
+                            gl::FRAMEBUFFER,
+                            gl::DEPTH_ATTACHMENT,
+                            self.directional.gl_object_id,
+                            0,
+                            try_into!(i),
+                        );
+                    }
+                    self.depth_material.set("lightSpace", light_space);
+                    draw_pass(
+                        renderer,
+                        scene,
+                        state,
+                        &self.depth_material,
+                        settings.resolution,
+                    )?;
+                }
+                block.num_cascades = try_into!(settings.cascades);
+            }
+
+            if let Some(light) = lighting.spot_shadow.map(|i| lighting.spot[i]) {
+                let far = light.range().min(settings.max_distance);
+                // A little wider than the cone so that PCF at its edge stays inside the map.
+                let fov = (light.outer_angle() * 2.0 * 1.1).min(170f32.to_radians());
+                let up = if light.direction.y.abs() > 0.99 {
+                    Vec3::Z
+                } else {
+                    Vec3::Y
+                };
+                let light_space = opengl::perspective(fov, 1.0, NEAR_PLANE, far)
+                    * view::look_at_mat4(light.position, light.position + light.direction, up);
+                block.spot_matrix = light_space;
+                block.spot_far = far;
+                block.spot_texel_scale = 2.0 * (fov * 0.5).tan() / settings.resolution as f32;
+
+                unsafe {
+                    gl::FramebufferTexture2D(
+                        gl::FRAMEBUFFER,
+                        gl::DEPTH_ATTACHMENT,
+                        gl::TEXTURE_2D,
+                        self.spot.gl_object_id,
+                        0,
+                    );
+                }
+                self.distance_material.set("lightSpace", light_space);
+                self.distance_material.set("lightPosition", light.position);
+                self.distance_material.set("farPlane", far);
+                draw_pass(
+                    renderer,
+                    scene,
+                    state,
+                    &self.distance_material,
+                    settings.resolution,
+                )?;
+            }
+
+            if let Some(light) = lighting.point_shadow.map(|i| lighting.point[i]) {
+                let far = light.range().min(settings.max_distance);
+                let projection = opengl::perspective(FRAC_PI_2, 1.0, NEAR_PLANE, far);
+                block.point_far = far;
+                block.point_texel_scale = 2.0 / settings.point_resolution as f32;
+
+                self.distance_material.set("lightPosition", light.position);
+                self.distance_material.set("farPlane", far);
+                for face in CubeFace::ALL {
+                    unsafe {
+                        gl::FramebufferTexture2D(
+                            gl::FRAMEBUFFER,
+                            gl::DEPTH_ATTACHMENT,
+                            face.into(),
+                            self.point.gl_object_id,
+                            0,
+                        );
+                    }
+                    self.distance_material
+                        .set("lightSpace", projection * face.view(light.position));
+                    draw_pass(
+                        renderer,
+                        scene,
+                        state,
+                        &self.distance_material,
+                        settings.point_resolution,
+                    )?;
+                }
+            }
+            Ok(())
+        })();
+
+        unsafe {
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, gl::TEXTURE_2D, 0, 0);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, try_into!(previous_framebuffer));
+            let [x, y, width, height] = previous_viewport;
+            gl::Viewport(x, y, width, height);
+        }
+        debug::check_errors("ShadowMaps::render");
+        result?;
+
+        self.buffer.update(&self.block);
+        self.directional.bind(DIRECTIONAL_SHADOW_UNIT);
+        self.spot.bind(SPOT_SHADOW_UNIT);
+        self.point.bind(POINT_SHADOW_UNIT);
+        Ok(())
+    }
+
+    /// Draws one of the shadow maps in grayscale to the given part of the bound framebuffer,
+    /// with near depths dark and far ones light. The viewport is restored afterwards.
+    pub fn draw_overlay(
+        &mut self,
+        state: &mut StateTracker,
+        view: ShadowView,
+        viewport: [i32; 4],
+    ) -> Result<()> {
+        let (mode, layer) = match view {
+            ShadowView::Cascade(cascade) if cascade < self.settings.cascades => (0, cascade),
+            ShadowView::Cascade(cascade) => {
+                return Err(format_err!(
+                    "there's no cascade {cascade}, only {}",
+                    self.settings.cascades
+                ))
+            }
+            ShadowView::Spot => (1, 0),
+            ShadowView::Point(face) => (2, face as usize),
+        };
+
+        let mut previous_viewport = [0; 4];
+        unsafe {
+            gl::GetIntegerv(gl::VIEWPORT, previous_viewport.as_mut_ptr());
+            let [x, y, width, height] = viewport;
+            gl::Viewport(x, y, width, height);
+        }
+
+        state.apply(&RenderState::DEFAULT);
+        let overlay = &mut self.overlay;
+        let program = overlay.model.program();
+        program.use_program();
+        for (unit, (sampler, texture)) in [
+            ("cascades", &self.directional),
+            ("spotMap", &self.spot),
+            ("pointMap", &self.point),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let unit = u32::try_from(unit).unwrap();
+            texture.bind(unit);
+            unsafe { gl::BindSampler(unit, overlay.sampler) };
+            program.set_uniform_1i(sampler, try_into!(unit))?;
+        }
+        program.set_uniform_1i("mode", mode)?;
+        program.set_uniform_1i("layer", try_into!(layer))?;
+        overlay.model.bind();
+        let result = overlay.model.try_draw_arrays(Primitive::Triangles);
+        overlay.model.unbind();
+
+        unsafe {
+            for unit in 0..3 {
+                gl::BindSampler(unit, 0);
+            }
+            let [x, y, width, height] = previous_viewport;
+            gl::Viewport(x, y, width, height);
+        }
+        debug::check_errors("ShadowMaps::draw_overlay");
+        result
+    }
+}
+
+/// Clears the attached depth map and draws the scene's shadow casters into it.
+fn draw_pass(
+    renderer: &mut Renderer,
+    scene: &mut Scene,
+    state: &mut StateTracker,
+    material: &Material,
+    resolution: u32,
+) -> Result<()> {
+    unsafe {
+        gl::Viewport(0, 0, try_into!(resolution), try_into!(resolution));
+    }
+    state.clear(&Clear::new().depth(1.0));
+    renderer.render_with(scene, state, material)
+}
+
+/// A depth texture that compares against a reference value when sampled, which lets hardware
+/// filtering blend the results of neighbouring texels. Lookups outside 2D maps count as lit.
+unsafe fn new_depth_texture<F: FnOnce(gl::types::GLenum)>(
+    target: Target,
+    allocate: F,
+) -> gl::types::GLuint {
+    let target = target.into();
+    let mut texture = 0;
+    gl::GenTextures(1, &mut texture);
+    gl::BindTexture(target, texture);
+    allocate(target);
+
+    gl::TexParameteri(target, gl::TEXTURE_MIN_FILTER, try_into!(gl::LINEAR));
+    gl::TexParameteri(target, gl::TEXTURE_MAG_FILTER, try_into!(gl::LINEAR));
+    gl::TexParameteri(
+        target,
+        gl::TEXTURE_COMPARE_MODE,
+        try_into!(gl::COMPARE_REF_TO_TEXTURE),
+    );
+    gl::TexParameteri(target, gl::TEXTURE_COMPARE_FUNC, try_into!(gl::LEQUAL));
+    if target == gl::TEXTURE_CUBE_MAP {
+        for wrap in [gl::TEXTURE_WRAP_S, gl::TEXTURE_WRAP_T, gl::TEXTURE_WRAP_R] {
+            gl::TexParameteri(target, wrap, try_into!(gl::CLAMP_TO_EDGE));
+        }
+    } else {
+        for wrap in [gl::TEXTURE_WRAP_S, gl::TEXTURE_WRAP_T] {
+            gl::TexParameteri(target, wrap, try_into!(gl::CLAMP_TO_BORDER));
+        }
+        let border = [1.0f32; 4];
+        gl::TexParameterfv(target, gl::TEXTURE_BORDER_COLOR, border.as_ptr());
+    }
+    gl::BindTexture(target, 0);
+    texture
+}
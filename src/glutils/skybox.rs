@@ -0,0 +1,72 @@
+use super::{
+    material::Material,
+    mesh::{shapes, MeshOptions},
+    model::{primitives::Primitive, usage::Usage, Model},
+    render_state::{comparison::Comparison, RenderState, StateTracker},
+    shader::{program::Linker, Shader, ShaderType},
+    texture::{target::Target, Texture},
+    uniform_buffer::{Matrices, UniformBuffer},
+};
+use anyhow::{format_err, Result};
+use std::path::PathBuf;
+
+/// Drawn at the far plane, behind everything else, and visible from inside the cube.
+const SKYBOX_STATE: RenderState = RenderState {
+    depth_test: Some(Comparison::LessEqual),
+    depth_write: false,
+    cull_mode: None,
+    ..RenderState::DEFAULT
+};
+
+/// A cube map drawn around the camera as if infinitely far away. It's meant to be drawn after
+/// opaque geometry so that the depth test skips the parts of the sky that are covered, and
+/// before anything transparent.
+pub struct Skybox {
+    model: Model,
+    material: Material,
+}
+
+impl Skybox {
+    /// Reads the camera from `matrices`, ignoring the translation of its view matrix.
+    pub fn new(cubemap: Texture, matrices: &UniformBuffer<Matrices>) -> Result<Self> {
+        if cubemap.target != Target::CubeMap {
+            return Err(format_err!("skybox needs a cube map texture"));
+        }
+
+        let shaders_dir = PathBuf::new().join("shaders").join("skybox");
+        let vs = Shader::new(
+            shaders_dir.join("skybox_vertex_shader.glsl"),
+            ShaderType::Vertex,
+        )?;
+        let fs = Shader::new(
+            shaders_dir.join("skybox_fragment_shader.glsl"),
+            ShaderType::Fragment,
+        )?;
+        let program = Linker::new().attach_shader(vs).attach_shader(fs).link()?;
+        matrices.attach(&program, "Matrices")?;
+
+        let cube = shapes::cube(
+            2.0,
+            MeshOptions {
+                normals: false,
+                tangents: false,
+                uvs: false,
+            },
+        );
+        let model = Model::from_mesh(&cube, program, Usage::Static)?;
+
+        let mut material = Material::new(program);
+        material.set_texture("skybox", cubemap);
+        material.set_render_state(SKYBOX_STATE);
+
+        Ok(Self { model, material })
+    }
+
+    pub fn cubemap(&self) -> Texture {
+        *self.material.texture("skybox").unwrap()
+    }
+
+    pub fn draw(&mut self, state: &mut StateTracker) -> Result<()> {
+        self.model.draw(&self.material, state, Primitive::Triangles)
+    }
+}
@@ -0,0 +1,238 @@
+use super::{
+    framebuffer::{attachment::ColorFormat, Framebuffer, FramebufferBuilder},
+    material::Material,
+    mesh::{shapes, MeshOptions},
+    model::{primitives::Primitive, usage::Usage, Model},
+    render_state::{RenderState, StateTracker},
+    shader::{program::Linker, Shader, ShaderType},
+    texture::{filter::Filter, wrap::Wrap, Texture, TextureBuilder},
+    uniform_buffer::{Matrices, UniformBuffer},
+};
+use anyhow::Result;
+use glam::{vec2, vec3, Vec3};
+use glfw::WindowEvent;
+use std::path::PathBuf;
+
+/// Length of the sample kernel array in `ssao_fragment_shader.glsl`.
+pub const MAX_KERNEL_SIZE: u32 = 64;
+
+/// Width and height of the tiled texture of random kernel rotations. The blur averages blocks
+/// of the same size, which hides the pattern it leaves.
+const NOISE_SIZE: u32 = 4;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SsaoSettings {
+    /// Radius of the hemisphere sampled around each pixel, in world units.
+    pub radius: f32,
+    /// How much closer than a sample a surface has to be to occlude it, which keeps flat
+    /// surfaces from shadowing themselves.
+    pub bias: f32,
+    /// Samples taken per pixel, at most [`MAX_KERNEL_SIZE`]. More samples trade speed for less
+    /// noise.
+    pub samples: u32,
+    /// Exponent the unoccluded fraction is raised to, above 1 to darken the occlusion.
+    pub power: f32,
+    /// Whether to blur away the noise left by rotating the kernel per pixel.
+    pub blur: bool,
+}
+
+impl Default for SsaoSettings {
+    fn default() -> Self {
+        Self {
+            radius: 0.5,
+            bias: 0.025,
+            samples: 32,
+            power: 1.5,
+            blur: true,
+        }
+    }
+}
+
+/// Screen-space ambient occlusion: estimates how much of the hemisphere above each pixel of a
+/// G-buffer is blocked by nearby geometry, by checking whether random points in it lie behind
+/// the surfaces the G-buffer saw. The result is one value per pixel, 1 where nothing occludes.
+pub struct Ssao {
+    settings: SsaoSettings,
+    /// The kernel for `settings.samples`, regenerated when the count changes.
+    kernel_size: u32,
+    occlusion: Framebuffer,
+    blurred: Framebuffer,
+    quad: Model,
+    material: Material,
+    blur_material: Material,
+    noise: Texture,
+}
+
+impl Ssao {
+    /// Makes buffers of `width` by `height` pixels, which should match the G-buffer. The
+    /// program reads the camera from `matrices`.
+    pub fn new(
+        width: u32,
+        height: u32,
+        settings: SsaoSettings,
+        matrices: &UniformBuffer<Matrices>,
+    ) -> Result<Self> {
+        let occlusion = FramebufferBuilder::new(width, height)
+            .color_attachment(ColorFormat::R8)
+            .build()?;
+        let blurred = FramebufferBuilder::new(width, height)
+            .color_attachment(ColorFormat::R8)
+            .build()?;
+
+        let link = |fragment: &str| -> Result<_> {
+            let vs = Shader::new(
+                PathBuf::new()
+                    .join("shaders")
+                    .join("postprocess")
+                    .join("fullscreen_vertex_shader.glsl"),
+                ShaderType::Vertex,
+            )?;
+            let fs = Shader::new(
+                PathBuf::new()
+                    .join("shaders")
+                    .join("deferred")
+                    .join(fragment),
+                ShaderType::Fragment,
+            )?;
+            Linker::new().attach_shader(vs).attach_shader(fs).link()
+        };
+        let program = link("ssao_fragment_shader.glsl")?;
+        let blur_program = link("ssao_blur_fragment_shader.glsl")?;
+        matrices.attach(&program, "Matrices")?;
+
+        let quad = Model::from_mesh(
+            &shapes::fullscreen_quad(MeshOptions {
+                normals: false,
+                tangents: false,
+                uvs: true,
+            }),
+            program,
+            Usage::Static,
+        )?;
+
+        let mut material = Material::new(program);
+        material.set_render_state(RenderState::DEFAULT);
+        let mut blur_material = Material::new(blur_program);
+        blur_material.set_render_state(RenderState::DEFAULT);
+
+        Ok(Self {
+            settings,
+            kernel_size: 0,
+            occlusion,
+            blurred,
+            quad,
+            material,
+            blur_material,
+            noise: noise_texture()?,
+        })
+    }
+
+    pub fn settings(&self) -> &SsaoSettings {
+        &self.settings
+    }
+
+    pub fn settings_mut(&mut self) -> &mut SsaoSettings {
+        &mut self.settings
+    }
+
+    /// Keeps the buffers the same size as the window.
+    pub fn handle_event(&mut self, event: &WindowEvent) -> Result<()> {
+        self.occlusion.handle_event(event)?;
+        self.blurred.handle_event(event)
+    }
+
+    /// Computes the occlusion of the surfaces in `gbuffer`, whose first two attachments hold
+    /// world space positions, with alpha marking covered pixels, and normals. Leaves one of its
+    /// own framebuffers bound.
+    pub fn render(&mut self, state: &mut StateTracker, gbuffer: &Framebuffer) -> Result<Texture> {
+        let settings = self.settings;
+        let samples = settings.samples.clamp(1, MAX_KERNEL_SIZE);
+        if samples != self.kernel_size {
+            for (i, sample) in kernel(samples).into_iter().enumerate() {
+                self.material.set(&format!("samples[{i}]"), sample);
+            }
+            self.kernel_size = samples;
+        }
+
+        self.material
+            .set_texture("gPosition", *gbuffer.color_texture(0).unwrap());
+        self.material
+            .set_texture("gNormal", *gbuffer.color_texture(1).unwrap());
+        self.material.set_texture("noise", self.noise);
+        self.material.set("kernelSize", i32::try_from(samples)?);
+        self.material.set("radius", settings.radius);
+        self.material.set("bias", settings.bias);
+        self.material.set("power", settings.power);
+        self.material.set(
+            "noiseScale",
+            vec2(
+                self.occlusion.width() as f32 / NOISE_SIZE as f32,
+                self.occlusion.height() as f32 / NOISE_SIZE as f32,
+            ),
+        );
+
+        self.occlusion.bind();
+        self.quad
+            .draw(&self.material, state, Primitive::Triangles)?;
+        let occlusion = *self.occlusion.color_texture(0).unwrap();
+        if !settings.blur {
+            return Ok(occlusion);
+        }
+
+        self.blurred.bind();
+        self.blur_material.set_texture("ssaoInput", occlusion);
+        self.quad
+            .draw(&self.blur_material, state, Primitive::Triangles)?;
+        Ok(*self.blurred.color_texture(0).unwrap())
+    }
+
+    pub fn delete(self) {
+        self.occlusion.delete();
+        self.blurred.delete();
+        self.noise.delete();
+    }
+}
+
+/// Points in the unit hemisphere around +Z, more of them close to the center where occluders
+/// matter most.
+fn kernel(size: u32) -> Vec<Vec3> {
+    (0..size)
+        .map(|i| {
+            let direction = vec3(
+                random(i, 0) * 2.0 - 1.0,
+                random(i, 1) * 2.0 - 1.0,
+                random(i, 2),
+            )
+            .normalize_or(Vec3::Z);
+            let t = i as f32 / size as f32;
+            direction * random(i, 3) * (0.1 + 0.9 * t * t)
+        })
+        .collect()
+}
+
+/// Random rotations about the normal, as tangent space directions in the XY plane packed into
+/// `[0, 1]`. Nearest filtered and repeated, so that each pixel of a tile gets its own.
+fn noise_texture() -> Result<Texture> {
+    let texels: Vec<u8> = (0..NOISE_SIZE * NOISE_SIZE)
+        .flat_map(|i| {
+            let [x, y] = [random(i, 4), random(i, 5)].map(|c| (c * 255.0).round() as u8);
+            [x, y, 0, 255]
+        })
+        .collect();
+    TextureBuilder::new()
+        .wrap(Wrap::Repeat, Wrap::Repeat)
+        .filter(Filter::Nearest, Filter::Nearest)?
+        .build_from_rgba(NOISE_SIZE, NOISE_SIZE, &texels)
+}
+
+/// A pseudo-random number in `[0, 1)` that's the same for the same `index` and `stream` on
+/// every run, so that the occlusion doesn't change between runs.
+fn random(index: u32, stream: u32) -> f32 {
+    let mut x = index.wrapping_mul(0x9e37_79b9) ^ stream.wrapping_mul(0x85eb_ca6b);
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb_352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846c_a68b);
+    x ^= x >> 16;
+    (x >> 8) as f32 / (1 << 24) as f32
+}
@@ -0,0 +1,234 @@
+use crate::glutils::texture::{filter::Filter, wrap::Wrap, Texture, TextureBuilder};
+use ab_glyph::{point, Font as _, FontVec, GlyphId, PxScale, ScaleFont};
+use anyhow::{format_err, Result};
+use glam::{vec2, Vec2};
+use std::{collections::HashMap, fs, path::Path};
+
+/// Width of the glyph atlas in texels. It's as tall as it needs to be.
+const ATLAS_WIDTH: u32 = 512;
+
+/// Empty texels around each glyph, so that linear filtering doesn't bleed neighbours in.
+const PADDING: u32 = 1;
+
+/// Drawn in place of characters that aren't in the atlas.
+const REPLACEMENT: char = '?';
+
+/// A glyph's bitmap in the atlas and how to place it, in pixels at the atlas's size.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Glyph {
+    id: GlyphId,
+    /// From the pen position on the baseline to the bitmap's top left corner, with y down.
+    pub offset: Vec2,
+    pub size: Vec2,
+    /// Texture coordinates of the bitmap's top left and bottom right corners.
+    pub uv_min: Vec2,
+    pub uv_max: Vec2,
+    /// How far the pen moves past the glyph.
+    pub advance: f32,
+}
+
+/// A TrueType font rasterized once at a fixed pixel size into an atlas texture of white glyphs
+/// with coverage in alpha. Text can be drawn at other sizes by scaling the glyphs' quads, which
+/// stays crisp up to about the rasterized size.
+pub struct Font {
+    font: FontVec,
+    pixel_size: f32,
+    glyphs: HashMap<char, Glyph>,
+    atlas: Texture,
+}
+
+impl Font {
+    /// Rasterizes printable ASCII and Latin-1 from the font at `path`, `pixel_size` pixels from
+    /// the highest ascender to the lowest descender.
+    pub fn from_path<P: AsRef<Path>>(path: P, pixel_size: f32) -> Result<Self> {
+        let font = read(path.as_ref())?;
+        let (glyphs, atlas_height, texels) = rasterize(&font, pixel_size, latin_1())?;
+        let atlas = TextureBuilder::new()
+            .wrap(Wrap::ClampToEdge, Wrap::ClampToEdge)
+            .filter(Filter::Linear, Filter::Linear)?
+            .build_from_rgba(ATLAS_WIDTH, atlas_height, &texels)?;
+
+        Ok(Self {
+            font,
+            pixel_size,
+            glyphs,
+            atlas,
+        })
+    }
+
+    /// The size the glyphs were rasterized at.
+    pub fn pixel_size(&self) -> f32 {
+        self.pixel_size
+    }
+
+    pub fn atlas(&self) -> Texture {
+        self.atlas
+    }
+
+    /// The glyph for `c`, or a replacement if the atlas doesn't have it.
+    pub fn glyph(&self, c: char) -> &Glyph {
+        self.glyphs
+            .get(&c)
+            .unwrap_or_else(|| &self.glyphs[&REPLACEMENT])
+    }
+
+    /// Extra space between two consecutive glyphs, usually negative, e.g. to tuck a `V` under
+    /// an `A`.
+    pub fn kerning(&self, first: &Glyph, second: &Glyph) -> f32 {
+        self.font
+            .as_scaled(PxScale::from(self.pixel_size))
+            .kern(first.id, second.id)
+    }
+
+    /// From the top of a line to its baseline.
+    pub fn ascent(&self) -> f32 {
+        self.font.as_scaled(PxScale::from(self.pixel_size)).ascent()
+    }
+
+    /// From one line's baseline to the next.
+    pub fn line_height(&self) -> f32 {
+        let scaled = self.font.as_scaled(PxScale::from(self.pixel_size));
+        scaled.ascent() - scaled.descent() + scaled.line_gap()
+    }
+
+    pub fn delete(self) {
+        self.atlas.delete();
+    }
+}
+
+fn read(path: &Path) -> Result<FontVec> {
+    let bytes =
+        fs::read(path).map_err(|e| format_err!("failed to load '{}': {e}", path.display()))?;
+    FontVec::try_from_vec(bytes)
+        .map_err(|e| format_err!("failed to parse '{}': {e}", path.display()))
+}
+
+/// Printable ASCII and Latin-1.
+fn latin_1() -> impl Iterator<Item = char> {
+    (' '..='~').chain('\u{a0}'..='\u{ff}')
+}
+
+/// Rasterizes `chars` and packs them into the texels of an [`ATLAS_WIDTH`] wide RGBA atlas,
+/// returning the glyphs by character along with the atlas's height and texels.
+fn rasterize(
+    font: &FontVec,
+    pixel_size: f32,
+    chars: impl Iterator<Item = char>,
+) -> Result<(HashMap<char, Glyph>, u32, Vec<u8>)> {
+    let scale = PxScale::from(pixel_size);
+    let scaled = font.as_scaled(scale);
+
+    // Rasterizes every glyph first, so that the atlas's height is known before packing.
+    let mut bitmaps = vec![];
+    for c in chars {
+        let id = font.glyph_id(c);
+        if id.0 == 0 && c != REPLACEMENT {
+            continue;
+        }
+        let glyph = id.with_scale_and_position(scale, point(0.0, 0.0));
+        let (offset, width, height, coverage) = match font.outline_glyph(glyph) {
+            Some(outlined) => {
+                let bounds = outlined.px_bounds();
+                let (width, height) = (bounds.width() as u32, bounds.height() as u32);
+                let mut coverage = vec![0u8; usize::try_from(width * height)?];
+                outlined.draw(|x, y, c| {
+                    if x < width && y < height {
+                        coverage[usize::try_from(y * width + x).unwrap()] =
+                            (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+                    }
+                });
+                (vec2(bounds.min.x, bounds.min.y), width, height, coverage)
+            }
+            // Spaces and other blank glyphs only move the pen.
+            None => (Vec2::ZERO, 0, 0, vec![]),
+        };
+        bitmaps.push((c, id, offset, width, height, coverage));
+    }
+
+    // Shelf packing: glyphs go left to right in rows as tall as their tallest glyph.
+    let mut positions = Vec::with_capacity(bitmaps.len());
+    let (mut x, mut y, mut row_height) = (PADDING, PADDING, 0);
+    for (_, _, _, width, height, _) in &bitmaps {
+        if x + width + PADDING > ATLAS_WIDTH {
+            x = PADDING;
+            y += row_height + PADDING;
+            row_height = 0;
+        }
+        positions.push((x, y));
+        x += width + PADDING;
+        row_height = row_height.max(*height);
+    }
+    let atlas_height = (y + row_height + PADDING).next_power_of_two();
+
+    // White, so that the color comes from the vertices and alpha from coverage.
+    let mut texels = [255, 255, 255, 0].repeat(usize::try_from(ATLAS_WIDTH * atlas_height)?);
+    let mut glyphs = HashMap::with_capacity(bitmaps.len());
+    for ((c, id, offset, width, height, coverage), (x, y)) in bitmaps.into_iter().zip(positions) {
+        for row in 0..height {
+            for column in 0..width {
+                let texel = usize::try_from(((y + row) * ATLAS_WIDTH + x + column) * 4 + 3)?;
+                texels[texel] = coverage[usize::try_from(row * width + column)?];
+            }
+        }
+        let atlas_size = vec2(ATLAS_WIDTH as f32, atlas_height as f32);
+        let size = vec2(width as f32, height as f32);
+        let uv_min = vec2(x as f32, y as f32) / atlas_size;
+        glyphs.insert(
+            c,
+            Glyph {
+                id,
+                offset,
+                size,
+                uv_min,
+                uv_max: uv_min + size / atlas_size,
+                advance: scaled.h_advance(id),
+            },
+        );
+    }
+    if !glyphs.contains_key(&REPLACEMENT) {
+        return Err(format_err!("font has no '{REPLACEMENT}' glyph"));
+    }
+    Ok((glyphs, atlas_height, texels))
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use crate::glutils::texture::target::Target;
+
+    /// DejaVu Sans without an atlas texture, for laying text out without a GL context.
+    pub(in crate::glutils::text) fn dejavu_sans(pixel_size: f32) -> Font {
+        let font = read(Path::new("fonts/DejaVuSans.ttf")).unwrap();
+        let (glyphs, atlas_height, _) = rasterize(&font, pixel_size, latin_1()).unwrap();
+        Font {
+            font,
+            pixel_size,
+            glyphs,
+            atlas: Texture {
+                gl_object_id: 0,
+                width: ATLAS_WIDTH,
+                height: atlas_height,
+                target: Target::Texture2D,
+            },
+        }
+    }
+
+    #[test]
+    fn packs_glyphs_inside_the_atlas() {
+        let font = dejavu_sans(32.0);
+        assert!(font.atlas().height.is_power_of_two());
+        for c in latin_1() {
+            let glyph = font.glyph(c);
+            assert!(glyph.uv_min.cmpge(Vec2::ZERO).all(), "{c:?}");
+            assert!(glyph.uv_max.cmple(Vec2::ONE).all(), "{c:?}");
+        }
+        assert_eq!(font.glyph(' ').size, Vec2::ZERO);
+        assert!(font.glyph(' ').advance > 0.0);
+    }
+
+    #[test]
+    fn missing_characters_fall_back_to_the_replacement() {
+        let font = dejavu_sans(32.0);
+        assert_eq!(font.glyph('\u{4e00}'), font.glyph(REPLACEMENT));
+    }
+}
@@ -0,0 +1,169 @@
+use super::font::{Font, Glyph};
+use glam::{vec2, Vec2};
+
+/// Where one glyph's bitmap goes, in pixels from the text's top left corner with y down.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Quad {
+    pub min: Vec2,
+    pub max: Vec2,
+    pub uv_min: Vec2,
+    pub uv_max: Vec2,
+}
+
+/// A string broken into lines and placed glyph by glyph.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Layout {
+    pub quads: Vec<Quad>,
+    /// Width of the widest line and height of all the lines.
+    pub size: Vec2,
+}
+
+/// Lays `text` out at `size` pixels per line, starting a new line at each `\n` and, if
+/// `max_width` is given, before words that would run past it. Words longer than a whole line
+/// are left to overflow.
+pub fn layout(font: &Font, text: &str, size: f32, max_width: Option<f32>) -> Layout {
+    let scale = size / font.pixel_size();
+    let line_height = font.line_height() * scale;
+    let space = font.glyph(' ').advance * scale;
+
+    let mut layout = Layout::default();
+    let mut baseline = font.ascent() * scale;
+    for line in text.split('\n') {
+        let mut x = 0.0;
+        for (i, word) in line.split(' ').enumerate() {
+            let start = if i == 0 { 0.0 } else { x + space };
+            let fits = max_width.is_none_or(|max| start + word_width(font, word, scale) <= max);
+            if !fits && x > 0.0 {
+                layout.size.x = layout.size.x.max(x);
+                baseline += line_height;
+                x = 0.0;
+            } else {
+                x = start;
+            }
+
+            let mut previous: Option<&Glyph> = None;
+            for c in word.chars() {
+                let glyph = font.glyph(c);
+                if let Some(previous) = previous {
+                    x += font.kerning(previous, glyph) * scale;
+                }
+                if glyph.size != Vec2::ZERO {
+                    let min = vec2(x, baseline) + glyph.offset * scale;
+                    layout.quads.push(Quad {
+                        min,
+                        max: min + glyph.size * scale,
+                        uv_min: glyph.uv_min,
+                        uv_max: glyph.uv_max,
+                    });
+                }
+                x += glyph.advance * scale;
+                previous = Some(glyph);
+            }
+        }
+        layout.size.x = layout.size.x.max(x);
+        baseline += line_height;
+    }
+    layout.size.y = baseline - font.ascent() * scale;
+    layout
+}
+
+/// How far `text` moves the pen, kerning included.
+fn word_width(font: &Font, text: &str, scale: f32) -> f32 {
+    let mut width = 0.0;
+    let mut previous: Option<&Glyph> = None;
+    for c in text.chars() {
+        let glyph = font.glyph(c);
+        if let Some(previous) = previous {
+            width += font.kerning(previous, glyph);
+        }
+        width += glyph.advance;
+        previous = Some(glyph);
+    }
+    width * scale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::glutils::text::font::tests::dejavu_sans;
+
+    const SIZE: f32 = 32.0;
+
+    fn width(font: &Font, text: &str) -> f32 {
+        layout(font, text, SIZE, None).size.x
+    }
+
+    fn line_height(font: &Font) -> f32 {
+        font.line_height() * SIZE / font.pixel_size()
+    }
+
+    /// Top edges of the quads' lines, in order.
+    fn line_tops(layout: &Layout) -> Vec<f32> {
+        let mut tops: Vec<f32> = vec![];
+        for quad in &layout.quads {
+            let top = quad.min.y;
+            if !tops.iter().any(|t| (t - top).abs() < SIZE / 2.0) {
+                tops.push(top);
+            }
+        }
+        tops
+    }
+
+    #[test]
+    fn single_line_is_one_line_high() {
+        let font = dejavu_sans(SIZE);
+        let layout = layout(&font, "Hello", SIZE, None);
+        assert_eq!(layout.quads.len(), 5);
+        assert!((layout.size.y - line_height(&font)).abs() < 1e-3);
+        assert!(layout.size.x > 0.0);
+    }
+
+    #[test]
+    fn size_covers_every_line() {
+        let font = dejavu_sans(SIZE);
+        for lines in 1..=4 {
+            let text = vec!["Text"; lines].join("\n");
+            let layout = layout(&font, &text, SIZE, None);
+            let expected = lines as f32 * line_height(&font);
+            assert!((layout.size.y - expected).abs() < 1e-3, "{lines} lines");
+            assert_eq!(line_tops(&layout).len(), lines);
+        }
+    }
+
+    #[test]
+    fn empty_lines_still_advance_the_baseline() {
+        let font = dejavu_sans(SIZE);
+        let layout = layout(&font, "A\n\nB", SIZE, None);
+        assert!((layout.size.y - 3.0 * line_height(&font)).abs() < 1e-3);
+        let tops = line_tops(&layout);
+        assert_eq!(tops.len(), 2);
+        assert!((tops[1] - tops[0] - 2.0 * line_height(&font)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn wraps_words_at_max_width() {
+        let font = dejavu_sans(SIZE);
+        let max_width = width(&font, "one two") + 1.0;
+        let layout = layout(&font, "one two three", SIZE, Some(max_width));
+
+        assert_eq!(line_tops(&layout).len(), 2);
+        assert!((layout.size.y - 2.0 * line_height(&font)).abs() < 1e-3);
+        assert!(layout.size.x <= max_width);
+        // "three" starts the second line at the left edge.
+        let second_line = layout.quads.iter().filter(|q| q.min.y > SIZE / 2.0);
+        let left = second_line.map(|q| q.min.x).fold(f32::MAX, f32::min);
+        assert!(left < SIZE / 4.0, "{left}");
+    }
+
+    #[test]
+    fn over_long_words_overflow() {
+        let font = dejavu_sans(SIZE);
+        let word = "incomprehensibilities";
+        let max_width = width(&font, word) / 2.0;
+        let layout = layout(&font, &format!("{word} a"), SIZE, Some(max_width));
+
+        assert_eq!(line_tops(&layout).len(), 2);
+        assert!((layout.size.x - width(&font, word)).abs() < 1e-3);
+        assert!(layout.size.x > max_width);
+    }
+}
@@ -0,0 +1,132 @@
+use super::{
+    material::Material,
+    model::{primitives::Primitive, stream::VertexStream},
+    render_state::{RenderState, StateTracker},
+    shader::{program::Linker, Shader, ShaderType},
+};
+use anyhow::Result;
+use glam::{vec2, Vec2, Vec4};
+use std::path::{Path, PathBuf};
+
+pub mod font;
+use font::Font;
+
+pub mod layout;
+use layout::{layout, Layout};
+
+/// Floats per vertex: position, texture coordinates and color.
+const VERTEX_SIZE: usize = 8;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TextStyle {
+    pub color: Vec4,
+    /// Height of a line in pixels.
+    pub size: f32,
+    /// Width in pixels past which lines wrap at the previous word, if any.
+    pub max_width: Option<f32>,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            color: Vec4::ONE,
+            size: 20.0,
+            max_width: None,
+        }
+    }
+}
+
+/// Draws text in screen space. Strings are queued by [`TextRenderer::draw`] and drawn together
+/// by [`TextRenderer::flush`], on top of whatever is in the framebuffer.
+pub struct TextRenderer {
+    font: Font,
+    material: Material,
+    stream: VertexStream,
+    vertices: Vec<f32>,
+}
+
+impl TextRenderer {
+    /// Rasterizes the font at `path` at `pixel_size`, the largest size text should look crisp
+    /// at.
+    pub fn new<P: AsRef<Path>>(path: P, pixel_size: f32) -> Result<Self> {
+        let shaders_dir = PathBuf::new().join("shaders").join("text");
+        let vs = Shader::new(
+            shaders_dir.join("text_vertex_shader.glsl"),
+            ShaderType::Vertex,
+        )?;
+        let fs = Shader::new(
+            shaders_dir.join("text_fragment_shader.glsl"),
+            ShaderType::Fragment,
+        )?;
+        let program = Linker::new().attach_shader(vs).attach_shader(fs).link()?;
+        let stream = VertexStream::new(
+            &program,
+            &[("aPos", 2), ("aTexCoord", 2), ("aCol", 4)],
+            "text",
+        )?;
+
+        let font = Font::from_path(path, pixel_size)?;
+        let mut material = Material::new(program);
+        material.set_texture("atlas", font.atlas());
+        material.set_render_state(RenderState::OVERLAY);
+
+        Ok(Self {
+            font,
+            material,
+            stream,
+            vertices: vec![],
+        })
+    }
+
+    pub fn font(&self) -> &Font {
+        &self.font
+    }
+
+    /// The size `text` would take up in `style`, in pixels.
+    pub fn measure(&self, text: &str, style: &TextStyle) -> Vec2 {
+        self.layout(text, style).size
+    }
+
+    /// Queues `text` with its top left corner at `position`, in pixels from the top left of the
+    /// screen. Returns the size it takes up.
+    pub fn draw(&mut self, text: &str, position: Vec2, style: &TextStyle) -> Vec2 {
+        let layout = self.layout(text, style);
+        let color = style.color.to_array();
+        for quad in &layout.quads {
+            let (min, max) = (position + quad.min, position + quad.max);
+            let corners = [
+                (min, quad.uv_min),
+                (vec2(max.x, min.y), vec2(quad.uv_max.x, quad.uv_min.y)),
+                (max, quad.uv_max),
+                (vec2(min.x, max.y), vec2(quad.uv_min.x, quad.uv_max.y)),
+            ];
+            for i in [0, 3, 2, 0, 2, 1] {
+                let (position, uv) = corners[i];
+                self.vertices
+                    .extend_from_slice(&[position.x, position.y, uv.x, uv.y]);
+                self.vertices.extend_from_slice(&color);
+            }
+        }
+        debug_assert!(self.vertices.len().is_multiple_of(VERTEX_SIZE));
+        layout.size
+    }
+
+    /// Draws the queued text into the bound framebuffer, which is `width` by `height` pixels.
+    pub fn flush(&mut self, state: &mut StateTracker, width: i32, height: i32) -> Result<()> {
+        self.material
+            .set("screenSize", vec2(width as f32, height as f32));
+        self.material.bind(state)?;
+        self.stream.draw(&self.vertices, Primitive::Triangles);
+        self.vertices.clear();
+        Ok(())
+    }
+
+    pub fn delete(self) {
+        self.stream.delete();
+        self.font.delete();
+    }
+
+    fn layout(&self, text: &str, style: &TextStyle) -> Layout {
+        layout(&self.font, text, style.size, style.max_width)
+    }
+}
@@ -0,0 +1,275 @@
+use super::{debug, filter::Filter, target::Target, try_into, Texture};
+use anyhow::{format_err, Result};
+use glam::{camera::rh::view, vec3, Mat4, Vec3, Vec4};
+use image::{imageops, RgbaImage};
+use std::{f32::consts::PI, ffi::c_void, path::Path};
+
+/// Faces of a cube map in the order GL numbers them, which is also the order face images are
+/// given in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CubeFace {
+    /// Right.
+    PositiveX,
+    /// Left.
+    NegativeX,
+    /// Top.
+    PositiveY,
+    /// Bottom.
+    NegativeY,
+    /// Front, the face seen when looking down +Z.
+    PositiveZ,
+    /// Back.
+    NegativeZ,
+}
+
+impl CubeFace {
+    pub const ALL: [Self; 6] = [
+        Self::PositiveX,
+        Self::NegativeX,
+        Self::PositiveY,
+        Self::NegativeY,
+        Self::PositiveZ,
+        Self::NegativeZ,
+    ];
+
+    /// Direction that samples the face at `s` and `t`, both in `[-1, 1]` with `t` growing
+    /// downwards through the face's image.
+    pub fn direction(self, s: f32, t: f32) -> Vec3 {
+        match self {
+            Self::PositiveX => vec3(1.0, -t, -s),
+            Self::NegativeX => vec3(-1.0, -t, s),
+            Self::PositiveY => vec3(s, 1.0, t),
+            Self::NegativeY => vec3(s, -1.0, -t),
+            Self::PositiveZ => vec3(s, -t, 1.0),
+            Self::NegativeZ => vec3(-s, -t, -1.0),
+        }
+    }
+
+    /// View matrix for rendering the face from `position` with a 90° square projection, so
+    /// that the result can be sampled by direction.
+    pub fn view(self, position: Vec3) -> Mat4 {
+        let up = match self {
+            Self::PositiveY => Vec3::Z,
+            Self::NegativeY => Vec3::NEG_Z,
+            _ => Vec3::NEG_Y,
+        };
+        view::look_at_mat4(position, position + self.direction(0.0, 0.0), up)
+    }
+}
+
+impl From<CubeFace> for gl::types::GLenum {
+    fn from(value: CubeFace) -> Self {
+        gl::TEXTURE_CUBE_MAP_POSITIVE_X + value as gl::types::GLenum
+    }
+}
+
+/// Builds cube map textures. Unlike 2D textures, cube map images are given top row first, so
+/// they're never flipped. Faces are always clamped to their edges since seamless filtering takes
+/// care of sampling across them.
+pub struct CubemapBuilder {
+    min_filter: Filter,
+    mag_filter: Filter,
+}
+
+impl Default for CubemapBuilder {
+    fn default() -> Self {
+        Self {
+            min_filter: Filter::Linear,
+            mag_filter: Filter::Linear,
+        }
+    }
+}
+
+impl CubemapBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn filter(mut self, min_filter: Filter, mag_filter: Filter) -> Result<Self> {
+        if mag_filter.uses_mipmaps() {
+            return Err(format_err!(
+                "magnification filter can only be nearest or linear"
+            ));
+        }
+        self.min_filter = min_filter;
+        self.mag_filter = mag_filter;
+        Ok(self)
+    }
+
+    /// Loads one image per face, in [`CubeFace::ALL`] order: right, left, top, bottom, front and
+    /// back.
+    pub fn build_from_paths<P: AsRef<Path>>(self, paths: [P; 6]) -> Result<Texture> {
+        let faces = paths
+            .iter()
+            .map(|path| {
+                let path = path.as_ref();
+                image::open(path)
+                    .map(|img| img.into_rgba8())
+                    .map_err(|e| format_err!("failed to load '{}': {e}", path.display()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.build_from_faces(&faces.try_into().unwrap())
+    }
+
+    /// Loads a single image with the faces laid out as a horizontal (4x3 faces) or vertical
+    /// (3x4 faces) cross, with the front face at the center.
+    pub fn build_from_cross<P: AsRef<Path>>(self, path: P) -> Result<Texture> {
+        let path = path.as_ref();
+        let img = image::open(path)
+            .map_err(|e| format_err!("failed to load '{}': {e}", path.display()))?
+            .into_rgba8();
+        let faces = cross_faces(&img).map_err(|e| format_err!("'{}': {e}", path.display()))?;
+        self.build_from_faces(&faces)
+    }
+
+    /// Loads an equirectangular panorama, with longitude across and latitude down the image,
+    /// and projects it onto faces of `face_size` texels.
+    pub fn build_from_equirectangular<P: AsRef<Path>>(
+        self,
+        path: P,
+        face_size: u32,
+    ) -> Result<Texture> {
+        let path = path.as_ref();
+        let img = image::open(path)
+            .map_err(|e| format_err!("failed to load '{}': {e}", path.display()))?
+            .into_rgba8();
+        self.build_from_faces(&equirectangular_faces(&img, face_size))
+    }
+
+    /// Builds a cube map from square faces of equal size, in [`CubeFace::ALL`] order.
+    pub fn build_from_faces(self, faces: &[RgbaImage; 6]) -> Result<Texture> {
+        let size = faces[0].width();
+        if let Some((face, img)) = CubeFace::ALL
+            .iter()
+            .zip(faces)
+            .find(|(_, img)| img.width() != size || img.height() != size)
+        {
+            return Err(format_err!(
+                "cube map faces need to be square and of equal size but the {face:?} face is {}x{} \
+                 rather than {size}x{size}",
+                img.width(),
+                img.height(),
+            ));
+        }
+        let texture = unsafe { self.build_impl(size, faces) };
+        debug::check_errors("CubemapBuilder::build");
+        Ok(texture)
+    }
+
+    unsafe fn build_impl(&self, size: u32, faces: &[RgbaImage; 6]) -> Texture {
+        let mut texture = 0;
+        gl::GenTextures(1, &mut texture);
+        gl::BindTexture(gl::TEXTURE_CUBE_MAP, texture);
+
+        for wrap in [gl::TEXTURE_WRAP_S, gl::TEXTURE_WRAP_T, gl::TEXTURE_WRAP_R] {
+            gl::TexParameteri(gl::TEXTURE_CUBE_MAP, wrap, try_into!(gl::CLAMP_TO_EDGE));
+        }
+        gl::TexParameteri(
+            gl::TEXTURE_CUBE_MAP,
+            gl::TEXTURE_MIN_FILTER,
+            try_into!(gl::types::GLenum::from(self.min_filter)),
+        );
+        gl::TexParameteri(
+            gl::TEXTURE_CUBE_MAP,
+            gl::TEXTURE_MAG_FILTER,
+            try_into!(gl::types::GLenum::from(self.mag_filter)),
+        );
+
+        gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+        for (face, img) in CubeFace::ALL.iter().zip(faces) {
+            gl::TexImage2D(
+                (*face).into(),
+                0,
+                try_into!(gl::RGBA8),
+                try_into!(size),
+                try_into!(size),
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                img.as_raw().as_ptr() as *const c_void,
+            );
+        }
+
+        if self.min_filter.uses_mipmaps() {
+            gl::GenerateMipmap(gl::TEXTURE_CUBE_MAP);
+        }
+
+        // Filters across face edges rather than within each face. It's global state but there's
+        // no reason to ever turn it off again.
+        gl::Enable(gl::TEXTURE_CUBE_MAP_SEAMLESS);
+        gl::BindTexture(gl::TEXTURE_CUBE_MAP, 0);
+
+        Texture {
+            gl_object_id: texture,
+            width: size,
+            height: size,
+            target: Target::CubeMap,
+        }
+    }
+}
+
+/// Cuts the faces out of a horizontal or vertical cross, in [`CubeFace::ALL`] order.
+pub fn cross_faces(img: &RgbaImage) -> Result<[RgbaImage; 6]> {
+    let (width, height) = img.dimensions();
+    let (size, vertical) = if width * 3 == height * 4 {
+        (width / 4, false)
+    } else if width * 4 == height * 3 {
+        (width / 3, true)
+    } else {
+        return Err(format_err!(
+            "a {width}x{height} image isn't laid out as a 4x3 or 3x4 cross of cube map faces"
+        ));
+    };
+
+    let face = |column: u32, row: u32| {
+        imageops::crop_imm(img, column * size, row * size, size, size).to_image()
+    };
+    let back = if vertical {
+        // The back face hangs below the bottom one, so it's upside down.
+        imageops::rotate180(&face(1, 3))
+    } else {
+        face(3, 1)
+    };
+    Ok([
+        face(2, 1),
+        face(0, 1),
+        face(1, 0),
+        face(1, 2),
+        face(1, 1),
+        back,
+    ])
+}
+
+/// Projects an equirectangular panorama onto faces of `size` texels, in [`CubeFace::ALL`] order.
+pub fn equirectangular_faces(img: &RgbaImage, size: u32) -> [RgbaImage; 6] {
+    CubeFace::ALL.map(|face| {
+        RgbaImage::from_fn(size, size, |x, y| {
+            let s = 2.0 * (x as f32 + 0.5) / size as f32 - 1.0;
+            let t = 2.0 * (y as f32 + 0.5) / size as f32 - 1.0;
+            let direction = face.direction(s, t).normalize();
+
+            let u = direction.z.atan2(direction.x) / (2.0 * PI) + 0.5;
+            let v = direction.y.clamp(-1.0, 1.0).acos() / PI;
+            let texel = sample_bilinear(img, u, v);
+            image::Rgba(texel.round().to_array().map(|c| c as u8))
+        })
+    })
+}
+
+/// Samples `img` at `u` and `v` in `[0, 1]`, wrapping around horizontally.
+fn sample_bilinear(img: &RgbaImage, u: f32, v: f32) -> Vec4 {
+    let (width, height) = img.dimensions();
+    let x = u * width as f32 - 0.5;
+    let y = (v * height as f32 - 0.5).clamp(0.0, (height - 1) as f32);
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+
+    let texel = |x: f32, y: f32| {
+        let x = (x as i64).rem_euclid(i64::from(width)) as u32;
+        let y = (y as u32).min(height - 1);
+        Vec4::from_array(img.get_pixel(x, y).0.map(f32::from))
+    };
+    let top = texel(x0, y0).lerp(texel(x0 + 1.0, y0), fx);
+    let bottom = texel(x0, y0 + 1.0).lerp(texel(x0 + 1.0, y0 + 1.0), fx);
+    top.lerp(bottom, fy)
+}
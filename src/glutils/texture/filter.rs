@@ -0,0 +1,29 @@
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Filter {
+    Nearest,
+    Linear,
+    NearestMipmapNearest,
+    LinearMipmapNearest,
+    NearestMipmapLinear,
+    LinearMipmapLinear,
+}
+
+impl Filter {
+    /// Whether sampling with this filter reads from the mipmap chain.
+    pub fn uses_mipmaps(&self) -> bool {
+        !matches!(self, Self::Nearest | Self::Linear)
+    }
+}
+
+impl From<Filter> for gl::types::GLenum {
+    fn from(value: Filter) -> Self {
+        match value {
+            Filter::Nearest => gl::NEAREST,
+            Filter::Linear => gl::LINEAR,
+            Filter::NearestMipmapNearest => gl::NEAREST_MIPMAP_NEAREST,
+            Filter::LinearMipmapNearest => gl::LINEAR_MIPMAP_NEAREST,
+            Filter::NearestMipmapLinear => gl::NEAREST_MIPMAP_LINEAR,
+            Filter::LinearMipmapLinear => gl::LINEAR_MIPMAP_LINEAR,
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use super::{debug, try_into};
+use anyhow::{format_err, Result};
+use image::DynamicImage;
+use std::{ffi::c_void, path::Path};
+
+pub mod cubemap;
+
+pub mod filter;
+use filter::Filter;
+
+pub mod target;
+use target::Target;
+
+pub mod wrap;
+use wrap::Wrap;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Texture {
+    pub gl_object_id: gl::types::GLuint,
+    pub width: u32,
+    pub height: u32,
+    pub target: Target,
+}
+
+pub struct TextureBuilder {
+    wrap_s: Wrap,
+    wrap_t: Wrap,
+    min_filter: Filter,
+    mag_filter: Filter,
+    flip_vertically: bool,
+}
+
+impl Texture {
+    /// Binds the texture to the given texture unit, i.e. `unit` 0 is `GL_TEXTURE0`.
+    pub fn bind(&self, unit: u32) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(self.target.into(), self.gl_object_id);
+        }
+    }
+
+    pub fn unbind(unit: u32) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+    }
+
+    /// Replaces a `width` by `height` region of a 2D texture, starting at texel `x`, `y`, with
+    /// tightly packed 8-bit RGBA texels. Mipmaps aren't regenerated.
+    pub fn update_rgba(
+        &self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        texels: &[u8],
+    ) -> Result<()> {
+        if self.target != Target::Texture2D {
+            return Err(format_err!("only 2D textures can be updated"));
+        }
+        if x + width > self.width || y + height > self.height {
+            return Err(format_err!(
+                "{width}x{height} region at ({x}, {y}) is outside the {}x{} texture",
+                self.width,
+                self.height
+            ));
+        }
+        if texels.len() != usize::try_from(width * height * 4)? {
+            return Err(format_err!(
+                "expected {width}x{height} RGBA texels but got {} bytes",
+                texels.len()
+            ));
+        }
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.gl_object_id);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+            gl::TexSubImage2D(
+                gl::TEXTURE_2D,
+                0,
+                try_into!(x),
+                try_into!(y),
+                try_into!(width),
+                try_into!(height),
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                texels.as_ptr() as *const c_void,
+            );
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+        debug::check_errors("Texture::update_rgba");
+        Ok(())
+    }
+
+    pub fn delete(self) {
+        unsafe { gl::DeleteTextures(1, &self.gl_object_id) }
+    }
+}
+
+impl Default for TextureBuilder {
+    fn default() -> Self {
+        Self {
+            wrap_s: Wrap::Repeat,
+            wrap_t: Wrap::Repeat,
+            min_filter: Filter::LinearMipmapLinear,
+            mag_filter: Filter::Linear,
+            flip_vertically: false,
+        }
+    }
+}
+
+impl TextureBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn wrap(mut self, wrap_s: Wrap, wrap_t: Wrap) -> Self {
+        self.wrap_s = wrap_s;
+        self.wrap_t = wrap_t;
+        self
+    }
+
+    pub fn filter(mut self, min_filter: Filter, mag_filter: Filter) -> Result<Self> {
+        if mag_filter.uses_mipmaps() {
+            return Err(format_err!(
+                "magnification filter can only be nearest or linear"
+            ));
+        }
+        self.min_filter = min_filter;
+        self.mag_filter = mag_filter;
+        Ok(self)
+    }
+
+    /// OpenGL expects the first row of texel data to be the bottom of the image whereas most
+    /// image formats store the top row first.
+    pub fn flip_vertically(mut self, flip: bool) -> Self {
+        self.flip_vertically = flip;
+        self
+    }
+
+    pub fn build_from_path<P: AsRef<Path>>(self, path: P) -> Result<Texture> {
+        let path = path.as_ref();
+        let img = image::open(path)
+            .map_err(|e| format_err!("failed to load '{}': {e}", path.display()))?;
+        self.build_from_image(&img)
+    }
+
+    pub fn build_from_image(self, img: &DynamicImage) -> Result<Texture> {
+        let img = if self.flip_vertically {
+            img.flipv().into_rgba8()
+        } else {
+            img.to_rgba8()
+        };
+        self.build_from_rgba(img.width(), img.height(), img.as_raw())
+    }
+
+    /// Builds a texture from tightly packed 8-bit RGBA texels.
+    pub fn build_from_rgba(self, width: u32, height: u32, texels: &[u8]) -> Result<Texture> {
+        if texels.len() != usize::try_from(width * height * 4).unwrap() {
+            return Err(format_err!(
+                "expected {width}x{height} RGBA texels but got {} bytes",
+                texels.len()
+            ));
+        }
+        let texture = unsafe { self.build_impl(width, height, texels) };
+        debug::check_errors("TextureBuilder::build");
+        Ok(texture)
+    }
+
+    unsafe fn build_impl(&self, width: u32, height: u32, texels: &[u8]) -> Texture {
+        let mut texture = 0;
+        gl::GenTextures(1, &mut texture);
+        gl::BindTexture(gl::TEXTURE_2D, texture);
+
+        gl::TexParameteri(
+            gl::TEXTURE_2D,
+            gl::TEXTURE_WRAP_S,
+            try_into!(gl::types::GLenum::from(self.wrap_s)),
+        );
+        gl::TexParameteri(
+            gl::TEXTURE_2D,
+            gl::TEXTURE_WRAP_T,
+            try_into!(gl::types::GLenum::from(self.wrap_t)),
+        );
+        gl::TexParameteri(
+            gl::TEXTURE_2D,
+            gl::TEXTURE_MIN_FILTER,
+            try_into!(gl::types::GLenum::from(self.min_filter)),
+        );
+        gl::TexParameteri(
+            gl::TEXTURE_2D,
+            gl::TEXTURE_MAG_FILTER,
+            try_into!(gl::types::GLenum::from(self.mag_filter)),
+        );
+
+        // Rows of RGBA8 texels are always 4-byte aligned but be explicit about it.
+        gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+        gl::TexImage2D(
+            gl::TEXTURE_2D,
+            0,
+            try_into!(gl::RGBA8),
+            try_into!(width),
+            try_into!(height),
+            0,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            texels.as_ptr() as *const c_void,
+        );
+
+        if self.min_filter.uses_mipmaps() {
+            gl::GenerateMipmap(gl::TEXTURE_2D);
+        }
+
+        gl::BindTexture(gl::TEXTURE_2D, 0);
+
+        Texture {
+            gl_object_id: texture,
+            width,
+            height,
+            target: Target::Texture2D,
+        }
+    }
+}
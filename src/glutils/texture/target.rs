@@ -0,0 +1,21 @@
+/// What a texture is bound as, which decides how shaders sample it.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// Sampled with a `sampler2D`.
+    #[default]
+    Texture2D,
+    /// Layers of equal size sampled with a `sampler2DArray` and a layer index.
+    Texture2DArray,
+    /// Six square faces sampled by direction with a `samplerCube`.
+    CubeMap,
+}
+
+impl From<Target> for gl::types::GLenum {
+    fn from(value: Target) -> Self {
+        match value {
+            Target::Texture2D => gl::TEXTURE_2D,
+            Target::Texture2DArray => gl::TEXTURE_2D_ARRAY,
+            Target::CubeMap => gl::TEXTURE_CUBE_MAP,
+        }
+    }
+}
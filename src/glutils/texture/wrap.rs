@@ -0,0 +1,19 @@
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Wrap {
+    #[default]
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+    ClampToBorder,
+}
+
+impl From<Wrap> for gl::types::GLenum {
+    fn from(value: Wrap) -> Self {
+        match value {
+            Wrap::Repeat => gl::REPEAT,
+            Wrap::MirroredRepeat => gl::MIRRORED_REPEAT,
+            Wrap::ClampToEdge => gl::CLAMP_TO_EDGE,
+            Wrap::ClampToBorder => gl::CLAMP_TO_BORDER,
+        }
+    }
+}
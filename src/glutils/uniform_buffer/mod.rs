@@ -0,0 +1,199 @@
+use super::{debug, model::usage::Usage, shader::program::Program, try_into};
+use anyhow::{format_err, Result};
+use glam::Mat4;
+use std::{any, collections::BTreeSet, ffi::c_void, marker::PhantomData};
+
+pub mod std140;
+use std140::{Std140, Std140Writer};
+
+std140::uniform_block! {
+    /// Camera matrices for programs that declare
+    /// `layout(std140) uniform Matrices { mat4 projection; mat4 view; };`.
+    #[derive(Copy, Clone, Debug, Default, PartialEq)]
+    pub struct Matrices {
+        pub projection: Mat4,
+        pub view: Mat4,
+    }
+}
+
+/// Hands out the binding points of a GL context to its uniform buffers. Binding points belong to
+/// a context, so there should be one of these per context; lessons use the harness's.
+pub struct UniformBindings {
+    used: BTreeSet<u32>,
+    max_bindings: u32,
+}
+
+impl UniformBindings {
+    /// Looks up how many binding points the current context has.
+    pub fn new() -> Self {
+        let mut max_bindings = 0;
+        unsafe { gl::GetIntegerv(gl::MAX_UNIFORM_BUFFER_BINDINGS, &mut max_bindings) };
+        Self::with_limit(u32::try_from(max_bindings).unwrap_or(0))
+    }
+
+    pub fn with_limit(max_bindings: u32) -> Self {
+        Self {
+            used: BTreeSet::new(),
+            max_bindings,
+        }
+    }
+
+    /// Takes the lowest free binding point.
+    fn allocate(&mut self) -> Result<u32> {
+        let binding = (0..self.max_bindings)
+            .find(|binding| !self.used.contains(binding))
+            .ok_or_else(|| {
+                format_err!(
+                    "all {} uniform buffer binding points are in use",
+                    self.max_bindings
+                )
+            })?;
+        self.used.insert(binding);
+        Ok(binding)
+    }
+
+    fn release(&mut self, binding: u32) {
+        self.used.remove(&binding);
+    }
+}
+
+/// A uniform buffer holding a `T` laid out with `std140`, so that values shared by several
+/// programs, such as camera matrices, only need to be uploaded once. Each buffer stays bound to
+/// its own binding point, and programs read from it once attached with
+/// [`UniformBuffer::attach`].
+pub struct UniformBuffer<T> {
+    pub gl_object_id: gl::types::GLuint,
+    binding: u32,
+    size: usize,
+    layout: Vec<(String, usize)>,
+    _contents: PhantomData<T>,
+}
+
+impl<T: Std140> UniformBuffer<T> {
+    /// Uploads `value` and binds the buffer to the lowest binding point that's free in
+    /// `bindings`.
+    pub fn new(value: &T, usage: Usage, bindings: &mut UniformBindings) -> Result<Self> {
+        let (bytes, layout) = encode(value);
+        let binding = bindings.allocate()?;
+
+        let mut ubo = 0;
+        unsafe {
+            gl::GenBuffers(1, &mut ubo);
+            gl::BindBuffer(gl::UNIFORM_BUFFER, ubo);
+            gl::BufferData(
+                gl::UNIFORM_BUFFER,
+                try_into!(bytes.len()),
+                bytes.as_ptr() as *const c_void,
+                usage.into(),
+            );
+            gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+            gl::BindBufferBase(gl::UNIFORM_BUFFER, binding, ubo);
+        }
+        debug::label(gl::BUFFER, ubo, any::type_name::<T>());
+        debug::check_errors("UniformBuffer::new");
+
+        Ok(Self {
+            gl_object_id: ubo,
+            binding,
+            size: bytes.len(),
+            layout,
+            _contents: PhantomData,
+        })
+    }
+
+    pub fn binding(&self) -> u32 {
+        self.binding
+    }
+
+    pub fn update(&self, value: &T) {
+        let (bytes, _) = encode(value);
+        unsafe {
+            gl::BindBuffer(gl::UNIFORM_BUFFER, self.gl_object_id);
+            gl::BufferSubData(
+                gl::UNIFORM_BUFFER,
+                0,
+                try_into!(bytes.len()),
+                bytes.as_ptr() as *const c_void,
+            );
+            gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+        }
+        debug::check_errors("UniformBuffer::update");
+    }
+
+    /// Makes `program` read its uniform block named `block` from this buffer. Fails if the
+    /// block's members don't line up with `T`, which usually means that the GLSL declaration is
+    /// missing `layout(std140)` or declares its members in a different order.
+    pub fn attach(&self, program: &Program, block: &str) -> Result<()> {
+        let type_name = any::type_name::<T>();
+        let (block_size, members) = program.uniform_block_layout(block)?;
+
+        for (name, offset) in &members {
+            let Some((_, expected)) = self.layout.iter().find(|(member, _)| member == name) else {
+                return Err(format_err!(
+                    "uniform block '{block}' has a member '{name}' that {type_name} doesn't"
+                ));
+            };
+            if expected != offset {
+                return Err(format_err!(
+                    "member '{name}' of uniform block '{block}' is at offset {offset} but \
+                    {type_name} puts it at {expected}"
+                ));
+            }
+        }
+        if let Some((name, _)) = self
+            .layout
+            .iter()
+            .find(|(name, _)| members.iter().all(|(member, _)| member != name))
+        {
+            return Err(format_err!(
+                "{type_name} has a member '{name}' that uniform block '{block}' doesn't"
+            ));
+        }
+        if block_size < self.size {
+            return Err(format_err!(
+                "uniform block '{block}' is {block_size} bytes but {type_name} is {} bytes",
+                self.size
+            ));
+        }
+
+        program.bind_uniform_block(block, self.binding)
+    }
+
+    /// Frees the buffer and gives its binding point back to `bindings`, which it was created
+    /// with.
+    pub fn delete(self, bindings: &mut UniformBindings) {
+        unsafe { gl::DeleteBuffers(1, &self.gl_object_id) };
+        bindings.release(self.binding);
+    }
+}
+
+fn encode<T: Std140>(value: &T) -> (Vec<u8>, Vec<(String, usize)>) {
+    let mut writer = Std140Writer::new();
+    value.write_std140(&mut writer, "");
+    writer.into_parts()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bindings_reuse_the_lowest_free_point() {
+        let mut bindings = UniformBindings::with_limit(3);
+        assert_eq!(bindings.allocate().unwrap(), 0);
+        assert_eq!(bindings.allocate().unwrap(), 1);
+        assert_eq!(bindings.allocate().unwrap(), 2);
+        assert!(bindings.allocate().is_err());
+
+        bindings.release(1);
+        assert_eq!(bindings.allocate().unwrap(), 1);
+    }
+
+    #[test]
+    fn bindings_are_per_allocator() {
+        let mut first = UniformBindings::with_limit(1);
+        let mut second = UniformBindings::with_limit(1);
+        assert_eq!(first.allocate().unwrap(), 0);
+        assert_eq!(second.allocate().unwrap(), 0);
+    }
+}
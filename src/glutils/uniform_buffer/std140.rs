@@ -0,0 +1,372 @@
+use glam::{IVec2, IVec3, IVec4, Mat2, Mat3, Mat4, UVec2, UVec3, UVec4, Vec2, Vec3, Vec4};
+
+/// A type that can be stored in a uniform block with the `std140` layout.
+///
+/// Scalars and vectors are aligned to their own size, except that three-component vectors are
+/// aligned like four-component ones. Arrays, matrix columns and structs are aligned to 16 bytes
+/// and padded to a multiple of 16 bytes. Structs implement this trait with [`uniform_block!`].
+pub trait Std140 {
+    const ALIGN: usize;
+
+    /// Appends `self` to `writer`, recording the offset of every leaf member under `name`.
+    fn write_std140(&self, writer: &mut Std140Writer, name: &str);
+}
+
+/// Serializes values into a `std140` buffer and remembers the offset at which each member was
+/// written, so that the layout can be compared with what the shader compiler came up with.
+#[derive(Default)]
+pub struct Std140Writer {
+    bytes: Vec<u8>,
+    offsets: Vec<(String, usize)>,
+}
+
+impl Std140Writer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pads up to the next multiple of `align`.
+    pub fn align(&mut self, align: usize) {
+        self.bytes
+            .resize(self.bytes.len().next_multiple_of(align), 0);
+    }
+
+    /// Writes a leaf member at the current offset, which needs to be aligned already.
+    pub fn write_member(&mut self, name: &str, bytes: &[u8]) {
+        self.offsets.push((name.to_string(), self.bytes.len()));
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Offset of every leaf member in the order they were written, named the way GL reports
+    /// block members, e.g. `lights[1].color`.
+    pub fn offsets(&self) -> &[(String, usize)] {
+        &self.offsets
+    }
+
+    pub fn into_parts(self) -> (Vec<u8>, Vec<(String, usize)>) {
+        (self.bytes, self.offsets)
+    }
+}
+
+/// Name of a struct member as reported by GL, given the name of the struct it's in.
+pub fn member_name(parent: &str, member: &str) -> String {
+    if parent.is_empty() {
+        member.to_string()
+    } else {
+        format!("{parent}.{member}")
+    }
+}
+
+macro_rules! impl_std140 {
+    ($($ty:ty => $align:expr),* $(,)?) => {
+        $(
+            impl Std140 for $ty {
+                const ALIGN: usize = $align;
+
+                fn write_std140(&self, writer: &mut Std140Writer, name: &str) {
+                    writer.align(Self::ALIGN);
+                    writer.write_member(name, bytemuck::bytes_of(self));
+                }
+            }
+        )*
+    };
+}
+
+impl_std140! {
+    f32 => 4,
+    i32 => 4,
+    u32 => 4,
+    Vec2 => 8,
+    IVec2 => 8,
+    UVec2 => 8,
+    Vec3 => 16,
+    IVec3 => 16,
+    UVec3 => 16,
+    Vec4 => 16,
+    IVec4 => 16,
+    UVec4 => 16,
+}
+
+/// GLSL booleans take up four bytes in a uniform block.
+impl Std140 for bool {
+    const ALIGN: usize = 4;
+
+    fn write_std140(&self, writer: &mut Std140Writer, name: &str) {
+        u32::from(*self).write_std140(writer, name);
+    }
+}
+
+/// Matrices are stored as arrays of column vectors, each padded to 16 bytes.
+fn write_columns(writer: &mut Std140Writer, name: &str, columns: &[Vec4]) {
+    writer.align(16);
+    writer.write_member(name, bytemuck::cast_slice(columns));
+}
+
+impl Std140 for Mat2 {
+    const ALIGN: usize = 16;
+
+    fn write_std140(&self, writer: &mut Std140Writer, name: &str) {
+        let columns = [self.x_axis, self.y_axis].map(|c| c.extend(0.0).extend(0.0));
+        write_columns(writer, name, &columns);
+    }
+}
+
+impl Std140 for Mat3 {
+    const ALIGN: usize = 16;
+
+    fn write_std140(&self, writer: &mut Std140Writer, name: &str) {
+        let columns = [self.x_axis, self.y_axis, self.z_axis].map(|c| c.extend(0.0));
+        write_columns(writer, name, &columns);
+    }
+}
+
+impl Std140 for Mat4 {
+    const ALIGN: usize = 16;
+
+    fn write_std140(&self, writer: &mut Std140Writer, name: &str) {
+        write_columns(writer, name, &self.to_cols_array_2d().map(Vec4::from));
+    }
+}
+
+/// Every element starts on a 16-byte boundary, even for arrays of scalars. GL reports arrays of
+/// basic types as a single `name[0]` member and arrays of structs member by member, which is
+/// mirrored here.
+impl<T: Std140, const N: usize> Std140 for [T; N] {
+    const ALIGN: usize = 16;
+
+    fn write_std140(&self, writer: &mut Std140Writer, name: &str) {
+        for (i, element) in self.iter().enumerate() {
+            writer.align(16);
+            let element_name = format!("{name}[{i}]");
+            let num_members = writer.offsets.len();
+            element.write_std140(writer, &element_name);
+            let members = &writer.offsets[num_members..];
+            let is_basic = members.len() == 1 && members[0].0 == element_name;
+            if i > 0 && is_basic {
+                writer.offsets.truncate(num_members);
+            }
+        }
+        writer.align(16);
+    }
+}
+
+/// Declares a struct usable as the contents of a [`UniformBuffer`](super::UniformBuffer) or as a
+/// member of another uniform block. Fields are laid out in declaration order following `std140`,
+/// so the struct needs to declare the same members in the same order as the GLSL block.
+///
+/// ```ignore
+/// uniform_block! {
+///     pub struct Matrices {
+///         pub projection: Mat4,
+///         pub view: Mat4,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! uniform_block {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($field_vis:vis $field:ident: $ty:ty),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $($field_vis $field: $ty),*
+        }
+
+        impl $crate::glutils::uniform_buffer::std140::Std140 for $name {
+            const ALIGN: usize = 16;
+
+            fn write_std140(
+                &self,
+                writer: &mut $crate::glutils::uniform_buffer::std140::Std140Writer,
+                name: &str,
+            ) {
+                writer.align(16);
+                $(
+                    $crate::glutils::uniform_buffer::std140::Std140::write_std140(
+                        &self.$field,
+                        writer,
+                        &$crate::glutils::uniform_buffer::std140::member_name(
+                            name,
+                            stringify!($field),
+                        ),
+                    );
+                )*
+                writer.align(16);
+            }
+        }
+    };
+}
+pub use uniform_block;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout<T: Std140>(value: &T) -> (Vec<u8>, Vec<(String, usize)>) {
+        let mut writer = Std140Writer::new();
+        value.write_std140(&mut writer, "");
+        writer.into_parts()
+    }
+
+    fn offsets(members: &[(&str, usize)]) -> Vec<(String, usize)> {
+        members
+            .iter()
+            .map(|(name, offset)| (name.to_string(), *offset))
+            .collect()
+    }
+
+    fn floats(bytes: &[u8]) -> Vec<f32> {
+        bytemuck::pod_collect_to_vec(bytes)
+    }
+
+    uniform_block! {
+        struct Vec3ThenFloat {
+            direction: Vec3,
+            intensity: f32,
+        }
+    }
+
+    uniform_block! {
+        struct Vec2ThenVec3 {
+            uv: Vec2,
+            color: Vec3,
+        }
+    }
+
+    uniform_block! {
+        struct Scalars {
+            weights: [f32; 3],
+            count: i32,
+        }
+    }
+
+    uniform_block! {
+        struct Matrices3 {
+            normal: Mat3,
+            scale: f32,
+            rotation: Mat2,
+        }
+    }
+
+    uniform_block! {
+        #[derive(Copy, Clone)]
+        struct Inner {
+            offset: Vec2,
+            strength: f32,
+        }
+    }
+
+    uniform_block! {
+        struct Outer {
+            enabled: bool,
+            inner: Inner,
+            lights: [Inner; 2],
+            tail: f32,
+        }
+    }
+
+    #[test]
+    fn float_packs_after_vec3() {
+        let (bytes, members) = layout(&Vec3ThenFloat {
+            direction: Vec3::new(1.0, 2.0, 3.0),
+            intensity: 4.0,
+        });
+        assert_eq!(members, offsets(&[("direction", 0), ("intensity", 12)]));
+        assert_eq!(floats(&bytes), [1.0, 2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn vec3_is_aligned_to_16_bytes() {
+        let (bytes, members) = layout(&Vec2ThenVec3 {
+            uv: Vec2::ONE,
+            color: Vec3::ONE,
+        });
+        assert_eq!(members, offsets(&[("uv", 0), ("color", 16)]));
+        // The struct is padded to a multiple of 16 bytes.
+        assert_eq!(bytes.len(), 32);
+    }
+
+    #[test]
+    fn array_elements_have_16_byte_stride() {
+        let (bytes, members) = layout(&Scalars {
+            weights: [1.0, 2.0, 3.0],
+            count: 7,
+        });
+        // GL reports arrays of basic types by their first element only.
+        assert_eq!(members, offsets(&[("weights[0]", 0), ("count", 48)]));
+        let values = floats(&bytes[..48]);
+        assert_eq!([values[0], values[4], values[8]], [1.0, 2.0, 3.0]);
+        assert_eq!(bytemuck::pod_read_unaligned::<i32>(&bytes[48..52]), 7);
+        assert_eq!(bytes.len(), 64);
+    }
+
+    #[test]
+    fn matrix_columns_are_padded_to_vec4() {
+        let normal = Mat3::from_cols_array(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0]);
+        let (bytes, members) = layout(&Matrices3 {
+            normal,
+            scale: 10.0,
+            rotation: Mat2::from_cols_array(&[11.0, 12.0, 13.0, 14.0]),
+        });
+        assert_eq!(
+            members,
+            offsets(&[("normal", 0), ("scale", 48), ("rotation", 64)])
+        );
+        assert_eq!(
+            floats(&bytes[..48]),
+            [1.0, 2.0, 3.0, 0.0, 4.0, 5.0, 6.0, 0.0, 7.0, 8.0, 9.0, 0.0]
+        );
+        // Even mat2 columns take up a vec4 each.
+        assert_eq!(
+            floats(&bytes[64..96]),
+            [11.0, 12.0, 0.0, 0.0, 13.0, 14.0, 0.0, 0.0]
+        );
+        assert_eq!(bytes.len(), 96);
+    }
+
+    #[test]
+    fn nested_structs_are_aligned_and_padded_to_16_bytes() {
+        let inner = Inner {
+            offset: Vec2::ONE,
+            strength: 2.0,
+        };
+        let (bytes, members) = layout(&Outer {
+            enabled: true,
+            inner,
+            lights: [inner; 2],
+            tail: 3.0,
+        });
+        assert_eq!(
+            members,
+            offsets(&[
+                ("enabled", 0),
+                ("inner.offset", 16),
+                ("inner.strength", 24),
+                ("lights[0].offset", 32),
+                ("lights[0].strength", 40),
+                ("lights[1].offset", 48),
+                ("lights[1].strength", 56),
+                ("tail", 64),
+            ])
+        );
+        assert_eq!(bytemuck::pod_read_unaligned::<u32>(&bytes[..4]), 1);
+        assert_eq!(bytes.len(), 80);
+    }
+
+    #[test]
+    fn mat4_is_written_column_major() {
+        let (bytes, members) = layout(&Mat4::from_cols_array(&std::array::from_fn(|i| i as f32)));
+        assert_eq!(members, offsets(&[("", 0)]));
+        assert_eq!(
+            floats(&bytes),
+            (0..16).map(|i| i as f32).collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,139 @@
+pub mod params;
+
+use crate::{
+    cli::Args,
+    glutils::{capture, debug, profiler::Profiler, uniform_buffer::UniformBindings},
+};
+use anyhow::{format_err, Result};
+use glfw::{Action, Glfw, Key, PWindow, WindowEvent, WindowHint};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Saves the current frame to the captures directory.
+pub const SCREENSHOT_KEY: Key = Key::F12;
+
+/// Kept apart from `screenshots`, whose tracked images are regenerated with `--screenshot`.
+const CAPTURES_DIR: &str = "captures";
+
+/// How many frames of GPU timings are in flight before their results are read back.
+const FRAMES_IN_FLIGHT: usize = 3;
+
+/// Hints every lesson applies on top of its own before creating its window. Debug builds ask for
+/// a debug context so that driver messages can be reported through `log`. Windows that only exist
+/// to take a `--screenshot` are hidden, which still gives them a default framebuffer to capture.
+pub fn window_hints(glfw: &mut Glfw, args: &Args) {
+    glfw.window_hint(WindowHint::OpenGlDebugContext(cfg!(debug_assertions)));
+    glfw.window_hint(WindowHint::Visible(args.screenshot.is_none()));
+}
+
+/// Per-lesson state for functionality that every lesson shares, driven by the command line.
+/// Lessons forward their window events to [`Harness::handle_event`] and call
+/// [`Harness::end_frame`] once the frame is drawn, right before swapping buffers.
+pub struct Harness {
+    frame: u64,
+    screenshot: Option<(PathBuf, u64)>,
+    record_dir: Option<PathBuf>,
+    screenshot_requested: bool,
+    profiler: Profiler,
+    uniform_bindings: UniformBindings,
+}
+
+impl Harness {
+    /// Needs to be called once the window's context is current and GL functions are loaded.
+    pub fn new(args: &Args) -> Result<Self> {
+        debug::enable_debug_output();
+
+        if let Some(dir) = args.record.as_ref() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format_err!("failed to create '{}': {e}", dir.display()))?;
+        }
+
+        Ok(Self {
+            frame: 0,
+            screenshot: args
+                .screenshot
+                .clone()
+                .map(|path| (path, args.after_frames.unwrap_or(1))),
+            record_dir: args.record.clone(),
+            screenshot_requested: false,
+            profiler: if args.profile {
+                Profiler::new(FRAMES_IN_FLIGHT, args.trace.clone())
+            } else {
+                Profiler::disabled()
+            },
+            uniform_bindings: UniformBindings::new(),
+        })
+    }
+
+    /// Number of frames presented so far.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Times GPU work when `--profile` is passed and does nothing otherwise.
+    pub fn profiler(&self) -> &Profiler {
+        &self.profiler
+    }
+
+    /// The binding points of the window's context, for creating uniform buffers.
+    pub fn uniform_bindings(&mut self) -> &mut UniformBindings {
+        &mut self.uniform_bindings
+    }
+
+    pub fn handle_event(&mut self, event: &WindowEvent) {
+        if let WindowEvent::Key(SCREENSHOT_KEY, _, Action::Press, _) = event {
+            self.screenshot_requested = true;
+        }
+    }
+
+    /// Captures the back buffer if a screenshot or recording calls for it. Once a screenshot
+    /// requested with `--screenshot` has been taken the window is closed.
+    pub fn end_frame(&mut self, window: &mut PWindow) -> Result<()> {
+        self.frame += 1;
+        self.profiler.end_frame();
+
+        let scheduled_screenshot = self
+            .screenshot
+            .as_ref()
+            .filter(|(_, after_frames)| *after_frames == self.frame)
+            .map(|(path, _)| path.clone());
+
+        if scheduled_screenshot.is_none() && !self.screenshot_requested && self.record_dir.is_none()
+        {
+            return Ok(());
+        }
+
+        let (width, height) = window.get_framebuffer_size();
+        let image = capture::read_back_buffer(u32::try_from(width)?, u32::try_from(height)?);
+
+        if let Some(dir) = self.record_dir.as_ref() {
+            capture::save_image(&image, dir.join(format!("frame_{:06}.png", self.frame)))?;
+        }
+
+        if self.screenshot_requested {
+            self.screenshot_requested = false;
+            let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            let path =
+                Path::new(CAPTURES_DIR).join(format!("screenshot_{timestamp}_{}.png", self.frame));
+            capture::save_image(&image, &path)?;
+            log::info!("saved screenshot to '{}'", path.display());
+        }
+
+        if let Some(path) = scheduled_screenshot {
+            capture::save_image(&image, &path)?;
+            log::info!("saved screenshot to '{}'", path.display());
+            window.set_should_close(true);
+        }
+
+        Ok(())
+    }
+
+    /// Reports GPU timings, if they were being collected. Call it after the render loop, while
+    /// the context is still current.
+    pub fn finish(self) -> Result<()> {
+        self.profiler.finish()
+    }
+}
@@ -0,0 +1,569 @@
+use crate::{cli::Args, glutils::postprocess::effect::Tonemapper};
+use anyhow::{format_err, Result};
+use glam::Vec3;
+use log::warn;
+use std::{
+    fs,
+    marker::PhantomData,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
+
+/// Where each lesson's last used parameters are saved.
+const SAVED_PARAMS_DIR: &str = "params";
+
+/// A fixed set of named values, for parameters that pick one of them.
+pub trait Choice: Copy + PartialEq + 'static {
+    const ALL: &'static [Self];
+
+    /// What the value is called on the command line, in parameter files and in the GUI.
+    fn name(self) -> &'static str;
+}
+
+impl Choice for Tonemapper {
+    const ALL: &'static [Self] = &Tonemapper::ALL;
+
+    fn name(self) -> &'static str {
+        Tonemapper::name(self)
+    }
+}
+
+/// Rust types that parameters can have.
+pub trait ParamType: Sized {
+    fn from_value(value: &Value) -> Self;
+    fn to_value(&self) -> Value;
+}
+
+impl ParamType for f32 {
+    fn from_value(value: &Value) -> Self {
+        match value {
+            Value::Float(v) => *v,
+            _ => unreachable!("float parameter holds {value:?}"),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        Value::Float(*self)
+    }
+}
+
+impl ParamType for i32 {
+    fn from_value(value: &Value) -> Self {
+        match value {
+            Value::Int(v) => *v,
+            _ => unreachable!("integer parameter holds {value:?}"),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        Value::Int(*self)
+    }
+}
+
+impl ParamType for bool {
+    fn from_value(value: &Value) -> Self {
+        match value {
+            Value::Bool(v) => *v,
+            _ => unreachable!("boolean parameter holds {value:?}"),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        Value::Bool(*self)
+    }
+}
+
+impl ParamType for Vec3 {
+    fn from_value(value: &Value) -> Self {
+        match value {
+            Value::Color(v) => *v,
+            _ => unreachable!("color parameter holds {value:?}"),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        Value::Color(*self)
+    }
+}
+
+impl<C: Choice> ParamType for C {
+    fn from_value(value: &Value) -> Self {
+        match value {
+            Value::Choice(i) => C::ALL[*i],
+            _ => unreachable!("choice parameter holds {value:?}"),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        Value::Choice(C::ALL.iter().position(|c| c == self).unwrap())
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Value {
+    Float(f32),
+    Int(i32),
+    Bool(bool),
+    /// RGB, usually from 0 to 1 but brighter for lights.
+    Color(Vec3),
+    /// Index into the choice's names.
+    Choice(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Kind {
+    Float(RangeInclusive<f32>),
+    Int(RangeInclusive<i32>),
+    Bool,
+    Color,
+    Choice(Vec<&'static str>),
+}
+
+impl Kind {
+    fn describe(&self) -> &'static str {
+        match self {
+            Self::Float(_) => "a number",
+            Self::Int(_) => "an integer",
+            Self::Bool => "a boolean",
+            Self::Color => "an array of three numbers",
+            Self::Choice(_) => "a string",
+        }
+    }
+}
+
+struct Param {
+    name: &'static str,
+    kind: Kind,
+    default: Value,
+    value: Value,
+}
+
+/// Refers to a declared parameter of type `T`, to read and write it through [`Params`].
+#[derive(Debug)]
+pub struct Handle<T> {
+    index: usize,
+    _type: PhantomData<T>,
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+/// Declares the parameters of a lesson, before their values are looked up by
+/// [`ParamsBuilder::build`]. Names are used as keys in parameter files and on the command line,
+/// so they should be snake case.
+#[derive(Default)]
+pub struct ParamsBuilder {
+    params: Vec<Param>,
+}
+
+impl ParamsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn float(
+        &mut self,
+        name: &'static str,
+        default: f32,
+        range: RangeInclusive<f32>,
+    ) -> Handle<f32> {
+        self.add(name, Kind::Float(range), default.to_value())
+    }
+
+    pub fn int(
+        &mut self,
+        name: &'static str,
+        default: i32,
+        range: RangeInclusive<i32>,
+    ) -> Handle<i32> {
+        self.add(name, Kind::Int(range), default.to_value())
+    }
+
+    pub fn toggle(&mut self, name: &'static str, default: bool) -> Handle<bool> {
+        self.add(name, Kind::Bool, default.to_value())
+    }
+
+    pub fn color(&mut self, name: &'static str, default: Vec3) -> Handle<Vec3> {
+        self.add(name, Kind::Color, default.to_value())
+    }
+
+    pub fn choice<C: Choice>(&mut self, name: &'static str, default: C) -> Handle<C> {
+        let names = C::ALL.iter().map(|c| c.name()).collect();
+        self.add(name, Kind::Choice(names), default.to_value())
+    }
+
+    /// Starts every parameter at its default and then overrides it with, in order, the values
+    /// saved by the last run of `args.lesson`, those in the `--params` file and those given with
+    /// `--set`. Bad saved values are skipped with a warning; the others are errors.
+    pub fn build(self, args: &Args) -> Result<Params> {
+        let mut params = Params {
+            params: self.params,
+            path: PathBuf::new()
+                .join(SAVED_PARAMS_DIR)
+                .join(format!("lesson_{}.toml", args.lesson)),
+        };
+
+        let saved = params.path.clone();
+        if saved.exists() {
+            if let Err(e) = params.load(&saved, false) {
+                warn!("ignoring saved parameters: {e}");
+            }
+        }
+        if let Some(path) = &args.params {
+            params.load(path, true)?;
+        }
+        for (name, value) in &args.set {
+            let param = params.find_mut(name)?;
+            param.value = parse(&param.kind, value)
+                .map_err(|e| format_err!("bad value '{value}' for '{name}': {e}"))?;
+        }
+        Ok(params)
+    }
+
+    fn add<T>(&mut self, name: &'static str, kind: Kind, default: Value) -> Handle<T> {
+        assert!(
+            self.params.iter().all(|p| p.name != name),
+            "parameter '{name}' declared twice"
+        );
+        self.params.push(Param {
+            name,
+            kind,
+            default,
+            value: default,
+        });
+        Handle {
+            index: self.params.len() - 1,
+            _type: PhantomData,
+        }
+    }
+}
+
+/// A lesson's named, typed parameters, which can be set from the command line, a TOML file or
+/// the GUI instead of being hard-coded. Lessons call [`Params::save`] on exit so that the next
+/// run starts where this one left off.
+pub struct Params {
+    params: Vec<Param>,
+    path: PathBuf,
+}
+
+impl Params {
+    pub fn get<T: ParamType>(&self, handle: Handle<T>) -> T {
+        T::from_value(&self.params[handle.index].value)
+    }
+
+    /// Sets the parameter, clamped to its range if it has one.
+    pub fn set<T: ParamType>(&mut self, handle: Handle<T>, value: T) {
+        let param = &mut self.params[handle.index];
+        param.value = clamp(&param.kind, value.to_value());
+    }
+
+    pub fn reset(&mut self) {
+        for param in &mut self.params {
+            param.value = param.default;
+        }
+    }
+
+    /// Writes the current values to the lesson's file in the saved parameters directory.
+    pub fn save(&self) -> Result<()> {
+        let table: toml::Table = self
+            .params
+            .iter()
+            .map(|p| (p.name.to_owned(), to_toml(&p.kind, &p.value)))
+            .collect();
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format_err!("failed to create '{}': {e}", dir.display()))?;
+        }
+        fs::write(&self.path, toml::to_string(&table)?)
+            .map_err(|e| format_err!("failed to write '{}': {e}", self.path.display()))
+    }
+
+    /// A widget per parameter and a button to reset them all. Returns whether any changed.
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        for param in &mut self.params {
+            let label = param.name.replace('_', " ");
+            changed |= match (&param.kind, &mut param.value) {
+                (Kind::Float(range), Value::Float(v)) => ui
+                    .add(egui::Slider::new(v, range.clone()).text(label))
+                    .changed(),
+                (Kind::Int(range), Value::Int(v)) => ui
+                    .add(egui::Slider::new(v, range.clone()).text(label))
+                    .changed(),
+                (Kind::Bool, Value::Bool(v)) => ui.checkbox(v, label).changed(),
+                (Kind::Color, Value::Color(v)) => {
+                    ui.horizontal(|ui| {
+                        let mut rgb = v.to_array();
+                        let changed = ui.color_edit_button_rgb(&mut rgb).changed();
+                        *v = Vec3::from_array(rgb);
+                        ui.label(label);
+                        changed
+                    })
+                    .inner
+                }
+                (Kind::Choice(names), Value::Choice(v)) => {
+                    let before = *v;
+                    egui::ComboBox::from_label(label)
+                        .selected_text(names[*v])
+                        .show_ui(ui, |ui| {
+                            for (i, name) in names.iter().enumerate() {
+                                ui.selectable_value(v, i, *name);
+                            }
+                        });
+                    *v != before
+                }
+                (kind, value) => unreachable!("{kind:?} parameter holds {value:?}"),
+            };
+        }
+        if ui.button("Reset to defaults").clicked() {
+            self.reset();
+            changed = true;
+        }
+        changed
+    }
+
+    /// [`Params::ui`] in a window of its own.
+    pub fn window(&mut self, ctx: &egui::Context, title: &str) -> bool {
+        egui::Window::new(title)
+            .show(ctx, |ui| self.ui(ui))
+            .and_then(|response| response.inner)
+            .unwrap_or(false)
+    }
+
+    /// Sets the parameters in the TOML file at `path`. Unless `strict`, entries that don't match
+    /// a parameter are skipped with a warning rather than being errors.
+    fn load(&mut self, path: &Path, strict: bool) -> Result<()> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format_err!("failed to read '{}': {e}", path.display()))?;
+        let table: toml::Table = toml::from_str(&text)
+            .map_err(|e| format_err!("failed to parse '{}': {e}", path.display()))?;
+        for (name, value) in &table {
+            let result = self.find_mut(name).and_then(|param| {
+                param.value = from_toml(&param.kind, value)
+                    .map_err(|e| format_err!("bad value for '{name}': {e}"))?;
+                Ok(())
+            });
+            match result {
+                Err(e) if strict => return Err(format_err!("{e} in '{}'", path.display())),
+                Err(e) => warn!("skipping {e} in '{}'", path.display()),
+                Ok(()) => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn find_mut(&mut self, name: &str) -> Result<&mut Param> {
+        let names = self
+            .params
+            .iter()
+            .map(|p| p.name)
+            .collect::<Vec<_>>()
+            .join(", ");
+        self.params
+            .iter_mut()
+            .find(|p| p.name == name)
+            .ok_or_else(|| format_err!("unknown parameter '{name}', expected one of: {names}"))
+    }
+}
+
+/// Parses a value given on the command line, e.g. `0.5`, `true`, `0.2,0.3,0.3` or `aces`.
+fn parse(kind: &Kind, text: &str) -> Result<Value> {
+    let value = match kind {
+        Kind::Float(_) => Value::Float(text.parse()?),
+        Kind::Int(_) => Value::Int(text.parse()?),
+        Kind::Bool => Value::Bool(text.parse()?),
+        Kind::Color => {
+            let channels = text
+                .split(',')
+                .map(|c| c.trim().parse::<f32>())
+                .collect::<Result<Vec<_>, _>>()?;
+            let [r, g, b] = channels[..] else {
+                return Err(format_err!("expected three comma-separated channels"));
+            };
+            Value::Color(Vec3::new(r, g, b))
+        }
+        Kind::Choice(names) => Value::Choice(choice_index(names, text)?),
+    };
+    Ok(clamp(kind, value))
+}
+
+fn from_toml(kind: &Kind, value: &toml::Value) -> Result<Value> {
+    let number = |value: &toml::Value| match value {
+        toml::Value::Float(v) => Some(*v as f32),
+        toml::Value::Integer(v) => Some(*v as f32),
+        _ => None,
+    };
+    let value = match (kind, value) {
+        (Kind::Float(_), v) if number(v).is_some() => Value::Float(number(v).unwrap()),
+        (Kind::Int(_), toml::Value::Integer(v)) => Value::Int(i32::try_from(*v)?),
+        (Kind::Bool, toml::Value::Boolean(v)) => Value::Bool(*v),
+        (Kind::Color, toml::Value::Array(channels)) => {
+            let channels = channels.iter().map(number).collect::<Option<Vec<_>>>();
+            let Some([r, g, b]) = channels.as_deref() else {
+                return Err(format_err!("expected an array of three numbers"));
+            };
+            Value::Color(Vec3::new(*r, *g, *b))
+        }
+        (Kind::Choice(names), toml::Value::String(name)) => {
+            Value::Choice(choice_index(names, name)?)
+        }
+        (kind, value) => {
+            return Err(format_err!(
+                "expected {} but got {}",
+                kind.describe(),
+                value.type_str()
+            ))
+        }
+    };
+    Ok(clamp(kind, value))
+}
+
+fn to_toml(kind: &Kind, value: &Value) -> toml::Value {
+    match value {
+        Value::Float(v) => toml::Value::Float(shortest_f64(*v)),
+        Value::Int(v) => toml::Value::Integer(i64::from(*v)),
+        Value::Bool(v) => toml::Value::Boolean(*v),
+        Value::Color(v) => toml::Value::Array(
+            v.to_array()
+                .map(|c| toml::Value::Float(shortest_f64(c)))
+                .to_vec(),
+        ),
+        Value::Choice(i) => match kind {
+            Kind::Choice(names) => toml::Value::String(names[*i].to_owned()),
+            _ => unreachable!("{kind:?} parameter holds a choice"),
+        },
+    }
+}
+
+/// `value` as the shortest `f64` that reads back as it, so that e.g. 0.2 is saved as 0.2 rather
+/// than as 0.20000000298023224.
+fn shortest_f64(value: f32) -> f64 {
+    value.to_string().parse().unwrap()
+}
+
+fn choice_index(names: &[&str], name: &str) -> Result<usize> {
+    names
+        .iter()
+        .position(|n| n.eq_ignore_ascii_case(name))
+        .ok_or_else(|| format_err!("expected one of: {}", names.join(", ")))
+}
+
+fn clamp(kind: &Kind, value: Value) -> Value {
+    match (kind, value) {
+        (Kind::Float(range), Value::Float(v)) => {
+            Value::Float(v.clamp(*range.start(), *range.end()))
+        }
+        (Kind::Int(range), Value::Int(v)) => Value::Int(v.clamp(*range.start(), *range.end())),
+        (Kind::Color, Value::Color(v)) => Value::Color(v.max(Vec3::ZERO)),
+        _ => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds() -> Vec<(Kind, Value)> {
+        vec![
+            (Kind::Float(0.0..=10.0), Value::Float(0.2)),
+            (Kind::Int(-5..=5), Value::Int(-3)),
+            (Kind::Bool, Value::Bool(true)),
+            (Kind::Color, Value::Color(Vec3::new(0.2, 0.3, 1.5))),
+            (
+                Kind::Choice(Tonemapper::ALL.iter().map(|t| t.name()).collect()),
+                Tonemapper::ALL[1].to_value(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn parse_command_line_values() {
+        let [float, int, bool, color, choice] = <[_; 5]>::try_from(kinds()).unwrap();
+        assert_eq!(parse(&float.0, "0.2").unwrap(), float.1);
+        assert_eq!(parse(&int.0, "-3").unwrap(), int.1);
+        assert_eq!(parse(&bool.0, "true").unwrap(), bool.1);
+        assert_eq!(parse(&color.0, "0.2, 0.3,1.5").unwrap(), color.1);
+        let name = Tonemapper::ALL[1].name().to_uppercase();
+        assert_eq!(parse(&choice.0, &name).unwrap(), choice.1);
+    }
+
+    #[test]
+    fn parse_clamps_to_range() {
+        assert_eq!(
+            parse(&Kind::Float(0.0..=1.0), "4").unwrap(),
+            Value::Float(1.0)
+        );
+        assert_eq!(parse(&Kind::Int(0..=3), "-2").unwrap(), Value::Int(0));
+        assert_eq!(
+            parse(&Kind::Color, "-1,0.5,2").unwrap(),
+            Value::Color(Vec3::new(0.0, 0.5, 2.0))
+        );
+    }
+
+    #[test]
+    fn parse_rejects_bad_values() {
+        assert!(parse(&Kind::Float(0.0..=1.0), "half").is_err());
+        assert!(parse(&Kind::Int(0..=3), "1.5").is_err());
+        assert!(parse(&Kind::Bool, "yes").is_err());
+        assert!(parse(&Kind::Color, "0.2,0.3").is_err());
+        assert!(parse(&Kind::Choice(vec!["a", "b"]), "c").is_err());
+    }
+
+    #[test]
+    fn toml_round_trip() {
+        for (kind, value) in kinds() {
+            let toml = to_toml(&kind, &value);
+            assert_eq!(from_toml(&kind, &toml).unwrap(), value, "{toml}");
+        }
+    }
+
+    #[test]
+    fn toml_round_trip_through_text() {
+        let table: toml::Table = kinds()
+            .iter()
+            .enumerate()
+            .map(|(i, (kind, value))| (format!("param_{i}"), to_toml(kind, value)))
+            .collect();
+        let text = toml::to_string(&table).unwrap();
+        assert!(text.contains("param_0 = 0.2\n"), "{text}");
+
+        let parsed: toml::Table = toml::from_str(&text).unwrap();
+        for (i, (kind, value)) in kinds().iter().enumerate() {
+            let toml = &parsed[&format!("param_{i}")];
+            assert_eq!(from_toml(kind, toml).unwrap(), *value);
+        }
+    }
+
+    #[test]
+    fn from_toml_accepts_integers_for_floats() {
+        let color = toml::Value::Array(vec![
+            toml::Value::Integer(1),
+            toml::Value::Float(0.5),
+            toml::Value::Integer(0),
+        ]);
+        assert_eq!(
+            from_toml(&Kind::Color, &color).unwrap(),
+            Value::Color(Vec3::new(1.0, 0.5, 0.0))
+        );
+        assert_eq!(
+            from_toml(&Kind::Float(0.0..=10.0), &toml::Value::Integer(2)).unwrap(),
+            Value::Float(2.0)
+        );
+    }
+
+    #[test]
+    fn from_toml_rejects_wrong_types() {
+        assert!(from_toml(&Kind::Int(0..=3), &toml::Value::Float(1.0)).is_err());
+        assert!(from_toml(&Kind::Bool, &toml::Value::Integer(1)).is_err());
+        assert!(from_toml(&Kind::Color, &toml::Value::Float(1.0)).is_err());
+        let short = toml::Value::Array(vec![toml::Value::Float(1.0)]);
+        assert!(from_toml(&Kind::Color, &short).is_err());
+        let unknown = toml::Value::String("sepia".to_owned());
+        assert!(from_toml(&Kind::Choice(vec!["aces"]), &unknown).is_err());
+    }
+}
@@ -1,12 +1,24 @@
 #![allow(dead_code)]
 use anyhow::{format_err, Result};
+use cli::Args;
+use env_logger::Env;
+use harness::params::ParamsBuilder;
 use std::process::ExitCode;
 
 mod _1_getting_started;
+mod _2_lighting;
+mod _4_advanced_opengl;
+mod _5_advanced_lighting;
+mod _6_pbr;
+mod _7_in_practice;
 
+mod cli;
 mod glutils;
+mod harness;
 
 fn main() -> ExitCode {
+    env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+
     match run() {
         Ok(_) => ExitCode::SUCCESS,
         Err(err) => {
@@ -16,19 +28,120 @@ fn main() -> ExitCode {
     }
 }
 
+/// A lesson's entry point and, for lessons with parameters, the function that declares them.
+struct Lesson {
+    run: fn(&Args) -> Result<()>,
+    declare_params: Option<fn(&mut ParamsBuilder)>,
+}
+
+impl Lesson {
+    fn new(run: fn(&Args) -> Result<()>) -> Self {
+        Self {
+            run,
+            declare_params: None,
+        }
+    }
+
+    fn with_params(mut self, declare_params: fn(&mut ParamsBuilder)) -> Self {
+        self.declare_params = Some(declare_params);
+        self
+    }
+}
+
+/// Every lesson, in the order of their numbers on the command line.
+fn lessons() -> Vec<Lesson> {
+    use _1_getting_started::{_1_hello_window, _2_hello_square, _2_hello_triangle, _3_shaders};
+    use _2_lighting::_6_multiple_lights;
+    use _5_advanced_lighting::_7_bloom;
+
+    vec![
+        Lesson::new(_1_hello_window::run),
+        Lesson::new(_2_hello_triangle::run).with_params(|params| {
+            _2_hello_triangle::declare_params(params);
+        }),
+        Lesson::new(_2_hello_square::run).with_params(|params| {
+            _2_hello_square::declare_params(params);
+        }),
+        Lesson::new(_3_shaders::run).with_params(|params| {
+            _3_shaders::declare_params(params);
+        }),
+        Lesson::new(_1_getting_started::_3_shaders_triangle::run),
+        Lesson::new(_4_advanced_opengl::_5_framebuffers::run),
+        Lesson::new(_6_multiple_lights::run).with_params(|params| {
+            _6_multiple_lights::declare_params(params);
+        }),
+        Lesson::new(_4_advanced_opengl::_6_cubemaps::run),
+        Lesson::new(_5_advanced_lighting::_3_shadow_mapping::run),
+        Lesson::new(_5_advanced_lighting::_8_deferred_shading::run),
+        Lesson::new(_6_pbr::_2_image_based_lighting::run),
+        Lesson::new(_7_bloom::run).with_params(|params| {
+            _7_bloom::declare_params(params);
+        }),
+        Lesson::new(_5_advanced_lighting::_5_parallax_mapping::run),
+        Lesson::new(_7_in_practice::_2_text_rendering::run),
+    ]
+}
+
 fn run() -> Result<()> {
-    let mut clargs = std::env::args();
-    let lesson = clargs
-        .nth(1)
-        .expect("expected number argument")
-        .parse::<usize>()?;
-
-    match lesson {
-        1 => _1_getting_started::_1_hello_window::run(),
-        2 => _1_getting_started::_2_hello_triangle::run(),
-        3 => _1_getting_started::_2_hello_square::run(),
-        4 => _1_getting_started::_3_shaders::run(),
-        5 => _1_getting_started::_3_shaders_triangle::run(),
-        _ => Err(format_err!("no lesson of number {lesson}")),
+    let args = Args::parse()?;
+    let lessons = lessons();
+    let lesson = args
+        .lesson
+        .checked_sub(1)
+        .and_then(|i| lessons.get(i))
+        .ok_or_else(|| format_err!("no lesson of number {}", args.lesson))?;
+
+    check_params(&args, &lessons)?;
+    (lesson.run)(&args)
+}
+
+/// Checks `--params` and `--set` against the parameters the lesson declares, so that mistakes
+/// show up before its window opens.
+fn check_params(args: &Args, lessons: &[Lesson]) -> Result<()> {
+    if args.params.is_none() && args.set.is_empty() {
+        return Ok(());
+    }
+
+    let Some(declare_params) = lessons[args.lesson - 1].declare_params else {
+        let with_params: Vec<usize> = (1..=lessons.len())
+            .filter(|n| lessons[n - 1].declare_params.is_some())
+            .collect();
+        return Err(format_err!(
+            "lesson {} has no parameters to set, only lessons {with_params:?} do",
+            args.lesson
+        ));
+    };
+    let mut params = ParamsBuilder::new();
+    declare_params(&mut params);
+    params.build(args)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &str) -> Args {
+        Args::parse_from(args.split_whitespace().map(String::from)).unwrap()
+    }
+
+    #[test]
+    fn only_lessons_with_parameters_take_them() {
+        let lessons = lessons();
+        let err = check_params(&args("1 --set clear_color=1,0,0"), &lessons).unwrap_err();
+        assert!(
+            err.to_string().contains("only lessons [2, 3, 4, 7, 12] do"),
+            "{err}"
+        );
+        assert!(check_params(&args("1"), &lessons).is_ok());
+        assert!(check_params(&args("3 --set clear_color=1,0,0"), &lessons).is_ok());
+    }
+
+    #[test]
+    fn parameters_are_checked_against_the_lesson() {
+        let lessons = lessons();
+        assert!(check_params(&args("12 --set exposure=2"), &lessons).is_ok());
+        assert!(check_params(&args("12 --set clear_color=1,0,0"), &lessons).is_err());
+        assert!(check_params(&args("4 --set pulse=maybe"), &lessons).is_err());
     }
 }